@@ -0,0 +1,309 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bson::doc;
+use clap::{Parser, Subcommand};
+use mongodb::options::UpdateModifications;
+use serde_json::json;
+use tracing::error;
+
+use crate::db::database::Database;
+use crate::db::model::{AutomodHit, Muted};
+use crate::model::application::Configuration;
+use crate::{connect_database, Error};
+
+#[derive(Parser)]
+#[command(author, version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the Discord bot. This is the default.
+    Run {
+        /// Only log actions on Discord instead of executing them.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Migrate database records to the current format.
+    Migrate,
+    /// Export moderation cases as JSON.
+    ExportCases {
+        /// The file to write to. Defaults to stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Check the configuration for errors.
+    VerifyConfig,
+    /// Create the indexes of all database collections and report failures. The bot creates them
+    /// on start as well, except for the expiry of logged messages.
+    BackfillIndexes,
+}
+
+// Indexes of the collections as collection, keys and whether the index is unique
+fn indexes() -> Vec<(&'static str, bson::Document, bool)> {
+    vec![
+        ("muted", doc! { "user_id": 1 }, true),
+        ("warnings", doc! { "guild_id": 1, "user_id": 1 }, false),
+        ("locked", doc! { "channel_id": 1 }, true),
+        ("quotes", doc! { "message_id": 1 }, true),
+        ("aliases", doc! { "guild_id": 1, "name": 1 }, true),
+        (
+            "interactions",
+            doc! { "user_id": 1, "timestamp": -1 },
+            false,
+        ),
+        ("automod_hits", doc! { "message_id": 1 }, true),
+        ("automod_hits", doc! { "rule": 1, "timestamp": -1 }, false),
+        ("activity", doc! { "guild_id": 1, "user_id": 1 }, true),
+        (
+            "voice_time",
+            doc! { "guild_id": 1, "user_id": 1, "day": 1 },
+            true,
+        ),
+        ("event_reminders", doc! { "event_id": 1, "offset": 1 }, true),
+        ("quiz_questions", doc! { "guild_id": 1, "topic": 1 }, false),
+        ("quiz_scores", doc! { "guild_id": 1, "score": -1 }, false),
+        ("giveaways", doc! { "message_id": 1 }, true),
+        (
+            "giveaway_entries",
+            doc! { "message_id": 1, "timestamp": 1 },
+            false,
+        ),
+        ("jury_mutes", doc! { "message_id": 1 }, true),
+        ("pin_requests", doc! { "message_id": 1 }, true),
+        ("panics", doc! { "guild_id": 1 }, true),
+        ("lockdowns", doc! { "guild_id": 1 }, true),
+        ("config_audit", doc! { "timestamp": -1 }, false),
+        ("message_log", doc! { "author_id": 1 }, false),
+        ("monitors", doc! { "channel_id": 1 }, true),
+        ("join_queue", doc! { "guild_id": 1, "user_id": 1 }, true),
+        ("support_responses", doc! { "thread_id": 1 }, true),
+        (
+            "support_responses",
+            doc! { "guild_id": 1, "timestamp": 1 },
+            false,
+        ),
+        ("counters", doc! { "name": 1 }, true),
+        ("cases", doc! { "guild_id": 1, "case_id": 1 }, true),
+        ("notes", doc! { "guild_id": 1, "note_id": 1 }, true),
+        ("notes", doc! { "guild_id": 1, "user_id": 1 }, false),
+        (
+            "vouches",
+            doc! { "guild_id": 1, "user_id": 1, "voucher_id": 1 },
+            true,
+        ),
+        ("elevations", doc! { "guild_id": 1, "user_id": 1 }, false),
+        (
+            "transcripts",
+            doc! { "title": "text", "content": "text" },
+            false,
+        ),
+        (
+            "watchwords",
+            doc! { "guild_id": 1, "user_id": 1, "pattern": 1 },
+            true,
+        ),
+        ("beta_requests", doc! { "guild_id": 1, "user_id": 1 }, false),
+        ("announcement_drafts", doc! { "message_id": 1 }, true),
+        ("rules", doc! { "guild_id": 1, "number": 1 }, false),
+        ("temp_bans", doc! { "guild_id": 1, "user_id": 1 }, true),
+        ("invite_pauses", doc! { "guild_id": 1 }, true),
+        ("slowmode_resets", doc! { "channel_id": 1 }, true),
+        ("appeals", doc! { "guild_id": 1, "appeal_id": 1 }, true),
+        ("appeals", doc! { "guild_id": 1, "user_id": 1 }, false),
+        ("interest_roles", doc! { "guild_id": 1 }, false),
+        ("failures", doc! { "guild_id": 1, "failure_id": 1 }, true),
+    ]
+}
+
+/// Create the indexes of all database collections. The bot relies on the unique ones to prevent
+/// duplicate records and on the text index to search transcripts.
+/// Existing indexes are left as they are, so this is done on every start.
+pub async fn ensure_indexes(database: &Database) {
+    for (collection, keys, unique) in indexes() {
+        if let Err(err) = database.create_index(collection, keys, unique).await {
+            error!("Failed to create index on {}: {}", collection, err);
+        }
+    }
+}
+
+/// Run a maintenance command, which does not require a connection to Discord.
+pub async fn maintain(command: Command) -> Result<(), Error> {
+    match command {
+        Command::Run {
+            ..
+        } => unreachable!("The bot is not a maintenance command"),
+        Command::Migrate => migrate(&connect_database().await).await,
+        Command::ExportCases {
+            output,
+        } => export_cases(&connect_database().await, output).await,
+        Command::VerifyConfig => verify_config(),
+        Command::BackfillIndexes => backfill_indexes(&connect_database().await).await,
+    }
+}
+
+async fn migrate(database: &Database) -> Result<(), Error> {
+    let configuration = Configuration::load()?;
+    let mutes = database.collect::<Muted>("muted", doc! {}).await?;
+
+    for mute in &mutes {
+        // Writing the record again encrypts reasons stored in plain text
+        let updated: bson::Document = Muted {
+            reason: mute.reason.clone(),
+            // Mutes from before scoped mute roles only applied the configured mute role
+            mute_roles: mute
+                .mute_roles
+                .clone()
+                .or_else(|| Some(vec![configuration.general.mute.role.to_string()])),
+            ..Default::default()
+        }
+        .into();
+
+        database
+            .update::<Muted>(
+                "muted",
+                doc! { "user_id": &mute.user_id },
+                UpdateModifications::Document(doc! { "$set": updated }),
+                None,
+            )
+            .await?;
+    }
+
+    println!("Migrated {} mutes.", mutes.len());
+
+    Ok(())
+}
+
+async fn export_cases(database: &Database, output: Option<PathBuf>) -> Result<(), Error> {
+    let mutes = database.collect::<Muted>("muted", doc! {}).await?;
+    let automod_hits = database
+        .collect::<AutomodHit>("automod_hits", doc! {})
+        .await?;
+
+    let cases = json!({
+        // Serialized by hand, because the serializer of the model would encrypt the reason again
+        "mutes": mutes.iter().map(|mute| json!({
+            "user_id": mute.user_id,
+            "guild_id": mute.guild_id,
+            "expires": mute.expires,
+            "reason": mute.reason,
+        })).collect::<Vec<_>>(),
+        "automod_hits": automod_hits,
+    });
+
+    let mut writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    };
+    serde_json::to_writer_pretty(&mut writer, &cases)?;
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+fn verify_config() -> Result<(), Error> {
+    let configuration = Configuration::load()?;
+    let mut problems = Vec::new();
+
+    if configuration.general.logging_channel == 0 {
+        problems.push("general.logging_channel is not set".to_string());
+    }
+
+    let mute = &configuration.general.mute;
+    if mute.role == 0 && mute.roles.is_empty() {
+        problems.push("general.mute has no mute role".to_string());
+    }
+
+    let automod = &configuration.automod;
+    if !automod.rules.is_empty() && automod.queue_channel == 0 {
+        problems.push("automod.queue_channel is not set".to_string());
+    }
+
+    for rule in &automod.rules {
+        for (name, threshold) in [
+            ("queue_threshold", rule.queue_threshold),
+            ("action_threshold", rule.action_threshold),
+        ] {
+            if !(0.0..=1.0).contains(&threshold) {
+                problems.push(format!(
+                    "automod rule {}: {} must be between 0 and 1",
+                    rule.name, name
+                ));
+            }
+        }
+
+        if rule.queue_threshold > rule.action_threshold {
+            problems.push(format!(
+                "automod rule {}: queue_threshold is greater than action_threshold",
+                rule.name
+            ));
+        }
+
+        if rule.patterns.is_empty() {
+            problems.push(format!("automod rule {} has no patterns", rule.name));
+        }
+    }
+
+    let impersonation = &configuration.impersonation;
+    if impersonation.enabled && !(0.0..=1.0).contains(&impersonation.name_similarity) {
+        problems.push("impersonation.name_similarity must be between 0 and 1".to_string());
+    }
+
+    if problems.is_empty() {
+        println!("The configuration is valid.");
+        return Ok(());
+    }
+
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+
+    Err(Error::from(format!(
+        "Found {} problems in the configuration",
+        problems.len()
+    )))
+}
+
+async fn backfill_indexes(database: &Database) -> Result<(), Error> {
+    let mut failed = 0;
+
+    for (collection, keys, unique) in indexes() {
+        match database.create_index(collection, keys, unique).await {
+            Ok(name) => println!("Created index {} on {}.", name, collection),
+            Err(err) => {
+                eprintln!("Failed to create index on {}: {}", collection, err);
+                failed += 1;
+            },
+        }
+    }
+
+    // Logged messages expire after the configured retention
+    let retention_days = Configuration::load()?.message_log.retention_days;
+    if retention_days > 0 {
+        match database
+            .create_ttl_index(
+                "message_log",
+                "logged_at",
+                Duration::from_secs(retention_days * 24 * 60 * 60),
+            )
+            .await
+        {
+            Ok(name) => println!("Created index {} on message_log.", name),
+            Err(err) => {
+                eprintln!("Failed to create index on message_log: {}", err);
+                failed += 1;
+            },
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::from(format!("Failed to create {} indexes", failed)));
+    }
+
+    Ok(())
+}
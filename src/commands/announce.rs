@@ -0,0 +1,183 @@
+use bson::doc;
+use chrono::Utc;
+use poise::serenity_prelude::{self as serenity, ButtonStyle, ChannelId};
+
+use crate::db::model::{AnnouncementDraft, DraftStatus, InterestRole};
+use crate::model::application::LogCategory;
+use crate::utils::announcement_draft::{describe_draft, MAX_CONTENT_LENGTH};
+use crate::utils::moderation::is_administrator;
+use crate::utils::time::parse_duration;
+use crate::{Context, Error};
+
+/// Draft announcements for the server.
+#[poise::command(slash_command, guild_only, subcommands("draft"))]
+pub async fn announce(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// The interest roles of the guild, synced from its onboarding questions.
+async fn interests(ctx: &Context<'_>) -> Result<Vec<InterestRole>, Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+
+    ctx.data()
+        .load_full()
+        .database
+        .collect::<InterestRole>("interest_roles", doc! { "guild_id": guild_id.to_string() })
+        .await
+}
+
+/// Find an interest role by its id, as chosen from the autocompletion, or by its name.
+async fn find_interest(ctx: &Context<'_>, interest: &str) -> Result<Option<InterestRole>, Error> {
+    Ok(interests(ctx).await?.into_iter().find(|i| {
+        i.role_id.as_deref() == Some(interest)
+            || i.name
+                .as_deref()
+                .is_some_and(|n| n.eq_ignore_ascii_case(interest))
+    }))
+}
+
+async fn autocomplete_interest(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<poise::AutocompleteChoice<String>> {
+    let partial = partial.to_lowercase();
+
+    interests(&ctx)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|i| Some((i.name?, i.role_id?)))
+        .filter(|(name, _)| name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|(name, role_id)| poise::AutocompleteChoice {
+            name,
+            value: role_id,
+        })
+        .collect()
+}
+
+/// Draft an announcement which is published once approved by an administrator.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn draft(
+    ctx: Context<'_>,
+    #[description = "The content of the announcement"] content: String,
+    #[description = "The channel to publish in, defaults to the announcement channel"]
+    channel: Option<serenity::GuildChannel>,
+    #[description = "Publish this long after approval at the earliest, such as 2h"]
+    publish_in: Option<String>,
+    #[description = "Ping the members interested in a topic"]
+    #[autocomplete = "autocomplete_interest"]
+    interest: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let configuration = &data.configuration;
+    let announcements = &configuration.announcements;
+
+    let member = ctx
+        .author_member()
+        .await
+        .ok_or("Failed to fetch your member")?;
+    if !is_administrator(&configuration.administrators, &member)
+        && !member
+            .roles
+            .iter()
+            .any(|r| announcements.staff_roles.contains(&r.0))
+    {
+        return Err("You do not have permission to draft announcements".into());
+    }
+
+    if content.chars().count() > MAX_CONTENT_LENGTH {
+        return Err(format!(
+            "Announcements can not be longer than {} characters",
+            MAX_CONTENT_LENGTH
+        )
+        .into());
+    }
+
+    let channel_id = match channel {
+        Some(channel) => channel.id,
+        None if announcements.channel != 0 => ChannelId(announcements.channel),
+        None => return Err("No announcement channel is configured".into()),
+    };
+
+    let publish_at = match publish_in {
+        Some(publish_in) => {
+            Some((Utc::now() + parse_duration(&publish_in).ok_or("Invalid duration")?).timestamp())
+        },
+        None => None,
+    };
+
+    let interest_role_id = match interest {
+        Some(interest) => Some(
+            find_interest(&ctx, &interest)
+                .await?
+                .and_then(|i| i.role_id)
+                .ok_or("Unknown interest")?,
+        ),
+        None => None,
+    };
+
+    let approval_channel = if announcements.approval_channel != 0 {
+        announcements.approval_channel
+    } else {
+        configuration.log_channel(LogCategory::Moderation)
+    };
+
+    let mut draft = AnnouncementDraft {
+        guild_id: ctx.guild_id().map(|g| g.to_string()),
+        channel_id: Some(channel_id.to_string()),
+        author_id: Some(ctx.author().id.to_string()),
+        content: Some(content),
+        interest_role_id,
+        publish_at,
+        status: Some(DraftStatus::Pending),
+        drafted: Some(Utc::now().timestamp()),
+        ..Default::default()
+    };
+
+    let message = ChannelId(approval_channel)
+        .send_message(&ctx.discord().http, |m| {
+            m.embed(|e| describe_draft(e, &draft, configuration.general.embed_color))
+                .components(|c| {
+                    c.create_action_row(|r| {
+                        r.create_button(|b| {
+                            b.custom_id("announce:approve")
+                                .label("Approve")
+                                .style(ButtonStyle::Success)
+                        })
+                        .create_button(|b| {
+                            b.custom_id("announce:edit")
+                                .label("Edit")
+                                .style(ButtonStyle::Secondary)
+                        })
+                        .create_button(|b| {
+                            b.custom_id("announce:reject")
+                                .label("Reject")
+                                .style(ButtonStyle::Danger)
+                        })
+                    })
+                })
+        })
+        .await?;
+
+    draft.message_id = Some(message.id.to_string());
+    data.database
+        .insert::<AnnouncementDraft>("announcement_drafts", draft, None)
+        .await?;
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title("Drafted announcement")
+                .description(format!(
+                    "Your draft was sent to <#{}> for approval.",
+                    approval_channel
+                ))
+                .color(configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
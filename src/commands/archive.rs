@@ -0,0 +1,211 @@
+use std::borrow::Cow;
+use std::io::Write;
+
+use chrono::Utc;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use poise::serenity_prelude::{
+    self as serenity,
+    AttachmentType,
+    ButtonStyle,
+    ChannelId,
+    InteractionResponseType,
+};
+use serde_json::json;
+use tracing::{debug, error};
+
+use crate::utils::dry_run;
+use crate::utils::moderation::lock_channel;
+use crate::utils::storage::upload_bytes;
+use crate::utils::transcript::fetch_history;
+use crate::{Context, Error};
+
+/// Archive channels.
+#[poise::command(slash_command, guild_only, subcommands("archive_channel"))]
+pub async fn archive(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Export the history of a channel, then delete or lock it.
+#[poise::command(slash_command, guild_only, rename = "channel")]
+pub async fn archive_channel(
+    ctx: Context<'_>,
+    #[description = "The channel to archive"]
+    #[channel_types("Text")]
+    channel: serenity::GuildChannel,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let data = ctx.data().load_full();
+    let configuration = &data.configuration;
+    let embed_color = configuration.general.embed_color;
+    let http = &ctx.discord().http;
+
+    let messages = fetch_history(http, channel.id).await?;
+
+    let transcript = json!({
+        "guild_id": channel.guild_id.to_string(),
+        "channel_id": channel.id.to_string(),
+        "name": channel.name,
+        "topic": channel.topic,
+        "archived_by": ctx.author().id.to_string(),
+        "archived_at": Utc::now().timestamp(),
+        "messages": messages.iter().map(|m| json!({
+            "id": m.id.to_string(),
+            "author_id": m.author.id.to_string(),
+            "author": m.author.tag(),
+            "content": m.content,
+            "timestamp": m.timestamp.unix_timestamp(),
+            "edited_timestamp": m.edited_timestamp.map(|t| t.unix_timestamp()),
+            "attachments": m.attachments.iter().map(|a| &a.url).collect::<Vec<_>>(),
+            "embeds": m.embeds,
+        })).collect::<Vec<_>>(),
+    });
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&serde_json::to_vec(&transcript)?)?;
+    let compressed = encoder.finish()?;
+    let filename = format!(
+        "{}-{}-{}.json.gz",
+        channel.name,
+        channel.id,
+        Utc::now().timestamp()
+    );
+
+    // Upload to the external storage if there is one, otherwise attach it to the log
+    let link = match &configuration.storage {
+        Some(storage) => Some(
+            upload_bytes(
+                storage,
+                &format!("archives/{}", filename),
+                "application/gzip",
+                compressed.clone(),
+            )
+            .await?,
+        ),
+        None => None,
+    };
+
+    ChannelId(configuration.archive.log_channel)
+        .send_message(http, |m| {
+            if link.is_none() {
+                m.add_file(AttachmentType::Bytes {
+                    data: Cow::from(compressed),
+                    filename: filename.clone(),
+                });
+            }
+
+            m.embed(|e| {
+                let e = e
+                    .title(format!("Archived #{}", channel.name))
+                    .field("Channel", format!("<#{}>", channel.id), true)
+                    .field("Messages", messages.len(), true)
+                    .field("Archived by", format!("<@{}>", ctx.author().id), true)
+                    .color(embed_color);
+
+                if let Some(link) = &link {
+                    e.field("Transcript", format!("[{}]({})", filename, link), false);
+                }
+
+                e
+            })
+        })
+        .await?;
+
+    debug!(
+        "{} archived {} messages of {}",
+        ctx.author().name,
+        messages.len(),
+        channel.name
+    );
+
+    let handle = ctx
+        .send(|f| {
+            f.embed(|e| {
+                e.title(format!("Archived {} messages", messages.len()))
+                    .description(format!(
+                        "The transcript of <#{}> was posted to the archive log. What should \
+                         happen to the channel?",
+                        channel.id
+                    ))
+                    .color(embed_color)
+            })
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id("archive:delete")
+                            .label("Delete")
+                            .style(ButtonStyle::Danger)
+                    })
+                    .create_button(|b| {
+                        b.custom_id("archive:lock")
+                            .label("Lock")
+                            .style(ButtonStyle::Primary)
+                    })
+                    .create_button(|b| {
+                        b.custom_id("archive:keep")
+                            .label("Keep")
+                            .style(ButtonStyle::Secondary)
+                    })
+                })
+            })
+        })
+        .await?;
+    let message = handle.message().await?;
+
+    let component = message
+        .await_component_interaction(ctx.discord())
+        .author_id(ctx.author().id)
+        .timeout(std::time::Duration::from_secs(60))
+        .await;
+
+    let status = match component.as_ref().map(|c| c.data.custom_id.as_str()) {
+        Some("archive:delete") => {
+            match dry_run::execute(format!("delete {}", channel.name), async {
+                channel.delete(http).await.map(|_| ())
+            })
+            .await
+            {
+                Ok(_) => format!("Deleted #{}.", channel.name),
+                Err(err) => {
+                    error!("Failed to delete archived channel: {:?}", err);
+                    format!("Failed to delete #{}: {}", channel.name, err)
+                },
+            }
+        },
+        Some("archive:lock") => match lock_channel(http, &data.database, &channel).await {
+            None => format!("Locked <#{}>.", channel.id),
+            Some(err) => format!("Failed to lock <#{}>: {}", channel.id, err.user_message()),
+        },
+        _ => format!("Kept <#{}>.", channel.id),
+    };
+
+    // The response is gone if the archived channel was deleted
+    if channel.id == ctx.channel_id() && status.starts_with("Deleted") {
+        return Ok(());
+    }
+
+    match &component {
+        Some(component) => {
+            component
+                .create_interaction_response(http, |r| {
+                    r.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|d| {
+                            d.embed(|e| e.description(&status).color(embed_color))
+                                .components(|c| c)
+                        })
+                })
+                .await?
+        },
+        None => {
+            handle
+                .edit(ctx, |f| {
+                    f.embed(|e| e.description(&status).color(embed_color))
+                        .components(|c| c)
+                })
+                .await?
+        },
+    }
+
+    Ok(())
+}
@@ -0,0 +1,405 @@
+use std::collections::HashSet;
+
+use bson::doc;
+use chrono::{TimeZone, Utc};
+use mongodb::options::FindOptions;
+use poise::serenity_prelude::{
+    ChannelType,
+    GuildChannel,
+    PermissionOverwriteType,
+    Permissions,
+    Role,
+    RoleId,
+    User,
+    UserId,
+};
+
+use crate::db::model::InteractionLog;
+use crate::model::application::LogCategory;
+use crate::{Context, Error};
+
+/// Audit the usage of the Discord bot.
+#[poise::command(slash_command, subcommands("interactions", "roles", "permissions"))]
+pub async fn audit(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// List recent command invocations of a user.
+#[poise::command(slash_command)]
+pub async fn interactions(
+    ctx: Context<'_>,
+    #[description = "The user to audit"] user: User,
+    #[min = 1]
+    #[max = 25]
+    #[description = "Amount of invocations to show"]
+    count: Option<i64>,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let mut cursor = data
+        .database
+        .find::<InteractionLog>(
+            "interactions",
+            InteractionLog {
+                user_id: Some(user.id.to_string()),
+                ..Default::default()
+            }
+            .into(),
+            Some(
+                FindOptions::builder()
+                    .sort(doc! { "timestamp": -1 })
+                    .limit(count.unwrap_or(10))
+                    .build(),
+            ),
+        )
+        .await?;
+
+    let mut fields = Vec::new();
+    while cursor.advance().await? {
+        let interaction: InteractionLog = cursor.deserialize_current()?;
+
+        fields.push((
+            format!(
+                "/{} {}",
+                interaction.command.unwrap_or_default(),
+                if interaction.success.unwrap_or_default() {
+                    "✅"
+                } else {
+                    "❌"
+                }
+            ),
+            format!(
+                "{}\nIn <#{}> at {}{}{}",
+                interaction
+                    .arguments
+                    .filter(|a| !a.is_empty())
+                    .map(|a| format!("`{}`", a.join(", ")))
+                    .unwrap_or_else(|| "No arguments".to_string()),
+                interaction.channel_id.unwrap_or_default(),
+                Utc.timestamp_opt(interaction.timestamp.unwrap_or_default(), 0)
+                    .single()
+                    .map(|t| format!("<t:{}:f>", t.timestamp()))
+                    .unwrap_or_default(),
+                interaction
+                    .latency
+                    .map(|l| format!(" ({} ms)", l))
+                    .unwrap_or_default(),
+                interaction
+                    .error
+                    .map(|e| format!("\nError: {}", e))
+                    .unwrap_or_default(),
+            ),
+            false,
+        ));
+    }
+
+    ctx.send(|f| {
+        f.ephemeral(true).embed(|f| {
+            f.title(format!("Interactions of {}", user.tag()))
+                .thumbnail(user.face())
+                .color(embed_color);
+
+            if fields.is_empty() {
+                f.description("No interactions recorded.")
+            } else {
+                f.fields(fields)
+            }
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// List roles without members, roles unused in permission overwrites and roles above the bot.
+#[poise::command(slash_command, guild_only)]
+pub async fn roles(ctx: Context<'_>) -> Result<(), Error> {
+    // The maximal amount of members which can be fetched at once
+    const MAX_FETCH: u64 = 1000;
+
+    ctx.defer_ephemeral().await?;
+
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let http = &ctx.discord().http;
+
+    let roles = guild_id.roles(http).await?;
+
+    let mut held = HashSet::new();
+    let mut after: Option<UserId> = None;
+    loop {
+        let page = guild_id.members(http, Some(MAX_FETCH), after).await?;
+        let exhausted = (page.len() as u64) < MAX_FETCH;
+        after = page.last().map(|m| m.user.id);
+        held.extend(page.into_iter().flat_map(|m| m.roles));
+
+        if exhausted {
+            break;
+        }
+    }
+
+    let overwritten = guild_id
+        .channels(http)
+        .await?
+        .into_values()
+        .flat_map(|c| c.permission_overwrites)
+        .filter_map(|o| match o.kind {
+            PermissionOverwriteType::Role(role_id) => Some(role_id),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    let bot = guild_id
+        .member(http, ctx.discord().cache.current_user_id())
+        .await?;
+    let bot_position = bot
+        .roles
+        .iter()
+        .filter_map(|r| roles.get(r))
+        .map(|r| r.position)
+        .max()
+        .unwrap_or_default();
+
+    // The everyone role is held by all members and can not be moved
+    let mut roles = roles
+        .into_values()
+        .filter(|r| r.id.0 != guild_id.0)
+        .collect::<Vec<_>>();
+    roles.sort_by_key(|r| -r.position);
+
+    let without_members = roles
+        .iter()
+        .filter(|r| !r.managed && !held.contains(&r.id))
+        .collect::<Vec<_>>();
+    let without_overwrites = roles
+        .iter()
+        .filter(|r| !overwritten.contains(&r.id))
+        .collect::<Vec<_>>();
+    let above_bot = roles
+        .iter()
+        .filter(|r| r.position >= bot_position && !bot.roles.contains(&r.id))
+        .collect::<Vec<_>>();
+
+    let data = ctx.data().load_full();
+    let mute_role = RoleId(data.configuration.general.mute.role);
+    let mute_role_blocked = above_bot.iter().any(|r| r.id == mute_role);
+
+    ctx.send(|f| {
+        f.ephemeral(true).embed(|e| {
+            let e = e
+                .title("Role audit")
+                .field(
+                    format!("Without members ({})", without_members.len()),
+                    list_roles(&without_members),
+                    false,
+                )
+                .field(
+                    format!(
+                        "Unused in permission overwrites ({})",
+                        without_overwrites.len()
+                    ),
+                    list_roles(&without_overwrites),
+                    false,
+                )
+                .field(
+                    format!("Above the bot ({})", above_bot.len()),
+                    list_roles(&above_bot),
+                    false,
+                )
+                .color(data.configuration.general.embed_color);
+
+            if mute_role_blocked {
+                e.description(format!(
+                    "The mute role <@&{}> is above the bot, so members can not be muted. Move \
+                     the role of the bot above it.",
+                    mute_role
+                ));
+            }
+
+            e
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Scan channels for permission mistakes which undermine moderation.
+#[poise::command(slash_command, guild_only)]
+pub async fn permissions(ctx: Context<'_>) -> Result<(), Error> {
+    ctx.defer_ephemeral().await?;
+
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let http = &ctx.discord().http;
+
+    let roles = guild_id.roles(http).await?;
+    let mut channels = guild_id
+        .channels(http)
+        .await?
+        .into_values()
+        .filter(|c| c.kind != ChannelType::Category)
+        .collect::<Vec<_>>();
+    channels.sort_by_key(|c| c.position);
+
+    let everyone = RoleId(guild_id.0);
+    let permissions_of = |role_ids: &[RoleId], channel: &GuildChannel| {
+        let base = role_ids
+            .iter()
+            .filter_map(|r| roles.get(r))
+            .fold(Permissions::empty(), |p, r| p | r.permissions);
+        channel_permissions(base, everyone, role_ids, channel)
+    };
+
+    let data = ctx.data().load_full();
+    let configuration = &data.configuration;
+    let mute = &configuration.general.mute;
+
+    // Members with a mute role still have the permissions of everyone
+    let mute_roles = mute.roles_for(None);
+    let unmuted_channels = if mute.native {
+        Vec::new()
+    } else {
+        channels
+            .iter()
+            .filter(|c| {
+                mute_roles.iter().any(|&r| {
+                    let p = permissions_of(&[everyone, RoleId(r)], c);
+                    p.view_channel() && p.send_messages()
+                })
+            })
+            .map(|c| format!("<#{}>", c.id))
+            .collect::<Vec<_>>()
+    };
+
+    let mention_everyone_channels = channels
+        .iter()
+        .filter(|c| permissions_of(&[everyone], c).mention_everyone())
+        .map(|c| format!("<#{}>", c.id))
+        .collect::<Vec<_>>();
+
+    let log_channels = [
+        LogCategory::Moderation,
+        LogCategory::Messages,
+        LogCategory::Members,
+        LogCategory::Voice,
+        LogCategory::Errors,
+    ]
+    .map(|category| configuration.log_channel(category));
+    let public_log_channels = channels
+        .iter()
+        .filter(|c| log_channels.contains(&c.id.0) && permissions_of(&[everyone], c).view_channel())
+        .map(|c| format!("<#{}>", c.id))
+        .collect::<Vec<_>>();
+
+    let check = |findings: &[String], fix: &str| {
+        if findings.is_empty() {
+            "✅ No issues found.".to_string()
+        } else {
+            format!("❌ {}\n{}", fix, list(findings))
+        }
+    };
+
+    ctx.send(|f| {
+        f.ephemeral(true).embed(|e| {
+            e.title("Permission audit")
+                .field(
+                    "Mute role can send messages",
+                    check(
+                        &unmuted_channels,
+                        "Deny Send Messages to the mute role in these channels.",
+                    ),
+                    false,
+                )
+                .field(
+                    "Everyone can mention everyone",
+                    check(
+                        &mention_everyone_channels,
+                        "Deny Mention Everyone to @everyone in these channels.",
+                    ),
+                    false,
+                )
+                .field(
+                    "Log channels are public",
+                    check(
+                        &public_log_channels,
+                        "Deny View Channel to @everyone in the log channels.",
+                    ),
+                    false,
+                )
+                .color(configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Apply the permission overwrites of a channel to the permissions of the given roles, like
+/// Discord does for a member holding exactly these roles.
+fn channel_permissions(
+    base: Permissions,
+    everyone: RoleId,
+    role_ids: &[RoleId],
+    channel: &GuildChannel,
+) -> Permissions {
+    if base.administrator() {
+        return Permissions::all();
+    }
+
+    let overwrites = &channel.permission_overwrites;
+    let mut permissions = base;
+
+    if let Some(o) = overwrites
+        .iter()
+        .find(|o| o.kind == PermissionOverwriteType::Role(everyone))
+    {
+        permissions = (permissions - o.deny) | o.allow;
+    }
+
+    let (deny, allow) = overwrites
+        .iter()
+        .filter(|o| {
+            matches!(o.kind, PermissionOverwriteType::Role(r) if r != everyone && role_ids.contains(&r))
+        })
+        .fold((Permissions::empty(), Permissions::empty()), |(deny, allow), o| {
+            (deny | o.deny, allow | o.allow)
+        });
+
+    (permissions - deny) | allow
+}
+
+/// Mention roles, fitting into the value of an embed field.
+fn list_roles(roles: &[&Role]) -> String {
+    list(
+        &roles
+            .iter()
+            .map(|r| format!("<@&{}>", r.id))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Join mentions, fitting into the value of an embed field.
+fn list(mentions: &[String]) -> String {
+    // The maximal amount of mentions, which keeps the list below the field limit
+    const MAX_LISTED: usize = 40;
+
+    if mentions.is_empty() {
+        return "None".to_string();
+    }
+
+    let mut list = mentions
+        .iter()
+        .take(MAX_LISTED)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if mentions.len() > MAX_LISTED {
+        list.push_str(&format!(" and {} more", mentions.len() - MAX_LISTED));
+    }
+
+    list
+}
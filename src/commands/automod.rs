@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+
+use bson::{doc, Document};
+use chrono::Utc;
+use poise::serenity_prelude::{self as serenity, MessageId};
+
+use crate::utils::automod::evaluate;
+use crate::utils::long_running::LongRunning;
+use crate::utils::time::parse_duration;
+use crate::{Context, Error};
+
+/// Manage automod.
+#[poise::command(slash_command, guild_only, subcommands("stats", "scan"))]
+pub async fn automod(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show trigger statistics of automod rules.
+#[poise::command(slash_command, guild_only)]
+pub async fn stats(
+    ctx: Context<'_>,
+    #[description = "The rule to show statistics for"] rule: Option<String>,
+    #[description = "The period to show statistics for, such as 7d"] period: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let mut filter = Document::new();
+    if let Some(rule) = &rule {
+        filter.insert("rule", rule);
+    }
+    if let Some(period) = &period {
+        let period = parse_duration(period).ok_or("Invalid period")?;
+        filter.insert(
+            "timestamp",
+            doc! { "$gte": (Utc::now() - period).timestamp() },
+        );
+    }
+
+    let count_status =
+        |status: &str| doc! { "$sum": { "$cond": [{ "$eq": ["$status", status] }, 1, 0] } };
+    let mut cursor = data
+        .database
+        .aggregate("automod_hits", vec![
+            doc! { "$match": filter },
+            doc! { "$group": {
+                "_id": "$rule",
+                "triggers": { "$sum": 1 },
+                "actioned": count_status("actioned"),
+                "approved": count_status("approved"),
+                "dismissed": count_status("dismissed"),
+                "queued": count_status("queued"),
+                "average_score": { "$avg": "$score" },
+            } },
+            doc! { "$sort": { "triggers": -1 } },
+        ])
+        .await?;
+
+    let mut fields = Vec::new();
+    while cursor.advance().await? {
+        let stats = cursor.deserialize_current()?;
+        let count = |key: &str| stats.get_i32(key).unwrap_or_default();
+
+        let reviewed = count("approved") + count("dismissed");
+        let false_positive_rate = if reviewed > 0 {
+            format!(
+                "{:.0}%",
+                count("dismissed") as f64 / reviewed as f64 * 100.0
+            )
+        } else {
+            "Unknown".to_string()
+        };
+
+        fields.push((
+            stats.get_str("_id").unwrap_or("Unknown rule").to_string(),
+            format!(
+                "Triggers: {}\nActioned automatically: {}\nApproved: {}\nDismissed: {}\nPending: {}\nAverage score: {:.2}\nFalse positive rate: {}",
+                count("triggers"),
+                count("actioned"),
+                count("approved"),
+                count("dismissed"),
+                count("queued"),
+                stats.get_f64("average_score").unwrap_or_default(),
+                false_positive_rate
+            ),
+            true,
+        ));
+    }
+
+    ctx.send(|f| {
+        f.ephemeral(true).embed(|e| {
+            e.title("Automod statistics").color(embed_color);
+
+            if let Some(period) = &period {
+                e.description(format!("In the last {}", period));
+            }
+
+            if fields.is_empty() {
+                e.field("No triggers", "No automod rule has been triggered.", false)
+            } else {
+                e.fields(fields)
+            }
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Run automod rules over recent messages without taking action.
+#[poise::command(slash_command, guild_only)]
+pub async fn scan(
+    ctx: Context<'_>,
+    #[description = "The channel to scan"]
+    #[channel_types("Text")]
+    channel: serenity::GuildChannel,
+    #[min = 1]
+    #[max = 1000]
+    #[description = "The amount of recent messages to scan"]
+    count: u64,
+) -> Result<(), Error> {
+    // The maximal amount of messages that can be fetched at once
+    const MAX_FETCH: u64 = 100;
+    // The maximal amount of example hits to show
+    const MAX_EXAMPLES: usize = 10;
+
+    let mut scan = LongRunning::start(ctx, format!("Scanning #{}", channel.name), true).await?;
+
+    let mut messages = Vec::new();
+    let mut before: Option<MessageId> = None;
+    while (messages.len() as u64) < count {
+        let limit = (count - messages.len() as u64).min(MAX_FETCH);
+        let page = channel
+            .messages(ctx.discord(), |m| {
+                if let Some(before) = before {
+                    m.before(before);
+                }
+                m.limit(limit)
+            })
+            .await?;
+
+        let exhausted = (page.len() as u64) < limit;
+        before = page.last().map(|m| m.id);
+        messages.extend(page);
+        scan.progress(format!("Fetched {}/{} messages", messages.len(), count))
+            .await?;
+
+        if exhausted {
+            break;
+        }
+    }
+
+    let data = ctx.data().load_full();
+    let automod = &data.configuration.automod;
+    let cache = &ctx.discord().cache;
+
+    // Rule name to the amount of messages which would have been queued and actioned
+    let mut summary = BTreeMap::<&str, (usize, usize)>::new();
+    let mut examples = Vec::new();
+    for message in &messages {
+        if message.author.bot {
+            continue;
+        }
+
+        let is_exempt = cache
+            .member(channel.guild_id, message.author.id)
+            .is_some_and(|m| m.roles.iter().any(|r| automod.exempt_roles.contains(&r.0)));
+        if is_exempt {
+            continue;
+        }
+
+        if let Some((rule, score)) = evaluate(&automod.rules, channel.id.0, &message.content) {
+            let (queued, actioned) = summary.entry(&rule.name).or_default();
+            if score >= rule.action_threshold {
+                *actioned += 1;
+            } else {
+                *queued += 1;
+            }
+
+            if examples.len() < MAX_EXAMPLES {
+                examples.push(format!(
+                    "[{}]({}) by <@{}>: {:.2}",
+                    rule.name,
+                    message.link(),
+                    message.author.id,
+                    score
+                ));
+            }
+        }
+    }
+
+    scan.finish(|e| {
+        e.title(format!("Automod scan of #{}", channel.name))
+            .description(format!(
+                "Scanned {} messages. No action was taken.",
+                messages.len()
+            ));
+
+        if summary.is_empty() {
+            e.field(
+                "No triggers",
+                "No automod rule would have been triggered.",
+                false,
+            );
+        } else {
+            e.fields(summary.iter().map(|(rule, (queued, actioned))| {
+                (
+                    *rule,
+                    format!(
+                        "Would be queued: {}\nWould be actioned: {}",
+                        queued, actioned
+                    ),
+                    true,
+                )
+            }));
+            e.field("Examples", examples.join("\n"), false);
+        }
+
+        e
+    })
+    .await
+}
@@ -0,0 +1,112 @@
+use bson::doc;
+use tracing::debug;
+
+use crate::db::model::BetaRequest;
+use crate::utils::beta::{grant_access, queue_request, unmet_criteria};
+use crate::utils::dry_run;
+use crate::{Context, Error};
+
+/// Manage your access to the beta testing channels.
+#[poise::command(slash_command, guild_only, subcommands("join", "leave"))]
+pub async fn beta(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Request access to the beta testing channels.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn join(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let beta = &data.configuration.beta;
+    if beta.role == 0 {
+        return Err("Beta testing is not enabled".into());
+    }
+
+    let mut member = ctx
+        .author_member()
+        .await
+        .ok_or("Failed to fetch your member")?
+        .into_owned();
+    if member.roles.iter().any(|r| r.0 == beta.role) {
+        return Err("You already have access to the beta testing channels".into());
+    }
+
+    let unmet = unmet_criteria(beta, &member);
+    let (title, description) = if !unmet.is_empty() {
+        ("You are not eligible yet", unmet.join("\n"))
+    } else if beta.approval {
+        if queue_request(ctx.discord(), &data, &member).await? {
+            (
+                "Requested beta access",
+                "Staff will review your request.".to_string(),
+            )
+        } else {
+            (
+                "Request pending",
+                "Your request is already waiting for review by staff.".to_string(),
+            )
+        }
+    } else {
+        grant_access(ctx.discord(), &data, &mut member, None).await?;
+        debug!("Granted beta access to {}", member.user.tag());
+        (
+            "Joined the beta",
+            format!("You now have the <@&{}> role.", beta.role),
+        )
+    };
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title(title)
+                .description(description)
+                .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Leave the beta testing channels.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn leave(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let beta = &data.configuration.beta;
+
+    let mut member = ctx
+        .author_member()
+        .await
+        .ok_or("Failed to fetch your member")?
+        .into_owned();
+    if !member.roles.iter().any(|r| r.0 == beta.role) {
+        return Err("You do not have access to the beta testing channels".into());
+    }
+
+    dry_run::execute(
+        format!("remove tester role from {}", member.user.tag()),
+        member.remove_role(ctx.discord(), beta.role),
+    )
+    .await?;
+
+    data.database
+        .delete_many(
+            "beta_requests",
+            BetaRequest {
+                guild_id: Some(member.guild_id.to_string()),
+                user_id: Some(member.user.id.to_string()),
+                ..Default::default()
+            }
+            .into(),
+            None,
+        )
+        .await?;
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description("You left the beta.")
+                .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
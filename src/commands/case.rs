@@ -0,0 +1,123 @@
+use bson::{doc, Document};
+use chrono::Utc;
+use mongodb::options::UpdateModifications;
+
+use crate::db::model::Case;
+use crate::utils::cases::find_case;
+use crate::utils::reply::reply_embed;
+use crate::utils::time::format_timestamp;
+use crate::{Context, Error};
+
+/// Reference and amend past moderation actions.
+#[poise::command(slash_command, guild_only, subcommands("view", "edit_reason"))]
+pub async fn case(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show a case.
+#[poise::command(slash_command, guild_only)]
+pub async fn view(
+    ctx: Context<'_>,
+    #[min = 1]
+    #[description = "The number of the case"]
+    id: i64,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+
+    let case = find_case(&data.database, guild_id, id)
+        .await?
+        .ok_or(format!("Case #{} does not exist", id))?;
+
+    reply_embed(&ctx, |e| {
+        e.title(format!("Case #{}", id))
+            .field("Action", case.action.as_deref().unwrap_or_default(), true)
+            .field(
+                "Moderator",
+                format!("<@{}>", case.moderator_id.as_deref().unwrap_or_default()),
+                true,
+            )
+            .field(
+                "Date",
+                case.timestamp
+                    .map_or("Unknown".to_string(), format_timestamp),
+                true,
+            )
+            .field(
+                "Reason",
+                case.reason.as_deref().unwrap_or("None specified"),
+                false,
+            )
+            .color(data.configuration.general.embed_color);
+
+        if let Some(user_id) = &case.user_id {
+            e.field("User", format!("<@{}>", user_id), true);
+        }
+        if let Some(channel_id) = &case.channel_id {
+            e.field("Channel", format!("<#{}>", channel_id), true);
+        }
+        if let Some(approver_id) = &case.approver_id {
+            e.field("Approved by", format!("<@{}>", approver_id), true);
+        }
+        if let (Some(edited_by), Some(edited)) = (&case.edited_by, case.edited) {
+            e.field(
+                "Reason edited",
+                format!("By <@{}> {}", edited_by, format_timestamp(edited)),
+                false,
+            );
+        }
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Change the reason of a case.
+#[poise::command(slash_command, guild_only, rename = "edit-reason")]
+pub async fn edit_reason(
+    ctx: Context<'_>,
+    #[min = 1]
+    #[description = "The number of the case"]
+    id: i64,
+    #[description = "The new reason"] reason: String,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+
+    // Serialize through the model, so the reason is encrypted like when the case was recorded
+    let edited: Document = Case {
+        reason: Some(reason.clone()),
+        edited_by: Some(ctx.author().id.to_string()),
+        edited: Some(Utc::now().timestamp()),
+        ..Default::default()
+    }
+    .into();
+
+    let result = data
+        .database
+        .update::<Case>(
+            "cases",
+            doc! { "guild_id": guild_id.to_string(), "case_id": id },
+            UpdateModifications::Document(doc! { "$set": edited }),
+            None,
+        )
+        .await?;
+    if result.matched_count == 0 {
+        return Err(format!("Case #{} does not exist", id).into());
+    }
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title(format!("Edited case #{}", id))
+                .field("Reason", &reason, false)
+                .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
@@ -1,25 +1,64 @@
-use tracing::debug;
+use std::sync::Arc;
 
+use bson::{doc, Document};
+use mongodb::options::{FindOneAndUpdateOptions, UpdateModifications};
+use serde_json::json;
+use tracing::{debug, error};
+
+use crate::db::model::Alias;
+use crate::utils::alias::{parse_arguments, register_alias};
 use crate::utils::bot::load_configuration;
-use crate::{Context, Error};
+use crate::utils::choices::register_commands;
+use crate::utils::config_audit::{record_config_change, record_configuration_changes};
+use crate::{Context, Data, Error};
+
+/// An alias as JSON for the configuration changelog.
+fn describe_alias(alias: &Alias) -> String {
+    json!({
+        "command": alias.command,
+        "arguments": alias.arguments,
+    })
+    .to_string()
+}
 
 /// Reload the Discord bot.
 #[poise::command(slash_command)]
 pub async fn reload(ctx: Context<'_>) -> Result<(), Error> {
     // Update the configuration
-    let configuration = load_configuration();
+    let previous = ctx.data().load_full().configuration.clone();
+    let configuration = Arc::new(load_configuration());
     // Use the embed color from the updated configuration
     let embed_color = configuration.general.embed_color;
-    // Also save the new configuration to the user data
-    ctx.data().write().await.configuration = configuration;
+    // Swap in the new configuration, handlers which are still running keep the old one
+    ctx.data().rcu(|data| Data {
+        configuration: configuration.clone(),
+        ..(**data).clone()
+    });
 
     debug!("{} reloaded the configuration.", ctx.author().name);
+    record_configuration_changes(&ctx, &previous, &configuration).await;
+
+    // Regenerate the slash commands, the choices of their parameters come from the configuration
+    let description = match register_commands(
+        ctx.discord(),
+        &ctx.framework().options().commands,
+        &configuration,
+    )
+    .await
+    {
+        Ok(_) => "Successfully reloaded configuration.".to_string(),
+        Err(err) => {
+            error!("Failed to register slash commands: {:?}", err);
+            format!(
+                "Successfully reloaded configuration, but failed to register the slash commands: {}",
+                err
+            )
+        },
+    };
 
     ctx.send(|f| {
-        f.ephemeral(true).embed(|f| {
-            f.description("Successfully reloaded configuration.")
-                .color(embed_color)
-        })
+        f.ephemeral(true)
+            .embed(|f| f.description(description).color(embed_color))
     })
     .await?;
 
@@ -31,7 +70,7 @@ pub async fn reload(ctx: Context<'_>) -> Result<(), Error> {
 pub async fn stop(ctx: Context<'_>) -> Result<(), Error> {
     debug!("{} stopped the bot.", ctx.author().name);
 
-    let color = ctx.data().read().await.configuration.general.embed_color;
+    let color = ctx.data().load_full().configuration.general.embed_color;
     ctx.send(|f| {
         f.ephemeral(true)
             .embed(|f| f.description("Stopped the bot.").color(color))
@@ -51,6 +90,217 @@ pub async fn stop(ctx: Context<'_>) -> Result<(), Error> {
 /// Register slash commands.
 #[poise::command(prefix_command, slash_command, ephemeral = true)]
 pub async fn register(ctx: Context<'_>) -> Result<(), Error> {
-    poise::builtins::register_application_commands_buttons(ctx).await?;
+    let data = ctx.data().load_full();
+
+    register_commands(
+        ctx.discord(),
+        &ctx.framework().options().commands,
+        &data.configuration,
+    )
+    .await?;
+
+    ctx.send(|f| {
+        f.embed(|f| {
+            f.description("Successfully registered slash commands.")
+                .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Manage command aliases.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("alias_add", "alias_remove", "alias_list")
+)]
+pub async fn alias(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Add an alias for a command with preset arguments.
+#[poise::command(slash_command, guild_only, rename = "add")]
+pub async fn alias_add(
+    ctx: Context<'_>,
+    #[description = "The name of the alias"] name: String,
+    #[description = "The command to alias"] command: String,
+    #[description = "Preset arguments in the form of key=value;key=value"] arguments: Option<
+        String,
+    >,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let name = name.to_lowercase();
+
+    let alias = Alias {
+        guild_id: Some(guild_id.to_string()),
+        name: Some(name.clone()),
+        command: Some(command.clone()),
+        arguments: Some(parse_arguments(&arguments.unwrap_or_default())),
+    };
+
+    let after = describe_alias(&alias);
+    let description =
+        match register_alias(ctx.discord(), &ctx.framework().options().commands, &alias).await {
+            Ok(_) => {
+                let updated: Document = alias.into();
+                let previous = data
+                    .database
+                    .find_and_update::<Alias>(
+                        "aliases",
+                        Alias {
+                            guild_id: Some(guild_id.to_string()),
+                            name: Some(name.clone()),
+                            ..Default::default()
+                        }
+                        .into(),
+                        UpdateModifications::Document(doc! { "$set": updated }),
+                        Some(FindOneAndUpdateOptions::builder().upsert(true).build()),
+                    )
+                    .await?;
+
+                debug!("{} added the alias {}.", ctx.author().name, name);
+                record_config_change(
+                    &ctx,
+                    &format!("the alias /{}", name),
+                    previous.as_ref().map(describe_alias),
+                    Some(after),
+                )
+                .await;
+                format!("Added alias `/{}` for `/{}`.", name, command)
+            },
+            Err(err) => format!("Failed to add alias `/{}`: {}", name, err),
+        };
+
+    ctx.send(|f| {
+        f.ephemeral(true)
+            .embed(|f| f.description(description).color(embed_color))
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Remove a command alias.
+#[poise::command(slash_command, guild_only, rename = "remove")]
+pub async fn alias_remove(
+    ctx: Context<'_>,
+    #[description = "The name of the alias"] name: String,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let http = &ctx.discord().http;
+
+    let removed = data
+        .database
+        .find_and_delete::<Alias>(
+            "aliases",
+            Alias {
+                guild_id: Some(guild_id.to_string()),
+                name: Some(name.clone()),
+                ..Default::default()
+            }
+            .into(),
+            None,
+        )
+        .await?;
+
+    if let Some(removed) = &removed {
+        record_config_change(
+            &ctx,
+            &format!("the alias /{}", name),
+            Some(describe_alias(removed)),
+            None,
+        )
+        .await;
+
+        if let Some(command) = guild_id
+            .get_application_commands(http)
+            .await?
+            .into_iter()
+            .find(|c| c.name == name)
+        {
+            guild_id
+                .delete_application_command(http, command.id)
+                .await?;
+        }
+    }
+
+    ctx.send(|f| {
+        f.ephemeral(true).embed(|f| {
+            f.description(if removed.is_some() {
+                format!("Removed alias `/{}`.", name)
+            } else {
+                format!("Alias `/{}` does not exist.", name)
+            })
+            .color(embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// List all command aliases.
+#[poise::command(slash_command, guild_only, rename = "list")]
+pub async fn alias_list(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let mut cursor = data
+        .database
+        .find::<Alias>(
+            "aliases",
+            Alias {
+                guild_id: Some(
+                    ctx.guild_id()
+                        .ok_or("This command can only be used in a server")?
+                        .to_string(),
+                ),
+                ..Default::default()
+            }
+            .into(),
+            None,
+        )
+        .await?;
+
+    let mut aliases = Vec::new();
+    while cursor.advance().await? {
+        let alias: Alias = cursor.deserialize_current()?;
+        aliases.push(format!(
+            "`/{}` → `/{}` {}",
+            alias.name.unwrap_or_default(),
+            alias.command.unwrap_or_default(),
+            alias
+                .arguments
+                .unwrap_or_default()
+                .iter()
+                .map(|(key, value)| format!("`{}:{}`", key, value))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ));
+    }
+
+    ctx.send(|f| {
+        f.ephemeral(true).embed(|f| {
+            f.title("Aliases")
+                .description(if aliases.is_empty() {
+                    "No aliases defined.".to_string()
+                } else {
+                    aliases.join("\n")
+                })
+                .color(embed_color)
+        })
+    })
+    .await?;
+
     Ok(())
 }
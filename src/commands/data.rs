@@ -0,0 +1,546 @@
+use std::borrow::Cow;
+
+use bson::{doc, Document};
+use mongodb::options::UpdateModifications;
+use poise::serenity_prelude::{AttachmentType, User};
+use serde_json::json;
+
+use crate::db::model::{
+    Activity,
+    AnnouncementDraft,
+    Appeal,
+    AutomodHit,
+    BetaRequest,
+    Case,
+    ConfigChange,
+    Elevation,
+    Giveaway,
+    GiveawayEntry,
+    InteractionLog,
+    JuryMute,
+    LoggedMessage,
+    Muted,
+    PinRequest,
+    QueuedJoin,
+    QuizScore,
+    Quote,
+    TempBan,
+    Transcript,
+    UserPreferences,
+    VoiceTime,
+    Vouch,
+    Warning,
+    Watchword,
+};
+use crate::error::BotError;
+use crate::utils::moderation::{respond_moderation, ModerationKind};
+use crate::utils::transcript::{messages_of, redact_messages_of};
+use crate::{Context, Error};
+
+// Placeholder for personal data which has been forgotten
+const ANONYMIZED: &str = "anonymized";
+
+/// The filter of transcripts with messages of a user, which are rendered with their tag.
+fn transcripts_of(user: &User) -> Document {
+    doc! { "content": { "$regex": regex::escape(&format!("] {}: ", user.tag())) } }
+}
+
+/// Manage your data stored by the Discord bot.
+#[poise::command(slash_command, subcommands("export"))]
+pub async fn mydata(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Receive all data stored about you as a direct message.
+#[poise::command(slash_command, ephemeral = true)]
+pub async fn export(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let database = &data.database;
+    let user = ctx.author();
+    let user_id = user.id.to_string();
+
+    let mutes = database
+        .collect::<Muted>("muted", doc! { "user_id": &user_id })
+        .await?;
+    let warnings = database
+        .collect::<Warning>("warnings", doc! { "user_id": &user_id })
+        .await?;
+    let quotes = database
+        .collect::<Quote>("quotes", doc! { "author_id": &user_id })
+        .await?;
+    let interactions = database
+        .collect::<InteractionLog>("interactions", doc! { "user_id": &user_id })
+        .await?;
+    let preferences = database
+        .collect::<UserPreferences>("preferences", doc! { "user_id": &user_id })
+        .await?;
+    let quiz_scores = database
+        .collect::<QuizScore>("quiz_scores", doc! { "user_id": &user_id })
+        .await?;
+    let appeals = database
+        .collect::<Appeal>("appeals", doc! { "user_id": &user_id })
+        .await?;
+    let voice_time = database
+        .collect::<VoiceTime>("voice_time", doc! { "user_id": &user_id })
+        .await?;
+    let logged_messages = database
+        .collect::<LoggedMessage>("message_log", doc! { "author_id": &user_id })
+        .await?;
+    let automod_hits = database
+        .collect::<AutomodHit>("automod_hits", doc! { "user_id": &user_id })
+        .await?;
+    let jury_mutes = database
+        .collect::<JuryMute>("jury_mutes", doc! { "user_id": &user_id })
+        .await?;
+    let jury_votes = database
+        .collect::<JuryMute>("jury_mutes", doc! { "voters": &user_id })
+        .await?;
+    let transcripts = database
+        .collect::<Transcript>("transcripts", transcripts_of(user))
+        .await?;
+    let cases = database
+        .collect::<Case>("cases", doc! { "user_id": &user_id })
+        .await?;
+    let watchwords = database
+        .collect::<Watchword>("watchwords", doc! { "user_id": &user_id })
+        .await?;
+    let giveaway_entries = database
+        .collect::<GiveawayEntry>("giveaway_entries", doc! { "user_id": &user_id })
+        .await?;
+    let giveaway_wins = database
+        .collect::<Giveaway>("giveaways", doc! { "winner_ids": &user_id })
+        .await?;
+    let temp_bans = database
+        .collect::<TempBan>("temp_bans", doc! { "user_id": &user_id })
+        .await?;
+    let queued_joins = database
+        .collect::<QueuedJoin>("join_queue", doc! { "user_id": &user_id })
+        .await?;
+    let config_changes = database
+        .collect::<ConfigChange>("config_audit", doc! { "user_id": &user_id })
+        .await?;
+    let announcement_drafts = database
+        .collect::<AnnouncementDraft>("announcement_drafts", doc! { "author_id": &user_id })
+        .await?;
+    let vouches = database
+        .collect::<Vouch>("vouches", doc! {
+            "$or": [{ "user_id": &user_id }, { "voucher_id": &user_id }]
+        })
+        .await?;
+    let elevations = database
+        .collect::<Elevation>("elevations", doc! { "user_id": &user_id })
+        .await?;
+    let elevation_vouches = database
+        .collect::<Elevation>("elevations", doc! { "vouchers": &user_id })
+        .await?;
+    let activity = database
+        .collect::<Activity>("activity", doc! { "user_id": &user_id })
+        .await?;
+    let beta_requests = database
+        .collect::<BetaRequest>("beta_requests", doc! { "user_id": &user_id })
+        .await?;
+    let pin_votes = database
+        .collect::<PinRequest>("pin_requests", doc! { "voters": &user_id })
+        .await?;
+
+    let archive = json!({
+        "user_id": user_id,
+        // Serialized by hand, because the serializer of the models would encrypt the reason again
+        "mutes": mutes.iter().map(|mute| json!({
+            "guild_id": mute.guild_id,
+            "expires": mute.expires,
+            "reason": mute.reason,
+        })).collect::<Vec<_>>(),
+        "warnings": warnings.iter().map(|warning| json!({
+            "guild_id": warning.guild_id,
+            "reason": warning.reason,
+            "timestamp": warning.timestamp,
+        })).collect::<Vec<_>>(),
+        "quotes": quotes,
+        "interactions": interactions,
+        "preferences": preferences,
+        "quiz_scores": quiz_scores,
+        "appeals": appeals.iter().map(|appeal| json!({
+            "guild_id": appeal.guild_id,
+            "action": appeal.action,
+            "content": appeal.content,
+            "status": appeal.status,
+            "timestamp": appeal.timestamp,
+        })).collect::<Vec<_>>(),
+        "voice_time": voice_time,
+        "logged_messages": logged_messages.iter().map(|message| json!({
+            "guild_id": message.guild_id,
+            "channel_id": message.channel_id,
+            "message_id": message.message_id,
+            "event": message.event,
+            "content": message.content,
+            "previous_content": message.previous_content,
+            "attachments": message.attachments,
+            "timestamp": message.logged_at.map(|logged_at| logged_at.timestamp_millis() / 1000),
+        })).collect::<Vec<_>>(),
+        "automod_hits": automod_hits.iter().map(|hit| json!({
+            "guild_id": hit.guild_id,
+            "channel_id": hit.channel_id,
+            "message_id": hit.message_id,
+            "rule": hit.rule,
+            "content": hit.content,
+            "status": hit.status,
+            "timestamp": hit.timestamp,
+        })).collect::<Vec<_>>(),
+        "jury_mutes": jury_mutes.iter().map(|mute| json!({
+            "guild_id": mute.guild_id,
+            "channel_id": mute.channel_id,
+            "message_id": mute.message_id,
+            "content": mute.content,
+            "status": mute.status,
+            "timestamp": mute.timestamp,
+        })).collect::<Vec<_>>(),
+        "jury_votes": jury_votes.iter().map(|mute| json!({
+            "guild_id": mute.guild_id,
+            "channel_id": mute.channel_id,
+            "message_id": mute.message_id,
+            "timestamp": mute.timestamp,
+        })).collect::<Vec<_>>(),
+        // Only the own messages of support threads, the other participants have their own say
+        "transcripts": transcripts.iter().map(|transcript| json!({
+            "guild_id": transcript.guild_id,
+            "thread_id": transcript.thread_id,
+            "title": transcript.title,
+            "messages": messages_of(transcript.content.as_deref().unwrap_or_default(), &user.tag()),
+            "timestamp": transcript.timestamp,
+        })).collect::<Vec<_>>(),
+        // Serialized by hand, because the serializer of the models would encrypt the reason again
+        "cases": cases.iter().map(|case| json!({
+            "guild_id": case.guild_id,
+            "case_id": case.case_id,
+            "action": case.action,
+            "reason": case.reason,
+            "timestamp": case.timestamp,
+        })).collect::<Vec<_>>(),
+        "watchwords": watchwords,
+        "giveaway_entries": giveaway_entries,
+        "giveaway_wins": giveaway_wins.iter().map(|giveaway| json!({
+            "guild_id": giveaway.guild_id,
+            "message_id": giveaway.message_id,
+            "prize": giveaway.prize,
+        })).collect::<Vec<_>>(),
+        "temp_bans": temp_bans,
+        "queued_joins": queued_joins,
+        "config_changes": config_changes.iter().map(|change| json!({
+            "guild_id": change.guild_id,
+            "command": change.command,
+            "subject": change.subject,
+            "timestamp": change.timestamp,
+        })).collect::<Vec<_>>(),
+        "announcement_drafts": announcement_drafts.iter().map(|draft| json!({
+            "guild_id": draft.guild_id,
+            "channel_id": draft.channel_id,
+            "content": draft.content,
+            "status": draft.status,
+            "drafted": draft.drafted,
+        })).collect::<Vec<_>>(),
+        "vouches": vouches,
+        "elevations": elevations.iter().map(|elevation| json!({
+            "guild_id": elevation.guild_id,
+            "status": elevation.status,
+            "opened": elevation.opened,
+            "decided": elevation.decided,
+        })).collect::<Vec<_>>(),
+        "elevation_vouches": elevation_vouches.iter().map(|elevation| json!({
+            "guild_id": elevation.guild_id,
+            "user_id": elevation.user_id,
+            "opened": elevation.opened,
+        })).collect::<Vec<_>>(),
+        "activity": activity,
+        "beta_requests": beta_requests.iter().map(|request| json!({
+            "guild_id": request.guild_id,
+            "status": request.status,
+            "requested": request.requested,
+            "decided": request.decided,
+        })).collect::<Vec<_>>(),
+        "pin_votes": pin_votes.iter().map(|request| json!({
+            "guild_id": request.guild_id,
+            "channel_id": request.channel_id,
+            "message_id": request.message_id,
+            "timestamp": request.timestamp,
+        })).collect::<Vec<_>>(),
+    });
+
+    let description = match user
+        .direct_message(&ctx.discord().http, |m| {
+            m.content("Here is all data stored about you.")
+                .add_file(AttachmentType::Bytes {
+                    data: Cow::from(serde_json::to_vec_pretty(&archive).unwrap()),
+                    filename: format!("{}.json", user.id),
+                })
+        })
+        .await
+    {
+        Ok(_) => "Sent your data as a direct message.",
+        Err(_) => {
+            "Failed to send you a direct message. Please allow direct messages and try again."
+        },
+    };
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description(description)
+                .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Administrative commands.
+#[poise::command(slash_command, subcommands("forget"))]
+pub async fn admin(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Anonymize all personal data of a user while preserving aggregate counts.
+#[poise::command(slash_command)]
+pub async fn forget(
+    ctx: Context<'_>,
+    #[description = "The user to forget"] user: User,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let database = &data.database;
+    let user_id = user.id.to_string();
+
+    let result: Result<(), Error> = async {
+        database
+            .update_many::<Muted>(
+                "muted",
+                doc! { "user_id": &user_id },
+                UpdateModifications::Document(doc! { "$unset": { "reason": "" } }),
+                None,
+            )
+            .await?;
+
+        database
+            .update_many::<Warning>(
+                "warnings",
+                doc! { "user_id": &user_id },
+                UpdateModifications::Document(doc! { "$unset": { "reason": "" } }),
+                None,
+            )
+            .await?;
+
+        database
+            .update_many::<Quote>(
+                "quotes",
+                doc! { "author_id": &user_id },
+                UpdateModifications::Document(doc! {
+                    "$set": { "author_id": ANONYMIZED, "author_tag": ANONYMIZED, "content": "", "attachments": [] }
+                }),
+                None,
+            )
+            .await?;
+
+        database
+            .update_many::<Quote>(
+                "quotes",
+                doc! { "quoted_by": &user_id },
+                UpdateModifications::Document(doc! { "$set": { "quoted_by": ANONYMIZED } }),
+                None,
+            )
+            .await?;
+
+        database
+            .update_many::<InteractionLog>(
+                "interactions",
+                doc! { "user_id": &user_id },
+                UpdateModifications::Document(doc! {
+                    "$set": { "user_id": ANONYMIZED, "arguments": [] }
+                }),
+                None,
+            )
+            .await?;
+
+        database
+            .delete("preferences", doc! { "user_id": &user_id }, None)
+            .await?;
+
+        database
+            .delete_many("quiz_scores", doc! { "user_id": &user_id }, None)
+            .await?;
+
+        database
+            .delete_many("notes", doc! { "user_id": &user_id }, None)
+            .await?;
+
+        database
+            .delete_many("appeals", doc! { "user_id": &user_id }, None)
+            .await?;
+
+        database
+            .delete_many("failures", doc! { "user_id": &user_id }, None)
+            .await?;
+
+        database
+            .delete_many("voice_time", doc! { "user_id": &user_id }, None)
+            .await?;
+
+        database
+            .delete_many("message_log", doc! { "author_id": &user_id }, None)
+            .await?;
+
+        // Hits are kept for the automod statistics
+        database
+            .update_many::<AutomodHit>(
+                "automod_hits",
+                doc! { "user_id": &user_id },
+                UpdateModifications::Document(doc! {
+                    "$set": { "user_id": ANONYMIZED, "content": "" }
+                }),
+                None,
+            )
+            .await?;
+
+        database
+            .update_many::<JuryMute>(
+                "jury_mutes",
+                doc! { "user_id": &user_id },
+                UpdateModifications::Document(doc! {
+                    "$set": { "user_id": ANONYMIZED, "content": "" }
+                }),
+                None,
+            )
+            .await?;
+
+        database
+            .update_many::<JuryMute>(
+                "jury_mutes",
+                doc! { "voters": &user_id },
+                UpdateModifications::Document(doc! { "$pull": { "voters": &user_id } }),
+                None,
+            )
+            .await?;
+
+        for transcript in database
+            .collect::<Transcript>("transcripts", transcripts_of(&user))
+            .await?
+        {
+            let content = redact_messages_of(
+                transcript.content.as_deref().unwrap_or_default(),
+                &user.tag(),
+                ANONYMIZED,
+            );
+            database
+                .update::<Transcript>(
+                    "transcripts",
+                    doc! { "thread_id": transcript.thread_id },
+                    UpdateModifications::Document(doc! { "$set": { "content": content } }),
+                    None,
+                )
+                .await?;
+        }
+
+        database
+            .update_many::<Case>(
+                "cases",
+                doc! { "user_id": &user_id },
+                UpdateModifications::Document(doc! { "$unset": { "reason": "" } }),
+                None,
+            )
+            .await?;
+
+        database
+            .delete_many("watchwords", doc! { "user_id": &user_id }, None)
+            .await?;
+
+        database
+            .delete_many("giveaway_entries", doc! { "user_id": &user_id }, None)
+            .await?;
+
+        database
+            .update_many::<Giveaway>(
+                "giveaways",
+                doc! { "entrants": &user_id },
+                UpdateModifications::Document(doc! { "$pull": { "entrants": &user_id } }),
+                None,
+            )
+            .await?;
+
+        // The amount of winners is kept
+        database
+            .update_many::<Giveaway>(
+                "giveaways",
+                doc! { "winner_ids": &user_id },
+                UpdateModifications::Document(doc! { "$set": { "winner_ids.$": ANONYMIZED } }),
+                None,
+            )
+            .await?;
+
+        database
+            .update_many::<ConfigChange>(
+                "config_audit",
+                doc! { "user_id": &user_id },
+                UpdateModifications::Document(doc! { "$set": { "user_id": ANONYMIZED } }),
+                None,
+            )
+            .await?;
+
+        database
+            .update_many::<AnnouncementDraft>(
+                "announcement_drafts",
+                doc! { "author_id": &user_id },
+                UpdateModifications::Document(doc! { "$set": { "author_id": ANONYMIZED } }),
+                None,
+            )
+            .await?;
+
+        database
+            .delete_many(
+                "vouches",
+                doc! { "$or": [{ "user_id": &user_id }, { "voucher_id": &user_id }] },
+                None,
+            )
+            .await?;
+
+        database
+            .delete_many("elevations", doc! { "user_id": &user_id }, None)
+            .await?;
+
+        database
+            .update_many::<Elevation>(
+                "elevations",
+                doc! { "vouchers": &user_id },
+                UpdateModifications::Document(doc! { "$pull": { "vouchers": &user_id } }),
+                None,
+            )
+            .await?;
+
+        database
+            .delete_many("activity", doc! { "user_id": &user_id }, None)
+            .await?;
+
+        database
+            .delete_many("beta_requests", doc! { "user_id": &user_id }, None)
+            .await?;
+
+        database
+            .update_many::<PinRequest>(
+                "pin_requests",
+                doc! { "voters": &user_id },
+                UpdateModifications::Document(doc! { "$pull": { "voters": &user_id } }),
+                None,
+            )
+            .await?;
+
+        // Pending temporary bans and queued joins only hold the id of the user and are removed
+        // once they are lifted or admitted, which forgetting the user must not prevent
+        Ok(())
+    }
+    .await;
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::Forget(user, result.err().map(BotError::Database)),
+        &data.configuration,
+        None,
+    )
+    .await
+}
@@ -0,0 +1,54 @@
+use std::cmp::Reverse;
+
+use bson::doc;
+
+use crate::db::model::Failure;
+use crate::utils::failures::{failure_buttons, failure_embed};
+use crate::{Context, Error};
+
+// Every failure is listed in its own message, so each can be resolved on its own
+const MAX_LISTED: usize = 10;
+
+/// Resolve moderation operations which failed in the background.
+#[poise::command(slash_command, guild_only, subcommands("failures_list"))]
+pub async fn failures(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// List the most recent failed operations, which can be retried or discarded.
+#[poise::command(slash_command, guild_only, ephemeral = true, rename = "list")]
+pub async fn failures_list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let mut failures = data
+        .database
+        .collect::<Failure>("failures", doc! { "guild_id": guild_id.to_string() })
+        .await?;
+    failures.sort_by_key(|f| Reverse(f.timestamp));
+
+    if failures.is_empty() {
+        ctx.send(|f| {
+            f.embed(|e| {
+                e.title("Failures")
+                    .description("No operations failed.")
+                    .color(embed_color)
+            })
+        })
+        .await?;
+        return Ok(());
+    }
+
+    for failure in failures.iter().take(MAX_LISTED) {
+        ctx.send(|f| {
+            f.embed(|e| failure_embed(e, failure, embed_color))
+                .components(|c| failure_buttons(c, failure))
+        })
+        .await?;
+    }
+
+    Ok(())
+}
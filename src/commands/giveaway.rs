@@ -0,0 +1,189 @@
+use bson::doc;
+use chrono::Utc;
+use mongodb::options::UpdateModifications;
+use poise::serenity_prelude as serenity;
+
+use crate::db::model::Giveaway;
+use crate::utils::giveaway::{
+    announce_winners,
+    end_giveaway,
+    entry_button,
+    pick_winners,
+    schedule_end,
+};
+use crate::utils::time::{format_timestamp, parse_duration};
+use crate::{Context, Error};
+
+/// Manage giveaways.
+#[poise::command(slash_command, guild_only, subcommands("start", "end", "reroll"))]
+pub async fn giveaway(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Start a giveaway in the current channel.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn start(
+    ctx: Context<'_>,
+    #[description = "The prize to give away"] prize: String,
+    #[description = "How long the giveaway runs, such as 1d12h"] duration: String,
+    #[min = 1]
+    #[max = 20]
+    #[description = "The amount of winners, defaults to 1"]
+    winners: Option<i32>,
+    #[description = "The role required to enter"] required_role: Option<serenity::Role>,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let duration = parse_duration(&duration).ok_or("Invalid duration")?;
+    let winners = winners.unwrap_or(1);
+    let expires = (Utc::now() + duration).timestamp();
+    let embed_color = ctx.data().load_full().configuration.general.embed_color;
+
+    let mut message = ctx
+        .channel_id()
+        .send_message(&ctx.discord().http, |m| {
+            m.embed(|e| {
+                e.title(format!("Giveaway: {}", prize))
+                    .description("Press the button below to enter.")
+                    .field("Ends", format_timestamp(expires), false)
+                    .field("Winners", winners.to_string(), true)
+                    .field("Hosted by", format!("<@{}>", ctx.author().id), true)
+                    .color(embed_color);
+
+                if let Some(role) = &required_role {
+                    e.field("Required role", format!("<@&{}>", role.id), true);
+                }
+
+                e
+            })
+        })
+        .await?;
+
+    // The custom id of the button refers to the message, so it can only be added afterwards
+    let message_id = message.id;
+    message
+        .edit(&ctx.discord().http, |m| {
+            m.components(|c| entry_button(c, message_id))
+        })
+        .await?;
+
+    ctx.data()
+        .load()
+        .database
+        .insert::<Giveaway>(
+            "giveaways",
+            Giveaway {
+                message_id: Some(message_id.to_string()),
+                channel_id: Some(ctx.channel_id().to_string()),
+                guild_id: Some(guild_id.to_string()),
+                host_id: Some(ctx.author().id.to_string()),
+                prize: Some(prize),
+                winners: Some(winners),
+                required_role: required_role.map(|r| r.id.to_string()),
+                expires: Some(expires),
+                entrants: Some(Vec::new()),
+                ended: Some(false),
+                ..Default::default()
+            },
+            None,
+        )
+        .await?;
+
+    schedule_end(
+        ctx.discord(),
+        message_id.to_string(),
+        duration.num_seconds().max(0) as u64,
+    );
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description(format!(
+                "Started the giveaway. It ends {}.",
+                format_timestamp(expires)
+            ))
+            .color(embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// End a giveaway early.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn end(
+    ctx: Context<'_>,
+    #[description = "The message id of the giveaway"] message_id: String,
+) -> Result<(), Error> {
+    end_giveaway(ctx.discord(), &message_id).await?;
+
+    let embed_color = ctx.data().load_full().configuration.general.embed_color;
+    ctx.send(|f| f.embed(|e| e.description("Ended the giveaway.").color(embed_color)))
+        .await?;
+
+    Ok(())
+}
+
+/// Draw new winners for an ended giveaway.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn reroll(
+    ctx: Context<'_>,
+    #[description = "The message id of the giveaway"] message_id: String,
+    #[min = 1]
+    #[max = 20]
+    #[description = "The amount of new winners, defaults to 1"]
+    winners: Option<usize>,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let mut cursor = data
+        .database
+        .find::<Giveaway>("giveaways", doc! { "message_id": &message_id }, None)
+        .await?;
+    let giveaway: Giveaway = if cursor.advance().await? {
+        cursor.deserialize_current()?
+    } else {
+        return Err("This giveaway does not exist".into());
+    };
+
+    if !giveaway.ended.unwrap_or_default() {
+        return Err("This giveaway has not ended yet".into());
+    }
+
+    // Previous winners can not win again
+    let previous = giveaway.winner_ids.clone().unwrap_or_default();
+    let new_winners = pick_winners(
+        giveaway.entrants.as_deref().unwrap_or_default(),
+        &previous,
+        winners.unwrap_or(1),
+    );
+    if new_winners.is_empty() {
+        return Err("There are no entrants left to draw from".into());
+    }
+
+    data.database
+        .update::<Giveaway>(
+            "giveaways",
+            doc! { "message_id": &message_id },
+            UpdateModifications::Document(doc! {
+                "$push": { "winner_ids": { "$each": &new_winners } }
+            }),
+            None,
+        )
+        .await?;
+    drop(data);
+
+    announce_winners(ctx.discord(), &giveaway, &new_winners, true).await?;
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description(format!("Drew {} new winners.", new_winners.len()))
+                .color(embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,275 @@
+use std::cmp::Reverse;
+
+use bson::doc;
+use chrono::Utc;
+use poise::serenity_prelude::{ChannelId, CreateEmbed, GuildId, User};
+use tracing::{debug, error};
+
+use crate::db::database::Database;
+use crate::db::model::{Case, Note};
+use crate::model::application::{Configuration, LogCategory};
+use crate::utils::cases::increment_counter;
+use crate::utils::paginator::Paginated;
+use crate::utils::time::format_timestamp;
+use crate::{Context, Error};
+
+/// Show all past moderation actions and notes of a user, newest first.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn history(
+    ctx: Context<'_>,
+    #[description = "The user to show the history of"] user: User,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+
+    let cases = data
+        .database
+        .collect::<Case>("cases", doc! {
+            "guild_id": guild_id.to_string(),
+            "user_id": user.id.to_string(),
+        })
+        .await?;
+
+    let mut entries = cases
+        .into_iter()
+        .map(|case| {
+            let timestamp = case.timestamp.unwrap_or_default();
+            (
+                timestamp,
+                (
+                    format!(
+                        "Case #{}: {}",
+                        case.case_id.unwrap_or_default(),
+                        case.action.unwrap_or_default()
+                    ),
+                    format!(
+                        "{}\nBy <@{}> {}",
+                        case.reason.unwrap_or_else(|| "None specified".to_string()),
+                        case.moderator_id.unwrap_or_default(),
+                        format_timestamp(timestamp)
+                    ),
+                ),
+            )
+        })
+        .chain(user_notes(&data.database, guild_id, &user).await?)
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|(timestamp, _)| Reverse(*timestamp));
+    let fields = entries.into_iter().map(|(_, field)| field).collect();
+
+    Paginated {
+        title: format!("History of {}", user.tag()),
+        thumbnail: Some(user.face()),
+        empty: "No moderation actions were recorded.".to_string(),
+        fields,
+        color: data.configuration.general.embed_color,
+    }
+    .send(ctx)
+    .await
+}
+
+/// Keep private notes about users, which are only shown to staff.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("note_add", "note_list", "note_remove")
+)]
+pub async fn note(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Add a note about a user, without taking any action.
+#[poise::command(slash_command, guild_only, ephemeral = true, rename = "add")]
+pub async fn note_add(
+    ctx: Context<'_>,
+    #[description = "The user to add a note about"] user: User,
+    #[description = "The note"] content: String,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let note_id = increment_counter(&data.database, &format!("notes:{}", guild_id)).await?;
+    data.database
+        .insert::<Note>(
+            "notes",
+            Note {
+                guild_id: Some(guild_id.to_string()),
+                note_id: Some(note_id),
+                user_id: Some(user.id.to_string()),
+                moderator_id: Some(ctx.author().id.to_string()),
+                content: Some(content.clone()),
+                timestamp: Some(Utc::now().timestamp()),
+            },
+            None,
+        )
+        .await?;
+
+    debug!(
+        "{} added note {} about {}",
+        ctx.author().name,
+        note_id,
+        user.tag()
+    );
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description(format!("Added note #{} about <@{}>.", note_id, user.id))
+                .color(embed_color)
+        })
+    })
+    .await?;
+
+    log_note(&ctx, &data.configuration, |e| {
+        e.title(format!("Note #{} added about {}", note_id, user.tag()))
+            .description(&content)
+            .field("Moderator", format!("<@{}>", ctx.author().id), true)
+            .thumbnail(user.face())
+    })
+    .await;
+
+    Ok(())
+}
+
+/// List the notes about a user.
+#[poise::command(slash_command, guild_only, ephemeral = true, rename = "list")]
+pub async fn note_list(
+    ctx: Context<'_>,
+    #[description = "The user to list the notes about"] user: User,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+
+    let fields = user_notes(&data.database, guild_id, &user)
+        .await?
+        .into_iter()
+        .map(|(_, field)| field)
+        .collect();
+
+    Paginated {
+        title: format!("Notes about {}", user.tag()),
+        thumbnail: Some(user.face()),
+        empty: "There are no notes about this user.".to_string(),
+        fields,
+        color: data.configuration.general.embed_color,
+    }
+    .send(ctx)
+    .await
+}
+
+/// Remove a note.
+#[poise::command(slash_command, guild_only, ephemeral = true, rename = "remove")]
+pub async fn note_remove(
+    ctx: Context<'_>,
+    #[min = 1]
+    #[description = "The number of the note"]
+    id: i64,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let removed = data
+        .database
+        .find_and_delete::<Note>(
+            "notes",
+            doc! { "guild_id": guild_id.to_string(), "note_id": id },
+            None,
+        )
+        .await?;
+
+    let removed = match removed {
+        Some(removed) => removed,
+        None => {
+            ctx.send(|f| {
+                f.embed(|e| {
+                    e.description(format!("Note #{} does not exist.", id))
+                        .color(embed_color)
+                })
+            })
+            .await?;
+            return Ok(());
+        },
+    };
+
+    debug!("{} removed note {}", ctx.author().name, id);
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description(format!("Removed note #{}.", id))
+                .color(embed_color)
+        })
+    })
+    .await?;
+
+    log_note(&ctx, &data.configuration, |e| {
+        e.title(format!("Note #{} removed", id))
+            .description(removed.content.unwrap_or_default())
+            .field(
+                "User",
+                format!("<@{}>", removed.user_id.unwrap_or_default()),
+                true,
+            )
+            .field("Moderator", format!("<@{}>", ctx.author().id), true)
+    })
+    .await;
+
+    Ok(())
+}
+
+/// The notes about a user as timestamps and fields, newest first.
+async fn user_notes(
+    database: &Database,
+    guild_id: GuildId,
+    user: &User,
+) -> Result<Vec<(i64, (String, String))>, Error> {
+    let mut notes = database
+        .collect::<Note>("notes", doc! {
+            "guild_id": guild_id.to_string(),
+            "user_id": user.id.to_string(),
+        })
+        .await?;
+    notes.sort_by_key(|n| Reverse(n.timestamp));
+
+    Ok(notes
+        .into_iter()
+        .map(|note| {
+            let timestamp = note.timestamp.unwrap_or_default();
+            (
+                timestamp,
+                (
+                    format!("Note #{}", note.note_id.unwrap_or_default()),
+                    format!(
+                        "{}\nBy <@{}> {}",
+                        note.content.unwrap_or_default(),
+                        note.moderator_id.unwrap_or_default(),
+                        format_timestamp(timestamp)
+                    ),
+                ),
+            )
+        })
+        .collect())
+}
+
+/// Mirror a change of notes to the moderation log, the only other place notes are shown.
+async fn log_note(
+    ctx: &Context<'_>,
+    configuration: &Configuration,
+    build: impl FnOnce(&mut CreateEmbed) -> &mut CreateEmbed,
+) {
+    if let Err(err) = ChannelId(configuration.log_channel(LogCategory::Moderation))
+        .send_message(&ctx.discord().http, |m| {
+            m.embed(|e| build(e.color(configuration.general.embed_color)))
+        })
+        .await
+    {
+        error!("Failed to log note: {:?}", err);
+    }
+}
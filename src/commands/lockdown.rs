@@ -0,0 +1,53 @@
+use tracing::info;
+
+use crate::utils::lockdown::{end_lockdown, start_lockdown};
+use crate::utils::moderation::{respond_moderation, ModerationKind};
+use crate::{Context, Error};
+
+/// Lock or unlock many channels at once.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("lockdown_start", "lockdown_end")
+)]
+pub async fn lockdown(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Lock the channels of the lockdown category and the configured channels.
+#[poise::command(slash_command, guild_only, rename = "start")]
+pub async fn lockdown_start(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+
+    ctx.defer().await?;
+
+    info!("{} started a lockdown in {}", ctx.author().tag(), guild_id);
+
+    let kind = match start_lockdown(ctx.discord(), guild_id, ctx.author().id).await {
+        Ok(result) => ModerationKind::Lockdown(result.succeeded, result.failures, None),
+        Err(err) => ModerationKind::Lockdown(Vec::new(), Vec::new(), Some(err)),
+    };
+
+    respond_moderation(&ctx, &kind, &ctx.data().load_full().configuration, None).await
+}
+
+/// Unlock the channels locked by the lockdown.
+#[poise::command(slash_command, guild_only, rename = "end")]
+pub async fn lockdown_end(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+
+    ctx.defer().await?;
+
+    info!("{} ended the lockdown in {}", ctx.author().tag(), guild_id);
+
+    let kind = match end_lockdown(ctx.discord(), guild_id).await {
+        Ok(result) => ModerationKind::LockdownEnd(result.succeeded, result.failures, None),
+        Err(err) => ModerationKind::LockdownEnd(Vec::new(), Vec::new(), Some(err)),
+    };
+
+    respond_moderation(&ctx, &kind, &ctx.data().load_full().configuration, None).await
+}
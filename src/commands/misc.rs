@@ -1,6 +1,16 @@
-use poise::serenity_prelude::{self as serenity, MessageId};
+use std::borrow::Cow;
+
+use bson::{doc, Document};
+use mongodb::options::{UpdateModifications, UpdateOptions};
+use poise::serenity_prelude::{self as serenity, AttachmentType, MessageId, PremiumTier, ShardId};
 use poise::ReplyHandle;
+use tracing::error;
 
+use crate::db::model::Quote;
+use crate::utils::member_cache;
+use crate::utils::mention_guard::request_ping;
+use crate::utils::process::memory_usage;
+use crate::utils::storage::{upload, upload_limit};
 use crate::{Context, Error};
 
 /// Make the Discord bot sentient.
@@ -44,3 +54,242 @@ async fn send_ephermal<'a>(
 ) -> Result<ReplyHandle<'a>, serenity::Error> {
     ctx.send(|f| f.ephemeral(true).content(content)).await
 }
+
+/// Quote a message.
+#[poise::command(context_menu_command = "Quote", guild_only)]
+pub async fn quote(
+    ctx: Context<'_>,
+    #[description = "The message to quote"] message: serenity::Message,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let configuration = &data.configuration;
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+
+    let upload_limit = ctx.guild().map_or(upload_limit(PremiumTier::Tier0), |g| {
+        upload_limit(g.premium_tier)
+    });
+
+    // Reupload attachments, so they can still be viewed after the original was deleted
+    let mut files = Vec::new();
+    let mut links = Vec::new();
+    for attachment in &message.attachments {
+        if attachment.size <= upload_limit {
+            match attachment.download().await {
+                Ok(bytes) => {
+                    files.push(AttachmentType::Bytes {
+                        data: Cow::from(bytes),
+                        filename: attachment.filename.clone(),
+                    });
+                    continue;
+                },
+                Err(err) => error!("Failed to download {}: {:?}", attachment.filename, err),
+            }
+        }
+
+        // Too large for Discord, upload to the external storage instead
+        let link = match &configuration.storage {
+            Some(storage) => match upload(storage, attachment).await {
+                Ok(url) => url,
+                Err(err) => {
+                    error!("Failed to upload {}: {:?}", attachment.filename, err);
+                    attachment.url.clone()
+                },
+            },
+            None => attachment.url.clone(),
+        };
+        links.push(format!("[{}]({})", attachment.filename, link));
+    }
+
+    let jump_link = format!(
+        "https://discord.com/channels/{}/{}/{}",
+        guild_id.0, message.channel_id, message.id
+    );
+
+    let handle = ctx
+        .send(|f| {
+            for file in files {
+                f.attachment(file);
+            }
+
+            f.embed(|e| {
+                e.author(|a| a.name(message.author.tag()).icon_url(message.author.face()))
+                    .description(&message.content)
+                    .field("Source", format!("[Jump to message]({})", jump_link), false)
+                    .footer(|f| f.text(format!("Quoted by {}", ctx.author().tag())))
+                    .timestamp(message.timestamp)
+                    .color(configuration.general.embed_color);
+
+                if !links.is_empty() {
+                    e.field("Attachments", links.join("\n"), false);
+                }
+                e
+            })
+        })
+        .await?;
+    let reply = handle.message().await?;
+
+    let attachments = reply
+        .attachments
+        .iter()
+        .map(|a| a.url.clone())
+        .chain(links.iter().cloned())
+        .collect::<Vec<_>>();
+
+    // Archive the quote so it can still be read after the original was deleted
+    let updated: Document = Quote {
+        channel_id: Some(message.channel_id.to_string()),
+        guild_id: Some(guild_id.to_string()),
+        author_id: Some(message.author.id.to_string()),
+        author_tag: Some(message.author.tag()),
+        content: Some(message.content.clone()),
+        attachments: Some(attachments),
+        quoted_by: Some(ctx.author().id.to_string()),
+        timestamp: Some(message.timestamp.unix_timestamp()),
+        ..Default::default()
+    }
+    .into();
+
+    data.database
+        .update::<Quote>(
+            "quotes",
+            Quote {
+                message_id: Some(message.id.to_string()),
+                ..Default::default()
+            }
+            .into(),
+            UpdateModifications::Document(doc! { "$set": updated }),
+            Some(UpdateOptions::builder().upsert(true).build()),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Show the status of the bot.
+#[poise::command(slash_command)]
+pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
+    let (embed_color, uptime, database, scheduled_tasks) = {
+        let data = ctx.data().load_full();
+        let scheduled_tasks = data
+            .pending_unmutes
+            .lock()
+            .await
+            .values()
+            .filter(|task| !task.is_finished())
+            .count();
+        (
+            data.configuration.general.embed_color,
+            data.start_time.elapsed(),
+            data.database.clone(),
+            scheduled_tasks,
+        )
+    };
+
+    let gateway_latency = {
+        let shard_manager = ctx.framework().shard_manager();
+        let shard_manager = shard_manager.lock().await;
+        let runners = shard_manager.runners.lock().await;
+        runners
+            .get(&ShardId(ctx.discord().shard_id))
+            .and_then(|runner| runner.latency)
+    };
+    let database_latency = database.ping().await;
+
+    let cache = &ctx.discord().cache;
+    let (member_hits, member_misses) = member_cache::stats();
+    let uptime = uptime.as_secs();
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title("Status")
+                .field(
+                    "Uptime",
+                    format!(
+                        "{}d {}h {}m {}s",
+                        uptime / 86400,
+                        uptime / 3600 % 24,
+                        uptime / 60 % 60,
+                        uptime % 60
+                    ),
+                    true,
+                )
+                .field(
+                    "Gateway latency",
+                    gateway_latency.map_or("Unknown".to_string(), |latency| {
+                        format!("{} ms", latency.as_millis())
+                    }),
+                    true,
+                )
+                .field(
+                    "Database latency",
+                    match database_latency {
+                        Ok(latency) => format!("{} ms", latency.as_millis()),
+                        Err(err) => format!("Unavailable: {}", err),
+                    },
+                    true,
+                )
+                .field("Scheduled tasks", scheduled_tasks.to_string(), true)
+                .field(
+                    "Cache",
+                    format!(
+                        "{} guilds, {} channels, {} users",
+                        cache.guild_count(),
+                        cache.guild_channel_count(),
+                        cache.user_count()
+                    ),
+                    true,
+                )
+                .field(
+                    "Member cache",
+                    format!(
+                        "{} hits, {} misses ({:.0}% hit rate)",
+                        member_hits,
+                        member_misses,
+                        member_hits as f64 / (member_hits + member_misses).max(1) as f64 * 100.0
+                    ),
+                    true,
+                )
+                .field(
+                    "Memory usage",
+                    memory_usage().map_or("Unknown".to_string(), |bytes| {
+                        format!("{:.1} MiB", bytes as f64 / 1024.0 / 1024.0)
+                    }),
+                    true,
+                )
+                .color(embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Ask staff to mention a role for you.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn pingrequest(
+    ctx: Context<'_>,
+    #[description = "The role to mention"] role: serenity::Role,
+    #[description = "The message to send with the mention"] message: String,
+) -> Result<(), Error> {
+    let embed_color = ctx.data().load_full().configuration.general.embed_color;
+
+    let description = match request_ping(
+        ctx.discord(),
+        ctx.channel_id(),
+        ctx.author(),
+        &role,
+        &message,
+    )
+    .await
+    {
+        Ok(_) => "Sent your ping request to staff.".to_string(),
+        Err(err) => err.to_string(),
+    };
+
+    ctx.send(|f| f.embed(|e| e.description(description).color(embed_color)))
+        .await?;
+
+    Ok(())
+}
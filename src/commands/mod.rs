@@ -1,4 +1,30 @@
+pub mod announce;
+pub mod archive;
+pub mod audit;
+pub mod automod;
+pub mod beta;
+pub mod case;
 pub mod configuration;
+pub mod data;
+pub mod failures;
+pub mod giveaway;
+pub mod history;
+pub mod lockdown;
 pub mod misc;
 pub mod moderation;
+pub mod monitor;
+pub mod panic;
+pub mod preferences;
+pub mod quiz;
+pub mod reactions;
+pub mod report;
+pub mod role;
+pub mod rules;
+pub mod search;
+pub mod setup;
+pub mod strikes;
+pub mod support;
 pub mod utils;
+pub mod voicetime;
+pub mod vouch;
+pub mod watchword;
@@ -1,31 +1,101 @@
+use std::cmp;
+use std::sync::Arc;
+
 use bson::{doc, Document};
-use chrono::{Duration, Utc};
-use mongodb::options::{UpdateModifications, UpdateOptions};
+use chrono::{Duration, TimeZone, Utc};
+use mongodb::options::{FindOptions, UpdateModifications, UpdateOptions};
 use poise::serenity_prelude::{
     self as serenity,
+    ButtonStyle,
+    GuildId,
     Member,
     PermissionOverwrite,
+    PermissionOverwriteType,
     Permissions,
     RoleId,
     User,
 };
+use regex::Regex;
 use tracing::log::error;
-use tracing::{debug, trace};
+use tracing::{debug, info, trace};
 
-use crate::db::model::{LockedChannel, Muted};
+use crate::db::database::Database;
+use crate::db::model::{InvitePause, LockedChannel, Muted, SlowmodeReset, TempBan, Warning};
+use crate::error::BotError;
+use crate::model::application::StrikeAction;
+use crate::utils::cases::{
+    ban_reason,
+    next_case_id,
+    open_case,
+    record_approver,
+    record_case,
+    CaseTarget,
+};
+use crate::utils::dry_run;
+use crate::utils::interaction::update_with_decision;
+use crate::utils::long_running::LongRunning;
+use crate::utils::member_cache::fetch_member;
 use crate::utils::moderation::{
+    await_ban_approval,
+    ban_approval_buttons,
+    ban_approval_days,
+    ban_approval_embed,
     ban_moderation,
+    ban_with_audit_reason,
+    can_moderate,
+    create_permission_with_audit_reason,
+    delete_permission_with_audit_reason,
+    disconnect_with_audit_reason,
+    lock_channel,
+    mute_member,
+    parse_user_id,
+    parse_user_ids,
+    queue_invite_resume,
+    queue_slowmode_reset,
+    queue_unban,
     queue_unmute_member,
+    remove_timeout,
+    resolve_user,
     respond_moderation,
+    set_invites_paused,
+    set_slowmode,
+    timeout_member,
+    unlock_channel,
+    voice_mute_with_audit_reason,
+    BanApprovalDecision,
     BanKind,
     ModerationKind,
 };
-use crate::{Context, Error};
+use crate::utils::notification::{notify_user, Notification};
+use crate::utils::strikes::{active_strikes, describe, points_for, step_for, total_points};
+use crate::utils::time::{format_local_timestamp, format_timestamp, parse_duration, user_timezone};
+use crate::{Context, Data, Error};
+
+/// Suggest the preset reasons containing what was typed, so reasons are worded consistently.
+async fn autocomplete_reason(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<poise::AutocompleteChoice<String>> {
+    let partial = partial.to_lowercase();
+
+    ctx.data()
+        .load()
+        .configuration
+        .preset_reasons
+        .iter()
+        .filter(|r| r.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|r| poise::AutocompleteChoice {
+            name: r.clone(),
+            value: r.clone(),
+        })
+        .collect()
+}
 
 /// Lock a channel.
-#[poise::command(slash_command)]
+#[poise::command(slash_command, guild_only)]
 pub async fn lock(ctx: Context<'_>) -> Result<(), Error> {
-    let data = &ctx.data().read().await;
+    let data = &ctx.data().load_full();
     let configuration = &data.configuration;
     let database = &data.database;
     let discord = &ctx.discord();
@@ -35,47 +105,123 @@ pub async fn lock(ctx: Context<'_>) -> Result<(), Error> {
     let channel_id = ctx.channel_id().0;
     let channel = &cache.guild_channel(channel_id).unwrap();
 
+    let error = lock_channel(http, database, channel).await;
+
+    let case_id = if error.is_none() {
+        open_case(
+            database,
+            channel.guild_id,
+            "lock",
+            CaseTarget::Channel(channel.id),
+            ctx.author().id,
+            None,
+        )
+        .await
+    } else {
+        None
+    };
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::Lock(channel.name.clone(), error),
+        configuration,
+        case_id,
+    )
+    .await
+}
+
+/// Unlock a channel.
+#[poise::command(slash_command, guild_only)]
+pub async fn unlock(ctx: Context<'_>) -> Result<(), Error> {
+    let data = &ctx.data().load_full();
+    let configuration = &data.configuration;
+    let database = &data.database;
+    let discord = &ctx.discord();
+    let cache = &discord.cache;
+    let http = &discord.http;
+
+    let channel = cache.guild_channel(ctx.channel_id()).unwrap();
+    let error = unlock_channel(http, database, &channel).await;
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::Unlock(channel.name.clone(), error),
+        configuration,
+        None,
+    )
+    .await
+}
+
+/// Lock a voice channel.
+#[poise::command(slash_command, guild_only)]
+pub async fn lockvc(
+    ctx: Context<'_>,
+    #[description = "The voice channel to lock"]
+    #[channel_types("Voice")]
+    channel: serenity::GuildChannel,
+    #[description = "Disconnect members who are not exempt"] disconnect: Option<bool>,
+) -> Result<(), Error> {
+    let data = &ctx.data().load_full();
+    let configuration = &data.configuration;
+    let database = &data.database;
+    let discord = &ctx.discord();
+    let http = &discord.http;
+    let guild_id = channel.guild_id;
+
     let query: Document = LockedChannel {
-        channel_id: Some(channel_id.to_string()),
+        channel_id: Some(channel.id.to_string()),
         ..Default::default()
     }
     .into();
 
-    // Check if channel is already muted, if so succeed.
-    if let Ok(mut cursor) = database
+    // Check if channel is already locked, if so succeed.
+    if database
         .find::<LockedChannel>("locked", query.clone(), None)
-        .await
+        .await?
+        .advance()
+        .await?
     {
-        if cursor.advance().await.unwrap() {
-            respond_moderation(
-                &ctx,
-                &ModerationKind::Lock(
-                    channel.name.clone(),
-                    Some(Error::from("Channel already locked")),
-                ),
-                configuration,
-            )
-            .await?;
-            return Ok(());
-        }
+        respond_moderation(
+            &ctx,
+            &ModerationKind::LockVoice(
+                channel.name.clone(),
+                0,
+                Some(BotError::Conflict("Channel already locked".to_string())),
+            ),
+            configuration,
+            None,
+        )
+        .await?;
+        return Ok(());
     }
 
-    // accumulate all roles with write permissions
-    let permission_overwrites: Vec<_> = channel
+    // the @everyone role shares its id with the guild
+    let everyone = RoleId(guild_id.0);
+    let everyone_overwrite = channel
         .permission_overwrites
         .iter()
-        .filter_map(|r| {
-            if r.allow.send_messages() || !r.deny.send_messages() {
-                Some(r.clone())
-            } else {
-                None
-            }
-        })
-        .collect();
+        .find(|o| o.kind == PermissionOverwriteType::Role(everyone));
+
+    dry_run::execute(
+        format!("lock {}", channel.name),
+        create_permission_with_audit_reason(
+            http,
+            channel.id,
+            &PermissionOverwrite {
+                allow: everyone_overwrite.map_or(Permissions::empty(), |o| o.allow)
+                    & !Permissions::CONNECT,
+                deny: everyone_overwrite.map_or(Permissions::empty(), |o| o.deny)
+                    | Permissions::CONNECT,
+                kind: PermissionOverwriteType::Role(everyone),
+            },
+            "Voice channel locked",
+        ),
+    )
+    .await?;
 
-    // save the original overwrites
+    // save the original overwrites once the channel is locked, so a failed lock is not recorded
     let updated: Document = LockedChannel {
-        overwrites: Some(permission_overwrites.clone()),
+        overwrites: Some(channel.permission_overwrites.clone()),
         ..Default::default()
     }
     .into();
@@ -89,47 +235,82 @@ pub async fn lock(ctx: Context<'_>) -> Result<(), Error> {
         )
         .await?;
 
-    // lock the channel by and creating the new permission overwrite
-    for permission_overwrite in &permission_overwrites {
-        let permission = Permissions::SEND_MESSAGES & Permissions::ADD_REACTIONS;
-
-        if let Err(err) = channel
-            .create_permission(http, &PermissionOverwrite {
-                allow: permission_overwrite.allow & !permission,
-                deny: permission_overwrite.deny | permission,
-                kind: permission_overwrite.kind,
+    let mut disconnected = 0;
+    if disconnect.unwrap_or(false) {
+        let administrators = &configuration.administrators;
+        let connected = discord
+            .cache
+            .guild(guild_id)
+            .map(|guild| {
+                guild
+                    .voice_states
+                    .values()
+                    .filter(|state| state.channel_id == Some(channel.id))
+                    .filter_map(|state| state.member.clone())
+                    .collect::<Vec<_>>()
             })
-            .await
-        {
-            error!("Failed to create the new permission: {:?}", err);
+            .unwrap_or_default();
+
+        for member in connected {
+            let is_exempt = member.user.bot
+                || administrators.users.contains(&member.user.id.0)
+                || member
+                    .roles
+                    .iter()
+                    .any(|role| administrators.roles.contains(&role.0));
+
+            if is_exempt {
+                continue;
+            }
+
+            if dry_run::is_enabled() {
+                info!("Dry run: disconnect {}", member.user.tag());
+                disconnected += 1;
+            } else if let Err(err) = guild_id.disconnect_member(http, member.user.id).await {
+                error!("Failed to disconnect {}: {:?}", member.user.tag(), err);
+            } else {
+                disconnected += 1;
+            }
         }
     }
 
+    let case_id = open_case(
+        database,
+        guild_id,
+        "lock",
+        CaseTarget::Channel(channel.id),
+        ctx.author().id,
+        None,
+    )
+    .await;
+
     respond_moderation(
         &ctx,
-        &ModerationKind::Lock(channel.name.clone(), None),
+        &ModerationKind::LockVoice(channel.name.clone(), disconnected, None),
         configuration,
+        case_id,
     )
     .await
 }
 
-/// Unlock a channel.
-#[poise::command(slash_command)]
-pub async fn unlock(ctx: Context<'_>) -> Result<(), Error> {
-    let data = &ctx.data().read().await;
+/// Unlock a voice channel.
+#[poise::command(slash_command, guild_only)]
+pub async fn unlockvc(
+    ctx: Context<'_>,
+    #[description = "The voice channel to unlock"]
+    #[channel_types("Voice")]
+    channel: serenity::GuildChannel,
+) -> Result<(), Error> {
+    let data = &ctx.data().load_full();
     let configuration = &data.configuration;
     let database = &data.database;
-    let discord = &ctx.discord();
-    let cache = &discord.cache;
-    let http = &discord.http;
-
-    let channel_id = ctx.channel_id().0;
+    let http = &ctx.discord().http;
 
     let delete_result = database
         .find_and_delete::<LockedChannel>(
             "locked",
             LockedChannel {
-                channel_id: Some(channel_id.to_string()),
+                channel_id: Some(channel.id.to_string()),
                 ..Default::default()
             }
             .into(),
@@ -137,61 +318,271 @@ pub async fn unlock(ctx: Context<'_>) -> Result<(), Error> {
         )
         .await;
 
-    let channel = cache.guild_channel(channel_id).unwrap();
     let mut error = None;
     if let Ok(Some(locked_channel)) = delete_result {
-        for overwrite in &locked_channel.overwrites.unwrap() {
-            channel.create_permission(http, overwrite).await?;
+        let overwrites = locked_channel.overwrites.unwrap_or_default();
+        let everyone = PermissionOverwriteType::Role(RoleId(channel.guild_id.0));
+
+        // the @everyone overwrite may not have existed before locking
+        if !overwrites.iter().any(|o| o.kind == everyone) {
+            dry_run::execute(
+                format!("unlock {}", channel.name),
+                delete_permission_with_audit_reason(
+                    http,
+                    channel.id,
+                    everyone,
+                    "Voice channel unlocked",
+                ),
+            )
+            .await?;
+        }
+
+        for overwrite in &overwrites {
+            dry_run::execute(
+                format!("unlock {}", channel.name),
+                create_permission_with_audit_reason(
+                    http,
+                    channel.id,
+                    overwrite,
+                    "Voice channel unlocked",
+                ),
+            )
+            .await?;
         }
+    } else if let Err(err) = delete_result {
+        error = Some(BotError::Database(err))
     } else {
-        error = Some(Error::from("Channel already unlocked"))
+        error = Some(BotError::NotFound("Channel already unlocked".to_string()))
     }
 
     respond_moderation(
         &ctx,
-        &ModerationKind::Unlock(channel.name.clone(), error), // TODO: handle error
+        &ModerationKind::UnlockVoice(channel.name.clone(), error),
         configuration,
+        None,
+    )
+    .await
+}
+
+/// Pause or resume invites to the server.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("invites_pause", "invites_resume")
+)]
+pub async fn invites(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Pause all invites to the server for a while.
+#[poise::command(slash_command, guild_only, rename = "pause")]
+pub async fn invites_pause(
+    ctx: Context<'_>,
+    #[description = "How long to pause invites, such as 6h"] duration: String,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let expires = (Utc::now() + parse_duration(&duration).ok_or("Invalid duration")?).timestamp();
+
+    let data = ctx.data().load_full();
+    let http = &ctx.discord().http;
+
+    let error = set_invites_paused(http, guild_id, true).await;
+    if error.is_none() {
+        debug!("{} paused the invites of {}", ctx.author().name, guild_id);
+
+        // Pausing again replaces the expiry of the previous pause
+        let invite_pause: Document = InvitePause {
+            moderator_id: Some(ctx.author().id.to_string()),
+            expires: Some(expires),
+            ..Default::default()
+        }
+        .into();
+        data.database
+            .update::<InvitePause>(
+                "invite_pauses",
+                doc! { "guild_id": guild_id.to_string() },
+                UpdateModifications::Document(doc! { "$set": invite_pause }),
+                Some(UpdateOptions::builder().upsert(true).build()),
+            )
+            .await?;
+
+        queue_invite_resume(
+            http,
+            &data.database,
+            guild_id,
+            (expires - Utc::now().timestamp()).max(0) as u64,
+        );
+    }
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::PauseInvites(format_timestamp(expires), error),
+        &data.configuration,
+        None,
+    )
+    .await
+}
+
+/// Resume invites to the server.
+#[poise::command(slash_command, guild_only, rename = "resume")]
+pub async fn invites_resume(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+
+    let data = ctx.data().load_full();
+
+    data.database
+        .delete(
+            "invite_pauses",
+            doc! { "guild_id": guild_id.to_string() },
+            None,
+        )
+        .await?;
+
+    let error = set_invites_paused(&ctx.discord().http, guild_id, false).await;
+    if error.is_none() {
+        debug!("{} resumed the invites of {}", ctx.author().name, guild_id);
+    }
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::ResumeInvites(error),
+        &data.configuration,
+        None,
+    )
+    .await
+}
+
+/// Set the slowmode of a channel, optionally restoring the previous slowmode after a while.
+#[poise::command(slash_command, guild_only)]
+pub async fn slowmode(
+    ctx: Context<'_>,
+    #[description = "The channel to set the slowmode of"]
+    #[channel_types("Text")]
+    channel: serenity::GuildChannel,
+    #[min = 0]
+    #[max = 21600]
+    #[description = "The seconds members have to wait between messages, 0 to disable"]
+    seconds: u64,
+    #[description = "How long until the previous slowmode is restored, such as 1h"]
+    duration: Option<String>,
+) -> Result<(), Error> {
+    let expires = match duration {
+        Some(duration) => {
+            Some((Utc::now() + parse_duration(&duration).ok_or("Invalid duration")?).timestamp())
+        },
+        None => None,
+    };
+
+    let data = ctx.data().load_full();
+    let http = &ctx.discord().http;
+    let filter = doc! { "channel_id": channel.id.to_string() };
+
+    let error = set_slowmode(http, channel.id, seconds).await;
+    if error.is_none() {
+        debug!(
+            "{} set the slowmode of {} to {}s",
+            ctx.author().name,
+            channel.name,
+            seconds
+        );
+
+        match expires {
+            Some(expires) => {
+                // Setting a temporary slowmode again keeps the slowmode from before the first one
+                data.database
+                    .update::<SlowmodeReset>(
+                        "slowmode_resets",
+                        filter,
+                        UpdateModifications::Document(doc! {
+                            "$set": {
+                                "guild_id": channel.guild_id.to_string(),
+                                "moderator_id": ctx.author().id.to_string(),
+                                "expires": expires,
+                            },
+                            "$setOnInsert": {
+                                "previous": channel.rate_limit_per_user.unwrap_or_default() as i64,
+                            },
+                        }),
+                        Some(UpdateOptions::builder().upsert(true).build()),
+                    )
+                    .await?;
+
+                queue_slowmode_reset(
+                    http,
+                    &data.database,
+                    channel.id,
+                    (expires - Utc::now().timestamp()).max(0) as u64,
+                );
+            },
+            // A permanent slowmode replaces a pending reset
+            None => {
+                data.database
+                    .delete("slowmode_resets", filter, None)
+                    .await?;
+            },
+        }
+    }
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::Slowmode(
+            channel.name.clone(),
+            seconds,
+            expires.map(format_timestamp),
+            error,
+        ),
+        &data.configuration,
+        None,
     )
     .await
 }
 
 /// Unmute a member.
-#[poise::command(slash_command)]
+#[poise::command(slash_command, guild_only)]
 pub async fn unmute(
     ctx: Context<'_>,
-    #[description = "The member to unmute"] member: Member,
+    #[description = "The member to unmute"] mut member: Member,
 ) -> Result<(), Error> {
     ctx.defer().await.expect("Failed to defer");
 
-    let data = &ctx.data().read().await;
+    let data = &ctx.data().load_full();
     let configuration = &data.configuration;
 
-    if let Some(pending_unmute) = data.pending_unmutes.get(&member.user.id.0) {
+    if let Some(pending_unmute) = data.pending_unmutes.lock().await.get(&member.user.id.0) {
         trace!("Cancelling pending unmute for {}", member.user.id.0);
         pending_unmute.abort();
     }
 
-    let queue = queue_unmute_member(
-        &ctx.discord().http,
-        &data.database,
-        &member,
-        configuration.general.mute.role,
-        0,
-    )
-    .await
-    .unwrap();
+    let queue = if configuration.general.mute.native {
+        remove_timeout(&ctx.discord().http, &mut member).await
+    } else {
+        queue_unmute_member(
+            &ctx.discord().http,
+            &data.database,
+            &member,
+            configuration.general.mute.role,
+            0,
+        )
+        .await
+        .unwrap()
+    };
 
     respond_moderation(
         &ctx,
         &ModerationKind::Unmute(member.user, queue),
         configuration,
+        None,
     )
     .await
 }
 
 /// Mute a member.
 #[allow(clippy::too_many_arguments)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, guild_only)]
 pub async fn mute(
     ctx: Context<'_>,
     #[description = "The member to mute"] mut member: Member,
@@ -200,7 +591,11 @@ pub async fn mute(
     #[description = "Hours"] hours: Option<i64>,
     #[description = "Days"] days: Option<i64>,
     #[description = "Months"] months: Option<i64>,
-    #[description = "The reason of the mute"] reason: String,
+    #[description = "A preset duration in seconds"] duration: Option<i64>,
+    #[description = "The reason of the mute"]
+    #[autocomplete = "autocomplete_reason"]
+    reason: String,
+    #[description = "The scope of the mute, such as voice"] scope: Option<String>,
 ) -> Result<(), Error> {
     let now = Utc::now();
     let mut mute_duration = Duration::zero();
@@ -227,246 +622,1243 @@ pub async fn mute(
             .checked_add(&Duration::days(months * DAYS_IN_MONTH))
             .unwrap();
     }
+    if let Some(duration) = duration {
+        mute_duration = mute_duration
+            .checked_add(&Duration::seconds(duration))
+            .unwrap();
+    }
 
-    let unmute_time = now + mute_duration;
+    let mut unmute_time = now + mute_duration;
+    let mut reason = reason;
 
-    let data = &mut *ctx.data().write().await;
-    let configuration = &data.configuration;
-    let mute = &configuration.general.mute;
-    let mute_role_id = mute.role;
-    let take = &mute.take;
-    let is_currently_muted = member.roles.iter().any(|r| r.0 == mute_role_id);
-
-    let result =
-        if let Err(add_role_result) = member.add_role(&ctx.discord().http, mute_role_id).await {
-            Some(Error::from(add_role_result))
-        } else {
-            // accumulate all roles to take from the member
-            let removed_roles = member
-                .roles
-                .iter()
-                .filter(|r| take.contains(&r.0))
-                .map(|r| r.to_string())
-                .collect::<Vec<_>>();
-            // take them from the member, get remaining roles
-            let remaining_roles = member
-                .remove_roles(
-                    &ctx.discord().http,
-                    &take.iter().map(|&r| RoleId::from(r)).collect::<Vec<_>>(),
-                )
-                .await;
-
-            if let Err(remove_role_result) = remaining_roles {
-                Some(Error::from(remove_role_result))
-            } else {
-                // Roles which were removed from the user
-                let updated: Document = Muted {
-                    guild_id: Some(member.guild_id.0.to_string()),
-                    expires: Some(unmute_time.timestamp() as u64),
-                    reason: Some(reason.clone()),
-                    taken_roles: if is_currently_muted {
-                        // Prevent the bot from overriding the "take" field.
-                        // This would happen otherwise, because the bot would accumulate the users roles and then override the value in the database
-                        // resulting in the user being muted to have no roles to add back later.
-                        None
-                    } else {
-                        Some(removed_roles)
-                    },
+    if let Some(err) = can_moderate(&ctx, member.user.id).await {
+        return respond_moderation(
+            &ctx,
+            &ModerationKind::Mute(
+                member.user,
+                reason,
+                format_timestamp(unmute_time.timestamp()),
+                Some(err),
+            ),
+            &ctx.data().load_full().configuration,
+            None,
+        )
+        .await;
+    }
+
+    let existing_mute = {
+        let data = ctx.data().load_full();
+        let mut cursor = data
+            .database
+            .find::<Muted>(
+                "muted",
+                Muted {
+                    user_id: Some(member.user.id.to_string()),
                     ..Default::default()
                 }
-                .into();
-
-                if let Err(database_update_result) = data
-                    .database
-                    .update::<Muted>(
-                        "muted",
-                        Muted {
-                            user_id: Some(member.user.id.0.to_string()),
-                            ..Default::default()
-                        }
-                        .into(),
-                        UpdateModifications::Document(doc! { "$set": updated}),
-                        Some(UpdateOptions::builder().upsert(true).build()),
-                    )
-                    .await
-                {
-                    Some(database_update_result)
-                } else {
-                    None
-                }
-            }
-        };
+                .into(),
+                Some(FindOptions::builder().limit(1).build()),
+            )
+            .await?;
 
-    if let Some(pending_unmute) = data.pending_unmutes.get(&member.user.id.0) {
-        trace!("Cancelling pending unmute for {}", member.user.id.0);
-        pending_unmute.abort();
+        if cursor.advance().await? {
+            Some(cursor.deserialize_current()?)
+        } else {
+            None
+        }
+    };
+
+    // Prevent conflicting mutes by letting the moderator decide what to do with the existing one
+    if let Some(existing_mute) = existing_mute {
+        let expires = existing_mute.expires.unwrap_or_default() as i64;
+
+        match prompt_existing_mute(&ctx, &member, &existing_mute).await? {
+            Some(ExistingMuteDecision::Extend) => {
+                unmute_time = Utc.timestamp(cmp::max(expires, now.timestamp()), 0) + mute_duration;
+                mute_duration = unmute_time - now;
+                reason = existing_mute.reason.unwrap_or(reason);
+            },
+            Some(ExistingMuteDecision::Replace) => {},
+            None => return Ok(()),
+        }
     }
 
-    data.pending_unmutes.insert(
-        member.user.id.0,
-        queue_unmute_member(
-            &ctx.discord().http,
-            &data.database,
-            &member,
-            mute_role_id,
-            mute_duration.num_seconds() as u64,
-        ),
-    );
+    let data = ctx.data().load_full();
+    let (result, case_id) = apply_mute(
+        &ctx,
+        &data,
+        &mut member,
+        mute_duration,
+        &reason,
+        scope.as_deref(),
+    )
+    .await;
 
     respond_moderation(
         &ctx,
         &ModerationKind::Mute(
             member.user,
             reason,
-            format!("<t:{}:F>", unmute_time.timestamp()),
+            format_timestamp(unmute_time.timestamp()),
             result,
         ),
-        configuration,
+        &data.configuration,
+        case_id,
     )
     .await
 }
 
-/// Delete recent messages of a user. Cannot delete messages older than 14 days.
-#[poise::command(slash_command)]
+/// Notify and mute a member, opening a case if the mute succeeded.
+async fn apply_mute(
+    ctx: &Context<'_>,
+    data: &Data,
+    member: &mut Member,
+    mute_duration: Duration,
+    reason: &str,
+    scope: Option<&str>,
+) -> (Option<BotError>, Option<i64>) {
+    let expires = (Utc::now() + mute_duration).timestamp();
+    notify_muted_member(ctx, data, member, reason, expires).await;
+
+    let result = mute_member(
+        &ctx.discord().http,
+        data,
+        member,
+        mute_duration,
+        reason,
+        scope,
+    )
+    .await;
+
+    let case_id = match result {
+        Some(_) => None,
+        None => {
+            open_case(
+                &data.database,
+                member.guild_id,
+                "mute",
+                CaseTarget::User(member.user.id),
+                ctx.author().id,
+                Some(reason),
+            )
+            .await
+        },
+    };
+
+    (result, case_id)
+}
+
+/// Let a member know why and until when they are muted, before they are muted.
+async fn notify_muted_member(
+    ctx: &Context<'_>,
+    data: &Data,
+    member: &Member,
+    reason: &str,
+    expires: i64,
+) {
+    // Direct messages are read outside of the server, so include the time in the timezone of the member
+    let duration = match user_timezone(&data.database, member.user.id.0).await {
+        Some(timezone) => format!(
+            "{}\n{}",
+            format_timestamp(expires),
+            format_local_timestamp(expires, timezone)
+        ),
+        None => format_timestamp(expires),
+    };
+
+    notify_user(
+        ctx.discord(),
+        &data.configuration,
+        member.guild_id,
+        &member.user,
+        Notification::Mute {
+            reason,
+            duration,
+        },
+    )
+    .await;
+}
+
+enum ExistingMuteDecision {
+    Extend,
+    Replace,
+}
+
+/// Ask the moderator whether to extend or replace the existing mute of a member.
+async fn prompt_existing_mute(
+    ctx: &Context<'_>,
+    member: &Member,
+    existing_mute: &Muted,
+) -> Result<Option<ExistingMuteDecision>, Error> {
+    let embed_color = ctx.data().load_full().configuration.general.embed_color;
+
+    let handle = ctx
+        .send(|f| {
+            f.embed(|e| {
+                e.title(format!("{} is already muted", member.user.tag()))
+                    .description(
+                        "Extend adds the duration to the current mute. Replace mutes with the new \
+                         duration and reason.",
+                    )
+                    .field(
+                        "Reason",
+                        existing_mute.reason.as_deref().unwrap_or("None specified"),
+                        false,
+                    )
+                    .field(
+                        "Expires",
+                        format_timestamp(existing_mute.expires.unwrap_or_default() as i64),
+                        false,
+                    )
+                    .thumbnail(member.user.face())
+                    .color(embed_color)
+            })
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id("mute:extend")
+                            .label("Extend")
+                            .style(ButtonStyle::Primary)
+                    })
+                    .create_button(|b| {
+                        b.custom_id("mute:replace")
+                            .label("Replace")
+                            .style(ButtonStyle::Secondary)
+                    })
+                    .create_button(|b| {
+                        b.custom_id("mute:cancel")
+                            .label("Cancel")
+                            .style(ButtonStyle::Danger)
+                    })
+                })
+            })
+        })
+        .await?;
+    let message = handle.message().await?;
+
+    let component = message
+        .await_component_interaction(ctx.discord())
+        .author_id(ctx.author().id)
+        .timeout(std::time::Duration::from_secs(60))
+        .await;
+
+    let component = match component {
+        Some(component) => component,
+        None => {
+            message
+                .channel_id
+                .edit_message(&ctx.discord().http, message.id, |m| m.components(|c| c))
+                .await?;
+            return Ok(None);
+        },
+    };
+
+    let (decision, result) = match component.data.custom_id.as_str() {
+        "mute:extend" => ("Extended", Some(ExistingMuteDecision::Extend)),
+        "mute:replace" => ("Replaced", Some(ExistingMuteDecision::Replace)),
+        _ => ("Cancelled", None),
+    };
+
+    update_with_decision(ctx.discord(), &component, decision).await;
+
+    Ok(result)
+}
+
+/// Delete recent messages matching all given filters. Cannot delete messages older than 14 days.
+#[allow(clippy::too_many_arguments)]
+#[poise::command(slash_command, guild_only)]
 pub async fn purge(
     ctx: Context<'_>,
-    #[description = "User"] user: Option<User>,
+    #[description = "Only messages of this user"] user: Option<User>,
     #[description = "Until message"] until: Option<String>,
     #[min = 1]
     #[max = 1000]
     #[description = "Count"]
     count: Option<i64>,
+    #[description = "Only messages containing this text"] contains: Option<String>,
+    #[description = "Only messages matching this regular expression"] regex: Option<String>,
+    #[description = "Only messages with attachments"] attachments: Option<bool>,
+    #[description = "Only messages of bots"] bots: Option<bool>,
+    #[description = "Only messages with links"] links: Option<bool>,
 ) -> Result<(), Error> {
     // The maximum amount of times to page through messages. If paged over MAX_PAGES amount of times without deleting messages, break.
     const MAX_PAGES: i8 = 2;
     // The maximal amount of messages that we can fetch at all
-    const MAX_BULK_DELETE: i64 = 100;
+    const MAX_BULK_DELETE: u64 = 100;
     // Discord does not let us bulk-delete messages older than 14 days
     const MAX_BULK_DELETE_AGO_SECS: i64 = 60 * 60 * 24 * 14;
 
-    let data = ctx.data().read().await;
-    let configuration = &data.configuration;
-    let embed_color = configuration.general.embed_color;
+    let regex = regex
+        .as_deref()
+        .map(Regex::new)
+        .transpose()
+        .map_err(|_| "Invalid regular expression")?;
+    let until = until.and_then(|u| u.parse::<u64>().ok());
+    let attachments = attachments.unwrap_or_default();
+    let bots = bots.unwrap_or_default();
+    let links = links.unwrap_or_default();
+
+    let mut filters = Vec::new();
+    if let Some(user) = &user {
+        filters.push(format!("From <@{}>", user.id));
+    }
+    if let Some(contains) = &contains {
+        filters.push(format!("Containing `{}`", contains));
+    }
+    if let Some(regex) = &regex {
+        filters.push(format!("Matching `{}`", regex));
+    }
+    if attachments {
+        filters.push("With attachments".to_string());
+    }
+    if bots {
+        filters.push("From bots".to_string());
+    }
+    if links {
+        filters.push("With links".to_string());
+    }
+    if let Some(until) = until {
+        filters.push(format!("Until message {}", until));
+    }
+
+    let matches = |m: &serenity::Message| {
+        user.as_ref().is_none_or(|u| m.author.id == u.id)
+            && contains
+                .as_ref()
+                .is_none_or(|c| m.content.contains(c.as_str()))
+            && regex.as_ref().is_none_or(|r| r.is_match(&m.content))
+            && (!attachments || !m.attachments.is_empty())
+            && (!bots || m.author.bot)
+            && (!links || m.content.contains("http://") || m.content.contains("https://"))
+    };
+
     let channel = ctx.channel_id();
+    let channel_name = channel
+        .name(ctx.discord())
+        .await
+        .map_or_else(|| channel.to_string(), |n| format!("#{}", n));
     let too_old_timestamp = Utc::now().timestamp() - MAX_BULK_DELETE_AGO_SECS;
 
-    let current_user = ctx.discord().http.get_current_user().await?;
-    let image = current_user.face();
+    let mut response = LongRunning::start(ctx, "Purging messages", false).await?;
+    // Messages are fetched before the response, so it is not purged itself
+    let mut before = response.handle().message().await?.id;
 
-    let handle = ctx
-        .send(|f| {
-            f.embed(|f| {
-                f.title("Purging messages")
-                    .description("Accumulating...")
-                    .color(embed_color)
-                    .thumbnail(&image)
-            })
-        })
-        .await?;
-    let mut response = handle.message().await?;
-
-    ctx.defer().await?;
-
-    let count_to_delete = count.unwrap_or(MAX_BULK_DELETE) as usize;
+    let count_to_delete = count.unwrap_or(MAX_BULK_DELETE as i64) as usize;
     let mut deleted_amount = 0;
     let mut empty_pages: i8 = 0;
+    let mut error = None;
 
     loop {
-        // Filter out messages that are too old
-        let mut messages = channel
-            .messages(&ctx.discord(), |m| {
-                m.limit(count_to_delete as u64).before(response.id)
-            })
-            .await?
-            .into_iter()
-            .take_while(|m| m.timestamp.timestamp() > too_old_timestamp)
-            .collect::<Vec<_>>();
-
-        // Filter for messages from the user
-        if let Some(ref user) = user {
-            messages = messages
-                .into_iter()
-                .filter(|msg| msg.author.id == user.id)
-                .collect::<Vec<_>>();
-
-            debug!("Filtered messages by {}. Left: {}", user, messages.len());
-        }
+        let page = channel
+            .messages(&ctx.discord(), |m| m.limit(MAX_BULK_DELETE).before(before))
+            .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = last.id;
 
-        // Filter for messages until the g/mutiven id
-        if let Some(ref message_id) = until {
-            if let Ok(message_id) = message_id.parse::<u64>() {
-                messages = messages
-                    .into_iter()
-                    .take_while(|m| m.id.0 > message_id)
-                    .collect::<Vec<_>>();
-                debug!(
-                    "Filtered messages until {}. Left: {}",
-                    message_id,
-                    messages.len()
-                );
+        // Messages are sorted from new to old, so the following pages can be skipped at the end
+        let mut exhausted = (page.len() as u64) < MAX_BULK_DELETE;
+        let mut messages = Vec::new();
+        for message in page {
+            if message.timestamp.timestamp() <= too_old_timestamp
+                || until.is_some_and(|until| message.id.0 <= until)
+            {
+                exhausted = true;
+                break;
+            }
+            if matches(&message) {
+                messages.push(message);
             }
         }
+        messages.truncate(count_to_delete - deleted_amount);
+        debug!("Found {} messages to purge", messages.len());
 
         let purge_count = messages.len();
         if purge_count > 0 {
+            empty_pages = 0;
+            if let Err(err) = dry_run::execute(
+                format!("delete {} messages in {}", purge_count, channel),
+                channel.delete_messages(&ctx.discord(), &messages),
+            )
+            .await
+            {
+                error = Some(BotError::from(err));
+                break;
+            }
             deleted_amount += purge_count;
-            channel.delete_messages(&ctx.discord(), &messages).await?;
+
+            response
+                .progress(format!("Deleted {} messages", deleted_amount))
+                .await?;
         } else {
             empty_pages += 1;
         }
 
-        if empty_pages >= MAX_PAGES || deleted_amount >= count_to_delete {
+        if exhausted || empty_pages >= MAX_PAGES || deleted_amount >= count_to_delete {
             break;
         }
     }
 
+    // The result is mirrored to the moderation log, which the progress is not
     response
-        .to_mut()
-        .edit(&ctx.discord(), |e| {
-            e.set_embed(
-                serenity::CreateEmbed::default()
-                    .title("Purge successful")
-                    .field("Deleted messages", deleted_amount.to_string(), false)
-                    .color(embed_color)
-                    .thumbnail(image)
-                    .clone(),
-            )
-        })
+        .handle()
+        .message()
+        .await?
+        .delete(ctx.discord())
         .await?;
-    Ok(())
+
+    let data = ctx.data().load_full();
+    respond_moderation(
+        &ctx,
+        &ModerationKind::Purge(channel_name, deleted_amount, filters, error),
+        &data.configuration,
+        None,
+    )
+    .await
 }
 
 /// Ban a user.
-#[poise::command(slash_command)]
+#[poise::command(slash_command, guild_only)]
 pub async fn ban(
     ctx: Context<'_>,
     #[description = "User"] user: User,
     #[description = "Amount of days to delete messages"] dmd: Option<u8>,
-    #[description = "Reason for the ban"] reason: Option<String>,
+    #[description = "Reason for the ban"]
+    #[autocomplete = "autocomplete_reason"]
+    reason: Option<String>,
+    #[description = "How long the ban lasts, such as 7d"] duration: Option<String>,
 ) -> Result<(), Error> {
-    handle_ban(&ctx, &BanKind::Ban(user, dmd, reason)).await
+    let expires = match duration {
+        Some(duration) => {
+            Some((Utc::now() + parse_duration(&duration).ok_or("Invalid duration")?).timestamp())
+        },
+        None => None,
+    };
+
+    handle_ban(&ctx, &BanKind::Ban(user, dmd, reason, expires)).await
 }
 
-/// Unban a user.
-#[poise::command(slash_command)]
-pub async fn unban(ctx: Context<'_>, #[description = "User"] user: User) -> Result<(), Error> {
+/// Unban a user, also by id if their account was deleted.
+#[poise::command(slash_command, guild_only)]
+pub async fn unban(
+    ctx: Context<'_>,
+    #[description = "The user or their id"] user: String,
+) -> Result<(), Error> {
+    let user_id = parse_user_id(&user).ok_or("Invalid user")?;
+    let user = resolve_user(&ctx, user_id).await;
+
     handle_ban(&ctx, &BanKind::Unban(user)).await
 }
 
-async fn handle_ban(ctx: &Context<'_>, kind: &BanKind) -> Result<(), Error> {
-    let data = ctx.data().read().await;
+/// Ban and immediately unban a user to delete their messages.
+#[poise::command(slash_command, guild_only)]
+pub async fn softban(
+    ctx: Context<'_>,
+    #[description = "User"] user: User,
+    #[description = "Amount of days to delete messages, 1 by default"] dmd: Option<u8>,
+    #[description = "Reason for the softban"]
+    #[autocomplete = "autocomplete_reason"]
+    reason: Option<String>,
+) -> Result<(), Error> {
+    handle_ban(&ctx, &BanKind::Softban(user, dmd, reason)).await
+}
 
-    let ban_result = ban_moderation(ctx, kind).await;
+/// Kick a member.
+#[poise::command(slash_command, guild_only)]
+pub async fn kick(
+    ctx: Context<'_>,
+    #[description = "The member to kick"] member: Member,
+    #[description = "The reason of the kick"]
+    #[autocomplete = "autocomplete_reason"]
+    reason: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+
+    let (error, case_id) = match can_moderate(&ctx, member.user.id).await {
+        Some(err) => (Some(err), None),
+        None => apply_kick(&ctx, &data, &member, reason.as_deref()).await,
+    };
 
     respond_moderation(
-        ctx,
-        &match kind {
-            BanKind::Ban(user, _, reason) => {
-                ModerationKind::Ban(user.clone(), reason.clone(), ban_result)
+        &ctx,
+        &ModerationKind::Kick(member.user, reason, error),
+        &data.configuration,
+        case_id,
+    )
+    .await
+}
+
+/// Kick a member, opening a case if the kick succeeded.
+async fn apply_kick(
+    ctx: &Context<'_>,
+    data: &Data,
+    member: &Member,
+    reason: Option<&str>,
+) -> (Option<BotError>, Option<i64>) {
+    if let Err(err) = dry_run::execute(
+        format!("kick {}", member.user.tag()),
+        member.kick_with_reason(&ctx.discord().http, reason.unwrap_or("None specified")),
+    )
+    .await
+    {
+        return (Some(BotError::from(err)), None);
+    }
+
+    debug!("{} kicked {}", ctx.author().name, member.user.tag());
+    let case_id = open_case(
+        &data.database,
+        member.guild_id,
+        "kick",
+        CaseTarget::User(member.user.id),
+        ctx.author().id,
+        reason,
+    )
+    .await;
+
+    (None, case_id)
+}
+
+/// Whether a member is connected to a voice channel.
+fn is_in_voice(ctx: &Context<'_>, member: &Member) -> bool {
+    ctx.discord()
+        .cache
+        .guild_field(member.guild_id, |g| {
+            g.voice_states
+                .get(&member.user.id)
+                .is_some_and(|v| v.channel_id.is_some())
+        })
+        .unwrap_or_default()
+}
+
+/// Disconnect a member from their voice channel.
+#[poise::command(slash_command, guild_only)]
+pub async fn voicekick(
+    ctx: Context<'_>,
+    #[description = "The member to disconnect"] member: Member,
+    #[description = "The reason of the disconnect"]
+    #[autocomplete = "autocomplete_reason"]
+    reason: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+
+    let mut error = can_moderate(&ctx, member.user.id).await;
+    if error.is_none() && !is_in_voice(&ctx, &member) {
+        error = Some(BotError::NotFound(
+            "The member is not connected to a voice channel.".to_string(),
+        ));
+    }
+
+    let mut case_id = None;
+    if error.is_none() {
+        error = dry_run::execute(
+            format!("disconnect {}", member.user.tag()),
+            disconnect_with_audit_reason(
+                &ctx.discord().http,
+                member.guild_id,
+                member.user.id,
+                reason.as_deref().unwrap_or("None specified"),
+            ),
+        )
+        .await
+        .err()
+        .map(BotError::from);
+    }
+    if error.is_none() {
+        debug!("{} disconnected {}", ctx.author().name, member.user.tag());
+        case_id = open_case(
+            &data.database,
+            member.guild_id,
+            "voicekick",
+            CaseTarget::User(member.user.id),
+            ctx.author().id,
+            reason.as_deref(),
+        )
+        .await;
+    }
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::VoiceKick(member.user, reason, error),
+        &data.configuration,
+        case_id,
+    )
+    .await
+}
+
+/// Mute or unmute a member in voice channels.
+#[poise::command(slash_command, guild_only)]
+pub async fn voicemute(
+    ctx: Context<'_>,
+    #[description = "The member to mute"] member: Member,
+    #[description = "Whether to mute or unmute the member, mute by default"] mute: Option<bool>,
+    #[description = "The reason of the mute"]
+    #[autocomplete = "autocomplete_reason"]
+    reason: Option<String>,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let mute = mute.unwrap_or(true);
+
+    // Discord only changes the voice state of connected members
+    let mut error = can_moderate(&ctx, member.user.id).await;
+    if error.is_none() && !is_in_voice(&ctx, &member) {
+        error = Some(BotError::NotFound(
+            "The member is not connected to a voice channel.".to_string(),
+        ));
+    }
+
+    let mut case_id = None;
+    if error.is_none() {
+        error = dry_run::execute(
+            format!(
+                "{} {}",
+                if mute { "voice mute" } else { "voice unmute" },
+                member.user.tag()
+            ),
+            voice_mute_with_audit_reason(
+                &ctx.discord().http,
+                member.guild_id,
+                member.user.id,
+                mute,
+                reason.as_deref().unwrap_or("None specified"),
+            ),
+        )
+        .await
+        .err()
+        .map(BotError::from);
+    }
+    if error.is_none() && mute {
+        debug!("{} voice muted {}", ctx.author().name, member.user.tag());
+        case_id = open_case(
+            &data.database,
+            member.guild_id,
+            "voicemute",
+            CaseTarget::User(member.user.id),
+            ctx.author().id,
+            reason.as_deref(),
+        )
+        .await;
+    }
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::VoiceMute(member.user, mute, reason, error),
+        &data.configuration,
+        case_id,
+    )
+    .await
+}
+
+/// Time out a member with the native timeout of Discord.
+#[poise::command(slash_command, guild_only)]
+pub async fn timeout(
+    ctx: Context<'_>,
+    #[description = "The member to time out"] mut member: Member,
+    #[description = "How long the timeout lasts, such as 1h"] duration: String,
+    #[description = "The reason of the timeout"]
+    #[autocomplete = "autocomplete_reason"]
+    reason: String,
+) -> Result<(), Error> {
+    let timeout_duration = parse_duration(&duration).ok_or("Invalid duration")?;
+    let expires = (Utc::now() + timeout_duration).timestamp();
+
+    let data = ctx.data().load_full();
+
+    let error = match can_moderate(&ctx, member.user.id).await {
+        Some(err) => Some(err),
+        None => {
+            notify_muted_member(&ctx, &data, &member, &reason, expires).await;
+            timeout_member(&ctx.discord().http, &mut member, timeout_duration).await
+        },
+    };
+
+    let case_id = if error.is_none() {
+        debug!("{} timed out {}", ctx.author().name, member.user.tag());
+        open_case(
+            &data.database,
+            member.guild_id,
+            "timeout",
+            CaseTarget::User(member.user.id),
+            ctx.author().id,
+            Some(&reason),
+        )
+        .await
+    } else {
+        None
+    };
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::Timeout(member.user, reason, format_timestamp(expires), error),
+        &data.configuration,
+        case_id,
+    )
+    .await
+}
+
+/// Warn a user.
+#[poise::command(slash_command, guild_only)]
+pub async fn warn(
+    ctx: Context<'_>,
+    #[description = "The user to warn"] user: User,
+    #[description = "The reason of the warning"]
+    #[autocomplete = "autocomplete_reason"]
+    reason: String,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+
+    if let Some(err) = can_moderate(&ctx, user.id).await {
+        return respond_moderation(
+            &ctx,
+            &ModerationKind::Warn(user, reason, Some(err)),
+            &data.configuration,
+            None,
+        )
+        .await;
+    }
+
+    let (error, case_id) = apply_warn(&ctx, &data, &user, &reason).await;
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::Warn(user, reason, error),
+        &data.configuration,
+        case_id,
+    )
+    .await
+}
+
+/// Notify a user of a warning and store it, opening a case if it was stored.
+async fn apply_warn(
+    ctx: &Context<'_>,
+    data: &Data,
+    user: &User,
+    reason: &str,
+) -> (Option<BotError>, Option<i64>) {
+    if let Some(guild_id) = ctx.guild_id() {
+        notify_user(
+            ctx.discord(),
+            &data.configuration,
+            guild_id,
+            user,
+            Notification::Warn {
+                reason,
+            },
+        )
+        .await;
+    }
+
+    let result = data
+        .database
+        .insert::<Warning>(
+            "warnings",
+            Warning {
+                guild_id: ctx.guild_id().map(|g| g.to_string()),
+                user_id: Some(user.id.to_string()),
+                moderator_id: Some(ctx.author().id.to_string()),
+                reason: Some(reason.to_string()),
+                timestamp: Some(Utc::now().timestamp()),
+            },
+            None,
+        )
+        .await;
+
+    debug!("{} warned {}: {}", ctx.author().name, user.tag(), reason);
+
+    let case_id = match (ctx.guild_id(), &result) {
+        (Some(guild_id), Ok(_)) => {
+            open_case(
+                &data.database,
+                guild_id,
+                "warn",
+                CaseTarget::User(user.id),
+                ctx.author().id,
+                Some(reason),
+            )
+            .await
+        },
+        _ => None,
+    };
+
+    (result.err().map(BotError::Database), case_id)
+}
+
+/// Punish a user with the step of the strike policy their infraction reaches.
+#[poise::command(slash_command, guild_only)]
+pub async fn punish(
+    ctx: Context<'_>,
+    #[description = "The user to punish"] user: User,
+    #[description = "The reason of the punishment"]
+    #[autocomplete = "autocomplete_reason"]
+    reason: String,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+    let configuration = &data.configuration;
+
+    let strikes = active_strikes(&data.database, configuration, guild_id.0, user.id.0).await?;
+    let points = total_points(&strikes) + points_for(configuration, "punish", &reason);
+    let step = step_for(&configuration.strikes, points);
+    let description = format!(
+        "{} at {} points",
+        step.map_or_else(|| "A warning".to_string(), describe),
+        points
+    );
+
+    if let Some(err) = can_moderate(&ctx, user.id).await {
+        return respond_moderation(
+            &ctx,
+            &ModerationKind::Punish(user, reason, description, Some(err)),
+            configuration,
+            None,
+        )
+        .await;
+    }
+
+    let duration = match step.and_then(|s| s.duration.as_deref()) {
+        Some(duration) => Some(parse_duration(duration).ok_or("Invalid duration of the step")?),
+        None => None,
+    };
+
+    // Users are warned until the first step is reached
+    let (error, case_id) = match step.map_or(&StrikeAction::Warning, |s| &s.action) {
+        StrikeAction::Warning => apply_warn(&ctx, &data, &user, &reason).await,
+        StrikeAction::Mute => match (
+            fetch_member(ctx.discord(), guild_id, user.id).await,
+            duration,
+        ) {
+            (Ok(mut member), Some(duration)) => {
+                apply_mute(&ctx, &data, &mut member, duration, &reason, None).await
             },
-            BanKind::Unban(user) => ModerationKind::Unban(user.clone(), ban_result),
+            (Ok(_), None) => (
+                Some(BotError::Config(
+                    "The mute step has no duration.".to_string(),
+                )),
+                None,
+            ),
+            (Err(err), _) => (Some(BotError::from(err)), None),
+        },
+        StrikeAction::Kick => match fetch_member(ctx.discord(), guild_id, user.id).await {
+            Ok(member) => apply_kick(&ctx, &data, &member, Some(&reason)).await,
+            Err(err) => (Some(BotError::from(err)), None),
         },
+        StrikeAction::Ban => {
+            let expires = duration.map(|d| (Utc::now() + d).timestamp());
+            match apply_ban(
+                &ctx,
+                &BanKind::Ban(user.clone(), None, Some(reason.clone()), expires),
+            )
+            .await?
+            {
+                (ModerationKind::Ban(.., error), case_id) => (error, case_id),
+                (_, case_id) => (None, case_id),
+            }
+        },
+    };
+
+    info!(
+        "{} punished {} with: {}",
+        ctx.author().tag(),
+        user.tag(),
+        description
+    );
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::Punish(user, reason, description, error),
+        configuration,
+        case_id,
+    )
+    .await
+}
+
+/// Ban many users by their ids at once, for example during a raid.
+#[poise::command(slash_command, guild_only)]
+pub async fn massban(
+    ctx: Context<'_>,
+    #[description = "User ids separated by commas or new lines"] ids: Option<String>,
+    #[description = "A text file with user ids"] file: Option<serenity::Attachment>,
+    #[description = "Amount of days to delete messages"] dmd: Option<u8>,
+    #[description = "Reason for the bans"]
+    #[autocomplete = "autocomplete_reason"]
+    reason: Option<String>,
+) -> Result<(), Error> {
+    // Bans sent to Discord at the same time, the rate limiter of serenity queues the rest
+    const CONCURRENT_BANS: usize = 5;
+    // Keeps a mass ban from taking longer than Discord lets the response be edited
+    const MAX_MASS_BANS: usize = 1000;
+
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+
+    let mut input = ids.unwrap_or_default();
+    if let Some(file) = file {
+        input.push('\n');
+        input.push_str(&String::from_utf8_lossy(&file.download().await?));
+    }
+
+    let (user_ids, invalid) = parse_user_ids(&input);
+    if user_ids.is_empty() {
+        return Err("No valid user ids were given".into());
+    }
+    if user_ids.len() > MAX_MASS_BANS {
+        return Err(format!("At most {} users can be banned at once", MAX_MASS_BANS).into());
+    }
+
+    let reason = reason.unwrap_or_else(|| "None specified".to_string());
+    let dmd = cmp::min(dmd.unwrap_or(0), 7);
+    let mut failures = invalid
+        .into_iter()
+        .map(|part| format!("`{}`: Not a user id", part))
+        .collect::<Vec<_>>();
+
+    let mut response = LongRunning::start(ctx, "Banning users", false).await?;
+
+    let data = ctx.data().load_full();
+    let approval = &data.configuration.bans.approval;
+
+    // Asking for an approval per user would stall a mass ban, so these bans are left to /ban
+    let mut allowed = Vec::new();
+    for user_id in user_ids {
+        if let Some(err) = can_moderate(&ctx, user_id).await {
+            failures.push(format!("`{}`: {}", user_id, err.user_message()));
+        } else if ban_approval_days(ctx.discord(), approval, guild_id, user_id)
+            .await
+            .is_some()
+        {
+            failures.push(format!(
+                "`{}`: Needs the approval of a second moderator, use /ban instead",
+                user_id
+            ));
+        } else {
+            allowed.push(user_id);
+        }
+    }
+
+    let http = ctx.discord().http.clone();
+    let mut banned = Vec::new();
+    for chunk in allowed.chunks(CONCURRENT_BANS) {
+        let handles = chunk
+            .iter()
+            .map(|&user_id| {
+                let http = http.clone();
+                let reason = reason.clone();
+                tokio::spawn(async move {
+                    let result = dry_run::execute(
+                        format!("ban {}", user_id),
+                        ban_with_audit_reason(&http, guild_id, user_id, dmd, &reason),
+                    )
+                    .await;
+                    (user_id, result)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            match handle.await? {
+                (user_id, Ok(_)) => banned.push(user_id),
+                (user_id, Err(err)) => failures.push(format!(
+                    "`{}`: {}",
+                    user_id,
+                    BotError::from(err).user_message()
+                )),
+            }
+        }
+
+        response
+            .progress_of(banned.len() + failures.len(), allowed.len())
+            .await?;
+    }
+
+    info!(
+        "{} mass banned {} users in {}",
+        ctx.author().tag(),
+        banned.len(),
+        guild_id
+    );
+
+    for user_id in &banned {
+        open_case(
+            &data.database,
+            guild_id,
+            "ban",
+            CaseTarget::User(*user_id),
+            ctx.author().id,
+            Some(&reason),
+        )
+        .await;
+    }
+
+    // The result is mirrored to the moderation log, which the progress is not
+    response
+        .handle()
+        .message()
+        .await?
+        .delete(ctx.discord())
+        .await?;
+
+    let error = if banned.is_empty() {
+        Some(BotError::Permission(
+            "None of the users could be banned.".to_string(),
+        ))
+    } else {
+        None
+    };
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::MassBan(banned, failures, reason, error),
         &data.configuration,
+        None,
+    )
+    .await
+}
+
+async fn handle_ban(ctx: &Context<'_>, kind: &BanKind) -> Result<(), Error> {
+    let (moderation, case_id) = apply_ban(ctx, kind).await?;
+
+    respond_moderation(
+        ctx,
+        &moderation,
+        &ctx.data().load_full().configuration,
+        case_id,
+    )
+    .await
+}
+
+/// Ban, unban or softban a user, opening a case if it succeeded.
+async fn apply_ban(
+    ctx: &Context<'_>,
+    kind: &BanKind,
+) -> Result<(ModerationKind, Option<i64>), Error> {
+    let data = ctx.data().load_full();
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+
+    let refuse = |err: BotError| match kind {
+        BanKind::Ban(user, _, reason, expires) => {
+            ModerationKind::Ban(user.clone(), reason.clone(), *expires, Some(err))
+        },
+        BanKind::Softban(user, _, reason) => {
+            ModerationKind::Softban(user.clone(), reason.clone(), Some(err))
+        },
+        BanKind::Unban(user) => ModerationKind::Unban(user.clone(), Some(err)),
+    };
+
+    // Unbans lift moderation, so they are not restricted by the hierarchy or need an approval
+    let mut approver_id = None;
+    if let BanKind::Ban(user, ..) | BanKind::Softban(user, ..) = kind {
+        if let Some(err) = can_moderate(ctx, user.id).await {
+            return Ok((refuse(err), None));
+        }
+
+        match request_ban_approval(ctx, &data, guild_id, user).await? {
+            BanApprovalDecision::NotNeeded => {},
+            BanApprovalDecision::Approved(user_id) => approver_id = Some(user_id),
+            BanApprovalDecision::Denied => {
+                return Ok((
+                    refuse(BotError::Permission(
+                        "The ban was not approved by a second moderator.".to_string(),
+                    )),
+                    None,
+                ));
+            },
+        }
+    }
+
+    // The reason of bans is composed from a template below, but users are told the bare reason
+    let notified_reason = match kind {
+        BanKind::Ban(_, _, reason, _) => reason.clone(),
+        _ => None,
+    };
+
+    // Reserve a case number, so banned users can reference it when appealing
+    let (kind, case_id) = match kind {
+        BanKind::Ban(user, dmd, reason, expires) => {
+            let reason = reason
+                .clone()
+                .unwrap_or_else(|| "None specified".to_string());
+            match next_case_id(&data.database, guild_id).await {
+                Ok(case_id) => (
+                    BanKind::Ban(
+                        user.clone(),
+                        *dmd,
+                        Some(ban_reason(&data.configuration.bans, case_id, &reason)),
+                        *expires,
+                    ),
+                    Some(case_id),
+                ),
+                Err(err) => {
+                    error!("Failed to reserve a case number: {:?}", err);
+                    (
+                        BanKind::Ban(user.clone(), *dmd, Some(reason), *expires),
+                        None,
+                    )
+                },
+            }
+        },
+        BanKind::Unban(user) => (BanKind::Unban(user.clone()), None),
+        BanKind::Softban(user, dmd, reason) => {
+            (BanKind::Softban(user.clone(), *dmd, reason.clone()), None)
+        },
+    };
+
+    if let BanKind::Ban(user, _, _, expires) = &kind {
+        notify_user(
+            ctx.discord(),
+            &data.configuration,
+            guild_id,
+            user,
+            Notification::Ban {
+                reason: notified_reason.as_deref().unwrap_or("None specified"),
+                duration: expires.map_or_else(|| "Never".to_string(), format_timestamp),
+                case_id,
+            },
+        )
+        .await;
+    }
+
+    let ban_result = ban_moderation(ctx, &kind).await;
+
+    // Failed bans are not recorded
+    let case_id = match (&kind, case_id, &ban_result) {
+        (BanKind::Ban(user, _, reason, _), Some(case_id), None) => {
+            if let Err(err) = record_case(
+                &data.database,
+                guild_id,
+                case_id,
+                "ban",
+                CaseTarget::User(user.id),
+                ctx.author().id,
+                reason.as_deref(),
+            )
+            .await
+            {
+                error!("Failed to record case {}: {:?}", case_id, err);
+            }
+            Some(case_id)
+        },
+        (BanKind::Softban(user, _, reason), _, None) => {
+            open_case(
+                &data.database,
+                guild_id,
+                "softban",
+                CaseTarget::User(user.id),
+                ctx.author().id,
+                reason.as_deref(),
+            )
+            .await
+        },
+        _ => None,
+    };
+
+    if let (Some(case_id), Some(approver_id)) = (case_id, approver_id) {
+        record_approver(&data.database, guild_id, case_id, approver_id).await;
+    }
+
+    if ban_result.is_none() {
+        if let Err(err) = track_temp_ban(ctx, &data.database, guild_id, &kind).await {
+            error!("Failed to store the expiry of the ban: {:?}", err);
+        }
+    }
+
+    let moderation = match kind {
+        BanKind::Ban(user, _, reason, expires) => {
+            ModerationKind::Ban(user, reason, expires, ban_result)
+        },
+        BanKind::Unban(user) => ModerationKind::Unban(user, ban_result),
+        BanKind::Softban(user, _, reason) => ModerationKind::Softban(user, reason, ban_result),
+    };
+
+    Ok((moderation, case_id))
+}
+
+/// Ask a second moderator to approve the ban of a long-standing member.
+async fn request_ban_approval(
+    ctx: &Context<'_>,
+    data: &Data,
+    guild_id: GuildId,
+    user: &User,
+) -> Result<BanApprovalDecision, Error> {
+    let Some(member_days) = ban_approval_days(
+        ctx.discord(),
+        &data.configuration.bans.approval,
+        guild_id,
+        user.id,
     )
     .await
+    else {
+        return Ok(BanApprovalDecision::NotNeeded);
+    };
+
+    let handle = ctx
+        .send(|f| {
+            f.embed(|e| {
+                ban_approval_embed(e, user, member_days, ctx.author().id)
+                    .color(data.configuration.general.embed_color)
+            })
+            .components(ban_approval_buttons)
+        })
+        .await?;
+    let message = handle.message().await?;
+
+    await_ban_approval(
+        ctx.discord(),
+        &data.configuration.administrators,
+        &message,
+        ctx.author().id,
+        user,
+    )
+    .await
+}
+
+/// Store and schedule the expiry of temporary bans. Other bans, softbans and unbans replace the
+/// expiry.
+async fn track_temp_ban(
+    ctx: &Context<'_>,
+    database: &Arc<Database>,
+    guild_id: GuildId,
+    kind: &BanKind,
+) -> Result<(), Error> {
+    let user_id = match kind {
+        BanKind::Ban(user, ..) | BanKind::Unban(user) | BanKind::Softban(user, ..) => user.id,
+    };
+    let query = doc! { "guild_id": guild_id.to_string(), "user_id": user_id.to_string() };
+
+    let expires = match kind {
+        BanKind::Ban(.., Some(expires)) => *expires,
+        _ => {
+            database.delete("temp_bans", query, None).await?;
+            return Ok(());
+        },
+    };
+
+    let temp_ban: Document = TempBan {
+        moderator_id: Some(ctx.author().id.to_string()),
+        expires: Some(expires),
+        ..Default::default()
+    }
+    .into();
+    database
+        .update::<TempBan>(
+            "temp_bans",
+            query,
+            UpdateModifications::Document(doc! { "$set": temp_ban }),
+            Some(UpdateOptions::builder().upsert(true).build()),
+        )
+        .await?;
+
+    queue_unban(
+        &ctx.discord().http,
+        database,
+        guild_id,
+        user_id,
+        (expires - Utc::now().timestamp()).max(0) as u64,
+    );
+
+    Ok(())
 }
@@ -0,0 +1,155 @@
+use bson::doc;
+use chrono::Utc;
+use mongodb::options::UpdateModifications;
+use poise::serenity_prelude::{self as serenity, ChannelId};
+use tracing::debug;
+
+use crate::db::model::Monitor;
+use crate::model::application::LogCategory;
+use crate::utils::monitor::parse_keywords;
+use crate::{Context, Error};
+
+/// Mirror messages matching keywords into a private thread for staff.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("monitor_channel", "monitor_stop")
+)]
+pub async fn monitor(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Monitor a channel for messages matching keywords.
+#[poise::command(slash_command, guild_only, ephemeral = true, rename = "channel")]
+pub async fn monitor_channel(
+    ctx: Context<'_>,
+    #[description = "The channel to monitor"]
+    #[channel_types("Text")]
+    channel: serenity::GuildChannel,
+    #[description = "Comma separated keywords, matched case insensitively"] keywords: String,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let configuration = &data.configuration;
+    let http = &ctx.discord().http;
+
+    let keywords = parse_keywords(&keywords);
+    if keywords.is_empty() {
+        return Err("No keywords given".into());
+    }
+
+    let monitors = data
+        .database
+        .collect::<Monitor>("monitors", doc! { "channel_id": channel.id.to_string() })
+        .await?;
+
+    let description = if let Some(monitor) = monitors.first() {
+        // Monitoring a channel again replaces its keywords
+        data.database
+            .update::<Monitor>(
+                "monitors",
+                doc! { "channel_id": channel.id.to_string() },
+                UpdateModifications::Document(doc! { "$set": { "keywords": &keywords } }),
+                None,
+            )
+            .await?;
+
+        format!(
+            "Updated the keywords of <#{}>, matches are mirrored to <#{}>.",
+            channel.id,
+            monitor.thread_id.as_deref().unwrap_or_default()
+        )
+    } else {
+        let parent = match configuration.monitoring.channel {
+            0 => ChannelId(configuration.log_channel(LogCategory::Messages)),
+            channel_id => ChannelId(channel_id),
+        };
+
+        let thread = parent
+            .create_private_thread(http, |t| t.name(format!("Monitoring #{}", channel.name)))
+            .await?;
+        thread.id.add_thread_member(http, ctx.author().id).await?;
+
+        data.database
+            .insert::<Monitor>(
+                "monitors",
+                Monitor {
+                    guild_id: Some(channel.guild_id.to_string()),
+                    channel_id: Some(channel.id.to_string()),
+                    thread_id: Some(thread.id.to_string()),
+                    keywords: Some(keywords.clone()),
+                    moderator_id: Some(ctx.author().id.to_string()),
+                    timestamp: Some(Utc::now().timestamp()),
+                },
+                None,
+            )
+            .await?;
+
+        format!(
+            "Monitoring <#{}>, matches are mirrored to <#{}>.",
+            channel.id, thread.id
+        )
+    };
+
+    debug!(
+        "{} monitors {} for {}",
+        ctx.author().tag(),
+        channel.name,
+        keywords.join(", ")
+    );
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description(description)
+                .field("Keywords", keywords.join(", "), false)
+                .color(configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Stop monitoring a channel.
+#[poise::command(slash_command, guild_only, ephemeral = true, rename = "stop")]
+pub async fn monitor_stop(
+    ctx: Context<'_>,
+    #[description = "The monitored channel"]
+    #[channel_types("Text")]
+    channel: serenity::GuildChannel,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+
+    let removed = data
+        .database
+        .find_and_delete::<Monitor>(
+            "monitors",
+            doc! { "channel_id": channel.id.to_string() },
+            None,
+        )
+        .await?;
+
+    // The thread is archived, so the mirrored messages can still be read
+    if let Some(thread_id) = removed
+        .as_ref()
+        .and_then(|m| m.thread_id.as_ref())
+        .and_then(|id| id.parse::<u64>().ok())
+    {
+        ChannelId(thread_id)
+            .edit_thread(&ctx.discord().http, |t| t.archived(true))
+            .await?;
+    }
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description(if removed.is_some() {
+                format!("Stopped monitoring <#{}>.", channel.id)
+            } else {
+                format!("<#{}> is not monitored.", channel.id)
+            })
+            .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
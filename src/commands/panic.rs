@@ -0,0 +1,104 @@
+use tracing::info;
+
+use crate::utils::moderation::{respond_moderation, ModerationKind};
+use crate::utils::panic::{end_panic, start_panic};
+use crate::utils::slow_join::queued_joins;
+use crate::{Context, Error};
+
+// The amount of queued members to list
+const QUEUE_PREVIEW_SIZE: usize = 20;
+
+/// Respond to a raid in one go.
+#[poise::command(slash_command, guild_only, subcommands("start", "end", "queue"))]
+pub async fn panic(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Enable raid mode, lock public channels, raise verification, pause invites and ping staff.
+#[poise::command(slash_command, guild_only)]
+pub async fn start(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+
+    ctx.defer().await?;
+
+    info!("{} started a panic in {}", ctx.author().tag(), guild_id);
+
+    let kind = match start_panic(ctx.discord(), guild_id, ctx.author().id).await {
+        Ok(steps) => ModerationKind::Panic(steps, None),
+        Err(err) => ModerationKind::Panic(Vec::new(), Some(err)),
+    };
+
+    respond_moderation(&ctx, &kind, &ctx.data().load_full().configuration, None).await
+}
+
+/// Revert everything a panic changed.
+#[poise::command(slash_command, guild_only)]
+pub async fn end(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+
+    ctx.defer().await?;
+
+    info!("{} ended the panic in {}", ctx.author().tag(), guild_id);
+
+    let kind = match end_panic(ctx.discord(), guild_id).await {
+        Ok(steps) => ModerationKind::PanicEnd(steps, None),
+        Err(err) => ModerationKind::PanicEnd(Vec::new(), Some(err)),
+    };
+
+    respond_moderation(&ctx, &kind, &ctx.data().load_full().configuration, None).await
+}
+
+/// Show the members waiting for access during raid mode.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn queue(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+    let slow_join = &data.configuration.slow_join;
+
+    let queued = queued_joins(ctx.discord(), guild_id, None).await?;
+    let per_minute = slow_join.per_minute.max(1);
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title("Join queue")
+                .description(if slow_join.role == 0 {
+                    "Slow join is not configured, new members are kicked during a panic."
+                        .to_string()
+                } else if queued.is_empty() {
+                    "Nobody is waiting for access.".to_string()
+                } else {
+                    queued
+                        .iter()
+                        .take(QUEUE_PREVIEW_SIZE)
+                        .enumerate()
+                        .map(|(position, q)| {
+                            format!(
+                                "{}. <@{}> since <t:{}:R>",
+                                position + 1,
+                                q.user_id.as_deref().unwrap_or_default(),
+                                q.timestamp.unwrap_or_default()
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+                .field("Waiting", queued.len().to_string(), true)
+                .field("Admitted per minute", per_minute.to_string(), true)
+                .field(
+                    "Empty in",
+                    format!("{} minutes", queued.len().div_ceil(per_minute)),
+                    true,
+                )
+                .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
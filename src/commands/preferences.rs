@@ -0,0 +1,75 @@
+use bson::doc;
+use chrono::Utc;
+use chrono_tz::Tz;
+use mongodb::options::{UpdateModifications, UpdateOptions};
+
+use crate::db::model::UserPreferences;
+use crate::utils::time::{format_local_timestamp, user_timezone};
+use crate::{Context, Error};
+
+/// Manage your timezone used for times in direct messages.
+#[poise::command(slash_command, subcommands("set", "show"))]
+pub async fn timezone(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set your timezone.
+#[poise::command(slash_command, ephemeral = true)]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "Your timezone, such as Europe/Berlin"] timezone: String,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let description = match timezone.parse::<Tz>() {
+        Ok(tz) => {
+            let updated: bson::Document = UserPreferences {
+                timezone: Some(tz.name().to_string()),
+                ..Default::default()
+            }
+            .into();
+
+            data.database
+                .update::<UserPreferences>(
+                    "preferences",
+                    doc! { "user_id": ctx.author().id.to_string() },
+                    UpdateModifications::Document(doc! { "$set": updated }),
+                    Some(UpdateOptions::builder().upsert(true).build()),
+                )
+                .await?;
+
+            format!(
+                "Set your timezone to {}. It is currently {}.",
+                tz.name(),
+                format_local_timestamp(Utc::now().timestamp(), tz)
+            )
+        },
+        Err(_) => format!(
+            "Unknown timezone {}. Use a name from the tz database, such as Europe/Berlin.",
+            timezone
+        ),
+    };
+
+    ctx.send(|f| f.embed(|e| e.description(description).color(embed_color)))
+        .await?;
+
+    Ok(())
+}
+
+/// Show your timezone.
+#[poise::command(slash_command, ephemeral = true)]
+pub async fn show(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let description = match user_timezone(&data.database, ctx.author().id.0).await {
+        Some(tz) => format!("Your timezone is {}.", tz.name()),
+        None => "You have not set a timezone. Times are shown in UTC.".to_string(),
+    };
+
+    ctx.send(|f| f.embed(|e| e.description(description).color(embed_color)))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,465 @@
+use std::collections::{HashMap, HashSet};
+
+use bson::doc;
+use mongodb::options::{FindOptions, UpdateModifications, UpdateOptions};
+use once_cell::sync::Lazy;
+use poise::futures_util::StreamExt;
+use poise::serenity_prelude::{
+    self as serenity,
+    ButtonStyle,
+    InteractionResponseType,
+    Mutex,
+    UserId,
+};
+use rand::seq::SliceRandom;
+
+use crate::db::model::{QuizQuestion, QuizScore};
+use crate::model::application::Question;
+use crate::utils::moderation::is_administrator;
+use crate::{Context, Error};
+
+// Labels of the answer buttons
+const ANSWER_LABELS: [&str; 5] = ["A", "B", "C", "D", "E"];
+// Discord limits the label of a button to 80 characters
+const MAX_LABEL_LENGTH: usize = 80;
+// The amount of members to show on the leaderboard
+const LEADERBOARD_SIZE: i64 = 10;
+
+// Channels in which a quiz is currently running
+static ACTIVE_QUIZZES: Lazy<Mutex<HashSet<u64>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Play trivia quizzes.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("start", "leaderboard", "upload", "clear")
+)]
+pub async fn quiz(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Respond with a permission error, if the author is not an administrator.
+async fn require_administrator(ctx: &Context<'_>) -> Result<bool, Error> {
+    let data = ctx.data().load_full();
+    let is_allowed = ctx
+        .author_member()
+        .await
+        .is_some_and(|m| is_administrator(&data.configuration.administrators, &m));
+
+    if !is_allowed {
+        ctx.send(|f| {
+            f.ephemeral(true).embed(|e| {
+                e.title("Permission error")
+                    .description("You do not have permission to use this command.")
+                    .color(data.configuration.general.embed_color)
+            })
+        })
+        .await?;
+    }
+
+    Ok(is_allowed)
+}
+
+fn is_valid(question: &Question) -> bool {
+    (2..=ANSWER_LABELS.len()).contains(&question.answers.len())
+        && question.correct < question.answers.len()
+}
+
+/// Start a quiz in the current channel.
+#[poise::command(slash_command, guild_only)]
+pub async fn start(
+    ctx: Context<'_>,
+    #[description = "The topic of the questions"] topic: Option<String>,
+    #[min = 1]
+    #[max = 20]
+    #[description = "The amount of questions, defaults to 5"]
+    rounds: Option<usize>,
+) -> Result<(), Error> {
+    if !require_administrator(&ctx).await? {
+        return Ok(());
+    }
+
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let channel_id = ctx.channel_id();
+
+    let (embed_color, answer_seconds, mut questions) = {
+        let data = ctx.data().load_full();
+        let quiz = &data.configuration.quiz;
+
+        let mut questions = quiz.questions.clone();
+        questions.extend(
+            data.database
+                .collect::<QuizQuestion>(
+                    "quiz_questions",
+                    doc! { "guild_id": guild_id.to_string() },
+                )
+                .await?
+                .into_iter()
+                .filter_map(|q| {
+                    Some(Question {
+                        topic: q.topic?,
+                        question: q.question?,
+                        answers: q.answers?,
+                        correct: q.correct? as usize,
+                    })
+                }),
+        );
+        questions.retain(|q| {
+            is_valid(q)
+                && topic
+                    .as_ref()
+                    .is_none_or(|topic| q.topic.eq_ignore_ascii_case(topic))
+        });
+
+        (
+            data.configuration.general.embed_color,
+            quiz.answer_seconds.max(5),
+            questions,
+        )
+    };
+
+    if questions.is_empty() {
+        ctx.send(|f| {
+            f.ephemeral(true).embed(|e| {
+                e.description("There are no questions for this topic.")
+                    .color(embed_color)
+            })
+        })
+        .await?;
+        return Ok(());
+    }
+
+    if !ACTIVE_QUIZZES.lock().await.insert(channel_id.0) {
+        ctx.send(|f| {
+            f.ephemeral(true).embed(|e| {
+                e.description("A quiz is already running in this channel.")
+                    .color(embed_color)
+            })
+        })
+        .await?;
+        return Ok(());
+    }
+
+    questions.shuffle(&mut rand::thread_rng());
+    questions.truncate(rounds.unwrap_or(5));
+
+    let result = run_rounds(&ctx, guild_id, &questions, answer_seconds, embed_color).await;
+    ACTIVE_QUIZZES.lock().await.remove(&channel_id.0);
+
+    let scores = result?;
+    let mut ranking = scores.into_iter().collect::<Vec<_>>();
+    ranking.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    channel_id
+        .send_message(&ctx.discord().http, |m| {
+            m.embed(|e| {
+                e.title("Quiz finished").color(embed_color);
+
+                if ranking.is_empty() {
+                    e.description("Nobody answered correctly.")
+                } else {
+                    e.description(
+                        ranking
+                            .iter()
+                            .enumerate()
+                            .map(|(i, (user, score))| format!("{}. <@{}>: {}", i + 1, user, score))
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    )
+                }
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Ask the questions one after another and return the amount of correct answers by user.
+async fn run_rounds(
+    ctx: &Context<'_>,
+    guild_id: serenity::GuildId,
+    questions: &[Question],
+    answer_seconds: u64,
+    embed_color: i32,
+) -> Result<HashMap<UserId, i64>, Error> {
+    let http = &ctx.discord().http;
+    let mut scores = HashMap::<UserId, i64>::new();
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title("Quiz")
+                .description(format!(
+                    "{} questions are coming up. You have {} seconds to answer each one.",
+                    questions.len(),
+                    answer_seconds
+                ))
+                .color(embed_color)
+        })
+    })
+    .await?;
+
+    for (round, question) in questions.iter().enumerate() {
+        let mut message = ctx
+            .channel_id()
+            .send_message(http, |m| {
+                m.embed(|e| {
+                    e.title(format!("Question {} of {}", round + 1, questions.len()))
+                        .description(&question.question)
+                        .footer(|f| f.text(&question.topic))
+                        .color(embed_color)
+                })
+                .components(|c| {
+                    c.create_action_row(|r| {
+                        for (i, answer) in question.answers.iter().enumerate() {
+                            r.create_button(|b| {
+                                b.custom_id(format!("quiz:{}", i))
+                                    .label(
+                                        format!("{}: {}", ANSWER_LABELS[i], answer)
+                                            .chars()
+                                            .take(MAX_LABEL_LENGTH)
+                                            .collect::<String>(),
+                                    )
+                                    .style(ButtonStyle::Primary)
+                            });
+                        }
+                        r
+                    })
+                })
+            })
+            .await?;
+
+        // Only the first answer of every member counts
+        let mut answers = HashMap::<UserId, usize>::new();
+        let mut interactions = message
+            .await_component_interactions(ctx.discord())
+            .timeout(std::time::Duration::from_secs(answer_seconds))
+            .build();
+
+        while let Some(interaction) = interactions.next().await {
+            let answer = interaction
+                .data
+                .custom_id
+                .strip_prefix("quiz:")
+                .and_then(|i| i.parse::<usize>().ok());
+            let answer = match answer {
+                Some(answer) if answer < question.answers.len() => answer,
+                _ => continue,
+            };
+
+            let content = match answers.entry(interaction.user.id) {
+                std::collections::hash_map::Entry::Occupied(_) => {
+                    "You have already answered this question.".to_string()
+                },
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    entry.insert(answer);
+                    format!("You answered {}.", ANSWER_LABELS[answer])
+                },
+            };
+
+            interaction
+                .create_interaction_response(http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| d.ephemeral(true).content(content))
+                })
+                .await?;
+        }
+
+        let winners = answers
+            .iter()
+            .filter(|(_, &answer)| answer == question.correct)
+            .map(|(user, _)| *user)
+            .collect::<Vec<_>>();
+
+        for user in &winners {
+            *scores.entry(*user).or_default() += 1;
+        }
+        add_scores(ctx, guild_id, &winners).await?;
+
+        message
+            .edit(http, |m| {
+                m.embed(|e| {
+                    e.title(format!("Question {} of {}", round + 1, questions.len()))
+                        .description(&question.question)
+                        .field(
+                            "Answer",
+                            format!(
+                                "{}: {}",
+                                ANSWER_LABELS[question.correct], question.answers[question.correct]
+                            ),
+                            false,
+                        )
+                        .field(
+                            "Answered correctly",
+                            format!("{} of {}", winners.len(), answers.len()),
+                            false,
+                        )
+                        .footer(|f| f.text(&question.topic))
+                        .color(embed_color)
+                })
+                .components(|c| c)
+            })
+            .await?;
+    }
+
+    Ok(scores)
+}
+
+/// Add a point to the stored score of each user.
+async fn add_scores(
+    ctx: &Context<'_>,
+    guild_id: serenity::GuildId,
+    users: &[UserId],
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+
+    for user in users {
+        data.database
+            .update::<QuizScore>(
+                "quiz_scores",
+                doc! { "guild_id": guild_id.to_string(), "user_id": user.to_string() },
+                UpdateModifications::Document(doc! { "$inc": { "score": 1_i64 } }),
+                Some(UpdateOptions::builder().upsert(true).build()),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Show the members with the most correct answers.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+
+    let mut cursor = data
+        .database
+        .find::<QuizScore>(
+            "quiz_scores",
+            doc! { "guild_id": guild_id.to_string() },
+            Some(
+                FindOptions::builder()
+                    .sort(doc! { "score": -1 })
+                    .limit(LEADERBOARD_SIZE)
+                    .build(),
+            ),
+        )
+        .await?;
+
+    let mut ranking = Vec::new();
+    while cursor.advance().await? {
+        let score: QuizScore = cursor.deserialize_current()?;
+        ranking.push(format!(
+            "{}. <@{}>: {}",
+            ranking.len() + 1,
+            score.user_id.unwrap_or_default(),
+            score.score.unwrap_or_default()
+        ));
+    }
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title("Quiz leaderboard")
+                .description(if ranking.is_empty() {
+                    "Nobody has answered a question correctly yet.".to_string()
+                } else {
+                    ranking.join("\n")
+                })
+                .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Add questions to the question bank from a JSON file.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn upload(
+    ctx: Context<'_>,
+    #[description = "A JSON array of objects with a topic, question, answers and correct index"]
+    file: serenity::Attachment,
+) -> Result<(), Error> {
+    if !require_administrator(&ctx).await? {
+        return Ok(());
+    }
+
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let questions: Vec<Question> = serde_json::from_slice(&file.download().await?)?;
+    let (valid, invalid): (Vec<_>, Vec<_>) = questions.into_iter().partition(is_valid);
+
+    let data = ctx.data().load_full();
+    for question in &valid {
+        data.database
+            .insert::<QuizQuestion>(
+                "quiz_questions",
+                QuizQuestion {
+                    guild_id: Some(guild_id.to_string()),
+                    topic: Some(question.topic.clone()),
+                    question: Some(question.question.clone()),
+                    answers: Some(question.answers.clone()),
+                    correct: Some(question.correct as i32),
+                },
+                None,
+            )
+            .await?;
+    }
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description(format!(
+                "Added {} questions. Skipped {} invalid questions.",
+                valid.len(),
+                invalid.len()
+            ))
+            .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Remove uploaded questions from the question bank.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn clear(
+    ctx: Context<'_>,
+    #[description = "The topic to remove, defaults to all topics"] topic: Option<String>,
+) -> Result<(), Error> {
+    if !require_administrator(&ctx).await? {
+        return Ok(());
+    }
+
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let mut filter = doc! { "guild_id": guild_id.to_string() };
+    if let Some(topic) = &topic {
+        filter.insert("topic", topic);
+    }
+
+    let data = ctx.data().load_full();
+    let result = data
+        .database
+        .delete_many("quiz_questions", filter, None)
+        .await?;
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description(format!(
+                "Removed {} uploaded questions. Questions from the configuration are kept.",
+                result.deleted_count
+            ))
+            .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
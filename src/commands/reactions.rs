@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use poise::serenity_prelude::{
+    self as serenity,
+    GuildChannel,
+    MessageId,
+    ReactionType,
+    User,
+    UserId,
+};
+use tracing::debug;
+
+use crate::error::BotError;
+use crate::utils::dry_run;
+use crate::utils::long_running::LongRunning;
+use crate::utils::moderation::{respond_moderation, ModerationKind};
+use crate::{Context, Error};
+
+// The maximal amount of messages that can be fetched at once
+const MAX_MESSAGES_PER_PAGE: u64 = 100;
+// Removing reactions one by one quickly runs into rate limits
+const REMOVAL_PACING: Duration = Duration::from_millis(250);
+
+/// Remove reaction spam left after an incident.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("reactions_clear", "reactions_clear_user")
+)]
+pub async fn reactions(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Remove all reactions, or all reactions with an emoji, from a message.
+#[poise::command(slash_command, guild_only, rename = "clear")]
+pub async fn reactions_clear(
+    ctx: Context<'_>,
+    #[description = "Link to the message"] message: serenity::Message,
+    #[description = "Only reactions with this emoji"] emoji: Option<String>,
+) -> Result<(), Error> {
+    let emoji = match emoji {
+        Some(emoji) => Some(
+            emoji
+                .trim()
+                .parse::<ReactionType>()
+                .map_err(|_| "Invalid emoji")?,
+        ),
+        None => None,
+    };
+
+    ctx.defer().await?;
+
+    let removed = message
+        .reactions
+        .iter()
+        .filter(|r| emoji.as_ref().is_none_or(|e| &r.reaction_type == e))
+        .map(|r| r.count as usize)
+        .sum();
+
+    let error = match &emoji {
+        Some(emoji) => {
+            dry_run::execute(
+                format!("remove {} reactions from {}", emoji, message.id),
+                message.delete_reaction_emoji(ctx.discord(), emoji.clone()),
+            )
+            .await
+        },
+        None => {
+            dry_run::execute(
+                format!("remove all reactions from {}", message.id),
+                message.delete_reactions(ctx.discord()),
+            )
+            .await
+        },
+    }
+    .err()
+    .map(BotError::from);
+
+    debug!(
+        "{} removed {} reactions from {}",
+        ctx.author().tag(),
+        removed,
+        message.id
+    );
+
+    let target = match &emoji {
+        Some(emoji) => format!("{} on {}", emoji, message.link()),
+        None => message.link(),
+    };
+    let removed = if error.is_some() { 0 } else { removed };
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::ClearReactions(target, removed, error),
+        &ctx.data().load_full().configuration,
+        None,
+    )
+    .await
+}
+
+/// Remove the reactions of a user from the recent messages of a channel.
+#[poise::command(slash_command, guild_only, rename = "clear-user")]
+pub async fn reactions_clear_user(
+    ctx: Context<'_>,
+    #[description = "The user whose reactions to remove"] user: User,
+    #[description = "The channel to remove the reactions in"] channel: GuildChannel,
+    #[min = 1]
+    #[max = 1000]
+    #[description = "The amount of recent messages to check, 100 by default"]
+    limit: Option<u64>,
+) -> Result<(), Error> {
+    let limit = limit.unwrap_or(MAX_MESSAGES_PER_PAGE);
+
+    let mut response =
+        LongRunning::start(ctx, format!("Removing reactions of {}", user.tag()), false).await?;
+
+    let mut before: Option<MessageId> = None;
+    let mut checked = 0;
+    let mut removed = 0;
+    let mut error = None;
+
+    'pages: while checked < limit {
+        let page = channel
+            .messages(ctx.discord(), |m| {
+                m.limit((limit - checked).min(MAX_MESSAGES_PER_PAGE));
+                if let Some(before) = before {
+                    m.before(before);
+                }
+                m
+            })
+            .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(last.id);
+        checked += page.len() as u64;
+
+        for message in &page {
+            for reaction in &message.reactions {
+                // Users are sorted by their id, so this only returns the user if they reacted
+                let reacted = message
+                    .reaction_users(
+                        ctx.discord(),
+                        reaction.reaction_type.clone(),
+                        Some(1),
+                        Some(UserId(user.id.0 - 1)),
+                    )
+                    .await?
+                    .iter()
+                    .any(|u| u.id == user.id);
+                if !reacted {
+                    continue;
+                }
+
+                if let Err(err) = dry_run::execute(
+                    format!(
+                        "remove the {} reaction of {} from {}",
+                        reaction.reaction_type,
+                        user.tag(),
+                        message.id
+                    ),
+                    channel.id.delete_reaction(
+                        ctx.discord(),
+                        message.id,
+                        Some(user.id),
+                        reaction.reaction_type.clone(),
+                    ),
+                )
+                .await
+                {
+                    error = Some(BotError::from(err));
+                    break 'pages;
+                }
+                removed += 1;
+
+                tokio::time::sleep(REMOVAL_PACING).await;
+            }
+        }
+
+        response
+            .progress(format!(
+                "Checked {} messages, removed {} reactions",
+                checked, removed
+            ))
+            .await?;
+
+        if (page.len() as u64) < MAX_MESSAGES_PER_PAGE {
+            break;
+        }
+    }
+
+    debug!(
+        "{} removed {} reactions of {} in {}",
+        ctx.author().tag(),
+        removed,
+        user.tag(),
+        channel.name
+    );
+
+    // The result is mirrored to the moderation log, which the progress is not
+    response
+        .handle()
+        .message()
+        .await?
+        .delete(ctx.discord())
+        .await?;
+
+    respond_moderation(
+        &ctx,
+        &ModerationKind::ClearReactions(
+            format!("{} in #{}", user.tag(), channel.name),
+            removed,
+            error,
+        ),
+        &ctx.data().load_full().configuration,
+        None,
+    )
+    .await
+}
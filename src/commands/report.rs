@@ -0,0 +1,153 @@
+use poise::serenity_prelude::{self as serenity, ChannelId, UserId};
+use tracing::debug;
+
+use crate::utils::dm_report::{extract_invites, extract_user_ids};
+use crate::utils::member_cache::fetch_member;
+use crate::utils::moderation::moderation_buttons;
+use crate::{Context, Error};
+
+// Discord allows at most 5 action rows per message
+const MAX_SUGGESTED_MEMBERS: usize = 5;
+
+/// Report a scam direct message to the staff.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn reportdm(
+    ctx: Context<'_>,
+    #[description = "The text of the message, including user ids or invites if possible"]
+    text: Option<String>,
+    #[description = "A screenshot of the message"] screenshot: Option<serenity::Attachment>,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    if text.is_none() && screenshot.is_none() {
+        return Err("Provide the text of the message or a screenshot".into());
+    }
+
+    let (report_channel, embed_color) = {
+        let data = ctx.data().load_full();
+        (
+            data.configuration.dm_reports.channel,
+            data.configuration.general.embed_color,
+        )
+    };
+    if report_channel == 0 {
+        return Err("Reporting direct messages is not enabled".into());
+    }
+
+    let http = &ctx.discord().http;
+    let text = text.unwrap_or_default();
+
+    // Cross-reference mentioned users with the members of the server
+    let mut members = Vec::new();
+    let mut strangers = Vec::new();
+    for user_id in extract_user_ids(&text) {
+        match fetch_member(ctx.discord(), guild_id, UserId(user_id)).await {
+            Ok(member) => members.push(member),
+            Err(_) => strangers.push(UserId(user_id)),
+        }
+    }
+
+    let mut invites = Vec::new();
+    for code in extract_invites(&text) {
+        let description = match http.get_invite(&code, true, false, None).await {
+            Ok(invite) => match invite.guild {
+                Some(guild) if guild.id == guild_id => {
+                    format!("`{}` leads to this server", code)
+                },
+                Some(guild) => format!(
+                    "`{}` leads to {} with about {} members",
+                    code,
+                    guild.name,
+                    invite.approximate_member_count.unwrap_or_default()
+                ),
+                None => format!("`{}` is not a server invite", code),
+            },
+            Err(err) => {
+                debug!("Failed to resolve invite {}: {:?}", code, err);
+                format!("`{}` is invalid or expired", code)
+            },
+        };
+        invites.push(description);
+    }
+
+    let mut suggestions = Vec::new();
+    if !members.is_empty() {
+        suggestions.push("Ban or mute the members below, if the report is genuine.");
+    }
+    if !invites.is_empty() {
+        suggestions.push("Add the invites to an automod rule.");
+    }
+    if members.is_empty() && strangers.is_empty() {
+        suggestions.push("Ask the reporter for the user id of the sender.");
+    }
+
+    ChannelId(report_channel)
+        .send_message(http, |m| {
+            m.embed(|e| {
+                e.title("Scam direct message report")
+                    .field("Reporter", format!("<@{}>", ctx.author().id), true)
+                    .color(embed_color);
+
+                if !text.is_empty() {
+                    e.description(&text);
+                }
+                if !members.is_empty() {
+                    e.field(
+                        "Members of this server",
+                        members
+                            .iter()
+                            .map(|m| {
+                                format!(
+                                    "<@{}> ({}), joined {}",
+                                    m.user.id,
+                                    m.user.tag(),
+                                    m.joined_at.map_or("at an unknown time".to_string(), |t| {
+                                        format!("<t:{}:R>", t.unix_timestamp())
+                                    })
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                        false,
+                    );
+                }
+                if !strangers.is_empty() {
+                    e.field(
+                        "Not in this server",
+                        strangers
+                            .iter()
+                            .map(|u| format!("<@{}>", u))
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                        false,
+                    );
+                }
+                if !invites.is_empty() {
+                    e.field("Invites", invites.join("\n"), false);
+                }
+                if let Some(screenshot) = &screenshot {
+                    e.image(&screenshot.url);
+                }
+
+                e.field("Suggested actions", suggestions.join("\n"), false)
+            })
+            .components(|c| {
+                for member in members.iter().take(MAX_SUGGESTED_MEMBERS) {
+                    moderation_buttons(c, member.user.id);
+                }
+                c
+            })
+        })
+        .await?;
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description("Thank you, your report has been sent to the staff.")
+                .color(embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
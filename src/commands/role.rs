@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+
+use bson::doc;
+use chrono::{Duration, Utc};
+use poise::serenity_prelude::{
+    self as serenity,
+    ButtonStyle,
+    InteractionResponseType,
+    Member,
+    UserId,
+};
+
+use crate::db::model::Activity;
+use crate::utils::dry_run;
+use crate::utils::long_running::LongRunning;
+use crate::{Context, Error};
+
+/// Manage roles.
+#[poise::command(slash_command, guild_only, subcommands("cleanup"))]
+pub async fn role(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Remove a role from members who have not sent a message recently.
+#[poise::command(slash_command, guild_only)]
+pub async fn cleanup(
+    ctx: Context<'_>,
+    #[description = "The role to remove"] role: serenity::Role,
+    #[min = 1]
+    #[description = "Days without a message, defaults to 30"]
+    inactive_days: Option<i64>,
+) -> Result<(), Error> {
+    // The maximal amount of members which can be fetched at once
+    const MAX_FETCH: u64 = 1000;
+    // The maximal amount of members to list in the preview
+    const MAX_PREVIEW: usize = 20;
+
+    ctx.defer().await?;
+
+    let inactive_days = inactive_days.unwrap_or(30);
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let http = &ctx.discord().http;
+
+    let mut holders: Vec<Member> = Vec::new();
+    let mut after: Option<UserId> = None;
+    loop {
+        let page = guild_id.members(http, Some(MAX_FETCH), after).await?;
+        let exhausted = (page.len() as u64) < MAX_FETCH;
+        after = page.last().map(|m| m.user.id);
+        holders.extend(page.into_iter().filter(|m| m.roles.contains(&role.id)));
+
+        if exhausted {
+            break;
+        }
+    }
+
+    let (embed_color, active) = {
+        let data = ctx.data().load_full();
+        let cutoff = (Utc::now() - Duration::days(inactive_days)).timestamp();
+        let active = data
+            .database
+            .collect::<Activity>("activity", doc! {
+                "guild_id": guild_id.to_string(),
+                "last_message": { "$gte": cutoff },
+            })
+            .await?
+            .into_iter()
+            .filter_map(|a| a.user_id)
+            .collect::<HashSet<_>>();
+
+        (data.configuration.general.embed_color, active)
+    };
+
+    let inactive = holders
+        .into_iter()
+        .filter(|m| !m.user.bot && !active.contains(&m.user.id.to_string()))
+        .collect::<Vec<_>>();
+
+    if inactive.is_empty() {
+        ctx.send(|f| {
+            f.embed(|e| {
+                e.description(format!(
+                    "No member with <@&{}> has been inactive for {} days.",
+                    role.id, inactive_days
+                ))
+                .color(embed_color)
+            })
+        })
+        .await?;
+        return Ok(());
+    }
+
+    let mut preview = inactive
+        .iter()
+        .take(MAX_PREVIEW)
+        .map(|m| format!("<@{}>", m.user.id))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if inactive.len() > MAX_PREVIEW {
+        preview.push_str(&format!(" and {} more", inactive.len() - MAX_PREVIEW));
+    }
+
+    let handle = ctx
+        .send(|f| {
+            f.embed(|e| {
+                e.title(format!(
+                    "Remove {} from {} members?",
+                    role.name,
+                    inactive.len()
+                ))
+                .description(format!(
+                    "These members have not sent a message in {} days. Members whose \
+                         activity has never been recorded are included.",
+                    inactive_days
+                ))
+                .field("Members", &preview, false)
+                .color(embed_color)
+            })
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id("cleanup:confirm")
+                            .label("Confirm")
+                            .style(ButtonStyle::Danger)
+                    })
+                    .create_button(|b| {
+                        b.custom_id("cleanup:cancel")
+                            .label("Cancel")
+                            .style(ButtonStyle::Secondary)
+                    })
+                })
+            })
+        })
+        .await?;
+    let message = handle.message().await?;
+
+    let component = message
+        .await_component_interaction(ctx.discord())
+        .author_id(ctx.author().id)
+        .timeout(std::time::Duration::from_secs(60))
+        .await;
+
+    let confirmed = component
+        .as_ref()
+        .is_some_and(|c| c.data.custom_id == "cleanup:confirm");
+    let status = if confirmed {
+        format!("Removing {} from {} members.", role.name, inactive.len())
+    } else {
+        "Cancelled the cleanup.".to_string()
+    };
+
+    match &component {
+        Some(component) => {
+            component
+                .create_interaction_response(http, |r| {
+                    r.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|d| {
+                            d.embed(|e| e.description(&status).color(embed_color))
+                                .components(|c| c)
+                        })
+                })
+                .await?
+        },
+        None => {
+            handle
+                .edit(ctx, |f| {
+                    f.embed(|e| e.description(&status).color(embed_color))
+                        .components(|c| c)
+                })
+                .await?
+        },
+    }
+
+    if !confirmed {
+        return Ok(());
+    }
+
+    let mut cleanup =
+        LongRunning::from_handle(ctx, handle, format!("Removing {} from members", role.name));
+
+    let mut removed = 0;
+    let mut failed = 0;
+    for (i, member) in inactive.iter().enumerate() {
+        match dry_run::execute(
+            format!("remove {} from {}", role.name, member.user.tag()),
+            http.remove_member_role(
+                guild_id.0,
+                member.user.id.0,
+                role.id.0,
+                Some("Inactive member cleanup"),
+            ),
+        )
+        .await
+        {
+            Ok(_) => removed += 1,
+            Err(_) => failed += 1,
+        }
+
+        cleanup.progress_of(i + 1, inactive.len()).await?;
+    }
+
+    cleanup
+        .finish(|e| {
+            e.title("Cleanup finished")
+                .description(format!("Removed {} from {} members.", role.name, removed))
+                .field("Failed", failed.to_string(), true)
+        })
+        .await
+}
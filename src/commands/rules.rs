@@ -0,0 +1,236 @@
+use bson::{doc, Document};
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument, UpdateModifications};
+use poise::serenity_prelude::User;
+use tracing::{debug, error};
+
+use crate::db::model::Rule;
+use crate::utils::rules::{format_rule, guild_rules, sync_rules};
+use crate::{Context, Data, Error};
+
+/// Manage the rules of the server.
+#[poise::command(slash_command, guild_only, subcommands("add", "edit", "remove"))]
+pub async fn rules(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Sync the rules embed and respond with the outcome of a change to the rules.
+async fn respond_changed(
+    ctx: &Context<'_>,
+    data: &Data,
+    title: &str,
+    rule: &str,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+
+    // The change is stored either way, so only report a failed sync
+    let description = match sync_rules(ctx.discord(), data, guild_id).await {
+        Ok(_) => rule.to_string(),
+        Err(err) => {
+            error!("Failed to sync the rules: {:?}", err);
+            format!("{}\n\nFailed to update the rules channel: {}", rule, err)
+        },
+    };
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title(title)
+                .description(description)
+                .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Add a rule after the existing ones.
+#[poise::command(slash_command, guild_only)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "The title of the rule"] title: String,
+    #[description = "The description of the rule"] description: String,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+
+    let number = guild_rules(&data.database, guild_id)
+        .await?
+        .last()
+        .and_then(|r| r.number)
+        .unwrap_or_default()
+        + 1;
+    let rule = Rule {
+        guild_id: Some(guild_id.to_string()),
+        number: Some(number),
+        title: Some(title),
+        description: Some(description),
+    };
+    let formatted = format_rule(&rule);
+
+    data.database.insert::<Rule>("rules", rule, None).await?;
+
+    debug!("{} added rule {}", ctx.author().name, number);
+    respond_changed(&ctx, &data, "Added rule", &formatted).await
+}
+
+/// Change the title or description of a rule.
+#[poise::command(slash_command, guild_only)]
+pub async fn edit(
+    ctx: Context<'_>,
+    #[min = 1]
+    #[description = "The number of the rule"]
+    number: i64,
+    #[description = "The new title of the rule"] title: Option<String>,
+    #[description = "The new description of the rule"] description: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+
+    if title.is_none() && description.is_none() {
+        return Err("Nothing to change".into());
+    }
+
+    let edited: Document = Rule {
+        title,
+        description,
+        ..Default::default()
+    }
+    .into();
+    let rule = data
+        .database
+        .find_and_update::<Rule>(
+            "rules",
+            doc! { "guild_id": guild_id.to_string(), "number": number },
+            UpdateModifications::Document(doc! { "$set": edited }),
+            Some(
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            ),
+        )
+        .await?
+        .ok_or(format!("Rule {} does not exist", number))?;
+
+    debug!("{} edited rule {}", ctx.author().name, number);
+    respond_changed(&ctx, &data, "Edited rule", &format_rule(&rule)).await
+}
+
+/// Remove a rule, renumbering the ones after it.
+#[poise::command(slash_command, guild_only)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[min = 1]
+    #[description = "The number of the rule"]
+    number: i64,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+
+    let rule = data
+        .database
+        .find_and_delete::<Rule>(
+            "rules",
+            doc! { "guild_id": guild_id.to_string(), "number": number },
+            None,
+        )
+        .await?
+        .ok_or(format!("Rule {} does not exist", number))?;
+
+    data.database
+        .update_many::<Rule>(
+            "rules",
+            doc! { "guild_id": guild_id.to_string(), "number": { "$gt": number } },
+            UpdateModifications::Document(doc! { "$inc": { "number": -1_i64 } }),
+            None,
+        )
+        .await?;
+
+    debug!("{} removed rule {}", ctx.author().name, number);
+    respond_changed(&ctx, &data, "Removed rule", &format_rule(&rule)).await
+}
+
+/// Cite a rule in the current channel.
+#[poise::command(slash_command, guild_only)]
+pub async fn rule(
+    ctx: Context<'_>,
+    #[min = 1]
+    #[description = "The number of the rule"]
+    number: i64,
+    #[description = "The user to remind of the rule by direct message"] user: Option<User>,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let rule = data
+        .database
+        .collect::<Rule>(
+            "rules",
+            doc! { "guild_id": guild_id.to_string(), "number": number },
+        )
+        .await?
+        .pop()
+        .ok_or(format!("Rule {} does not exist", number))?;
+    let title = format!(
+        "Rule {}: {}",
+        number,
+        rule.title.as_deref().unwrap_or_default()
+    );
+    let description = rule.description.unwrap_or_default();
+
+    let mut dm_failed = false;
+    if let Some(user) = &user {
+        let guild_name = guild_id
+            .name(ctx.discord())
+            .unwrap_or_else(|| "the server".to_string());
+
+        if let Err(err) = user
+            .direct_message(ctx.discord(), |m| {
+                m.embed(|e| {
+                    e.title(&title)
+                        .description(&description)
+                        .footer(|f| f.text(format!("A reminder from the staff of {}", guild_name)))
+                        .color(embed_color)
+                })
+            })
+            .await
+        {
+            debug!(
+                "Failed to remind {} of rule {}: {:?}",
+                user.tag(),
+                number,
+                err
+            );
+            dm_failed = true;
+        }
+    }
+
+    ctx.send(|f| {
+        if let Some(user) = &user {
+            f.content(format!("<@{}>", user.id));
+        }
+
+        f.embed(|e| {
+            e.title(&title).description(&description).color(embed_color);
+
+            if dm_failed {
+                e.footer(|f| f.text("The user does not accept direct messages."));
+            }
+
+            e
+        })
+    })
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use bson::doc;
+use chrono::{TimeZone, Utc};
+use mongodb::options::FindOptions;
+
+use crate::db::model::Transcript;
+use crate::{Context, Error};
+
+// The maximal amount of characters of a transcript excerpt
+const EXCERPT_LENGTH: usize = 200;
+
+/// Search the records of the Discord bot.
+#[poise::command(slash_command, guild_only, subcommands("transcripts"))]
+pub async fn search(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Search the transcripts of resolved support threads.
+#[poise::command(slash_command, guild_only)]
+pub async fn transcripts(
+    ctx: Context<'_>,
+    #[description = "The words to search for"] query: String,
+    #[min = 1]
+    #[max = 25]
+    #[description = "Amount of transcripts to show"]
+    count: Option<i64>,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let mut cursor = data
+        .database
+        .find::<Transcript>(
+            "transcripts",
+            doc! {
+                "guild_id": ctx.guild_id().unwrap().to_string(),
+                "$text": { "$search": &query },
+            },
+            Some(
+                FindOptions::builder()
+                    .sort(doc! { "score": { "$meta": "textScore" } })
+                    .limit(count.unwrap_or(10))
+                    .build(),
+            ),
+        )
+        .await?;
+
+    let mut fields = Vec::new();
+    while cursor.advance().await? {
+        let transcript: Transcript = cursor.deserialize_current()?;
+
+        let content = transcript.content.unwrap_or_default();
+        let mut excerpt = content.chars().take(EXCERPT_LENGTH).collect::<String>();
+        if content.chars().count() > EXCERPT_LENGTH {
+            excerpt.push('…');
+        }
+
+        fields.push((
+            transcript.title.unwrap_or_default(),
+            format!(
+                "<#{}> resolved by <@{}> {}\n```\n{}\n```",
+                transcript.thread_id.unwrap_or_default(),
+                transcript.resolved_by.unwrap_or_default(),
+                Utc.timestamp_opt(transcript.timestamp.unwrap_or_default(), 0)
+                    .single()
+                    .map(|t| format!("<t:{}:R>", t.timestamp()))
+                    .unwrap_or_default(),
+                excerpt.replace("```", "'''")
+            ),
+            false,
+        ));
+    }
+
+    ctx.send(|f| {
+        f.ephemeral(true).embed(|f| {
+            f.title(format!("Transcripts matching \"{}\"", query))
+                .color(embed_color);
+
+            if fields.is_empty() {
+                f.description("No transcripts found.")
+            } else {
+                f.fields(fields)
+            }
+        })
+    })
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,618 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use poise::serenity_prelude::{
+    self as serenity,
+    ActionRowComponent,
+    ButtonStyle,
+    ChannelId,
+    ChannelType,
+    CreateComponents,
+    CreateEmbed,
+    Guild,
+    InputTextStyle,
+    InteractionResponseType,
+    Member,
+    Message,
+    MessageComponentInteraction,
+    PermissionOverwrite,
+    PermissionOverwriteType,
+    Permissions,
+    RoleId,
+};
+use regex::Regex;
+use tracing::{debug, error};
+
+use crate::model::application::{AutomodAction, AutomodPattern, AutomodRule, Configuration};
+use crate::utils::config_audit::record_configuration_changes;
+use crate::utils::dry_run;
+use crate::{Context, Data, Error};
+
+// How long each step of the setup waits for the administrator
+const STEP_TIMEOUT: Duration = Duration::from_secs(300);
+// The maximal amount of options of a select menu
+const MAX_OPTIONS: usize = 25;
+
+const LOGGING_CHANNEL_QUESTION: &str =
+    "Which channel should moderation actions and automod be logged to?";
+
+/// Automod rules which can be enabled during the setup, as name, description and pattern.
+const AUTOMOD_PRESETS: [(&str, &str, &str); 3] = [
+    (
+        "Invites",
+        "Delete invites to other servers",
+        r"(?i)discord(?:\.gg|(?:app)?\.com/invite)/\w+",
+    ),
+    (
+        "Mass mentions",
+        "Delete messages mentioning five or more users",
+        r"(?:<@!?\d+>.*){5,}",
+    ),
+    (
+        "Nitro scams",
+        "Delete messages offering free Nitro",
+        r"(?i)(?:free|gift).{0,20}nitro",
+    ),
+];
+
+// Members with the mute role can not do any of this in any channel
+const MUTED_PERMISSIONS: Permissions = Permissions::SEND_MESSAGES
+    .union(Permissions::SEND_MESSAGES_IN_THREADS)
+    .union(Permissions::CREATE_PUBLIC_THREADS)
+    .union(Permissions::CREATE_PRIVATE_THREADS)
+    .union(Permissions::ADD_REACTIONS)
+    .union(Permissions::SPEAK);
+
+/// The choices made during the setup.
+#[derive(Default)]
+struct Setup {
+    mute_role: Option<RoleId>,
+    logging_channel: Option<ChannelId>,
+    automod_presets: Vec<usize>,
+    checks: Vec<String>,
+}
+
+impl Setup {
+    fn check(&mut self, passed: bool, description: impl Into<String>) {
+        let symbol = if passed { "✅" } else { "⚠️" };
+        self.checks
+            .push(format!("{} {}", symbol, description.into()));
+    }
+
+    /// Apply the choices to a configuration.
+    fn apply(&self, configuration: &mut Configuration) {
+        if let Some(mute_role) = self.mute_role {
+            configuration.general.mute.role = mute_role.0;
+        }
+        if let Some(logging_channel) = self.logging_channel {
+            configuration.general.logging_channel = logging_channel.0;
+            configuration.automod.queue_channel = logging_channel.0;
+        }
+
+        for &preset in &self.automod_presets {
+            let (name, _, pattern) = AUTOMOD_PRESETS[preset];
+            if configuration.automod.rules.iter().any(|r| r.name == name) {
+                continue;
+            }
+
+            configuration.automod.rules.push(AutomodRule {
+                name: name.to_string(),
+                channels: Vec::new(),
+                patterns: vec![AutomodPattern {
+                    match_field: Regex::new(pattern).expect("The automod presets are valid"),
+                    weight: 1.0,
+                }],
+                queue_threshold: 1.0,
+                action_threshold: 1.0,
+                action: AutomodAction {
+                    delete: true,
+                    mute: None,
+                },
+            });
+        }
+    }
+}
+
+/// The permissions of the bot in the guild and a channel.
+fn bot_permissions(guild: &Guild, bot: &Member, channel: Option<ChannelId>) -> Permissions {
+    match channel.and_then(|c| guild.channels.get(&c)) {
+        Some(serenity::Channel::Guild(channel)) => guild
+            .user_permissions_in(channel, bot)
+            .unwrap_or_else(|_| Permissions::empty()),
+        _ => {
+            // Members always have the permissions of the everyone role, whose id is the guild id
+            let permissions = bot
+                .roles
+                .iter()
+                .chain(std::iter::once(&RoleId(guild.id.0)))
+                .filter_map(|r| guild.roles.get(r))
+                .fold(Permissions::empty(), |p, r| p | r.permissions);
+
+            match permissions.administrator() {
+                true => Permissions::all(),
+                false => permissions,
+            }
+        },
+    }
+}
+
+fn step_embed<'a>(
+    e: &'a mut CreateEmbed,
+    step: usize,
+    description: &str,
+    setup: &Setup,
+    embed_color: i32,
+) -> &'a mut CreateEmbed {
+    e.title(format!("Setup ({}/4)", step))
+        .description(description)
+        .color(embed_color);
+
+    if !setup.checks.is_empty() {
+        e.field("Checks", setup.checks.join("\n"), false);
+    }
+
+    e
+}
+
+fn mute_role_menu<'a>(c: &'a mut CreateComponents, guild: &Guild) -> &'a mut CreateComponents {
+    let mut roles = guild
+        .roles
+        .values()
+        .filter(|r| r.id.0 != guild.id.0 && !r.managed)
+        .collect::<Vec<_>>();
+    roles.sort_by_key(|r| -r.position);
+
+    c.create_action_row(|r| {
+        r.create_select_menu(|m| {
+            m.custom_id("setup:mute_role")
+                .placeholder("Choose the mute role")
+                .options(|o| {
+                    o.create_option(|o| {
+                        o.label("Create a new mute role")
+                            .value("create")
+                            .description("Denies sending messages, reacting and speaking")
+                    });
+                    for role in roles.iter().take(MAX_OPTIONS - 1) {
+                        o.create_option(|o| o.label(&role.name).value(role.id));
+                    }
+                    o
+                })
+        })
+    })
+}
+
+fn logging_channel_menu<'a>(
+    c: &'a mut CreateComponents,
+    guild: &Guild,
+) -> &'a mut CreateComponents {
+    let mut channels = guild
+        .channels
+        .values()
+        .filter_map(|c| c.clone().guild())
+        .filter(|c| c.kind == ChannelType::Text)
+        .collect::<Vec<_>>();
+    channels.sort_by_key(|c| c.position);
+
+    c.create_action_row(|r| {
+        r.create_select_menu(|m| {
+            m.custom_id("setup:logging_channel")
+                .placeholder("Choose the logging channel")
+                .options(|o| {
+                    for channel in channels.iter().take(MAX_OPTIONS) {
+                        o.create_option(|o| {
+                            o.label(format!("#{}", channel.name)).value(channel.id)
+                        });
+                    }
+                    o
+                })
+        })
+    })
+}
+
+fn automod_menu(c: &mut CreateComponents) -> &mut CreateComponents {
+    c.create_action_row(|r| {
+        r.create_select_menu(|m| {
+            m.custom_id("setup:automod")
+                .placeholder("Choose automod rules, or none")
+                .min_values(0)
+                .max_values(AUTOMOD_PRESETS.len() as u64)
+                .options(|o| {
+                    for (i, (name, description, _)) in AUTOMOD_PRESETS.iter().enumerate() {
+                        o.create_option(|o| o.label(name).value(i).description(description));
+                    }
+                    o
+                })
+        })
+    })
+}
+
+fn confirm_buttons(c: &mut CreateComponents) -> &mut CreateComponents {
+    c.create_action_row(|r| {
+        r.create_button(|b| {
+            b.custom_id("setup:save")
+                .label("Save")
+                .style(ButtonStyle::Success)
+        })
+        .create_button(|b| {
+            b.custom_id("setup:cancel")
+                .label("Cancel")
+                .style(ButtonStyle::Secondary)
+        })
+    })
+}
+
+async fn next_component(
+    ctx: Context<'_>,
+    message: &Message,
+) -> Option<Arc<MessageComponentInteraction>> {
+    message
+        .await_component_interaction(ctx.discord())
+        .author_id(ctx.author().id)
+        .timeout(STEP_TIMEOUT)
+        .await
+}
+
+/// Ask for the name of a new mute role, then create it and deny it sending messages everywhere.
+async fn create_mute_role(
+    ctx: Context<'_>,
+    message: &Message,
+    component: &MessageComponentInteraction,
+    guild: &Guild,
+    setup: &mut Setup,
+) -> Result<Option<Arc<serenity::ModalSubmitInteraction>>, Error> {
+    let http = &ctx.discord().http;
+
+    component
+        .create_interaction_response(http, |r| {
+            r.kind(InteractionResponseType::Modal)
+                .interaction_response_data(|d| {
+                    d.custom_id("setup:mute_role_name")
+                        .title("Create a mute role")
+                        .components(|c| {
+                            c.create_action_row(|r| {
+                                r.create_input_text(|t| {
+                                    t.custom_id("name")
+                                        .label("Name")
+                                        .style(InputTextStyle::Short)
+                                        .max_length(100)
+                                        .value("Muted")
+                                        .required(true)
+                                })
+                            })
+                        })
+                })
+        })
+        .await?;
+
+    let submitted = match message
+        .await_modal_interaction(ctx.discord())
+        .author_id(ctx.author().id)
+        .timeout(STEP_TIMEOUT)
+        .await
+    {
+        Some(submitted) => submitted,
+        None => return Ok(None),
+    };
+    let name = submitted
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) => Some(input.value.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| "Muted".to_string());
+
+    let role_id = dry_run::execute(format!("create mute role {}", name), async {
+        guild
+            .id
+            .create_role(http, |r| r.name(&name).permissions(Permissions::empty()))
+            .await
+            .map(|r| Some(r.id))
+    })
+    .await?;
+
+    let role_id = match role_id {
+        Some(role_id) => role_id,
+        None => {
+            setup.check(true, "Dry run: no mute role was created");
+            return Ok(Some(submitted));
+        },
+    };
+    setup.mute_role = Some(role_id);
+
+    let mut failed = 0;
+    for channel in guild.channels.values().filter_map(|c| c.clone().guild()) {
+        let overwrite = PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: MUTED_PERMISSIONS,
+            kind: PermissionOverwriteType::Role(role_id),
+        };
+        if let Err(err) = dry_run::execute(
+            format!("deny the mute role in {}", channel.id),
+            channel.id.create_permission(http, &overwrite),
+        )
+        .await
+        {
+            debug!("Failed to deny the mute role in {}: {:?}", channel.id, err);
+            failed += 1;
+        }
+    }
+    setup.check(failed == 0, match failed {
+        0 => format!("Created <@&{}> and denied it in all channels", role_id),
+        _ => format!(
+            "Created <@&{}>, but failed to deny it in {} channels",
+            role_id, failed
+        ),
+    });
+
+    Ok(Some(submitted))
+}
+
+/// Walk through the configuration of the essentials: the mute role, logging and automod.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn setup(ctx: Context<'_>) -> Result<(), Error> {
+    let guild = ctx
+        .guild()
+        .ok_or("This command can only be used in a server")?;
+    let bot = guild
+        .member(ctx.discord(), ctx.discord().cache.current_user_id())
+        .await?;
+    let embed_color = ctx.data().load_full().configuration.general.embed_color;
+    let http = &ctx.discord().http;
+    let mut setup = Setup::default();
+
+    let permissions = bot_permissions(&guild, &bot, None);
+    setup.check(
+        permissions.manage_roles() && permissions.manage_channels(),
+        "Manage Roles and Manage Channels to create the mute role",
+    );
+
+    let handle = ctx
+        .send(|f| {
+            f.embed(|e| {
+                step_embed(
+                    e,
+                    1,
+                    "Which role should muted members get?",
+                    &setup,
+                    embed_color,
+                )
+            })
+            .components(|c| mute_role_menu(c, &guild))
+        })
+        .await?;
+    let message = handle.message().await?;
+
+    let expire = || async {
+        handle
+            .edit(ctx, |f| {
+                f.embed(|e| e.description("The setup expired.").color(embed_color))
+                    .components(|c| c)
+            })
+            .await
+    };
+
+    // Step 1: The mute role
+    let component = match next_component(ctx, &message).await {
+        Some(component) => component,
+        None => return Ok(expire().await?),
+    };
+    let choice = component.data.values.first().cloned().unwrap_or_default();
+    let bot_position = bot
+        .highest_role_info(ctx.discord())
+        .map_or(0, |(_, position)| position);
+
+    let mut embed = CreateEmbed::default();
+    if choice == "create" {
+        let submitted =
+            match create_mute_role(ctx, &message, &component, &guild, &mut setup).await? {
+                Some(submitted) => submitted,
+                None => return Ok(expire().await?),
+            };
+        step_embed(&mut embed, 2, LOGGING_CHANNEL_QUESTION, &setup, embed_color);
+        submitted
+            .create_interaction_response(http, |r| {
+                r.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| {
+                        d.set_embed(embed)
+                            .components(|c| logging_channel_menu(c, &guild))
+                    })
+            })
+            .await?;
+    } else {
+        let role_id = RoleId(choice.parse()?);
+        let position = guild.roles.get(&role_id).map_or(0, |r| r.position);
+        setup.mute_role = Some(role_id);
+        setup.check(
+            position < bot_position,
+            format!("<@&{}> is below the role of the bot", role_id),
+        );
+
+        step_embed(&mut embed, 2, LOGGING_CHANNEL_QUESTION, &setup, embed_color);
+        component
+            .create_interaction_response(http, |r| {
+                r.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| {
+                        d.set_embed(embed)
+                            .components(|c| logging_channel_menu(c, &guild))
+                    })
+            })
+            .await?;
+    }
+
+    // Step 2: The logging channel
+    let component = match next_component(ctx, &message).await {
+        Some(component) => component,
+        None => return Ok(expire().await?),
+    };
+    let channel_id = ChannelId(
+        component
+            .data
+            .values
+            .first()
+            .ok_or("No channel was chosen")?
+            .parse()?,
+    );
+    setup.logging_channel = Some(channel_id);
+    let permissions = bot_permissions(&guild, &bot, Some(channel_id));
+    setup.check(
+        permissions.view_channel() && permissions.send_messages() && permissions.embed_links(),
+        format!(
+            "View Channel, Send Messages and Embed Links in <#{}>",
+            channel_id
+        ),
+    );
+
+    component
+        .create_interaction_response(http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.embed(|e| {
+                        step_embed(
+                            e,
+                            3,
+                            "Which automod rules should be enabled? Matching messages are deleted \
+                             and logged.",
+                            &setup,
+                            embed_color,
+                        )
+                    })
+                    .components(automod_menu)
+                })
+        })
+        .await?;
+
+    // Step 3: Automod
+    let component = match next_component(ctx, &message).await {
+        Some(component) => component,
+        None => return Ok(expire().await?),
+    };
+    setup.automod_presets = component
+        .data
+        .values
+        .iter()
+        .filter_map(|v| v.parse::<usize>().ok())
+        .filter(|&i| i < AUTOMOD_PRESETS.len())
+        .collect();
+    if !setup.automod_presets.is_empty() {
+        setup.check(
+            bot_permissions(&guild, &bot, None).manage_messages(),
+            "Manage Messages to delete messages matching automod rules",
+        );
+    }
+
+    let summary = format!(
+        "Mute role: {}\nLogging channel: <#{}>\nAutomod rules: {}",
+        setup
+            .mute_role
+            .map_or("Unchanged".to_string(), |r| format!("<@&{}>", r)),
+        channel_id,
+        match setup.automod_presets.is_empty() {
+            true => "None".to_string(),
+            false => setup
+                .automod_presets
+                .iter()
+                .map(|&i| AUTOMOD_PRESETS[i].0)
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    );
+
+    component
+        .create_interaction_response(http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.embed(|e| {
+                        step_embed(
+                            e,
+                            4,
+                            &format!("Save this configuration?\n\n{}", summary),
+                            &setup,
+                            embed_color,
+                        )
+                    })
+                    .components(confirm_buttons)
+                })
+        })
+        .await?;
+
+    // Step 4: Save
+    let component = match next_component(ctx, &message).await {
+        Some(component) => component,
+        None => return Ok(expire().await?),
+    };
+    let status = if component.data.custom_id == "setup:save" {
+        match save(ctx, &setup).await {
+            Ok(_) => {
+                debug!("{} saved the setup", ctx.author().name);
+                "Saved and reloaded the configuration.".to_string()
+            },
+            Err(err) => {
+                error!("Failed to save the setup: {:?}", err);
+                format!("Failed to save the configuration: {}", err)
+            },
+        }
+    } else {
+        "Cancelled the setup.".to_string()
+    };
+
+    component
+        .create_interaction_response(http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.embed(|e| e.description(status).color(embed_color))
+                        .components(|c| c)
+                })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Write the choices to the configuration file and swap in the new configuration.
+async fn save(ctx: Context<'_>, setup: &Setup) -> Result<(), Error> {
+    // Start from the file, so changes which were not reloaded yet are kept
+    let mut configuration = Configuration::load()?;
+    // Only the choices are logged as changes, not what was not reloaded yet
+    let previous = Configuration::load()?;
+    setup.apply(&mut configuration);
+    configuration.save()?;
+    record_configuration_changes(&ctx, &previous, &configuration).await;
+
+    let configuration = Arc::new(configuration);
+    ctx.data().rcu(|data| Data {
+        configuration: configuration.clone(),
+        ..(**data).clone()
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::configuration;
+
+    #[test]
+    fn applies_choices_once() {
+        let mut configuration = configuration(|c| c.automod.rules.clear());
+        let setup = Setup {
+            mute_role: Some(RoleId(1)),
+            logging_channel: Some(ChannelId(2)),
+            automod_presets: vec![0, 2],
+            ..Default::default()
+        };
+
+        setup.apply(&mut configuration);
+        setup.apply(&mut configuration);
+
+        assert_eq!(configuration.general.mute.role, 1);
+        assert_eq!(configuration.automod.queue_channel, 2);
+        assert_eq!(configuration.automod.rules.len(), 2);
+        assert!(configuration.automod.rules[0].patterns[0]
+            .match_field
+            .is_match("join discord.gg/abc"));
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use poise::serenity_prelude::User;
+
+use crate::utils::moderation::is_administrator;
+use crate::utils::strikes::{active_strikes, describe, next_step, step_for, total_points};
+use crate::utils::time::format_timestamp;
+use crate::{Context, Error};
+
+/// Show the active strikes of a user and what the next infraction would lead to.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn strikes(
+    ctx: Context<'_>,
+    #[description = "The user to show the strikes of, defaults to you"] user: Option<User>,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let user = user.unwrap_or_else(|| ctx.author().clone());
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    // Everyone can see their own strikes, but only staff can see the strikes of others
+    if user.id != ctx.author().id {
+        let is_allowed = ctx
+            .author_member()
+            .await
+            .is_some_and(|m| is_administrator(&data.configuration.administrators, &m));
+        if !is_allowed {
+            ctx.send(|f| {
+                f.embed(|e| {
+                    e.title("Permission error")
+                        .description("You can only see your own strikes.")
+                        .color(embed_color)
+                })
+            })
+            .await?;
+            return Ok(());
+        }
+    }
+
+    let policy = &data.configuration.strikes;
+    let strikes =
+        active_strikes(&data.database, &data.configuration, guild_id.0, user.id.0).await?;
+    let points = total_points(&strikes);
+
+    let standing = match step_for(policy, points) {
+        Some(step) if points > 0 => format!(
+            "{} active strikes worth {} points. The last step reached was: {}.",
+            strikes.len(),
+            points,
+            describe(step)
+        ),
+        _ => format!("{} active strikes worth {} points.", strikes.len(), points),
+    };
+    let next = match next_step(policy, points) {
+        Some(step) => format!("{} at {} points", describe(step), step.points),
+        None => "No further action is configured.".to_string(),
+    };
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title(format!("Strikes of {}", user.tag()))
+                .description(standing)
+                .field("Next step", next, false)
+                .thumbnail(user.face())
+                .color(embed_color);
+
+            if !strikes.is_empty() {
+                e.field(
+                    "Active strikes",
+                    strikes
+                        .iter()
+                        .map(|s| {
+                            format!(
+                                "{} ({} points) on <t:{}:d>, decays {}",
+                                s.reason,
+                                s.points,
+                                s.timestamp,
+                                format_timestamp(s.expires)
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    false,
+                );
+            }
+
+            e
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Show the breakdown of the active points of a user by action.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn points(
+    ctx: Context<'_>,
+    #[description = "The user to show the points of"] user: User,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+    let policy = &data.configuration.strikes;
+
+    let strikes =
+        active_strikes(&data.database, &data.configuration, guild_id.0, user.id.0).await?;
+    let points = total_points(&strikes);
+
+    // Points and amount of infractions by action
+    let mut by_action = BTreeMap::<&str, (u32, usize)>::new();
+    for strike in &strikes {
+        let entry = by_action.entry(&strike.action).or_default();
+        entry.0 += strike.points;
+        entry.1 += 1;
+    }
+
+    let current = step_for(policy, points).map_or("None".to_string(), describe);
+    let next = next_step(policy, points).map_or("None".to_string(), |step| {
+        format!("{} at {} points", describe(step), step.points)
+    });
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title(format!("Points of {}", user.tag()))
+                .description(format!("{} active points.", points))
+                .field("Current step", current, true)
+                .field("Next step", next, true)
+                .thumbnail(user.face())
+                .color(data.configuration.general.embed_color);
+
+            if !by_action.is_empty() {
+                e.field(
+                    "Breakdown",
+                    by_action
+                        .iter()
+                        .map(|(action, (points, count))| {
+                            format!("{}: {} points from {} infractions", action, points, count)
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    false,
+                );
+            }
+
+            e
+        })
+    })
+    .await?;
+
+    Ok(())
+}
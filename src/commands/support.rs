@@ -0,0 +1,296 @@
+use chrono::Utc;
+use poise::serenity_prelude::{
+    self as serenity,
+    ActionRowComponent,
+    ButtonStyle,
+    ChannelType,
+    CreateComponents,
+    CreateEmbed,
+    InputTextStyle,
+    InteractionResponseType,
+};
+use tracing::error;
+
+use crate::db::model::Resolution;
+use crate::utils::dry_run;
+use crate::utils::reply_draft::{request_draft, MAX_REPLY_LENGTH};
+use crate::utils::support::similar_faq;
+use crate::utils::transcript::save_transcript;
+use crate::{Context, Error};
+
+/// Mark the current support thread as resolved.
+#[poise::command(slash_command, guild_only)]
+pub async fn resolve(
+    ctx: Context<'_>,
+    #[description = "A summary of the solution"] summary: String,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let configuration = &data.configuration;
+
+    let thread = ctx
+        .channel_id()
+        .to_channel(ctx.discord())
+        .await?
+        .guild()
+        .filter(|channel| channel.kind == ChannelType::PublicThread)
+        .ok_or("This command can only be used in threads")?;
+    let parent_id = thread
+        .parent_id
+        .ok_or("This thread has no parent channel")?;
+
+    if !configuration.support.channels.contains(&parent_id.0) {
+        return Err(Error::from("This thread is not in a support channel"));
+    }
+
+    data.database
+        .insert::<Resolution>(
+            "resolutions",
+            Resolution {
+                thread_id: Some(thread.id.to_string()),
+                channel_id: Some(parent_id.to_string()),
+                guild_id: Some(thread.guild_id.to_string()),
+                title: Some(thread.name.clone()),
+                summary: Some(summary.clone()),
+                resolved_by: Some(ctx.author().id.to_string()),
+                timestamp: Some(Utc::now().timestamp()),
+            },
+            None,
+        )
+        .await?;
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title("Resolved")
+                .description(&summary)
+                .color(configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    // A missing transcript should not keep the thread from being resolved
+    if let Err(err) = save_transcript(&ctx.discord().http, &data, &thread, ctx.author().id).await {
+        error!(
+            "Failed to save the transcript of {}: {:?}",
+            thread.name, err
+        );
+    }
+
+    // Archive the thread after responding, because archived threads can not be responded in
+    dry_run::execute(format!("archive {}", thread.name), async {
+        ctx.channel_id()
+            .edit_thread(ctx.discord(), |t| t.archived(true))
+            .await
+            .map(|_| ())
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Answer a frequently asked question.
+#[poise::command(slash_command)]
+pub async fn faq(
+    ctx: Context<'_>,
+    #[description = "The question to answer"] topic: String,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let configuration = &data.configuration;
+
+    // Choices are truncated when registered, so compare the truncated question
+    let faq = configuration
+        .support
+        .faq
+        .iter()
+        .find(|f| f.question.chars().take(100).collect::<String>() == topic)
+        .ok_or("This question is not in the FAQ")?;
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title(&faq.question)
+                .description(&faq.url)
+                .url(&faq.url)
+                .color(configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+// How long a draft waits for the staff member to act on it
+const DRAFT_TIMEOUT_SECS: u64 = 600;
+
+fn describe_draft<'a>(
+    e: &'a mut CreateEmbed,
+    draft: &str,
+    embed_color: i32,
+) -> &'a mut CreateEmbed {
+    e.title("Draft reply")
+        .description(draft)
+        .footer(|f| f.text("Only you can see this draft until you send it."))
+        .color(embed_color)
+}
+
+fn draft_buttons(c: &mut CreateComponents) -> &mut CreateComponents {
+    c.create_action_row(|r| {
+        r.create_button(|b| {
+            b.custom_id("draftreply:send")
+                .label("Send")
+                .style(ButtonStyle::Success)
+        })
+        .create_button(|b| {
+            b.custom_id("draftreply:edit")
+                .label("Edit")
+                .style(ButtonStyle::Primary)
+        })
+        .create_button(|b| {
+            b.custom_id("draftreply:discard")
+                .label("Discard")
+                .style(ButtonStyle::Secondary)
+        })
+    })
+}
+
+/// Draft a reply to a support question, which can be edited before the bot sends it.
+#[poise::command(context_menu_command = "Draft reply", guild_only, ephemeral = true)]
+pub async fn draft_reply(
+    ctx: Context<'_>,
+    #[description = "The question to reply to"] message: serenity::Message,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let support = &data.configuration.support;
+    let embed_color = data.configuration.general.embed_color;
+    if !support.reply_drafting.enabled {
+        return Err("Reply drafting is not enabled".into());
+    }
+    if message.content.trim().is_empty() {
+        return Err("This message has no question to reply to".into());
+    }
+
+    // Language models take a while to respond
+    ctx.defer_ephemeral().await?;
+
+    let faq = similar_faq(support, &message.content);
+    let mut draft = request_draft(&support.reply_drafting, &message.content, &faq).await?;
+
+    let handle = ctx
+        .send(|f| {
+            f.embed(|e| describe_draft(e, &draft, embed_color))
+                .components(draft_buttons)
+        })
+        .await?;
+    let response = handle.message().await?;
+    let http = &ctx.discord().http;
+
+    let status = loop {
+        let component = response
+            .await_component_interaction(ctx.discord())
+            .author_id(ctx.author().id)
+            .timeout(std::time::Duration::from_secs(DRAFT_TIMEOUT_SECS))
+            .await;
+        let component = match component {
+            Some(component) => component,
+            None => break None,
+        };
+
+        match component.data.custom_id.as_str() {
+            "draftreply:edit" => {
+                component
+                    .create_interaction_response(http, |r| {
+                        r.kind(InteractionResponseType::Modal)
+                            .interaction_response_data(|d| {
+                                d.custom_id("draftreply:modal")
+                                    .title("Edit reply")
+                                    .components(|c| {
+                                        c.create_action_row(|r| {
+                                            r.create_input_text(|t| {
+                                                t.custom_id("content")
+                                                    .label("Reply")
+                                                    .style(InputTextStyle::Paragraph)
+                                                    .max_length(MAX_REPLY_LENGTH as u64)
+                                                    .value(&draft)
+                                                    .required(true)
+                                            })
+                                        })
+                                    })
+                            })
+                    })
+                    .await?;
+
+                // Closing the modal leaves the draft as it was
+                let submitted = match response
+                    .await_modal_interaction(ctx.discord())
+                    .author_id(ctx.author().id)
+                    .timeout(std::time::Duration::from_secs(DRAFT_TIMEOUT_SECS))
+                    .await
+                {
+                    Some(submitted) => submitted,
+                    None => continue,
+                };
+
+                if let Some(content) = submitted
+                    .data
+                    .components
+                    .iter()
+                    .flat_map(|row| &row.components)
+                    .find_map(|component| match component {
+                        ActionRowComponent::InputText(input) => Some(input.value.clone()),
+                        _ => None,
+                    })
+                {
+                    draft = content;
+                }
+
+                submitted
+                    .create_interaction_response(http, |r| {
+                        r.kind(InteractionResponseType::UpdateMessage)
+                            .interaction_response_data(|d| {
+                                d.embed(|e| describe_draft(e, &draft, embed_color))
+                                    .components(draft_buttons)
+                            })
+                    })
+                    .await?;
+            },
+            "draftreply:send" => {
+                let status =
+                    match dry_run::execute(format!("reply to {} with a draft", message.id), async {
+                        message.reply(http, &draft).await.map(|_| ())
+                    })
+                    .await
+                    {
+                        Ok(_) => "Sent the reply.".to_string(),
+                        Err(err) => {
+                            error!("Failed to send drafted reply: {:?}", err);
+                            format!("Failed to send the reply: {}", err)
+                        },
+                    };
+                break Some((component, status));
+            },
+            _ => break Some((component, "Discarded the draft.".to_string())),
+        }
+    };
+
+    match status {
+        Some((component, status)) => {
+            component
+                .create_interaction_response(http, |r| {
+                    r.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|d| {
+                            d.embed(|e| e.description(&status).color(embed_color))
+                                .components(|c| c)
+                        })
+                })
+                .await?
+        },
+        None => {
+            handle
+                .edit(ctx, |f| {
+                    f.embed(|e| e.description("The draft expired.").color(embed_color))
+                        .components(|c| c)
+                })
+                .await?
+        },
+    }
+
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use bson::doc;
+use poise::serenity_prelude::User;
+
+use crate::db::model::VoiceTime;
+use crate::utils::voice_time::{current_session, days_ago, format_voice_time};
+use crate::{Context, Error};
+
+// The amount of members to show on the leaderboard
+const LEADERBOARD_SIZE: i64 = 10;
+const DEFAULT_LEADERBOARD_DAYS: i64 = 30;
+
+/// See how long members spent in voice channels.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("voicetime_user", "voicetime_leaderboard")
+)]
+pub async fn voicetime(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show how long a member spent in voice channels.
+#[poise::command(slash_command, guild_only, ephemeral = true, rename = "user")]
+pub async fn voicetime_user(
+    ctx: Context<'_>,
+    #[description = "The member, yourself by default"] user: Option<User>,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let data = ctx.data().load_full();
+
+    let days = data
+        .database
+        .collect::<VoiceTime>(
+            "voice_time",
+            doc! { "guild_id": guild_id.to_string(), "user_id": user.id.to_string() },
+        )
+        .await?;
+
+    // The current session is only recorded when the member leaves
+    let current = current_session(guild_id, user.id).await;
+    let total_since = |since: Option<String>| {
+        current
+            + days
+                .iter()
+                .filter(|d| {
+                    since
+                        .as_ref()
+                        .is_none_or(|since| d.day.as_ref().is_some_and(|day| day >= since))
+                })
+                .filter_map(|d| d.seconds)
+                .sum::<i64>()
+    };
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title(format!("Voice time of {}", user.tag()))
+                .field(
+                    "Last 7 days",
+                    format_voice_time(total_since(Some(days_ago(6)))),
+                    true,
+                )
+                .field(
+                    "Last 30 days",
+                    format_voice_time(total_since(Some(days_ago(29)))),
+                    true,
+                )
+                .field("All time", format_voice_time(total_since(None)), true)
+                .thumbnail(user.face())
+                .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Show the members who spent the most time in voice channels.
+#[poise::command(slash_command, guild_only, ephemeral = true, rename = "leaderboard")]
+pub async fn voicetime_leaderboard(
+    ctx: Context<'_>,
+    #[min = 1]
+    #[max = 365]
+    #[description = "The amount of recent days to count, 30 by default"]
+    days: Option<i64>,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let days = days.unwrap_or(DEFAULT_LEADERBOARD_DAYS);
+    let data = ctx.data().load_full();
+
+    let mut cursor = data
+        .database
+        .aggregate("voice_time", vec![
+            doc! { "$match": {
+                "guild_id": guild_id.to_string(),
+                "day": { "$gte": days_ago(days - 1) },
+            } },
+            doc! { "$group": { "_id": "$user_id", "seconds": { "$sum": "$seconds" } } },
+            doc! { "$sort": { "seconds": -1 } },
+            doc! { "$limit": LEADERBOARD_SIZE },
+        ])
+        .await?;
+
+    let mut ranking = Vec::new();
+    while cursor.advance().await? {
+        let entry = cursor.deserialize_current()?;
+        ranking.push(format!(
+            "{}. <@{}>: {}",
+            ranking.len() + 1,
+            entry.get_str("_id").unwrap_or_default(),
+            format_voice_time(entry.get_i64("seconds").unwrap_or_default())
+        ));
+    }
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title(format!("Voice leaderboard of the last {} days", days))
+                .description(if ranking.is_empty() {
+                    "Nobody has spent time in voice channels yet.".to_string()
+                } else {
+                    ranking.join("\n")
+                })
+                .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use bson::{doc, Document};
+use chrono::Utc;
+use mongodb::options::{UpdateModifications, UpdateOptions};
+use poise::serenity_prelude::Member;
+use tracing::{debug, error};
+
+use crate::db::model::Vouch;
+use crate::utils::moderation::is_administrator;
+use crate::utils::vouch::open_poll;
+use crate::{Context, Error};
+
+/// Vouch for a member to become a helper.
+#[poise::command(slash_command, guild_only, ephemeral = true)]
+pub async fn vouch(
+    ctx: Context<'_>,
+    #[description = "The member to vouch for"] member: Member,
+    #[description = "Why the member would be a good helper"] reason: Option<String>,
+) -> Result<(), Error> {
+    let guild_id = ctx
+        .guild_id()
+        .ok_or("This command can only be used in a server")?;
+    let data = ctx.data().load_full();
+    let vouching = &data.configuration.vouching;
+    if vouching.helper_role == 0 || vouching.threshold == 0 {
+        return Err("Vouching is not enabled".into());
+    }
+
+    // Only helpers can vouch, unless other roles are configured
+    let author = ctx
+        .author_member()
+        .await
+        .ok_or("Failed to fetch your member")?;
+    let can_vouch = is_administrator(&data.configuration.administrators, &author)
+        || author.roles.iter().any(|r| {
+            if vouching.voucher_roles.is_empty() {
+                r.0 == vouching.helper_role
+            } else {
+                vouching.voucher_roles.contains(&r.0)
+            }
+        });
+    if !can_vouch {
+        return Err("Only helpers can vouch for members".into());
+    }
+
+    if member.user.id == author.user.id {
+        return Err("You can not vouch for yourself".into());
+    }
+    if member.roles.iter().any(|r| r.0 == vouching.helper_role) {
+        return Err(format!("{} is already a helper", member.user.tag()).into());
+    }
+
+    let vouch: Document = Vouch {
+        reason: reason.clone(),
+        timestamp: Some(Utc::now().timestamp()),
+        ..Default::default()
+    }
+    .into();
+    let result = data
+        .database
+        .update::<Vouch>(
+            "vouches",
+            doc! {
+                "guild_id": guild_id.to_string(),
+                "user_id": member.user.id.to_string(),
+                "voucher_id": author.user.id.to_string(),
+            },
+            UpdateModifications::Document(doc! { "$setOnInsert": vouch }),
+            Some(UpdateOptions::builder().upsert(true).build()),
+        )
+        .await?;
+    if result.upserted_id.is_none() {
+        return Err(format!("You already vouched for {}", member.user.tag()).into());
+    }
+
+    let vouches = data
+        .database
+        .collect::<Vouch>(
+            "vouches",
+            doc! { "guild_id": guild_id.to_string(), "user_id": member.user.id.to_string() },
+        )
+        .await?;
+
+    debug!(
+        "{} vouched for {} ({}/{})",
+        author.user.tag(),
+        member.user.tag(),
+        vouches.len(),
+        vouching.threshold
+    );
+
+    if vouches.len() >= vouching.threshold {
+        if let Err(err) = open_poll(ctx.discord(), &data, guild_id, &member.user, &vouches).await {
+            error!("Failed to open helper poll: {:?}", err);
+        }
+    }
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title(format!("Vouched for {}", member.user.tag()))
+                .description(format!(
+                    "{} of {} vouches needed for a staff poll.",
+                    vouches.len(),
+                    vouching.threshold
+                ))
+                .color(data.configuration.general.embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use bson::doc;
+use mongodb::options::{UpdateModifications, UpdateOptions};
+use tracing::debug;
+
+use crate::db::model::Watchword;
+use crate::utils::watchword::compile;
+use crate::{Context, Error};
+
+/// Manage your watchwords, which alert you of matching messages.
+#[poise::command(
+    slash_command,
+    guild_only,
+    subcommands("watchword_add", "watchword_remove", "watchword_list")
+)]
+pub async fn watchword(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Get alerted of messages matching a pattern.
+#[poise::command(slash_command, guild_only, ephemeral = true, rename = "add")]
+pub async fn watchword_add(
+    ctx: Context<'_>,
+    #[description = "A regular expression, matched case insensitively"] pattern: String,
+    #[description = "Whether to alert you as a direct message instead of in the staff channel"]
+    direct_message: Option<bool>,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    let user_id = ctx.author().id.to_string();
+
+    let subscribed = data
+        .database
+        .collect::<Watchword>(
+            "watchwords",
+            Watchword {
+                guild_id: Some(guild_id.clone()),
+                user_id: Some(user_id.clone()),
+                ..Default::default()
+            }
+            .into(),
+        )
+        .await?;
+    let is_new = !subscribed
+        .iter()
+        .any(|w| w.pattern.as_ref() == Some(&pattern));
+
+    let description = if let Err(err) = compile(&pattern) {
+        format!("Invalid pattern `{}`: {}", pattern, err)
+    } else if is_new && subscribed.len() >= data.configuration.watchwords.max_per_user {
+        format!(
+            "You can not subscribe to more than {} watchwords.",
+            data.configuration.watchwords.max_per_user
+        )
+    } else {
+        let updated: bson::Document = Watchword {
+            direct_message: Some(direct_message.unwrap_or_default()),
+            ..Default::default()
+        }
+        .into();
+
+        data.database
+            .update::<Watchword>(
+                "watchwords",
+                Watchword {
+                    guild_id: Some(guild_id),
+                    user_id: Some(user_id),
+                    pattern: Some(pattern.clone()),
+                    ..Default::default()
+                }
+                .into(),
+                UpdateModifications::Document(doc! { "$set": updated }),
+                Some(UpdateOptions::builder().upsert(true).build()),
+            )
+            .await?;
+
+        debug!("{} added the watchword {}", ctx.author().name, pattern);
+        format!("Added the watchword `{}`.", pattern)
+    };
+
+    ctx.send(|f| f.embed(|e| e.description(description).color(embed_color)))
+        .await?;
+
+    Ok(())
+}
+
+/// Stop getting alerted of messages matching a pattern.
+#[poise::command(slash_command, guild_only, ephemeral = true, rename = "remove")]
+pub async fn watchword_remove(
+    ctx: Context<'_>,
+    #[description = "The pattern to remove"] pattern: String,
+) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let removed = data
+        .database
+        .find_and_delete::<Watchword>(
+            "watchwords",
+            Watchword {
+                guild_id: Some(ctx.guild_id().unwrap().to_string()),
+                user_id: Some(ctx.author().id.to_string()),
+                pattern: Some(pattern.clone()),
+                ..Default::default()
+            }
+            .into(),
+            None,
+        )
+        .await?;
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.description(if removed.is_some() {
+                format!("Removed the watchword `{}`.", pattern)
+            } else {
+                format!("You are not subscribed to `{}`.", pattern)
+            })
+            .color(embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// List your watchwords.
+#[poise::command(slash_command, guild_only, ephemeral = true, rename = "list")]
+pub async fn watchword_list(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data().load_full();
+    let embed_color = data.configuration.general.embed_color;
+
+    let watchwords = data
+        .database
+        .collect::<Watchword>(
+            "watchwords",
+            Watchword {
+                guild_id: Some(ctx.guild_id().unwrap().to_string()),
+                user_id: Some(ctx.author().id.to_string()),
+                ..Default::default()
+            }
+            .into(),
+        )
+        .await?;
+
+    let description = if watchwords.is_empty() {
+        "You are not subscribed to any watchwords.".to_string()
+    } else {
+        watchwords
+            .iter()
+            .map(|w| {
+                format!(
+                    "`{}` {}",
+                    w.pattern.as_deref().unwrap_or_default(),
+                    if w.direct_message.unwrap_or_default() {
+                        "(direct message)"
+                    } else {
+                        "(staff channel)"
+                    }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    ctx.send(|f| {
+        f.embed(|e| {
+            e.title("Watchwords")
+                .description(description)
+                .color(embed_color)
+        })
+    })
+    .await?;
+
+    Ok(())
+}
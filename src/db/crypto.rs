@@ -0,0 +1,104 @@
+use std::env;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use tracing::{error, warn};
+
+// Prefix to distinguish encrypted values from legacy plaintext values
+const PREFIX: &str = "enc:";
+const NONCE_LENGTH: usize = 12;
+
+static CIPHER: OnceCell<Option<Aes256Gcm>> = OnceCell::new();
+
+/// Initialize the cipher from the base64 encoded 256-bit `DATABASE_ENCRYPTION_KEY` environment variable.
+pub fn init() {
+    CIPHER.get_or_init(|| {
+        let key = match env::var("DATABASE_ENCRYPTION_KEY") {
+            Ok(key) => key,
+            Err(_) => {
+                warn!("DATABASE_ENCRYPTION_KEY not set, sensitive fields are stored in plaintext");
+                return None;
+            },
+        };
+
+        let key = base64::decode(key).expect("DATABASE_ENCRYPTION_KEY is not valid base64");
+        Some(
+            Aes256Gcm::new_from_slice(&key).expect("DATABASE_ENCRYPTION_KEY must be 32 bytes long"),
+        )
+    });
+}
+
+fn cipher() -> Option<&'static Aes256Gcm> {
+    CIPHER.get().and_then(Option::as_ref)
+}
+
+pub fn encrypt(plaintext: &str) -> String {
+    let cipher = match cipher() {
+        Some(cipher) => cipher,
+        None => return plaintext.to_string(),
+    };
+
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    match cipher.encrypt(&nonce, plaintext.as_bytes()) {
+        Ok(ciphertext) => {
+            let mut payload = nonce.to_vec();
+            payload.extend(ciphertext);
+            format!("{}{}", PREFIX, base64::encode(payload))
+        },
+        Err(err) => {
+            error!("Failed to encrypt value: {:?}", err);
+            plaintext.to_string()
+        },
+    }
+}
+
+pub fn decrypt(value: &str) -> String {
+    let payload = match value.strip_prefix(PREFIX) {
+        Some(payload) => payload,
+        None => return value.to_string(), // not encrypted
+    };
+
+    let cipher = match cipher() {
+        Some(cipher) => cipher,
+        None => {
+            warn!("Found encrypted value but DATABASE_ENCRYPTION_KEY is not set");
+            return value.to_string();
+        },
+    };
+
+    match base64::decode(payload)
+        .ok()
+        .filter(|payload| payload.len() > NONCE_LENGTH)
+        .and_then(|payload| {
+            let (nonce, ciphertext) = payload.split_at(NONCE_LENGTH);
+            cipher.decrypt(Nonce::from_slice(nonce), ciphertext).ok()
+        })
+        .and_then(|plaintext| String::from_utf8(plaintext).ok())
+    {
+        Some(plaintext) => plaintext,
+        None => {
+            error!("Failed to decrypt value");
+            value.to_string()
+        },
+    }
+}
+
+/// Serde adapter for optional sensitive strings, use with `#[serde(with = "crate::db::crypto::encrypted")]`.
+pub mod encrypted {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<String>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.as_deref().map(encrypt).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<String>, D::Error> {
+        Ok(Option::<String>::deserialize(deserializer)?.map(|value| decrypt(&value)))
+    }
+}
@@ -1,18 +1,21 @@
 use std::error::Error;
+use std::time::{Duration, Instant};
 
-use bson::Document;
+use bson::{doc, Document};
 use mongodb::options::{
     ClientOptions,
     DeleteOptions,
     FindOneAndDeleteOptions,
+    FindOneAndUpdateOptions,
     FindOptions,
+    IndexOptions,
     InsertOneOptions,
     ResolverConfig,
     UpdateModifications,
     UpdateOptions,
 };
 use mongodb::results::{DeleteResult, InsertOneResult, UpdateResult};
-use mongodb::{Client, Collection, Cursor};
+use mongodb::{Client, Collection, Cursor, IndexModel};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
@@ -54,6 +57,21 @@ impl Database {
         Ok(result)
     }
 
+    pub async fn update_many<T>(
+        &self,
+        collection: &str,
+        query: Document,
+        update_modifications: UpdateModifications,
+        options: Option<UpdateOptions>,
+    ) -> Result<UpdateResult, Box<dyn Error + Send + Sync>> {
+        let result = self
+            .open::<T>(collection)
+            .update_many(query, update_modifications, options)
+            .await?;
+
+        Ok(result)
+    }
+
     pub async fn find<T>(
         &self,
         collection: &str,
@@ -65,6 +83,19 @@ impl Database {
         Ok(cursor)
     }
 
+    pub async fn aggregate(
+        &self,
+        collection: &str,
+        pipeline: Vec<Document>,
+    ) -> Result<Cursor<Document>, Box<dyn Error + Send + Sync>> {
+        let cursor = self
+            .open::<Document>(collection)
+            .aggregate(pipeline, None)
+            .await?;
+
+        Ok(cursor)
+    }
+
     pub async fn find_and_delete<T: DeserializeOwned>(
         &self,
         collection: &str,
@@ -79,7 +110,72 @@ impl Database {
         Ok(result)
     }
 
-    #[allow(dead_code)]
+    pub async fn find_and_update<T: DeserializeOwned>(
+        &self,
+        collection: &str,
+        filter: Document,
+        update_modifications: UpdateModifications,
+        options: Option<FindOneAndUpdateOptions>,
+    ) -> Result<Option<T>, Box<dyn Error + Send + Sync>> {
+        let result = self
+            .open(collection)
+            .find_one_and_update(filter, update_modifications, options)
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn collect<T: DeserializeOwned + Unpin + Send + Sync>(
+        &self,
+        collection: &str,
+        filter: Document,
+    ) -> Result<Vec<T>, Box<dyn Error + Send + Sync>> {
+        let mut cursor = self.find::<T>(collection, filter, None).await?;
+
+        let mut documents = Vec::new();
+        while cursor.advance().await? {
+            documents.push(cursor.deserialize_current()?);
+        }
+
+        Ok(documents)
+    }
+
+    pub async fn create_index(
+        &self,
+        collection: &str,
+        keys: Document,
+        unique: bool,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let index = IndexModel::builder()
+            .keys(keys)
+            .options(IndexOptions::builder().unique(unique).build())
+            .build();
+        let result = self
+            .open::<Document>(collection)
+            .create_index(index, None)
+            .await?;
+
+        Ok(result.index_name)
+    }
+
+    pub async fn create_ttl_index(
+        &self,
+        collection: &str,
+        field: &str,
+        expire_after: Duration,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let index = IndexModel::builder()
+            .keys(doc! { field: 1 })
+            .options(IndexOptions::builder().expire_after(expire_after).build())
+            .build();
+        let result = self
+            .open::<Document>(collection)
+            .create_index(index, None)
+            .await?;
+
+        Ok(result.index_name)
+    }
+
     pub async fn insert<T: Serialize>(
         &self,
         collection: &str,
@@ -91,7 +187,6 @@ impl Database {
         Ok(result)
     }
 
-    #[allow(dead_code)]
     pub async fn delete(
         &self,
         collection: &str,
@@ -105,4 +200,28 @@ impl Database {
 
         Ok(result)
     }
+
+    pub async fn delete_many(
+        &self,
+        collection: &str,
+        query: Document,
+        options: Option<DeleteOptions>,
+    ) -> Result<DeleteResult, Box<dyn Error + Send + Sync>> {
+        let result = self
+            .open::<Document>(collection)
+            .delete_many(query, options)
+            .await?;
+
+        Ok(result)
+    }
+
+    pub async fn ping(&self) -> Result<Duration, Box<dyn Error + Send + Sync>> {
+        let start = Instant::now();
+        self.client
+            .database(&self.database)
+            .run_command(doc! { "ping": 1 }, None)
+            .await?;
+
+        Ok(start.elapsed())
+    }
 }
@@ -0,0 +1,43 @@
+use std::error::Error;
+use std::future::Future;
+
+use bson::Document;
+use serde::de::DeserializeOwned;
+
+use super::database::Database;
+
+type StoreResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Reading documents independently of MongoDB, so logic built on it can be tested against the
+/// in-memory document store of the testing module.
+pub trait DocumentStore {
+    fn find_documents(
+        &self,
+        collection: &str,
+        filter: Document,
+    ) -> impl Future<Output = StoreResult<Vec<Document>>> + Send;
+}
+
+/// The records of a collection matching a filter.
+pub async fn collect<T: DeserializeOwned>(
+    store: &impl DocumentStore,
+    collection: &str,
+    filter: Document,
+) -> StoreResult<Vec<T>> {
+    store
+        .find_documents(collection, filter)
+        .await?
+        .into_iter()
+        .map(|document| Ok(bson::from_document(document)?))
+        .collect()
+}
+
+impl DocumentStore for Database {
+    async fn find_documents(
+        &self,
+        collection: &str,
+        filter: Document,
+    ) -> StoreResult<Vec<Document>> {
+        self.collect::<Document>(collection, filter).await
+    }
+}
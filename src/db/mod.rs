@@ -1,2 +1,4 @@
+pub mod crypto;
 pub mod database;
+pub mod document_store;
 pub mod model;
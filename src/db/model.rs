@@ -1,7 +1,8 @@
+use std::collections::HashMap;
 use std::fmt::Display;
 
 use bson::Document;
-use poise::serenity_prelude::{PermissionOverwrite};
+use poise::serenity_prelude::{PermissionOverwrite, VerificationLevel};
 use serde::{Deserialize, Serialize};
 use serde_with_macros::skip_serializing_none;
 
@@ -12,10 +13,23 @@ pub struct Muted {
     pub user_id: Option<String>,
     pub guild_id: Option<String>,
     pub taken_roles: Option<Vec<String>>,
+    pub mute_roles: Option<Vec<String>>,
     pub expires: Option<u64>,
+    #[serde(default, with = "crate::db::crypto::encrypted")]
     pub reason: Option<String>,
 }
 
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Warning {
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub moderator_id: Option<String>,
+    #[serde(default, with = "crate::db::crypto::encrypted")]
+    pub reason: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
 #[skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct LockedChannel {
@@ -23,18 +37,675 @@ pub struct LockedChannel {
     pub overwrites: Option<Vec<PermissionOverwrite>>,
 }
 
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Quote {
+    pub message_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub guild_id: Option<String>,
+    pub author_id: Option<String>,
+    pub author_tag: Option<String>,
+    pub content: Option<String>,
+    pub attachments: Option<Vec<String>>,
+    pub quoted_by: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Alias {
+    pub guild_id: Option<String>,
+    pub name: Option<String>,
+    pub command: Option<String>,
+    pub arguments: Option<HashMap<String, String>>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct InteractionLog {
+    pub user_id: Option<String>,
+    pub guild_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub command: Option<String>,
+    pub arguments: Option<Vec<String>>,
+    pub success: Option<bool>,
+    pub error: Option<String>,
+    pub latency: Option<i64>,
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AutomodHitStatus {
+    Queued,
+    Approved,
+    Dismissed,
+    Actioned,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct AutomodHit {
+    pub message_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub rule: Option<String>,
+    pub content: Option<String>,
+    pub score: Option<f64>,
+    pub status: Option<AutomodHitStatus>,
+    pub moderator_id: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct UserPreferences {
+    pub user_id: Option<String>,
+    pub timezone: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Resolution {
+    pub thread_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub guild_id: Option<String>,
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub resolved_by: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SupportResponse {
+    pub thread_id: Option<String>,
+    pub guild_id: Option<String>,
+    pub responder_id: Option<String>,
+    // Seconds from the creation of the thread to the first response of staff
+    pub response_time: Option<i64>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Transcript {
+    pub thread_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub guild_id: Option<String>,
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub resolved_by: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Watchword {
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub pattern: Option<String>,
+    pub direct_message: Option<bool>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Activity {
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub last_message: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct QueuedJoin {
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Monitor {
+    pub guild_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub thread_id: Option<String>,
+    pub keywords: Option<Vec<String>>,
+    pub moderator_id: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct LoggedMessage {
+    pub guild_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub message_id: Option<String>,
+    pub author_id: Option<String>,
+    pub event: Option<MessageEvent>,
+    pub content: Option<String>,
+    pub previous_content: Option<String>,
+    pub attachments: Option<Vec<String>>,
+    // A date, so the entry can expire by a TTL index
+    pub logged_at: Option<bson::DateTime>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageEvent {
+    Edit,
+    Delete,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ConfigChange {
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub command: Option<String>,
+    pub subject: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct VoiceTime {
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub day: Option<String>,
+    pub seconds: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct EventReminder {
+    pub event_id: Option<String>,
+    pub offset: Option<i64>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct QuizQuestion {
+    pub guild_id: Option<String>,
+    pub topic: Option<String>,
+    pub question: Option<String>,
+    pub answers: Option<Vec<String>>,
+    pub correct: Option<i32>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct QuizScore {
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub score: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Giveaway {
+    pub message_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub guild_id: Option<String>,
+    pub host_id: Option<String>,
+    pub prize: Option<String>,
+    pub winners: Option<i32>,
+    pub required_role: Option<String>,
+    pub expires: Option<i64>,
+    pub entrants: Option<Vec<String>>,
+    pub winner_ids: Option<Vec<String>>,
+    pub ended: Option<bool>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct GiveawayEntry {
+    pub message_id: Option<String>,
+    pub user_id: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JuryMuteStatus {
+    Pending,
+    Upheld,
+    Reverted,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct JuryMute {
+    pub message_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub content: Option<String>,
+    pub voters: Option<Vec<String>>,
+    pub status: Option<JuryMuteStatus>,
+    pub moderator_id: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PinRequestStatus {
+    Pending,
+    Approved,
+    Denied,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PinRequest {
+    pub message_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub guild_id: Option<String>,
+    pub voters: Option<Vec<String>>,
+    pub status: Option<PinRequestStatus>,
+    pub moderator_id: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Case {
+    pub guild_id: Option<String>,
+    pub case_id: Option<i64>,
+    pub action: Option<String>,
+    pub user_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub moderator_id: Option<String>,
+    #[serde(default, with = "crate::db::crypto::encrypted")]
+    pub reason: Option<String>,
+    pub timestamp: Option<i64>,
+    pub edited_by: Option<String>,
+    pub edited: Option<i64>,
+    pub approver_id: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Note {
+    pub guild_id: Option<String>,
+    pub note_id: Option<i64>,
+    pub user_id: Option<String>,
+    pub moderator_id: Option<String>,
+    #[serde(default, with = "crate::db::crypto::encrypted")]
+    pub content: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AppealStatus {
+    Pending,
+    Accepted,
+    Denied,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Appeal {
+    pub guild_id: Option<String>,
+    pub appeal_id: Option<i64>,
+    pub user_id: Option<String>,
+    pub action: Option<String>,
+    #[serde(default, with = "crate::db::crypto::encrypted")]
+    pub content: Option<String>,
+    pub status: Option<AppealStatus>,
+    pub moderator_id: Option<String>,
+    pub timestamp: Option<i64>,
+    pub decided: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Panic {
+    pub guild_id: Option<String>,
+    pub moderator_id: Option<String>,
+    pub verification_level: Option<VerificationLevel>,
+    pub invites_disabled: Option<bool>,
+    pub locked_channels: Option<Vec<String>>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Lockdown {
+    pub guild_id: Option<String>,
+    pub moderator_id: Option<String>,
+    pub locked_channels: Option<Vec<String>>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Counter {
+    pub name: Option<String>,
+    pub value: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Vouch {
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub voucher_id: Option<String>,
+    pub reason: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ElevationStatus {
+    Pending,
+    Approved,
+    Denied,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Elevation {
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub vouchers: Option<Vec<String>>,
+    pub poll_message_id: Option<String>,
+    pub status: Option<ElevationStatus>,
+    pub moderator_id: Option<String>,
+    pub opened: Option<i64>,
+    pub decided: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BetaStatus {
+    Pending,
+    Approved,
+    Denied,
+    Revoked,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct BetaRequest {
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub status: Option<BetaStatus>,
+    pub moderator_id: Option<String>,
+    pub requested: Option<i64>,
+    pub decided: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TempBan {
+    pub guild_id: Option<String>,
+    pub user_id: Option<String>,
+    pub moderator_id: Option<String>,
+    pub expires: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct InvitePause {
+    pub guild_id: Option<String>,
+    pub moderator_id: Option<String>,
+    pub expires: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SlowmodeReset {
+    pub guild_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub moderator_id: Option<String>,
+    pub previous: Option<u64>,
+    pub expires: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Rule {
+    pub guild_id: Option<String>,
+    pub number: Option<i64>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DraftStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Published,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct InterestRole {
+    pub guild_id: Option<String>,
+    pub role_id: Option<String>,
+    pub name: Option<String>,
+    pub prompt: Option<String>,
+    pub synced: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailedOperation {
+    AddRoles,
+    RemoveRoles,
+    Unban,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Failure {
+    pub guild_id: Option<String>,
+    pub failure_id: Option<i64>,
+    pub operation: Option<FailedOperation>,
+    pub user_id: Option<String>,
+    pub role_ids: Option<Vec<String>>,
+    pub error: Option<String>,
+    pub timestamp: Option<i64>,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct AnnouncementDraft {
+    pub message_id: Option<String>,
+    pub guild_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub author_id: Option<String>,
+    pub content: Option<String>,
+    pub interest_role_id: Option<String>,
+    pub publish_at: Option<i64>,
+    pub status: Option<DraftStatus>,
+    pub moderator_id: Option<String>,
+    pub drafted: Option<i64>,
+    pub decided: Option<i64>,
+}
+
 impl From<Muted> for Document {
     fn from(muted: Muted) -> Self {
         to_document(&muted)
     }
 }
 
+impl From<Warning> for Document {
+    fn from(warning: Warning) -> Self {
+        to_document(&warning)
+    }
+}
+
 impl From<LockedChannel> for Document {
     fn from(locked: LockedChannel) -> Self {
         to_document(&locked)
     }
 }
 
+impl From<Quote> for Document {
+    fn from(quote: Quote) -> Self {
+        to_document(&quote)
+    }
+}
+
+impl From<Alias> for Document {
+    fn from(alias: Alias) -> Self {
+        to_document(&alias)
+    }
+}
+
+impl From<InteractionLog> for Document {
+    fn from(interaction: InteractionLog) -> Self {
+        to_document(&interaction)
+    }
+}
+
+impl From<AutomodHit> for Document {
+    fn from(hit: AutomodHit) -> Self {
+        to_document(&hit)
+    }
+}
+
+impl From<UserPreferences> for Document {
+    fn from(preferences: UserPreferences) -> Self {
+        to_document(&preferences)
+    }
+}
+
+impl From<Resolution> for Document {
+    fn from(resolution: Resolution) -> Self {
+        to_document(&resolution)
+    }
+}
+
+impl From<EventReminder> for Document {
+    fn from(reminder: EventReminder) -> Self {
+        to_document(&reminder)
+    }
+}
+
+impl From<QuizQuestion> for Document {
+    fn from(question: QuizQuestion) -> Self {
+        to_document(&question)
+    }
+}
+
+impl From<QuizScore> for Document {
+    fn from(score: QuizScore) -> Self {
+        to_document(&score)
+    }
+}
+
+impl From<Giveaway> for Document {
+    fn from(giveaway: Giveaway) -> Self {
+        to_document(&giveaway)
+    }
+}
+
+impl From<GiveawayEntry> for Document {
+    fn from(entry: GiveawayEntry) -> Self {
+        to_document(&entry)
+    }
+}
+
+impl From<JuryMute> for Document {
+    fn from(jury_mute: JuryMute) -> Self {
+        to_document(&jury_mute)
+    }
+}
+
+impl From<PinRequest> for Document {
+    fn from(pin_request: PinRequest) -> Self {
+        to_document(&pin_request)
+    }
+}
+
+impl From<SupportResponse> for Document {
+    fn from(response: SupportResponse) -> Self {
+        to_document(&response)
+    }
+}
+
+impl From<Note> for Document {
+    fn from(note: Note) -> Self {
+        to_document(&note)
+    }
+}
+
+impl From<Case> for Document {
+    fn from(case: Case) -> Self {
+        to_document(&case)
+    }
+}
+
+impl From<Vouch> for Document {
+    fn from(vouch: Vouch) -> Self {
+        to_document(&vouch)
+    }
+}
+
+impl From<Elevation> for Document {
+    fn from(elevation: Elevation) -> Self {
+        to_document(&elevation)
+    }
+}
+
+impl From<Transcript> for Document {
+    fn from(transcript: Transcript) -> Self {
+        to_document(&transcript)
+    }
+}
+
+impl From<Watchword> for Document {
+    fn from(watchword: Watchword) -> Self {
+        to_document(&watchword)
+    }
+}
+
+impl From<BetaRequest> for Document {
+    fn from(request: BetaRequest) -> Self {
+        to_document(&request)
+    }
+}
+
+impl From<AnnouncementDraft> for Document {
+    fn from(draft: AnnouncementDraft) -> Self {
+        to_document(&draft)
+    }
+}
+
+impl From<TempBan> for Document {
+    fn from(temp_ban: TempBan) -> Self {
+        to_document(&temp_ban)
+    }
+}
+
+impl From<InvitePause> for Document {
+    fn from(invite_pause: InvitePause) -> Self {
+        to_document(&invite_pause)
+    }
+}
+
+impl From<SlowmodeReset> for Document {
+    fn from(slowmode_reset: SlowmodeReset) -> Self {
+        to_document(&slowmode_reset)
+    }
+}
+
+impl From<Rule> for Document {
+    fn from(rule: Rule) -> Self {
+        to_document(&rule)
+    }
+}
+
 fn to_document<T>(t: &T) -> Document
 where
     T: Serialize,
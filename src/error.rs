@@ -0,0 +1,65 @@
+use poise::serenity_prelude::{ModelError, SerenityError};
+use thiserror::Error;
+
+/// Errors which can occur while moderating.
+#[derive(Debug, Error)]
+pub enum BotError {
+    #[error("Database error: {0}")]
+    Database(Box<dyn std::error::Error + Send + Sync>),
+    #[error("Discord error: {0}")]
+    Discord(SerenityError),
+    #[error("Missing permission: {0}")]
+    Permission(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Configuration error: {0}")]
+    Config(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
+}
+
+impl From<SerenityError> for BotError {
+    fn from(err: SerenityError) -> Self {
+        let missing_permissions = "The bot is missing the permissions to do this.".to_string();
+
+        match &err {
+            SerenityError::Http(http) => match http.status_code().map(|s| s.as_u16()) {
+                Some(403) => BotError::Permission(missing_permissions),
+                Some(404) => {
+                    BotError::NotFound("The user, role or channel no longer exists.".to_string())
+                },
+                _ => BotError::Discord(err),
+            },
+            SerenityError::Model(ModelError::Hierarchy) => BotError::Permission(
+                "The role of the bot is not high enough to do this.".to_string(),
+            ),
+            SerenityError::Model(ModelError::InvalidPermissions(_)) => {
+                BotError::Permission(missing_permissions)
+            },
+            _ => BotError::Discord(err),
+        }
+    }
+}
+
+impl BotError {
+    /// A message which explains the error to the moderator without exposing internals.
+    pub fn user_message(&self) -> String {
+        match self {
+            BotError::Database(_) => {
+                "The database could not be reached. Please try again later.".to_string()
+            },
+            BotError::Discord(SerenityError::Http(err))
+                if err.status_code().is_some_and(|s| s.as_u16() == 429) =>
+            {
+                "Discord is rate limiting the bot. Please try again later.".to_string()
+            },
+            BotError::Discord(_) => {
+                "Something went wrong while talking to Discord. Please try again later.".to_string()
+            },
+            BotError::Permission(message)
+            | BotError::NotFound(message)
+            | BotError::Conflict(message) => message.clone(),
+            BotError::Config(message) => format!("The bot is misconfigured: {}", message),
+        }
+    }
+}
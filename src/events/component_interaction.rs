@@ -0,0 +1,35 @@
+use poise::serenity_prelude::MessageComponentInteraction;
+
+use super::*;
+use crate::utils::announcement_draft::handle_draft_interaction;
+use crate::utils::appeal::handle_appeal_interaction;
+use crate::utils::automod::handle_queue_interaction;
+use crate::utils::beta::handle_beta_interaction;
+use crate::utils::failures::handle_failure_interaction;
+use crate::utils::giveaway::handle_giveaway_interaction;
+use crate::utils::jury::handle_jury_interaction;
+use crate::utils::mention_guard::handle_ping_request_interaction;
+use crate::utils::moderation::handle_moderation_interaction;
+use crate::utils::pin_vote::handle_pin_interaction;
+use crate::utils::vouch::handle_vouch_interaction;
+
+// Dispatch component interactions by the prefix of their custom id
+pub async fn component_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    match component.data.custom_id.split(':').next() {
+        Some("automod") => handle_queue_interaction(ctx, component).await,
+        Some("moderate") => handle_moderation_interaction(ctx, component).await,
+        Some("pingrequest") => handle_ping_request_interaction(ctx, component).await,
+        Some("giveaway") => handle_giveaway_interaction(ctx, component).await,
+        Some("jury") => handle_jury_interaction(ctx, component).await,
+        Some("vouch") => handle_vouch_interaction(ctx, component).await,
+        Some("beta") => handle_beta_interaction(ctx, component).await,
+        Some("announce") => handle_draft_interaction(ctx, component).await,
+        Some("pin") => handle_pin_interaction(ctx, component).await,
+        Some("appeal") => handle_appeal_interaction(ctx, component).await,
+        Some("failure") => handle_failure_interaction(ctx, component).await,
+        _ => {},
+    }
+}
@@ -1,9 +1,21 @@
 use super::*;
 use crate::utils::decancer::cure;
+use crate::utils::impersonation::check_impersonation;
+use crate::utils::member_log::log_join;
 use crate::utils::moderation::mute_on_join;
+use crate::utils::panic::kick_during_panic;
+use crate::utils::slow_join::admit_member;
 
 pub async fn guild_member_addition(ctx: &serenity::Context, new_member: &mut serenity::Member) {
+    log_join(ctx, new_member).await;
+
+    if !admit_member(ctx, new_member).await {
+        kick_during_panic(ctx, new_member).await;
+    }
+
     mute_on_join(ctx, new_member).await;
 
     cure(ctx, &None, new_member).await;
+
+    check_impersonation(ctx, &None, new_member).await;
 }
@@ -0,0 +1,10 @@
+use super::*;
+use crate::utils::member_log::log_leave;
+
+pub async fn guild_member_removal(
+    ctx: &serenity::Context,
+    user: &serenity::User,
+    member_data_if_available: Option<&serenity::Member>,
+) {
+    log_leave(ctx, user, member_data_if_available).await;
+}
@@ -1,10 +1,19 @@
 use super::*;
 use crate::utils::decancer::cure;
+use crate::utils::impersonation::check_impersonation;
+use crate::utils::member_cache::evict;
+use crate::utils::moderation::handle_external_unmute;
 
 pub async fn guild_member_update(
     ctx: &serenity::Context,
     old_if_available: &Option<serenity::Member>,
     new: &serenity::Member,
 ) {
+    evict(new.guild_id, new.user.id).await;
+
     cure(ctx, old_if_available, new).await;
+
+    check_impersonation(ctx, old_if_available, new).await;
+
+    handle_external_unmute(ctx, old_if_available, new).await;
 }
@@ -1,10 +1,43 @@
 use super::*;
+use crate::utils::activity::record_activity;
+use crate::utils::announcement::handle_announcement;
+use crate::utils::automod::automod;
 use crate::utils::autorespond::auto_respond;
+use crate::utils::contributor_window::{enforce_windows, open_windows};
+use crate::utils::crosspost::dedupe_crossposts;
+use crate::utils::link_cleaner::clean_links;
 use crate::utils::media_channel::handle_media_channel;
+use crate::utils::mention_guard::guard_mentions;
+use crate::utils::mention_redirect::redirect_mentions;
+use crate::utils::monitor::mirror_monitored;
+use crate::utils::qr_code::scan_qr_codes;
+use crate::utils::support::{record_response, suggest_for_message};
+use crate::utils::watchword::alert_watchwords;
+use crate::utils::welcome::clean_welcome_channel;
 
 pub async fn message_create(ctx: &serenity::Context, new_message: &serenity::Message) {
+    record_activity(ctx, new_message).await;
+    record_response(ctx, new_message).await;
+    handle_announcement(ctx, new_message).await;
+    clean_welcome_channel(ctx, new_message).await;
+    open_windows(ctx, new_message).await;
+    mirror_monitored(ctx, new_message).await;
+
+    if enforce_windows(ctx, new_message).await
+        || guard_mentions(ctx, new_message).await
+        || redirect_mentions(ctx, new_message).await
+        || scan_qr_codes(ctx, new_message).await
+        || clean_links(ctx, new_message).await
+        || dedupe_crossposts(ctx, new_message).await
+    {
+        return;
+    }
+
     let is_media_channel = handle_media_channel(ctx, new_message).await;
     if !is_media_channel {
+        automod(ctx, new_message).await;
         auto_respond(ctx, new_message).await;
+        suggest_for_message(ctx, new_message).await;
+        alert_watchwords(ctx, new_message).await;
     }
 }
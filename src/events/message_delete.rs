@@ -0,0 +1,13 @@
+use poise::serenity_prelude::{ChannelId, GuildId, MessageId};
+
+use super::*;
+use crate::utils::message_log::log_deleted_message;
+
+pub async fn message_delete(
+    ctx: &serenity::Context,
+    channel_id: ChannelId,
+    deleted_message_id: MessageId,
+    guild_id: Option<GuildId>,
+) {
+    log_deleted_message(ctx, channel_id, deleted_message_id, guild_id).await;
+}
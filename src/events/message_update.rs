@@ -0,0 +1,12 @@
+use poise::serenity_prelude::{Message, MessageUpdateEvent};
+
+use super::*;
+use crate::utils::message_log::log_edited_message;
+
+pub async fn message_update(
+    ctx: &serenity::Context,
+    old_if_available: Option<&Message>,
+    event: &MessageUpdateEvent,
+) {
+    log_edited_message(ctx, old_if_available, event).await;
+}
@@ -1,14 +1,24 @@
 use std::sync::Arc;
 
+use arc_swap::ArcSwap;
 use poise::serenity_prelude::{self as serenity, Mutex, RwLock, ShardManager, UserId};
 
+use crate::utils::alias::{register_aliases, resolve_alias};
 use crate::{Data, Error};
 
+mod component_interaction;
 mod guild_member_addition;
+mod guild_member_removal;
 mod guild_member_update;
 mod message_create;
+mod message_delete;
+mod message_update;
+mod modal_submit;
+mod presence_update;
+mod reaction_add;
 mod ready;
 mod thread_create;
+mod voice_state_update;
 
 pub struct Handler<T> {
     options: poise::FrameworkOptions<T, Error>,
@@ -45,11 +55,39 @@ impl<T: Send + Sync> Handler<T> {
 
 // Manually dispatch events from serenity to poise
 #[serenity::async_trait]
-impl serenity::EventHandler for Handler<Arc<RwLock<Data>>> {
+impl serenity::EventHandler for Handler<Arc<ArcSwap<Data>>> {
     async fn ready(&self, ctx: serenity::Context, ready: serenity::Ready) {
         *self.bot_id.write().await = Some(ready.user.id);
 
+        ready::warm_member_cache(&ctx, &ready);
+
+        ready::cache_guild_invites(&ctx, &ready).await;
+
         ready::load_muted_members(&ctx, &ready).await;
+
+        ready::sync_mention_guard(&ctx, &ready).await;
+
+        ready::schedule_support_digest(&ctx);
+
+        ready::schedule_event_reminders(&ctx);
+
+        ready::schedule_giveaways(&ctx, &ready).await;
+
+        ready::schedule_beta_revocation(&ctx);
+
+        ready::schedule_onboarding_sync(&ctx);
+
+        ready::schedule_staff_reports(&ctx);
+
+        ready::schedule_milestone_roles(&ctx);
+
+        ready::schedule_join_queue(&ctx);
+
+        ready::schedule_announcements(&ctx).await;
+
+        ready::register_commands(&ctx, &self.options.commands).await;
+
+        register_aliases(&ctx, &self.options.commands).await;
     }
 
     async fn message(&self, ctx: serenity::Context, new_message: serenity::Message) {
@@ -61,13 +99,40 @@ impl serenity::EventHandler for Handler<Arc<RwLock<Data>>> {
         .await;
     }
 
-    async fn interaction_create(&self, ctx: serenity::Context, interaction: serenity::Interaction) {
+    async fn interaction_create(
+        &self,
+        ctx: serenity::Context,
+        mut interaction: serenity::Interaction,
+    ) {
+        match &mut interaction {
+            serenity::Interaction::ApplicationCommand(command) => {
+                resolve_alias(&ctx, &self.options.commands, command).await
+            },
+            serenity::Interaction::MessageComponent(component) => {
+                component_interaction::component_interaction(&ctx, component).await
+            },
+            serenity::Interaction::ModalSubmit(modal) => {
+                modal_submit::modal_submit(&ctx, modal).await
+            },
+            _ => {},
+        }
+
         self.dispatch_poise_event(&ctx, &poise::Event::InteractionCreate {
             interaction,
         })
         .await;
     }
 
+    async fn message_delete(
+        &self,
+        ctx: serenity::Context,
+        channel_id: serenity::ChannelId,
+        deleted_message_id: serenity::MessageId,
+        guild_id: Option<serenity::GuildId>,
+    ) {
+        message_delete::message_delete(&ctx, channel_id, deleted_message_id, guild_id).await;
+    }
+
     async fn message_update(
         &self,
         ctx: serenity::Context,
@@ -75,6 +140,8 @@ impl serenity::EventHandler for Handler<Arc<RwLock<Data>>> {
         new: Option<serenity::Message>,
         event: serenity::MessageUpdateEvent,
     ) {
+        message_update::message_update(&ctx, old_if_available.as_ref(), &event).await;
+
         self.dispatch_poise_event(&ctx, &poise::Event::MessageUpdate {
             old_if_available,
             new,
@@ -83,14 +150,33 @@ impl serenity::EventHandler for Handler<Arc<RwLock<Data>>> {
         .await;
     }
 
+    async fn reaction_add(&self, ctx: serenity::Context, add_reaction: serenity::Reaction) {
+        reaction_add::reaction_add(&ctx, &add_reaction).await;
+    }
+
     async fn thread_create(&self, ctx: serenity::Context, thread: serenity::GuildChannel) {
         thread_create::thread_create(&ctx, &thread).await;
     }
 
-    async fn guild_member_addition(&self, ctx: serenity::Context, mut new_member: serenity::Member) {
+    async fn guild_member_addition(
+        &self,
+        ctx: serenity::Context,
+        mut new_member: serenity::Member,
+    ) {
         guild_member_addition::guild_member_addition(&ctx, &mut new_member).await;
     }
 
+    async fn guild_member_removal(
+        &self,
+        ctx: serenity::Context,
+        _: serenity::GuildId,
+        user: serenity::User,
+        member_data_if_available: Option<serenity::Member>,
+    ) {
+        guild_member_removal::guild_member_removal(&ctx, &user, member_data_if_available.as_ref())
+            .await;
+    }
+
     async fn guild_member_update(
         &self,
         ctx: serenity::Context,
@@ -99,4 +185,17 @@ impl serenity::EventHandler for Handler<Arc<RwLock<Data>>> {
     ) {
         guild_member_update::guild_member_update(&ctx, &old_if_available, &new).await;
     }
+
+    async fn presence_update(&self, ctx: serenity::Context, new_data: serenity::Presence) {
+        presence_update::presence_update(&ctx, &new_data).await;
+    }
+
+    async fn voice_state_update(
+        &self,
+        ctx: serenity::Context,
+        old: Option<serenity::VoiceState>,
+        new: serenity::VoiceState,
+    ) {
+        voice_state_update::voice_state_update(&ctx, old.as_ref(), &new).await;
+    }
 }
@@ -0,0 +1,14 @@
+use poise::serenity_prelude::ModalSubmitInteraction;
+
+use super::*;
+use crate::utils::announcement_draft::handle_draft_modal;
+use crate::utils::appeal::handle_appeal_modal;
+
+// Dispatch modal submissions by the prefix of their custom id
+pub async fn modal_submit(ctx: &serenity::Context, modal: &ModalSubmitInteraction) {
+    match modal.data.custom_id.split(':').next() {
+        Some("announce") => handle_draft_modal(ctx, modal).await,
+        Some("appeal") => handle_appeal_modal(ctx, modal).await,
+        _ => {},
+    }
+}
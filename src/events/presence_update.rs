@@ -0,0 +1,8 @@
+use super::*;
+use crate::utils::live_role::update_live_role;
+
+pub async fn presence_update(ctx: &serenity::Context, new_data: &serenity::Presence) {
+    if let Some(guild_id) = new_data.guild_id {
+        update_live_role(ctx, guild_id, new_data.user.id).await;
+    }
+}
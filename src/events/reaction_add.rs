@@ -0,0 +1,10 @@
+use super::*;
+use crate::utils::announcement::handle_announcement_reaction;
+use crate::utils::jury::handle_jury_reaction;
+use crate::utils::pin_vote::handle_pin_reaction;
+
+pub async fn reaction_add(ctx: &serenity::Context, add_reaction: &serenity::Reaction) {
+    handle_announcement_reaction(ctx, add_reaction).await;
+    handle_jury_reaction(ctx, add_reaction).await;
+    handle_pin_reaction(ctx, add_reaction).await;
+}
@@ -1,56 +1,81 @@
-use chrono::Utc;
-use tracing::trace;
+use tracing::error;
 
 use super::*;
-use crate::db::model::Muted;
-use crate::utils::bot::get_data_lock;
-use crate::utils::moderation::queue_unmute_member;
+use crate::utils::announcement_draft::load_drafts;
+use crate::utils::beta::schedule_revocation;
+use crate::utils::bot::{get_data, requeue_expiries, schedule_expiry_sweeper};
+use crate::utils::choices;
+use crate::utils::event_reminder::schedule_reminders;
+use crate::utils::giveaway::load_giveaways;
+use crate::utils::member_cache::warm;
+use crate::utils::member_log::cache_invites;
+use crate::utils::mention_guard::sync_automod_rule;
+use crate::utils::milestones::schedule_milestones;
+use crate::utils::onboarding::schedule_sync;
+use crate::utils::slow_join::schedule_release;
+use crate::utils::staff_report::schedule_reports;
+use crate::utils::support::schedule_digest;
 
 pub async fn load_muted_members(ctx: &serenity::Context, _: &serenity::Ready) {
-    let data = get_data_lock(ctx).await;
-    let data = &mut *data.write().await;
-    let mute_role_id = data.configuration.general.mute.role;
-
-    let mut cursor = data
-        .database
-        .find::<Muted>(
-            "muted",
-            Muted {
-                ..Default::default()
-            }
-            .into(),
-            None,
-        )
-        .await
-        .unwrap();
-
-    let http_ref = &ctx.http;
-
-    while cursor.advance().await.unwrap() {
-        let current: Muted = cursor.deserialize_current().unwrap();
-        let guild_id = current.guild_id.unwrap().parse::<u64>().unwrap();
-        let member_id = current.user_id.unwrap().parse::<u64>().unwrap();
-
-        if let Ok(guild) = http_ref.get_guild(guild_id).await {
-            if let Ok(member) = guild.member(http_ref, member_id).await {
-                let amount_left =
-                    std::cmp::max(current.expires.unwrap() as i64 - Utc::now().timestamp(), 0);
-
-                data.pending_unmutes.insert(
-                    member.user.id.0,
-                    queue_unmute_member(
-                        &ctx.http,
-                        &data.database,
-                        &member,
-                        mute_role_id,
-                        amount_left as u64, // i64 as u64 is handled properly here
-                    ),
-                );
-            } else {
-                trace!("Failed to find member {} in guild {}", member_id, guild_id);
-            }
-        } else {
-            trace!("Guild {} unavailable", guild_id);
-        }
+    requeue_expiries(ctx).await;
+
+    schedule_expiry_sweeper(ctx);
+}
+
+pub fn warm_member_cache(ctx: &serenity::Context, ready: &serenity::Ready) {
+    warm(ctx, ready.guilds.iter().map(|g| g.id));
+}
+
+pub async fn cache_guild_invites(ctx: &serenity::Context, ready: &serenity::Ready) {
+    cache_invites(ctx, ready.guilds.iter().map(|g| g.id)).await;
+}
+
+pub async fn sync_mention_guard(ctx: &serenity::Context, ready: &serenity::Ready) {
+    for guild in &ready.guilds {
+        sync_automod_rule(ctx, guild.id).await;
+    }
+}
+
+pub fn schedule_support_digest(ctx: &serenity::Context) {
+    schedule_digest(ctx);
+}
+
+pub async fn schedule_giveaways(ctx: &serenity::Context, _: &serenity::Ready) {
+    load_giveaways(ctx).await;
+}
+
+pub fn schedule_event_reminders(ctx: &serenity::Context) {
+    schedule_reminders(ctx);
+}
+
+pub fn schedule_staff_reports(ctx: &serenity::Context) {
+    schedule_reports(ctx);
+}
+
+pub fn schedule_beta_revocation(ctx: &serenity::Context) {
+    schedule_revocation(ctx);
+}
+
+pub fn schedule_onboarding_sync(ctx: &serenity::Context) {
+    schedule_sync(ctx);
+}
+
+pub fn schedule_milestone_roles(ctx: &serenity::Context) {
+    schedule_milestones(ctx);
+}
+
+pub fn schedule_join_queue(ctx: &serenity::Context) {
+    schedule_release(ctx);
+}
+
+pub async fn schedule_announcements(ctx: &serenity::Context) {
+    load_drafts(ctx).await;
+}
+
+pub async fn register_commands<U, E>(ctx: &serenity::Context, commands: &[poise::Command<U, E>]) {
+    let data = get_data(ctx).await;
+
+    if let Err(err) = choices::register_commands(&ctx.http, commands, &data.configuration).await {
+        error!("Failed to register slash commands: {:?}", err);
     }
 }
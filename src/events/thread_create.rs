@@ -1,7 +1,8 @@
 use tracing::{debug, error};
 
 use super::*;
-use crate::utils::bot::get_data_lock;
+use crate::utils::bot::get_data;
+use crate::utils::support::suggest_for_thread;
 
 pub async fn thread_create(ctx: &serenity::Context, thread: &serenity::GuildChannel) {
     if thread.member.is_some() {
@@ -11,10 +12,9 @@ pub async fn thread_create(ctx: &serenity::Context, thread: &serenity::GuildChan
 
     debug!("Thread created: {:?}", thread);
 
-    let data_lock = get_data_lock(ctx).await;
-    let configuration_lock = data_lock.read().await;
+    let data = get_data(ctx).await;
 
-    let thread_introductions = &configuration_lock.configuration.thread_introductions;
+    let thread_introductions = &data.configuration.thread_introductions;
 
     if let Some(introducer) = thread_introductions.iter().find(|introducer| {
         introducer
@@ -29,4 +29,6 @@ pub async fn thread_create(ctx: &serenity::Context, thread: &serenity::GuildChan
             error!("Error sending message: {:?}", why);
         }
     }
+
+    suggest_for_thread(ctx, thread).await;
 }
@@ -0,0 +1,18 @@
+use super::*;
+use crate::utils::live_role::update_live_role;
+use crate::utils::voice_log::log_voice_state;
+use crate::utils::voice_time::track_voice_time;
+
+pub async fn voice_state_update(
+    ctx: &serenity::Context,
+    old: Option<&serenity::VoiceState>,
+    new: &serenity::VoiceState,
+) {
+    log_voice_state(ctx, old, new).await;
+
+    track_voice_time(ctx, new).await;
+
+    if let Some(guild_id) = new.guild_id {
+        update_live_role(ctx, guild_id, new.user_id).await;
+    }
+}
@@ -1,58 +1,202 @@
 use std::collections::HashMap;
 use std::env;
 use std::sync::Arc;
+use std::time::Instant;
 
-use commands::{configuration, misc, moderation};
+use arc_swap::ArcSwap;
+use clap::Parser;
+use cli::{Cli, Command};
+use commands::{
+    announce,
+    archive,
+    audit,
+    automod,
+    beta,
+    case,
+    configuration,
+    data,
+    failures,
+    giveaway,
+    history,
+    lockdown,
+    misc,
+    moderation,
+    monitor,
+    panic,
+    preferences,
+    quiz,
+    reactions,
+    report,
+    role,
+    rules,
+    search,
+    setup,
+    strikes,
+    support,
+    voicetime,
+    vouch,
+    watchword,
+};
 use db::database::Database;
+use error::BotError;
 use events::Handler;
-use poise::serenity_prelude::{self as serenity, RwLock, UserId};
+use poise::serenity_prelude::{self as serenity, Mutex, UserId};
 use tokio::task::JoinHandle;
 use tracing::{error, trace};
+use utils::audit::{log_command_error, record_interaction};
 use utils::bot::load_configuration;
+use utils::dry_run;
+use utils::moderation::is_administrator;
 
 use crate::model::application::Configuration;
 
+mod cli;
 mod commands;
 mod db;
+mod error;
 mod events;
 mod logger;
 mod model;
+#[cfg(any(test, feature = "testing"))]
+mod testing;
 mod utils;
 
+// Commands which can be used by everyone
+const PUBLIC_COMMANDS: [&str; 10] = [
+    "announce",
+    "beta",
+    "faq",
+    "mydata",
+    "pingrequest",
+    "quiz",
+    "reportdm",
+    "strikes",
+    "timezone",
+    "vouch",
+];
+
+// Amount of messages to cache per channel, so deleted messages can be logged
+const MESSAGE_CACHE_SIZE: usize = 500;
+
 type Error = Box<dyn std::error::Error + Send + Sync>;
-type Context<'a> = poise::Context<'a, Arc<RwLock<Data>>, Error>;
+type Context<'a> = poise::Context<'a, Arc<ArcSwap<Data>>, Error>;
 
 impl serenity::TypeMapKey for Data {
-    type Value = Arc<RwLock<Data>>;
+    type Value = Arc<ArcSwap<Data>>;
 }
 
+// Independently shared components, so unrelated handlers never wait for each other
+#[derive(Clone)]
 pub struct Data {
-    configuration: Configuration,
+    configuration: Arc<Configuration>,
     database: Arc<Database>,
-    pending_unmutes: HashMap<u64, JoinHandle<Option<Error>>>,
+    pending_unmutes: Arc<Mutex<HashMap<u64, JoinHandle<Option<BotError>>>>>,
+    start_time: Instant,
 }
 
 #[tokio::main]
 async fn main() {
+    let cli = Cli::parse();
+
     // Initialize the logging framework
     logger::init();
 
     // Load environment variables from .env file
     dotenv::dotenv().ok();
 
+    // Initialize the encryption of sensitive database fields
+    db::crypto::init();
+
+    match cli.command.unwrap_or(Command::Run {
+        dry_run: false,
+    }) {
+        Command::Run {
+            dry_run,
+        } => run(dry_run).await,
+        command => {
+            if let Err(err) = cli::maintain(command).await {
+                error!("{}", err);
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+async fn connect_database() -> Database {
+    Database::new(
+        &env::var("MONGODB_URI").expect("MONGODB_URI environment variable not set"),
+        "revanced_discord_bot",
+    )
+    .await
+    .unwrap()
+}
+
+async fn run(dry_run: bool) {
+    if dry_run {
+        dry_run::force();
+    }
+
     // Define poise framework commands (also in src/commands/mod.rs for serenity framework's manually dispatched events)
     let mut commands = vec![
         configuration::register(),
         configuration::reload(),
         configuration::stop(),
+        configuration::alias(),
         moderation::mute(),
         moderation::unmute(),
         moderation::purge(),
         moderation::ban(),
         moderation::unban(),
+        moderation::softban(),
+        moderation::massban(),
+        moderation::warn(),
+        moderation::punish(),
+        moderation::kick(),
+        moderation::voicekick(),
+        moderation::voicemute(),
+        moderation::timeout(),
         moderation::lock(),
         moderation::unlock(),
+        moderation::lockvc(),
+        moderation::unlockvc(),
+        moderation::slowmode(),
+        moderation::invites(),
+        reactions::reactions(),
+        panic::panic(),
+        lockdown::lockdown(),
+        failures::failures(),
+        monitor::monitor(),
         misc::reply(),
+        misc::quote(),
+        misc::status(),
+        misc::pingrequest(),
+        audit::audit(),
+        automod::automod(),
+        data::mydata(),
+        data::admin(),
+        preferences::timezone(),
+        support::resolve(),
+        support::faq(),
+        support::draft_reply(),
+        role::role(),
+        quiz::quiz(),
+        voicetime::voicetime(),
+        giveaway::giveaway(),
+        strikes::strikes(),
+        strikes::points(),
+        report::reportdm(),
+        vouch::vouch(),
+        archive::archive(),
+        search::search(),
+        watchword::watchword(),
+        beta::beta(),
+        announce::announce(),
+        case::case(),
+        history::history(),
+        history::note(),
+        rules::rules(),
+        setup::setup(),
+        rules::rule(),
     ];
     poise::set_qualified_names(&mut commands);
 
@@ -68,17 +212,14 @@ async fn main() {
         .into_iter()
         .collect();
 
-    let data = Arc::new(RwLock::new(Data {
-        configuration,
-        database: Arc::new(
-            Database::new(
-                &env::var("MONGODB_URI").expect("MONGODB_URI environment variable not set"),
-                "revanced_discord_bot",
-            )
-            .await
-            .unwrap(),
-        ),
-        pending_unmutes: HashMap::new(),
+    let database = connect_database().await;
+    cli::ensure_indexes(&database).await;
+
+    let data = Arc::new(ArcSwap::from_pointee(Data {
+        configuration: Arc::new(configuration),
+        database: Arc::new(database),
+        pending_unmutes: Arc::new(Mutex::new(HashMap::new())),
+        start_time: Instant::now(),
     }));
 
     let handler = Arc::new(Handler::new(
@@ -87,53 +228,71 @@ async fn main() {
             commands,
             on_error: |error| {
                 Box::pin(async {
+                    // Record failed invocations to the interaction audit log
+                    match &error {
+                        poise::FrameworkError::Command {
+                            error,
+                            ctx,
+                        } => {
+                            record_interaction(*ctx, Some(error.to_string())).await;
+                            log_command_error(*ctx, &error.to_string()).await;
+                        },
+                        poise::FrameworkError::CommandCheckFailed {
+                            ctx, ..
+                        } => record_interaction(*ctx, Some("Check failed".to_string())).await,
+                        _ => {},
+                    }
+
                     poise::samples::on_error(error)
                         .await
                         .unwrap_or_else(|error| tracing::error!("{}", error));
                 })
             },
+            pre_command: |ctx| {
+                Box::pin(async move {
+                    ctx.set_invocation_data(Instant::now()).await;
+                })
+            },
+            post_command: |ctx| {
+                Box::pin(async move {
+                    record_interaction(ctx, None).await;
+                })
+            },
             command_check: Some(|ctx| {
                 Box::pin(async move {
-                    if let Some(member) = ctx.author_member().await {
-                        let data_lock = &ctx.data().read().await;
-                        let configuration = &data_lock.configuration;
-                        let administrators = &configuration.administrators;
-
-                        if !(administrators
-                            .users
-                            // Check if the user is an administrator
-                            .contains(&member.user.id.0)
-                            || administrators
-                                .roles
-                                .iter()
-                                // Has one of the administative roles
-                                .any(|&role_id| {
-                                    member
-                                        .roles
-                                        .iter()
-                                        .any(|member_role| member_role.0 == role_id)
-                                }))
-                        {
-                            if let Err(e) = ctx
-                                .send(|m| {
-                                    m.ephemeral(true).embed(|e| {
-                                        e.title("Permission error")
-                                            .description(
-                                                "You do not have permission to use this command.",
-                                            )
-                                            .color(configuration.general.embed_color)
-                                            .thumbnail(member.user.avatar_url().unwrap_or_else(
-                                                || member.user.default_avatar_url(),
-                                            ))
-                                    })
+                    let root_command = ctx.command().qualified_name.split(' ').next();
+                    if root_command.is_some_and(|name| PUBLIC_COMMANDS.contains(&name)) {
+                        return Ok(true);
+                    }
+
+                    let data = ctx.data().load_full();
+                    let configuration = &data.configuration;
+                    let administrators = &configuration.administrators;
+
+                    let is_allowed = match ctx.author_member().await {
+                        Some(member) => is_administrator(administrators, &member),
+                        // There are no roles in direct messages, only allow administrators by id
+                        None => administrators.users.contains(&ctx.author().id.0),
+                    };
+
+                    if !is_allowed {
+                        if let Err(e) = ctx
+                            .send(|m| {
+                                m.ephemeral(true).embed(|e| {
+                                    e.title("Permission error")
+                                        .description(
+                                            "You do not have permission to use this command.",
+                                        )
+                                        .color(configuration.general.embed_color)
+                                        .thumbnail(ctx.author().face())
                                 })
-                                .await
-                            {
-                                error!("Error sending message: {:?}", e)
-                            }
-                            trace!("{} is not an administrator.", member.user.name);
-                            return Ok(false); // Not an administrator, don't allow command execution
+                            })
+                            .await
+                        {
+                            error!("Error sending message: {:?}", e)
                         }
+                        trace!("{} is not an administrator.", ctx.author().name);
+                        return Ok(false); // Not an administrator, don't allow command execution
                     }
                     Ok(true)
                 })
@@ -154,8 +313,10 @@ async fn main() {
             .expect("DISCORD_AUTHORIZATION_TOKEN environment variable not set"),
         serenity::GatewayIntents::non_privileged()
             | serenity::GatewayIntents::MESSAGE_CONTENT
-            | serenity::GatewayIntents::GUILD_MEMBERS,
+            | serenity::GatewayIntents::GUILD_MEMBERS
+            | serenity::GatewayIntents::GUILD_PRESENCES,
     )
+    .cache_settings(|settings| settings.max_messages(MESSAGE_CACHE_SIZE))
     .event_handler_arc(handler.clone())
     .await
     .unwrap();
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{Read, Result, Write};
 use std::path::Path;
@@ -5,6 +6,7 @@ use std::path::Path;
 use dirs::config_dir;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_with_macros::skip_serializing_none;
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct Configuration {
@@ -12,12 +14,96 @@ pub struct Configuration {
     pub administrators: Administrators,
     pub thread_introductions: Vec<Introduction>,
     pub message_responses: Vec<MessageResponse>,
+    #[serde(default)]
+    pub impersonation: Impersonation,
+    #[serde(default)]
+    pub announcement_threads: Vec<AnnouncementThread>,
+    #[serde(default)]
+    pub storage: Option<Storage>,
+    #[serde(default)]
+    pub automod: Automod,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub mention_guard: MentionGuard,
+    #[serde(default)]
+    pub mention_redirect: MentionRedirect,
+    #[serde(default)]
+    pub live_role: LiveRole,
+    #[serde(default)]
+    pub support: Support,
+    #[serde(default)]
+    pub event_reminders: EventReminders,
+    #[serde(default)]
+    pub quiz: Quiz,
+    #[serde(default)]
+    pub strikes: Strikes,
+    #[serde(default)]
+    pub welcome_cleanup: WelcomeCleanup,
+    #[serde(default)]
+    pub dm_reports: DmReports,
+    #[serde(default)]
+    pub choices: Choices,
+    #[serde(default)]
+    pub ocr: Ocr,
+    #[serde(default)]
+    pub qr_codes: QrCodes,
+    #[serde(default)]
+    pub jury: Jury,
+    #[serde(default)]
+    pub bans: Bans,
+    #[serde(default)]
+    pub vouching: Vouching,
+    #[serde(default)]
+    pub archive: Archive,
+    #[serde(default)]
+    pub watchwords: Watchwords,
+    #[serde(default)]
+    pub beta: Beta,
+    #[serde(default)]
+    pub announcements: Announcements,
+    #[serde(default)]
+    pub rules: Rules,
+    #[serde(default)]
+    pub pin_votes: Vec<PinVote>,
+    #[serde(default)]
+    pub staff_reports: StaffReports,
+    #[serde(default)]
+    pub panic: Panic,
+    #[serde(default)]
+    pub notifications: Notifications,
+    #[serde(default)]
+    pub appeals: Appeals,
+    #[serde(default)]
+    pub onboarding: Onboarding,
+    #[serde(default)]
+    pub milestones: Milestones,
+    #[serde(default)]
+    pub lockdown: Lockdown,
+    #[serde(default)]
+    pub preset_reasons: Vec<String>,
+    #[serde(default)]
+    pub responses: Responses,
+    #[serde(default)]
+    pub config_audit: ConfigAudit,
+    #[serde(default)]
+    pub message_log: MessageLog,
+    #[serde(default)]
+    pub monitoring: Monitoring,
+    #[serde(default)]
+    pub member_log: MemberLog,
+    #[serde(default)]
+    pub slow_join: SlowJoin,
+    #[serde(default)]
+    pub voice_log: VoiceLog,
+    #[serde(default)]
+    pub logging: Logging,
 }
 
 const CONFIG_PATH: &str = "configuration.json";
 
 impl Configuration {
-    fn save(&self) -> Result<()> {
+    pub fn save(&self) -> Result<()> {
         let sys_config_dir = config_dir().expect("find config dir");
 
         fs::create_dir_all(format!(
@@ -60,20 +146,91 @@ impl Configuration {
 
         Ok(serde_json::from_str(&buf)?)
     }
+
+    /// The channel the logs of a category are sent to, 0 if they are not sent.
+    /// Moderation, message and member logs fall back to the logging channel.
+    pub fn log_channel(&self, category: LogCategory) -> u64 {
+        let channel = match category {
+            LogCategory::Moderation => self.logging.moderation,
+            LogCategory::Messages => self.logging.messages,
+            LogCategory::Members => self.logging.members,
+            // Voice and error logs are noisy, so they are opt-in
+            LogCategory::Voice => return self.logging.voice,
+            LogCategory::Errors => return self.logging.errors,
+        };
+
+        match channel {
+            0 => self.general.logging_channel,
+            channel_id => channel_id,
+        }
+    }
 }
 
 #[derive(Default, Serialize, Deserialize)]
 pub struct General {
     pub embed_color: i32,
+    #[serde(default)]
+    pub colors: Colors,
     pub mute: Mute,
     pub media_channels: Vec<u64>,
     pub logging_channel: u64,
 }
 
+// Colors which override the embed color. Per action colors take precedence over success
+#[skip_serializing_none]
+#[derive(Default, Serialize, Deserialize)]
+pub struct Colors {
+    pub success: Option<i32>,
+    pub failure: Option<i32>,
+    pub mute: Option<i32>,
+    pub unmute: Option<i32>,
+    pub ban: Option<i32>,
+    pub unban: Option<i32>,
+    pub lock: Option<i32>,
+    pub unlock: Option<i32>,
+    pub forget: Option<i32>,
+    pub warn: Option<i32>,
+    pub kick: Option<i32>,
+}
+
 #[derive(Default, Serialize, Deserialize)]
 pub struct Mute {
     pub role: u64,
     pub take: Vec<u64>,
+    #[serde(default)]
+    pub roles: Vec<ScopedMuteRole>,
+    #[serde(default)]
+    pub native: bool,
+}
+
+impl Mute {
+    /// The mute roles applicable to the given scope, or all mute roles if no scope is given.
+    pub fn roles_for(&self, scope: Option<&str>) -> Vec<u64> {
+        let scoped = self
+            .roles
+            .iter()
+            .filter(|r| scope.is_none_or(|scope| r.scope == scope))
+            .map(|r| r.role);
+
+        match scope {
+            Some(_) => scoped.collect(),
+            None => std::iter::once(self.role)
+                .filter(|&role| role != 0)
+                .chain(scoped)
+                .collect(),
+        }
+    }
+
+    /// Check if a role is one of the mute roles.
+    pub fn is_mute_role(&self, role: u64) -> bool {
+        self.role == role || self.roles.iter().any(|r| r.role == role)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ScopedMuteRole {
+    pub role: u64,
+    pub scope: String,
 }
 #[derive(Default, Serialize, Deserialize)]
 pub struct Administrators {
@@ -81,6 +238,441 @@ pub struct Administrators {
     pub users: Vec<u64>,
 }
 
+#[derive(Default, Serialize, Deserialize)]
+pub struct Beta {
+    pub role: u64,
+    pub account_age: String,
+    pub membership_age: String,
+    pub level_roles: Vec<u64>,
+    pub approval: bool,
+    pub approval_channel: u64,
+    pub inactivity: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Rules {
+    pub channel: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Announcements {
+    pub staff_roles: Vec<u64>,
+    pub channel: u64,
+    pub approval_channel: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Watchwords {
+    pub alert_channel: u64,
+    pub max_per_user: usize,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Archive {
+    pub log_channel: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Vouching {
+    pub helper_role: u64,
+    pub voucher_roles: Vec<u64>,
+    pub threshold: usize,
+    pub poll_channel: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Bans {
+    pub reason_template: String,
+    pub appeal_url: String,
+    #[serde(default)]
+    pub approval: BanApproval,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct BanApproval {
+    pub member_days: i64,
+    pub roles: Vec<u64>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Jury {
+    pub enabled: bool,
+    pub emoji: String,
+    pub votes: usize,
+    pub trusted_roles: Vec<u64>,
+    pub duration: String,
+    pub review_channel: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct StaffReports {
+    pub lead_roles: Vec<u64>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Onboarding {
+    pub enabled: bool,
+    pub prompts: Vec<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Milestones {
+    pub enabled: bool,
+    pub tiers: Vec<MilestoneTier>,
+    pub announcement_channel: u64,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MilestoneTier {
+    pub name: String,
+    pub days: i64,
+    pub role: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Lockdown {
+    pub category: u64,
+    pub channels: Vec<u64>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Responses {
+    pub staff_channel: u64,
+    pub commands: HashMap<String, Visibility>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct VoiceLog {
+    pub channel: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Logging {
+    pub moderation: u64,
+    pub messages: u64,
+    pub members: u64,
+    pub voice: u64,
+    pub errors: u64,
+}
+
+#[derive(Clone, Copy)]
+pub enum LogCategory {
+    Moderation,
+    Messages,
+    Members,
+    Voice,
+    Errors,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct SlowJoin {
+    pub role: u64,
+    pub per_minute: usize,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct MemberLog {
+    pub channel: u64,
+    pub new_account_days: i64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Monitoring {
+    pub channel: u64,
+    pub context_messages: u8,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct MessageLog {
+    pub channel: u64,
+    pub store: bool,
+    pub retention_days: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct ConfigAudit {
+    pub channel: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    Public,
+    Ephemeral,
+    Staff,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Appeals {
+    pub review_channel: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Notifications {
+    pub mute: String,
+    pub ban: String,
+    pub warn: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Panic {
+    pub channels: Vec<u64>,
+    pub on_duty_role: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct PinVote {
+    pub channels: Vec<u64>,
+    pub emoji: String,
+    pub threshold: usize,
+    pub approval_channel: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct QrCodes {
+    pub enabled: bool,
+    pub domains: Vec<String>,
+    pub max_size: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Ocr {
+    pub enabled: bool,
+    pub endpoint: String,
+    pub authorization: Option<String>,
+    pub language: String,
+    pub max_size: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Choices {
+    pub durations: Vec<DurationChoice>,
+    pub reasons: Vec<Choice>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DurationChoice {
+    pub name: String,
+    pub seconds: i64,
+    #[serde(default)]
+    pub localizations: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Choice {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub localizations: HashMap<String, String>,
+    #[serde(default)]
+    pub points: Option<u32>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct DmReports {
+    pub channel: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct WelcomeCleanup {
+    pub channel: u64,
+    pub delay: String,
+    pub notices: bool,
+    pub chatter: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Strikes {
+    pub decay: String,
+    pub steps: Vec<StrikeStep>,
+    #[serde(default)]
+    pub points: HashMap<String, u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StrikeStep {
+    // Steps used to apply at an amount of strikes, which are worth one point each by default
+    #[serde(alias = "strikes")]
+    pub points: u32,
+    pub action: StrikeAction,
+    pub duration: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrikeAction {
+    Warning,
+    Mute,
+    Kick,
+    Ban,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Quiz {
+    pub answer_seconds: u64,
+    pub questions: Vec<Question>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Question {
+    pub topic: String,
+    pub question: String,
+    pub answers: Vec<String>,
+    pub correct: usize,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct EventReminders {
+    pub channel: u64,
+    pub role: u64,
+    pub offsets: Vec<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Support {
+    pub channels: Vec<u64>,
+    pub digest_channel: u64,
+    pub similarity: f64,
+    pub faq: Vec<Faq>,
+    #[serde(default)]
+    pub transcript_channel: u64,
+    #[serde(default)]
+    pub reply_drafting: ReplyDrafting,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct ReplyDrafting {
+    pub enabled: bool,
+    pub endpoint: String,
+    pub authorization: Option<String>,
+    pub model: String,
+    pub prompt: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Faq {
+    pub question: String,
+    pub url: String,
+    #[serde(default)]
+    pub localizations: HashMap<String, String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct LiveRole {
+    pub role: u64,
+    pub keywords: Vec<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct MentionGuard {
+    pub enabled: bool,
+    pub roles: Vec<u64>,
+    pub everyone: bool,
+    pub exempt_roles: Vec<u64>,
+    pub request_channel: u64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct MentionRedirect {
+    pub enabled: bool,
+    pub users: Vec<u64>,
+    pub channels: Vec<u64>,
+    pub forum: u64,
+    pub delete: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Impersonation {
+    pub enabled: bool,
+    pub roles: Vec<u64>,
+    pub name_similarity: f64,
+    pub avatar_distance: u32,
+    pub rename: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct Automod {
+    pub queue_channel: u64,
+    pub exempt_roles: Vec<u64>,
+    pub rules: Vec<AutomodRule>,
+    #[serde(default)]
+    pub links: AutomodLinks,
+    #[serde(default)]
+    pub crossposts: AutomodCrossposts,
+    #[serde(default)]
+    pub windows: Vec<ContributorWindow>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct AutomodLinks {
+    pub enabled: bool,
+    pub shorteners: Vec<String>,
+    pub tracking_parameters: Vec<String>,
+    pub blocked_domains: Vec<String>,
+    pub suppress_embeds: bool,
+    pub repost: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct AutomodCrossposts {
+    pub enabled: bool,
+    pub minutes: i64,
+    pub min_length: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ContributorWindow {
+    pub name: String,
+    pub trigger_channel: u64,
+    pub channels: Vec<u64>,
+    pub roles: Vec<u64>,
+    pub minutes: i64,
+    #[serde(default)]
+    pub announce: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AutomodRule {
+    pub name: String,
+    #[serde(default)]
+    pub channels: Vec<u64>,
+    pub patterns: Vec<AutomodPattern>,
+    pub queue_threshold: f64,
+    pub action_threshold: f64,
+    pub action: AutomodAction,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AutomodPattern {
+    #[serde(rename = "match", with = "serde_regex")]
+    pub match_field: Regex,
+    pub weight: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AutomodAction {
+    pub delete: bool,
+    pub mute: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    pub endpoint: String,
+    pub public_url: String,
+    pub authorization: Option<String>,
+    pub max_size: u64,
+    pub mime_types: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AnnouncementThread {
+    pub channels: Vec<u64>,
+    pub name: String,
+    #[serde(default)]
+    pub reactions: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Introduction {
     pub channels: Vec<u64>,
@@ -166,3 +758,22 @@ pub struct Condition {
 pub struct User {
     pub server_age: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::configuration;
+
+    #[test]
+    fn routes_logs_by_category() {
+        let configuration = configuration(|c| {
+            c.general.logging_channel = 1;
+            c.logging.members = 2;
+        });
+
+        assert_eq!(configuration.log_channel(LogCategory::Moderation), 1);
+        assert_eq!(configuration.log_channel(LogCategory::Members), 2);
+        assert_eq!(configuration.log_channel(LogCategory::Voice), 0);
+        assert_eq!(configuration.log_channel(LogCategory::Errors), 0);
+    }
+}
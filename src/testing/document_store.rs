@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use bson::{Bson, Document};
+use serde::Serialize;
+
+use crate::db::document_store::DocumentStore;
+
+type StoreResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// A document store keeping the documents in memory, to test logic built on [`DocumentStore`]
+/// without MongoDB.
+#[derive(Default)]
+pub struct MemoryStore {
+    collections: Mutex<HashMap<String, Vec<Document>>>,
+}
+
+impl MemoryStore {
+    /// Store a record in a collection.
+    pub fn insert<T: Serialize>(&self, collection: &str, record: &T) {
+        self.collections
+            .lock()
+            .unwrap()
+            .entry(collection.to_string())
+            .or_default()
+            .push(bson::to_document(record).unwrap());
+    }
+}
+
+impl DocumentStore for MemoryStore {
+    async fn find_documents(
+        &self,
+        collection: &str,
+        filter: Document,
+    ) -> StoreResult<Vec<Document>> {
+        Ok(self
+            .collections
+            .lock()
+            .unwrap()
+            .get(collection)
+            .map(|documents| {
+                documents
+                    .iter()
+                    .filter(|d| matches(d, &filter))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+/// Whether a document matches a filter of equalities and comparison operators, like MongoDB.
+fn matches(document: &Document, filter: &Document) -> bool {
+    filter.iter().all(|(key, condition)| {
+        let value = document.get(key);
+        match condition {
+            Bson::Document(operators) if operators.keys().all(|k| k.starts_with('$')) => operators
+                .iter()
+                .all(|(operator, operand)| compare(value, operator, operand)),
+            expected => equals(value, expected),
+        }
+    })
+}
+
+/// Whether a value equals the expected one. Arrays match if one of their items does.
+fn equals(value: Option<&Bson>, expected: &Bson) -> bool {
+    match value {
+        Some(Bson::Array(items)) if !matches!(expected, Bson::Array(_)) => {
+            items.iter().any(|item| equals(Some(item), expected))
+        },
+        Some(value) => match (number(value), number(expected)) {
+            (Some(value), Some(expected)) => value == expected,
+            _ => value == expected,
+        },
+        None => *expected == Bson::Null,
+    }
+}
+
+// Numbers are compared regardless of their width, like MongoDB does
+fn number(value: &Bson) -> Option<f64> {
+    match value {
+        Bson::Int32(n) => Some(*n as f64),
+        Bson::Int64(n) => Some(*n as f64),
+        Bson::Double(n) => Some(*n),
+        _ => None,
+    }
+}
+
+fn compare(value: Option<&Bson>, operator: &str, operand: &Bson) -> bool {
+    match operator {
+        "$eq" => equals(value, operand),
+        "$ne" => !equals(value, operand),
+        "$in" => match operand {
+            Bson::Array(candidates) => candidates.iter().any(|c| equals(value, c)),
+            _ => false,
+        },
+        "$exists" => value.is_some() == (*operand == Bson::Boolean(true)),
+        "$lt" | "$lte" | "$gt" | "$gte" => match (value.and_then(number), number(operand)) {
+            (Some(value), Some(operand)) => match operator {
+                "$lt" => value < operand,
+                "$lte" => value <= operand,
+                "$gt" => value > operand,
+                _ => value >= operand,
+            },
+            _ => false,
+        },
+        // Silently matching would make tests pass for the wrong reasons
+        _ => panic!("The in-memory document store does not support {}", operator),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::doc;
+
+    use super::*;
+
+    #[test]
+    fn matches_like_mongodb() {
+        let document = doc! { "user_id": "1", "expires": 10_i64, "voters": ["2", "3"] };
+
+        assert!(matches(&document, &doc! {}));
+        assert!(matches(&document, &doc! { "user_id": "1", "voters": "3" }));
+        assert!(matches(
+            &document,
+            &doc! { "expires": { "$lte": 10, "$gt": 5 } }
+        ));
+        assert!(matches(
+            &document,
+            &doc! { "user_id": { "$in": ["1", "4"] } }
+        ));
+        assert!(matches(&document, &doc! { "reason": null }));
+        assert!(!matches(&document, &doc! { "expires": { "$lt": 10 } }));
+        assert!(!matches(&document, &doc! { "user_id": "2" }));
+    }
+}
@@ -0,0 +1,68 @@
+//! Fixtures and an in-memory document store to test the bot without connecting to Discord or MongoDB.
+//!
+//! Enabled in tests and with the `testing` feature.
+
+// Outside of tests, nothing in the bot itself uses the fixtures
+#![allow(dead_code)]
+
+use poise::serenity_prelude::Message;
+use serde_json::json;
+
+use crate::model::application::Configuration;
+
+pub mod document_store;
+
+const EXAMPLE_CONFIGURATION: &str = include_str!("../../configuration.example.json");
+
+/// The example configuration, adjusted by a closure.
+pub fn configuration(f: impl FnOnce(&mut Configuration)) -> Configuration {
+    let mut configuration = serde_json::from_str(EXAMPLE_CONFIGURATION)
+        .expect("The example configuration does not match the configuration");
+    f(&mut configuration);
+    configuration
+}
+
+fn user(user_id: u64) -> serde_json::Value {
+    json!({
+        "id": user_id.to_string(),
+        "username": format!("user{}", user_id),
+        "discriminator": "0001",
+        "avatar": null,
+    })
+}
+
+/// A message without attachments, embeds or mentions.
+pub fn message(guild_id: u64, channel_id: u64, author_id: u64, content: &str) -> Message {
+    serde_json::from_value(json!({
+        "id": "1",
+        "guild_id": guild_id.to_string(),
+        "channel_id": channel_id.to_string(),
+        "author": user(author_id),
+        "content": content,
+        "timestamp": "1970-01-01T00:00:00+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "pinned": false,
+        "type": 0,
+    }))
+    .expect("Invalid message fixture")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures_deserialize() {
+        configuration(|_| {});
+
+        let message = message(1, 4, 2, "Hello");
+        assert_eq!(message.channel_id.0, 4);
+        assert_eq!(message.content, "Hello");
+    }
+}
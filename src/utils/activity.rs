@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use bson::doc;
+use chrono::Utc;
+use mongodb::options::{UpdateModifications, UpdateOptions};
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::Mutex;
+use tracing::error;
+
+use super::bot::get_data;
+use super::*;
+use crate::db::model::Activity;
+
+// Only record the activity of a member once in this interval to spare the database
+const RECORD_INTERVAL_SECS: i64 = 60 * 60;
+
+// The last time the activity of a member was recorded by guild and user
+static LAST_RECORDED: Lazy<Mutex<HashMap<(u64, u64), i64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Record the time of the last message of a member.
+pub async fn record_activity(ctx: &serenity::Context, new_message: &serenity::Message) {
+    let guild_id = match new_message.guild_id {
+        Some(guild_id) if !new_message.author.bot => guild_id,
+        _ => return,
+    };
+
+    let now = Utc::now().timestamp();
+    {
+        let mut last_recorded = LAST_RECORDED.lock().await;
+        let key = (guild_id.0, new_message.author.id.0);
+        if last_recorded
+            .get(&key)
+            .is_some_and(|&last| now - last < RECORD_INTERVAL_SECS)
+        {
+            return;
+        }
+        last_recorded.insert(key, now);
+    }
+
+    let data = get_data(ctx).await;
+
+    if let Err(err) = data
+        .database
+        .update::<Activity>(
+            "activity",
+            doc! {
+                "guild_id": guild_id.to_string(),
+                "user_id": new_message.author.id.to_string(),
+            },
+            UpdateModifications::Document(doc! { "$set": { "last_message": now } }),
+            Some(UpdateOptions::builder().upsert(true).build()),
+        )
+        .await
+    {
+        error!("Failed to record activity: {:?}", err);
+    }
+}
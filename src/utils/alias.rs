@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use poise::serenity_prelude::{
+    ApplicationCommandInteraction,
+    CommandDataOption,
+    CreateApplicationCommand,
+    GuildId,
+};
+use serde_json::{json, Value};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::command_sync::sync_guild_commands;
+use super::*;
+use crate::db::model::Alias;
+
+/// Parse preset arguments in the form of `key=value;key=value`.
+pub fn parse_arguments(arguments: &str) -> HashMap<String, String> {
+    arguments
+        .split(';')
+        .filter_map(|argument| {
+            let (key, value) = argument.split_once('=')?;
+            Some((key.trim().to_string(), value.trim().to_string()))
+        })
+        .filter(|(key, _)| !key.is_empty())
+        .collect()
+}
+
+/// Create the slash command for an alias by copying the aliased command without its preset options.
+pub fn create_alias_command<U, E>(
+    commands: &[poise::Command<U, E>],
+    alias: &Alias,
+) -> Option<CreateApplicationCommand> {
+    let command = commands
+        .iter()
+        .find(|c| Some(&c.name) == alias.command.as_ref() && c.subcommands.is_empty())?;
+    let mut builder = command.create_as_slash_command()?;
+    let presets = alias.arguments.clone().unwrap_or_default();
+
+    builder.name(alias.name.as_ref()?).description(format!(
+        "Alias of /{} {}",
+        command.name,
+        presets
+            .iter()
+            .map(|(key, value)| format!("{}:{}", key, value))
+            .collect::<Vec<_>>()
+            .join(" ")
+    ));
+
+    if let Some(Value::Array(options)) = builder.0.get_mut("options") {
+        options.retain(|option| {
+            !option["name"]
+                .as_str()
+                .is_some_and(|name| presets.contains_key(name))
+        });
+    }
+
+    Some(builder)
+}
+
+/// Register the aliases of all guilds as guild-scoped slash commands.
+/// Only aliases which differ from the registered commands are sent to Discord.
+pub async fn register_aliases<U, E>(ctx: &serenity::Context, commands: &[poise::Command<U, E>]) {
+    let data = get_data(ctx).await;
+
+    let mut cursor = match data
+        .database
+        .find::<Alias>("aliases", Alias::default().into(), None)
+        .await
+    {
+        Ok(cursor) => cursor,
+        Err(err) => {
+            error!("Failed to query database for aliases: {:?}", err);
+            return;
+        },
+    };
+
+    let mut desired: HashMap<GuildId, Vec<Value>> = HashMap::new();
+    while let Ok(true) = cursor.advance().await {
+        let alias: Alias = match cursor.deserialize_current() {
+            Ok(alias) => alias,
+            Err(err) => {
+                error!("Failed to deserialize alias: {:?}", err);
+                continue;
+            },
+        };
+
+        let guild_id = match alias
+            .guild_id
+            .as_ref()
+            .and_then(|id| id.parse::<u64>().ok())
+        {
+            Some(guild_id) => GuildId(guild_id),
+            None => continue,
+        };
+        let command = match create_alias_command(commands, &alias)
+            .map(|builder| serde_json::to_value(builder.0))
+        {
+            Some(Ok(command)) => command,
+            _ => {
+                error!("Failed to create the command of alias {:?}", alias.name);
+                continue;
+            },
+        };
+
+        desired.entry(guild_id).or_default().push(command);
+    }
+
+    // Guild commands of guilds without aliases are left alone
+    for (guild_id, desired) in desired {
+        if let Err(err) = sync_guild_commands(&ctx.http, guild_id, desired).await {
+            error!(
+                "Failed to register aliases of guild {}: {:?}",
+                guild_id, err
+            );
+        }
+    }
+}
+
+/// Register a single alias as a guild-scoped slash command.
+pub async fn register_alias<U, E>(
+    ctx: &serenity::Context,
+    commands: &[poise::Command<U, E>],
+    alias: &Alias,
+) -> Result<(), crate::Error> {
+    let guild_id = GuildId(
+        alias
+            .guild_id
+            .as_ref()
+            .ok_or("Alias without guild")?
+            .parse::<u64>()?,
+    );
+    let builder = create_alias_command(commands, alias).ok_or("Unknown command")?;
+
+    guild_id
+        .create_application_command(&ctx.http, |c| {
+            *c = builder;
+            c
+        })
+        .await?;
+
+    debug!("Registered alias {:?} in guild {}", alias.name, guild_id);
+    Ok(())
+}
+
+/// Rewrite an interaction invoking an alias into an interaction of the aliased command.
+pub async fn resolve_alias<U, E>(
+    ctx: &serenity::Context,
+    commands: &[poise::Command<U, E>],
+    interaction: &mut ApplicationCommandInteraction,
+) {
+    if commands.iter().any(|c| c.name == interaction.data.name) {
+        return;
+    }
+
+    let guild_id = match interaction.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    let data = get_data(ctx).await;
+    let alias = match data
+        .database
+        .find::<Alias>(
+            "aliases",
+            Alias {
+                guild_id: Some(guild_id.to_string()),
+                name: Some(interaction.data.name.clone()),
+                ..Default::default()
+            }
+            .into(),
+            None,
+        )
+        .await
+    {
+        Ok(mut cursor) => match cursor.advance().await {
+            Ok(true) => cursor.deserialize_current().ok(),
+            _ => None,
+        },
+        Err(_) => None,
+    };
+
+    let (alias, command) = match alias.and_then(|alias| {
+        let command = commands
+            .iter()
+            .find(|c| Some(&c.name) == alias.command.as_ref())?;
+        Some((alias, command))
+    }) {
+        Some(found) => found,
+        None => return,
+    };
+
+    for (key, value) in alias.arguments.unwrap_or_default() {
+        let kind = command
+            .parameters
+            .iter()
+            .find(|p| p.name == key)
+            .and_then(|p| p.create_as_slash_command_option())
+            .and_then(|o| o.0.get("type").and_then(Value::as_u64))
+            .unwrap_or(3);
+
+        // Convert the preset string into the JSON type Discord would send for the option
+        let value = match kind {
+            4 => value.parse::<i64>().map(Value::from).unwrap_or(Value::Null),
+            5 => value
+                .parse::<bool>()
+                .map(Value::from)
+                .unwrap_or(Value::Null),
+            10 => value.parse::<f64>().map(Value::from).unwrap_or(Value::Null),
+            _ => Value::from(value),
+        };
+
+        match serde_json::from_value::<CommandDataOption>(json!({
+            "name": key,
+            "type": kind,
+            "value": value,
+        })) {
+            Ok(option) => interaction.data.options.push(option),
+            Err(err) => error!("Failed to create preset option {}: {:?}", key, err),
+        }
+    }
+
+    debug!(
+        "Resolved alias {} to {}",
+        interaction.data.name, command.name
+    );
+    interaction.data.name = command.name.clone();
+}
@@ -0,0 +1,96 @@
+use chrono::Utc;
+use poise::serenity_prelude::ReactionType;
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::*;
+
+// Discord limits the length of thread names
+const MAX_THREAD_NAME_LENGTH: usize = 100;
+
+/// Fill the placeholders `{author}`, `{title}` and `{date}` of a thread name template.
+fn thread_name(template: &str, message: &serenity::Message) -> String {
+    let title = message
+        .content
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .map(|line| line.trim_start_matches('#').trim())
+        .unwrap_or("Discussion");
+
+    let name = template
+        .replace("{author}", &message.author.name)
+        .replace("{title}", title)
+        .replace("{date}", &Utc::now().format("%Y-%m-%d").to_string());
+
+    name.chars().take(MAX_THREAD_NAME_LENGTH).collect()
+}
+
+pub async fn handle_announcement(ctx: &serenity::Context, new_message: &serenity::Message) {
+    if new_message.guild_id.is_none() {
+        return;
+    }
+
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+
+    let announcement = match configuration
+        .announcement_threads
+        .iter()
+        .find(|a| a.channels.contains(&new_message.channel_id.0))
+    {
+        Some(announcement) => announcement,
+        None => return,
+    };
+
+    let name = thread_name(&announcement.name, new_message);
+    match new_message
+        .channel_id
+        .create_public_thread(&ctx.http, new_message.id, |t| t.name(&name))
+        .await
+    {
+        Ok(_) => debug!("Created discussion thread {}", name),
+        Err(err) => error!("Failed to create discussion thread: {:?}", err),
+    }
+
+    for reaction in &announcement.reactions {
+        match reaction.parse::<ReactionType>() {
+            Ok(reaction) => {
+                if let Err(err) = new_message.react(&ctx.http, reaction).await {
+                    error!("Failed to react to announcement: {:?}", err);
+                }
+            },
+            Err(_) => error!("Invalid reaction {} in configuration", reaction),
+        }
+    }
+}
+
+/// Remove reactions to announcements which are not part of the curated set.
+pub async fn handle_announcement_reaction(ctx: &serenity::Context, reaction: &serenity::Reaction) {
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+
+    let announcement = match configuration
+        .announcement_threads
+        .iter()
+        .find(|a| a.channels.contains(&reaction.channel_id.0))
+    {
+        Some(announcement) => announcement,
+        None => return,
+    };
+
+    if announcement.reactions.is_empty() {
+        return;
+    }
+
+    let is_curated = announcement
+        .reactions
+        .iter()
+        .filter_map(|r| r.parse::<ReactionType>().ok())
+        .any(|r| r == reaction.emoji);
+
+    if !is_curated {
+        if let Err(err) = reaction.delete(&ctx.http).await {
+            error!("Failed to remove reaction from announcement: {:?}", err);
+        }
+    }
+}
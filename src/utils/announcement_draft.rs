@@ -0,0 +1,345 @@
+use bson::{doc, Document};
+use chrono::Utc;
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument, UpdateModifications};
+use poise::serenity_prelude::{
+    ActionRowComponent,
+    ChannelId,
+    CreateEmbed,
+    InputTextStyle,
+    InteractionResponseType,
+    MessageComponentInteraction,
+    ModalSubmitInteraction,
+};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::interaction::{respond_ephemeral, update_with_decision};
+use super::moderation::is_administrator;
+use super::time::format_timestamp;
+use super::{dry_run, *};
+use crate::db::model::{AnnouncementDraft, DraftStatus};
+use crate::Error;
+
+// Discord rejects messages longer than this
+pub const MAX_CONTENT_LENGTH: usize = 2000;
+
+/// Describe a draft in the embed of its approval message.
+pub fn describe_draft<'a>(
+    e: &'a mut CreateEmbed,
+    draft: &AnnouncementDraft,
+    embed_color: i32,
+) -> &'a mut CreateEmbed {
+    e.title("Announcement draft")
+        .description(draft.content.as_deref().unwrap_or_default())
+        .field(
+            "Author",
+            format!("<@{}>", draft.author_id.as_deref().unwrap_or_default()),
+            true,
+        )
+        .field(
+            "Channel",
+            format!("<#{}>", draft.channel_id.as_deref().unwrap_or_default()),
+            true,
+        )
+        .field(
+            "Publication",
+            draft
+                .publish_at
+                .map_or("When approved".to_string(), format_timestamp),
+            true,
+        );
+
+    if let Some(role_id) = &draft.interest_role_id {
+        e.field("Audience", format!("<@&{}>", role_id), true);
+    }
+
+    e.color(embed_color)
+}
+
+async fn find_draft(ctx: &serenity::Context, message_id: &str) -> Option<AnnouncementDraft> {
+    let data = get_data(ctx).await;
+    match data
+        .database
+        .collect::<AnnouncementDraft>("announcement_drafts", doc! { "message_id": message_id })
+        .await
+    {
+        Ok(mut drafts) => drafts.pop(),
+        Err(err) => {
+            error!(
+                "Failed to query database for draft {}: {:?}",
+                message_id, err
+            );
+            None
+        },
+    }
+}
+
+/// Publish an approved draft, unless it was already published.
+pub async fn publish(ctx: &serenity::Context, message_id: &str) -> Result<(), Error> {
+    let data = get_data(ctx).await;
+
+    // Claim the draft first, so it is never published twice
+    let draft = data
+        .database
+        .find_and_update::<AnnouncementDraft>(
+            "announcement_drafts",
+            doc! { "message_id": message_id, "status": "approved" },
+            UpdateModifications::Document(doc! { "$set": { "status": "published" } }),
+            Some(
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            ),
+        )
+        .await?;
+    let draft = match draft {
+        Some(draft) => draft,
+        None => return Ok(()),
+    };
+
+    let channel_id = ChannelId(
+        draft
+            .channel_id
+            .as_deref()
+            .unwrap_or_default()
+            .parse::<u64>()?,
+    );
+    // Targeted announcements ping the members interested in their topic
+    let content = match draft.interest_role_id {
+        Some(role_id) => format!("<@&{}>\n{}", role_id, draft.content.unwrap_or_default()),
+        None => draft.content.unwrap_or_default(),
+    };
+
+    let result = dry_run::execute(
+        format!("publish announcement {} in {}", message_id, channel_id),
+        async { channel_id.say(&ctx.http, &content).await.map(|_| ()) },
+    )
+    .await;
+
+    if let Err(err) = result {
+        // Let the next attempt publish it
+        data.database
+            .update::<AnnouncementDraft>(
+                "announcement_drafts",
+                doc! { "message_id": message_id },
+                UpdateModifications::Document(doc! { "$set": { "status": "approved" } }),
+                None,
+            )
+            .await?;
+        return Err(err.into());
+    }
+
+    debug!("Published announcement {} in {}", message_id, channel_id);
+    Ok(())
+}
+
+/// Publish an approved draft after a delay.
+pub fn schedule_publication(ctx: &serenity::Context, message_id: String, seconds: u64) {
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(seconds)).await;
+
+        if let Err(err) = publish(&ctx, &message_id).await {
+            error!("Failed to publish announcement {}: {:?}", message_id, err);
+        }
+    });
+}
+
+/// Schedule the publication of all approved drafts which were not published yet.
+pub async fn load_drafts(ctx: &serenity::Context) {
+    let drafts = {
+        let data = get_data(ctx).await;
+        match data
+            .database
+            .collect::<AnnouncementDraft>("announcement_drafts", doc! { "status": "approved" })
+            .await
+        {
+            Ok(drafts) => drafts,
+            Err(err) => {
+                error!("Failed to load announcement drafts: {:?}", err);
+                return;
+            },
+        }
+    };
+
+    let now = Utc::now().timestamp();
+    for draft in drafts {
+        if let Some(message_id) = draft.message_id {
+            let delay = draft.publish_at.map_or(0, |at| (at - now).max(0));
+            schedule_publication(ctx, message_id, delay as u64);
+        }
+    }
+}
+
+/// Handle the Approve, Edit and Reject buttons of announcement drafts.
+pub async fn handle_draft_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    let action = match component.data.custom_id.strip_prefix("announce:") {
+        Some(action) => action,
+        None => return,
+    };
+    let message_id = component.message.id.to_string();
+
+    let draft = match find_draft(ctx, &message_id).await {
+        Some(draft) if draft.status == Some(DraftStatus::Pending) => draft,
+        _ => {
+            respond_ephemeral(ctx, component, "This draft was already decided.").await;
+            return;
+        },
+    };
+
+    let data = get_data(ctx).await;
+    let is_administrator = component
+        .member
+        .as_ref()
+        .is_some_and(|m| is_administrator(&data.configuration.administrators, m));
+    let is_author = draft.author_id == Some(component.user.id.to_string());
+
+    // Authors can edit their drafts, but only administrators can decide them
+    if !(is_administrator || (action == "edit" && is_author)) {
+        respond_ephemeral(ctx, component, "You do not have permission to do this.").await;
+        return;
+    }
+
+    let (status, decision) = match action {
+        "edit" => {
+            if let Err(err) = component
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::Modal)
+                        .interaction_response_data(|d| {
+                            d.custom_id(format!("announce:edit:{}", message_id))
+                                .title("Edit announcement")
+                                .components(|c| {
+                                    c.create_action_row(|r| {
+                                        r.create_input_text(|t| {
+                                            t.custom_id("content")
+                                                .label("Announcement")
+                                                .style(InputTextStyle::Paragraph)
+                                                .max_length(MAX_CONTENT_LENGTH as u64)
+                                                .value(draft.content.as_deref().unwrap_or_default())
+                                                .required(true)
+                                        })
+                                    })
+                                })
+                        })
+                })
+                .await
+            {
+                error!("Failed to open announcement editor: {:?}", err);
+            }
+            return;
+        },
+        "approve" => (DraftStatus::Approved, match draft.publish_at {
+            Some(at) if at > Utc::now().timestamp() => format!(
+                "Approved by <@{}>, publishing {}",
+                component.user.id,
+                format_timestamp(at)
+            ),
+            _ => format!("Approved and published by <@{}>", component.user.id),
+        }),
+        "reject" => (
+            DraftStatus::Rejected,
+            format!("Rejected by <@{}>", component.user.id),
+        ),
+        _ => return,
+    };
+
+    let decided: Document = AnnouncementDraft {
+        status: Some(status),
+        moderator_id: Some(component.user.id.to_string()),
+        decided: Some(Utc::now().timestamp()),
+        ..Default::default()
+    }
+    .into();
+    match data
+        .database
+        .update::<AnnouncementDraft>(
+            "announcement_drafts",
+            doc! { "message_id": &message_id, "status": "pending" },
+            UpdateModifications::Document(doc! { "$set": decided }),
+            None,
+        )
+        .await
+    {
+        Ok(result) if result.modified_count == 0 => {
+            respond_ephemeral(ctx, component, "This draft was already decided.").await;
+            return;
+        },
+        Ok(_) => {},
+        Err(err) => {
+            error!("Failed to decide announcement draft: {:?}", err);
+            respond_ephemeral(ctx, component, "Failed to decide this draft.").await;
+            return;
+        },
+    }
+
+    if status == DraftStatus::Approved {
+        let delay = draft
+            .publish_at
+            .map_or(0, |at| (at - Utc::now().timestamp()).max(0));
+        schedule_publication(ctx, message_id, delay as u64);
+    }
+
+    update_with_decision(ctx, component, &decision).await;
+}
+
+/// Handle the submission of an edited announcement draft.
+pub async fn handle_draft_modal(ctx: &serenity::Context, modal: &ModalSubmitInteraction) {
+    let message_id = match modal.data.custom_id.strip_prefix("announce:edit:") {
+        Some(message_id) => message_id,
+        None => return,
+    };
+    let content = modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == "content" => {
+                Some(input.value.clone())
+            },
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let data = get_data(ctx).await;
+    let draft = match data
+        .database
+        .find_and_update::<AnnouncementDraft>(
+            "announcement_drafts",
+            doc! { "message_id": message_id, "status": "pending" },
+            UpdateModifications::Document(doc! { "$set": { "content": &content } }),
+            Some(
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            ),
+        )
+        .await
+    {
+        Ok(Some(draft)) => draft,
+        Ok(None) => return,
+        Err(err) => {
+            error!("Failed to edit announcement draft: {:?}", err);
+            return;
+        },
+    };
+
+    let embed_color = data.configuration.general.embed_color;
+    if let Err(err) = modal
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.embed(|e| {
+                        describe_draft(e, &draft, embed_color)
+                            .footer(|f| f.text(format!("Last edited by {}", modal.user.tag())))
+                    })
+                })
+        })
+        .await
+    {
+        error!("Failed to update announcement draft: {:?}", err);
+    }
+}
@@ -0,0 +1,364 @@
+use bson::doc;
+use chrono::Utc;
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument, UpdateModifications};
+use poise::serenity_prelude::{
+    ActionRowComponent,
+    ButtonStyle,
+    ChannelId,
+    CreateComponents,
+    GuildId,
+    InputTextStyle,
+    InteractionResponseType,
+    MessageComponentInteraction,
+    ModalSubmitInteraction,
+    UserId,
+};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::cases::increment_counter;
+use super::interaction::{respond_ephemeral, update_with_decision};
+use super::moderation::{is_administrator, unban_with_audit_reason, unmute_user};
+use super::{dry_run, *};
+use crate::db::model::{Appeal, AppealStatus};
+use crate::Error;
+
+// Keeps appeals readable in the embed of the review message
+const MAX_APPEAL_LENGTH: u64 = 1000;
+
+/// Add a button which lets a muted or banned user appeal, if appeals are enabled.
+pub fn appeal_button<'a>(
+    components: &'a mut CreateComponents,
+    review_channel: u64,
+    guild_id: GuildId,
+    action: &str,
+) -> &'a mut CreateComponents {
+    if review_channel == 0 {
+        return components;
+    }
+
+    components.create_action_row(|r| {
+        r.create_button(|b| {
+            b.custom_id(format!("appeal:open:{}:{}", guild_id, action))
+                .label("Appeal")
+                .style(ButtonStyle::Primary)
+        })
+    })
+}
+
+/// Find the pending appeal of a user against an action, so each action is only appealed once.
+async fn find_pending(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    action: &str,
+) -> Result<Option<Appeal>, Error> {
+    let data = get_data(ctx).await;
+
+    Ok(data
+        .database
+        .collect::<Appeal>("appeals", doc! {
+            "guild_id": guild_id.to_string(),
+            "user_id": user_id.to_string(),
+            "action": action,
+            "status": "pending",
+        })
+        .await?
+        .into_iter()
+        .next())
+}
+
+/// Open the appeal form for the Appeal button of a direct message.
+async fn open_form(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+    guild_id: GuildId,
+    action: &str,
+) {
+    match find_pending(ctx, guild_id, component.user.id, action).await {
+        Ok(None) => {},
+        Ok(Some(_)) => {
+            respond_ephemeral(ctx, component, "Your appeal is still being reviewed.").await;
+            return;
+        },
+        Err(err) => {
+            error!("Failed to find pending appeals: {:?}", err);
+            respond_ephemeral(ctx, component, "Failed to open the appeal form.").await;
+            return;
+        },
+    }
+
+    if let Err(err) = component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::Modal)
+                .interaction_response_data(|d| {
+                    d.custom_id(format!("appeal:submit:{}:{}", guild_id, action))
+                        .title(format!("Appeal your {}", action))
+                        .components(|c| {
+                            c.create_action_row(|r| {
+                                r.create_input_text(|t| {
+                                    t.custom_id("content")
+                                        .label("Why should it be lifted?")
+                                        .style(InputTextStyle::Paragraph)
+                                        .max_length(MAX_APPEAL_LENGTH)
+                                        .required(true)
+                                })
+                            })
+                        })
+                })
+        })
+        .await
+    {
+        error!("Failed to open appeal form: {:?}", err);
+    }
+}
+
+/// Set the status of a pending appeal, returning it if it was still pending.
+async fn decide(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    appeal_id: i64,
+    status: AppealStatus,
+    moderator_id: UserId,
+) -> Result<Option<Appeal>, Error> {
+    let data = get_data(ctx).await;
+
+    data.database
+        .find_and_update::<Appeal>(
+            "appeals",
+            // Only decide once, even if both buttons are clicked at the same time
+            doc! { "guild_id": guild_id.to_string(), "appeal_id": appeal_id, "status": "pending" },
+            UpdateModifications::Document(doc! { "$set": {
+                "status": bson::to_bson(&status)?,
+                "moderator_id": moderator_id.to_string(),
+                "decided": Utc::now().timestamp(),
+            } }),
+            Some(
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            ),
+        )
+        .await
+}
+
+/// Lift the mute or ban of an accepted appeal.
+async fn lift(ctx: &serenity::Context, guild_id: GuildId, appeal: &Appeal) -> Result<(), Error> {
+    let user_id = UserId(appeal.user_id.as_ref().ok_or("No user")?.parse()?);
+
+    match appeal.action.as_deref() {
+        Some("ban") => {
+            dry_run::execute(
+                format!("unban {} after their appeal", user_id),
+                unban_with_audit_reason(&ctx.http, guild_id, user_id, "Appeal accepted"),
+            )
+            .await?;
+            Ok(())
+        },
+        Some("mute") => unmute_user(ctx, guild_id, user_id).await,
+        _ => Err("Unknown action".into()),
+    }
+}
+
+/// Handle the Appeal button of direct messages and the Accept and Deny buttons of appeals.
+pub async fn handle_appeal_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    let mut parts = component.data.custom_id.splitn(4, ':').skip(1);
+    let (action, guild_id, argument) = match (
+        parts.next(),
+        parts.next().and_then(|id| id.parse::<u64>().ok()),
+        parts.next(),
+    ) {
+        (Some(action), Some(guild_id), Some(argument)) => (action, GuildId(guild_id), argument),
+        _ => return,
+    };
+
+    if action == "open" {
+        open_form(ctx, component, guild_id, argument).await;
+        return;
+    }
+
+    let appeal_id = match argument.parse::<i64>() {
+        Ok(appeal_id) => appeal_id,
+        Err(_) => return,
+    };
+
+    {
+        let data = get_data(ctx).await;
+        let is_allowed = component
+            .member
+            .as_ref()
+            .is_some_and(|m| is_administrator(&data.configuration.administrators, m));
+
+        if !is_allowed {
+            drop(data);
+            respond_ephemeral(ctx, component, "You do not have permission to do this.").await;
+            return;
+        }
+    }
+
+    let status = match action {
+        "accept" => AppealStatus::Accepted,
+        "deny" => AppealStatus::Denied,
+        _ => return,
+    };
+
+    let appeal = match decide(ctx, guild_id, appeal_id, status, component.user.id).await {
+        Ok(Some(appeal)) => appeal,
+        Ok(None) => {
+            respond_ephemeral(ctx, component, "This appeal was already decided.").await;
+            return;
+        },
+        Err(err) => {
+            error!("Failed to decide appeal: {:?}", err);
+            respond_ephemeral(ctx, component, "Failed to decide this appeal.").await;
+            return;
+        },
+    };
+
+    let decision = match status {
+        AppealStatus::Accepted => match lift(ctx, guild_id, &appeal).await {
+            Ok(_) => format!("Accepted by <@{}>", component.user.id),
+            Err(err) => {
+                error!("Failed to lift appealed action: {:?}", err);
+                format!(
+                    "Accepted by <@{}>, but failed to lift the {}: {}",
+                    component.user.id,
+                    appeal.action.as_deref().unwrap_or_default(),
+                    err
+                )
+            },
+        },
+        _ => format!("Denied by <@{}>", component.user.id),
+    };
+
+    update_with_decision(ctx, component, &decision).await;
+
+    // Banned users no longer share a server with the bot, but can still be reached if unbanned
+    if let Some(user_id) = appeal.user_id.and_then(|id| id.parse::<u64>().ok()) {
+        let outcome = match status {
+            AppealStatus::Accepted => "accepted",
+            _ => "denied",
+        };
+        let sent = match UserId(user_id).create_dm_channel(&ctx.http).await {
+            Ok(channel) => channel
+                .say(&ctx.http, format!("Your appeal was {}.", outcome))
+                .await
+                .map(|_| ()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = sent {
+            debug!("Failed to tell {} about their appeal: {:?}", user_id, err);
+        }
+    }
+}
+
+/// Store a submitted appeal and post it to the review channel.
+pub async fn handle_appeal_modal(ctx: &serenity::Context, modal: &ModalSubmitInteraction) {
+    let mut parts = modal.data.custom_id.splitn(4, ':').skip(1);
+    let (guild_id, action) = match (
+        parts.next(),
+        parts.next().and_then(|id| id.parse::<u64>().ok()),
+        parts.next(),
+    ) {
+        (Some("submit"), Some(guild_id), Some(action)) => (GuildId(guild_id), action),
+        _ => return,
+    };
+    let content = modal
+        .data
+        .components
+        .iter()
+        .flat_map(|row| &row.components)
+        .find_map(|component| match component {
+            ActionRowComponent::InputText(input) if input.custom_id == "content" => {
+                Some(input.value.clone())
+            },
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let response = match submit(ctx, guild_id, modal.user.id, action, &content).await {
+        Ok(true) => "Your appeal was submitted. You will be notified once staff reviewed it.",
+        Ok(false) => "Your appeal is still being reviewed.",
+        Err(err) => {
+            error!("Failed to submit appeal: {:?}", err);
+            "Failed to submit your appeal. Please try again later."
+        },
+    };
+
+    if let Err(err) = modal
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.content(response))
+        })
+        .await
+    {
+        error!("Failed to respond to appeal: {:?}", err);
+    }
+}
+
+/// Store an appeal and post it to the review channel, unless one is pending already.
+async fn submit(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    action: &str,
+    content: &str,
+) -> Result<bool, Error> {
+    if find_pending(ctx, guild_id, user_id, action)
+        .await?
+        .is_some()
+    {
+        return Ok(false);
+    }
+
+    let data = get_data(ctx).await;
+    let appeal_id = increment_counter(&data.database, &format!("appeals:{}", guild_id)).await?;
+    data.database
+        .insert::<Appeal>(
+            "appeals",
+            Appeal {
+                guild_id: Some(guild_id.to_string()),
+                appeal_id: Some(appeal_id),
+                user_id: Some(user_id.to_string()),
+                action: Some(action.to_string()),
+                content: Some(content.to_string()),
+                status: Some(AppealStatus::Pending),
+                timestamp: Some(Utc::now().timestamp()),
+                ..Default::default()
+            },
+            None,
+        )
+        .await?;
+
+    debug!("{} appealed their {} in {}", user_id, action, guild_id);
+
+    ChannelId(data.configuration.appeals.review_channel)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title(format!("Appeal #{} against a {}", appeal_id, action))
+                    .description(content)
+                    .field("User", format!("<@{}>", user_id), true)
+                    .color(data.configuration.general.embed_color)
+            })
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id(format!("appeal:accept:{}:{}", guild_id, appeal_id))
+                            .label("Accept")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|b| {
+                        b.custom_id(format!("appeal:deny:{}:{}", guild_id, appeal_id))
+                            .label("Deny")
+                            .style(ButtonStyle::Danger)
+                    })
+                })
+            })
+        })
+        .await?;
+
+    Ok(true)
+}
@@ -0,0 +1,82 @@
+use std::time::Instant;
+
+use chrono::Utc;
+use poise::serenity_prelude::{ChannelId, CommandDataOption};
+use tracing::error;
+
+use crate::db::model::InteractionLog;
+use crate::model::application::LogCategory;
+use crate::Context;
+
+/// Flatten the options of an interaction into `name: value` pairs.
+fn flatten_options(options: &[CommandDataOption], arguments: &mut Vec<String>) {
+    for option in options {
+        if let Some(value) = &option.value {
+            arguments.push(format!("{}: {}", option.name, value));
+        }
+        flatten_options(&option.options, arguments);
+    }
+}
+
+/// Record a command invocation to the interaction audit log.
+pub async fn record_interaction(ctx: Context<'_>, error: Option<String>) {
+    let latency = ctx
+        .invocation_data::<Instant>()
+        .await
+        .map(|started| started.elapsed().as_millis() as i64);
+
+    let arguments = match ctx {
+        poise::Context::Application(ctx) => {
+            let mut arguments = Vec::new();
+            flatten_options(ctx.args, &mut arguments);
+            arguments
+        },
+        poise::Context::Prefix(ctx) => vec![ctx.args.to_string()],
+    };
+
+    let interaction = InteractionLog {
+        user_id: Some(ctx.author().id.to_string()),
+        guild_id: ctx.guild_id().map(|g| g.to_string()),
+        channel_id: Some(ctx.channel_id().to_string()),
+        command: Some(ctx.command().qualified_name.clone()),
+        arguments: Some(arguments),
+        success: Some(error.is_none()),
+        error,
+        latency,
+        timestamp: Some(Utc::now().timestamp()),
+    };
+
+    if let Err(err) = ctx
+        .data()
+        .load()
+        .database
+        .insert::<InteractionLog>("interactions", interaction, None)
+        .await
+    {
+        error!("Failed to record interaction: {:?}", err);
+    }
+}
+
+/// Log a failed command invocation to the error log, if configured.
+pub async fn log_command_error(ctx: Context<'_>, error: &str) {
+    let configuration = &ctx.data().load().configuration;
+    let channel_id = configuration.log_channel(LogCategory::Errors);
+    if channel_id == 0 {
+        return;
+    }
+
+    if let Err(err) = ChannelId(channel_id)
+        .send_message(&ctx.discord().http, |m| {
+            m.allowed_mentions(|a| a.empty_parse()).embed(|e| {
+                e.title(format!("Command failed: /{}", ctx.command().qualified_name))
+                    .description(format!("```{}```", error))
+                    .field("User", format!("<@{}>", ctx.author().id), true)
+                    .field("Channel", format!("<#{}>", ctx.channel_id()), true)
+                    .color(configuration.general.embed_color)
+            })
+        })
+        .await
+    {
+        error!("Failed to log command error: {:?}", err);
+    }
+}
@@ -0,0 +1,393 @@
+use bson::{doc, Document};
+use chrono::{Duration, Utc};
+use mongodb::options::UpdateModifications;
+use poise::serenity_prelude::{
+    ButtonStyle,
+    ChannelId,
+    GuildId,
+    MessageComponentInteraction,
+    MessageId,
+    UserId,
+};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::interaction::{respond_ephemeral, update_with_decision};
+use super::member_cache::fetch_member;
+use super::moderation::{is_administrator, moderation_buttons, mute_member};
+use super::ocr::image_text;
+use super::{dry_run, *};
+use crate::db::model::{AutomodHit, AutomodHitStatus};
+use crate::model::application::{AutomodAction, AutomodRule, LogCategory};
+use crate::Error;
+
+/// Score a message against a rule. The score is the sum of the weights of all matching patterns.
+pub fn score(rule: &AutomodRule, content: &str) -> f64 {
+    rule.patterns
+        .iter()
+        .filter(|p| p.match_field.is_match(content))
+        .map(|p| p.weight)
+        .sum::<f64>()
+        .min(1.0)
+}
+
+/// Find the rule with the highest score a message triggers in the given channel.
+pub fn evaluate<'a>(
+    rules: &'a [AutomodRule],
+    channel_id: u64,
+    content: &str,
+) -> Option<(&'a AutomodRule, f64)> {
+    rules
+        .iter()
+        .filter(|r| r.channels.is_empty() || r.channels.contains(&channel_id))
+        .map(|r| (r, score(r, content)))
+        .filter(|(r, score)| *score > 0.0 && *score >= r.queue_threshold)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+/// Apply the action of a rule to the message and author of a hit.
+async fn apply_action(
+    ctx: &serenity::Context,
+    action: &AutomodAction,
+    rule: &str,
+    hit: &AutomodHit,
+) -> Result<(), Error> {
+    let channel_id = ChannelId(hit.channel_id.as_ref().ok_or("No channel")?.parse()?);
+    let message_id = MessageId(hit.message_id.as_ref().ok_or("No message")?.parse()?);
+    let guild_id = GuildId(hit.guild_id.as_ref().ok_or("No guild")?.parse()?);
+    let user_id = UserId(hit.user_id.as_ref().ok_or("No user")?.parse()?);
+
+    if action.delete {
+        if let Err(err) = dry_run::execute(
+            format!("delete message {}", message_id),
+            channel_id.delete_message(&ctx.http, message_id),
+        )
+        .await
+        {
+            debug!("Failed to delete message {}: {:?}", message_id, err);
+        }
+    }
+
+    if let Some(seconds) = action.mute {
+        let mut member = fetch_member(ctx, guild_id, user_id).await?;
+        let data = get_data(ctx).await;
+
+        if let Some(err) = mute_member(
+            &ctx.http,
+            &data,
+            &mut member,
+            Duration::seconds(seconds),
+            &format!("Automod: {}", rule),
+            None,
+        )
+        .await
+        {
+            return Err(err.into());
+        }
+    }
+
+    Ok(())
+}
+
+async fn set_status(
+    ctx: &serenity::Context,
+    message_id: &str,
+    status: AutomodHitStatus,
+    moderator_id: Option<String>,
+) -> Option<AutomodHit> {
+    let data = get_data(ctx).await;
+
+    let updated: Document = AutomodHit {
+        status: Some(status),
+        moderator_id,
+        ..Default::default()
+    }
+    .into();
+
+    if let Err(err) = data
+        .database
+        .update::<AutomodHit>(
+            "automod_hits",
+            AutomodHit {
+                message_id: Some(message_id.to_string()),
+                ..Default::default()
+            }
+            .into(),
+            UpdateModifications::Document(doc! { "$set": updated }),
+            None,
+        )
+        .await
+    {
+        error!("Failed to update automod hit: {:?}", err);
+    }
+
+    let mut cursor = data
+        .database
+        .find::<AutomodHit>(
+            "automod_hits",
+            AutomodHit {
+                message_id: Some(message_id.to_string()),
+                ..Default::default()
+            }
+            .into(),
+            None,
+        )
+        .await
+        .ok()?;
+
+    match cursor.advance().await {
+        Ok(true) => cursor.deserialize_current().ok(),
+        _ => None,
+    }
+}
+
+pub async fn automod(ctx: &serenity::Context, new_message: &serenity::Message) {
+    if new_message.guild_id.is_none() || new_message.author.bot {
+        return;
+    }
+
+    let data = get_data(ctx).await;
+    let automod = &data.configuration.automod;
+
+    if let Some(member) = &new_message.member {
+        if member
+            .roles
+            .iter()
+            .any(|r| automod.exempt_roles.contains(&r.0))
+        {
+            return;
+        }
+    }
+
+    // Screenshots are checked as well, they are commonly used to get around text filters
+    let mut content = new_message.content.clone();
+    if let Some(text) = image_text(&data.configuration.ocr, new_message).await {
+        content.push('\n');
+        content.push_str(&text);
+    }
+
+    let (rule, score) = match evaluate(&automod.rules, new_message.channel_id.0, &content) {
+        Some(found) => found,
+        None => return,
+    };
+
+    let confident = score >= rule.action_threshold;
+    let hit = AutomodHit {
+        message_id: Some(new_message.id.to_string()),
+        channel_id: Some(new_message.channel_id.to_string()),
+        guild_id: new_message.guild_id.map(|g| g.to_string()),
+        user_id: Some(new_message.author.id.to_string()),
+        rule: Some(rule.name.clone()),
+        content: Some(content.clone()),
+        score: Some(score),
+        status: Some(if confident {
+            AutomodHitStatus::Actioned
+        } else {
+            AutomodHitStatus::Queued
+        }),
+        timestamp: Some(Utc::now().timestamp()),
+        ..Default::default()
+    };
+
+    debug!(
+        "Automod rule {} triggered by {} with a score of {:.2}",
+        rule.name,
+        new_message.author.tag(),
+        score
+    );
+
+    if let Err(err) = data
+        .database
+        .insert::<AutomodHit>("automod_hits", hit.clone(), None)
+        .await
+    {
+        error!("Failed to store automod hit: {:?}", err);
+    }
+
+    if confident {
+        let rule_name = rule.name.clone();
+        let action = AutomodAction {
+            delete: rule.action.delete,
+            mute: rule.action.mute,
+        };
+        let result = apply_action(ctx, &action, &rule_name, &hit).await;
+        if let Err(err) = &result {
+            error!("Failed to apply automod action: {:?}", err);
+        }
+
+        if let Err(err) = ChannelId(data.configuration.log_channel(LogCategory::Moderation))
+            .send_message(&ctx.http, |m| {
+                m.embed(|e| {
+                    let e = e
+                        .title(format!("Automod: {}", rule_name))
+                        .description(&content)
+                        .field("User", format!("<@{}>", new_message.author.id), true)
+                        .field("Score", format!("{:.2}", score), true)
+                        .thumbnail(new_message.author.face())
+                        .color(data.configuration.general.embed_color);
+
+                    if let Err(err) = &result {
+                        e.field("Exception", err.to_string(), false);
+                    }
+
+                    e
+                })
+                .components(|c| moderation_buttons(c, new_message.author.id))
+            })
+            .await
+        {
+            error!("Failed to log automod action: {:?}", err);
+        }
+        return;
+    }
+
+    // Not confident enough to act automatically, let staff decide
+    if let Err(err) = ChannelId(automod.queue_channel)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title(format!("Automod: {}", rule.name))
+                    .description(&content)
+                    .field("User", format!("<@{}>", new_message.author.id), true)
+                    .field("Score", format!("{:.2}", score), true)
+                    .field(
+                        "Reference",
+                        format!("[Jump to message]({})", new_message.link()),
+                        true,
+                    )
+                    .thumbnail(new_message.author.face())
+                    .color(data.configuration.general.embed_color)
+            })
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id(format!("automod:approve:{}", new_message.id))
+                            .label("Approve")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|b| {
+                        b.custom_id(format!("automod:dismiss:{}", new_message.id))
+                            .label("Dismiss")
+                            .style(ButtonStyle::Danger)
+                    })
+                })
+            })
+        })
+        .await
+    {
+        error!("Failed to queue automod hit: {:?}", err);
+    }
+}
+
+/// Handle the Approve and Dismiss buttons of queued automod hits.
+pub async fn handle_queue_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    let mut parts = component.data.custom_id.splitn(3, ':').skip(1);
+    let (decision, message_id) = match (parts.next(), parts.next()) {
+        (Some(decision), Some(message_id)) => (decision, message_id),
+        _ => return,
+    };
+
+    {
+        let data = get_data(ctx).await;
+        let is_allowed = component
+            .member
+            .as_ref()
+            .is_some_and(|m| is_administrator(&data.configuration.administrators, m));
+
+        if !is_allowed {
+            drop(data);
+            respond_ephemeral(ctx, component, "You do not have permission to do this.").await;
+            return;
+        }
+    }
+
+    let moderator = Some(component.user.id.to_string());
+    let decision = match decision {
+        "approve" => {
+            let hit = match set_status(ctx, message_id, AutomodHitStatus::Approved, moderator).await
+            {
+                Some(hit) => hit,
+                None => {
+                    respond_ephemeral(ctx, component, "This automod hit no longer exists.").await;
+                    return;
+                },
+            };
+
+            let action = {
+                let data = get_data(ctx).await;
+                data.configuration
+                    .automod
+                    .rules
+                    .iter()
+                    .find(|r| Some(&r.name) == hit.rule.as_ref())
+                    .map(|r| AutomodAction {
+                        delete: r.action.delete,
+                        mute: r.action.mute,
+                    })
+            };
+
+            match action {
+                Some(action) => {
+                    match apply_action(ctx, &action, hit.rule.as_deref().unwrap_or_default(), &hit)
+                        .await
+                    {
+                        Ok(_) => format!("Approved by <@{}>", component.user.id),
+                        Err(err) => format!(
+                            "Approved by <@{}>, but failed to take action: {}",
+                            component.user.id, err
+                        ),
+                    }
+                },
+                None => format!(
+                    "Approved by <@{}>, but the rule no longer exists",
+                    component.user.id
+                ),
+            }
+        },
+        "dismiss" => {
+            set_status(ctx, message_id, AutomodHitStatus::Dismissed, moderator).await;
+            format!("Dismissed by <@{}>", component.user.id)
+        },
+        _ => return,
+    };
+
+    update_with_decision(ctx, component, &decision).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{configuration, message};
+
+    #[test]
+    fn evaluates_rules_in_their_channels() {
+        let configuration = configuration(|c| {
+            c.automod.rules = serde_json::from_value(serde_json::json!([{
+                "name": "Scam",
+                "channels": [1],
+                "patterns": [
+                    { "match": "free", "weight": 0.5 },
+                    { "match": "nitro", "weight": 0.5 }
+                ],
+                "queue_threshold": 0.5,
+                "action_threshold": 1,
+                "action": { "delete": true, "mute": null }
+            }]))
+            .unwrap();
+        });
+        let rules = &configuration.automod.rules;
+
+        let scam = message(0, 1, 2, "free nitro");
+        let (rule, score) = evaluate(rules, scam.channel_id.0, &scam.content).unwrap();
+        assert_eq!(rule.name, "Scam");
+        assert_eq!(score, 1.0);
+
+        let elsewhere = message(0, 3, 2, "free nitro");
+        assert!(evaluate(rules, elsewhere.channel_id.0, &elsewhere.content).is_none());
+
+        let harmless = message(0, 1, 2, "hello");
+        assert!(evaluate(rules, harmless.channel_id.0, &harmless.content).is_none());
+    }
+}
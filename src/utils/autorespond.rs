@@ -3,7 +3,7 @@ use regex::Regex;
 use tracing::log::error;
 
 use super::*;
-use crate::utils::bot::get_data_lock;
+use crate::utils::bot::get_data;
 
 pub fn contains_match(regex: &[Regex], text: &str) -> bool {
     regex.iter().any(|r| r.is_match(text))
@@ -14,8 +14,8 @@ pub async fn auto_respond(ctx: &serenity::Context, new_message: &serenity::Messa
         return;
     }
 
-    let data_lock = get_data_lock(ctx).await;
-    let responses = &data_lock.read().await.configuration.message_responses;
+    let data = get_data(ctx).await;
+    let responses = &data.configuration.message_responses;
     let message = &new_message.content;
 
     for response in responses {
@@ -0,0 +1,378 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bson::{doc, Document};
+use chrono::Utc;
+use mongodb::options::{UpdateModifications, UpdateOptions};
+use poise::serenity_prelude::{
+    ButtonStyle,
+    ChannelId,
+    GuildId,
+    MessageComponentInteraction,
+    UserId,
+};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::interaction::{respond_ephemeral, update_with_decision};
+use super::member_cache::fetch_member;
+use super::moderation::is_administrator;
+use super::time::parse_duration;
+use super::{dry_run, *};
+use crate::db::model::{Activity, BetaRequest, BetaStatus};
+use crate::model::application::{Beta, LogCategory};
+use crate::{Data, Error};
+
+// How often to check for inactive testers
+const REVOCATION_INTERVAL_SECS: u64 = 60 * 60;
+
+static REVOCATION_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+/// The criteria for beta access a member does not meet.
+pub fn unmet_criteria(beta: &Beta, member: &Member) -> Vec<String> {
+    let now = Utc::now();
+    let mut unmet = Vec::new();
+
+    if let Some(age) = parse_duration(&beta.account_age).filter(|d| !d.is_zero()) {
+        if now.timestamp() - member.user.created_at().unix_timestamp() < age.num_seconds() {
+            unmet.push(format!("Your account must be {} old.", beta.account_age));
+        }
+    }
+
+    if let Some(age) = parse_duration(&beta.membership_age).filter(|d| !d.is_zero()) {
+        let is_member_long_enough = member
+            .joined_at
+            .is_some_and(|j| now.timestamp() - j.unix_timestamp() >= age.num_seconds());
+        if !is_member_long_enough {
+            unmet.push(format!("You must be a member for {}.", beta.membership_age));
+        }
+    }
+
+    if !beta.level_roles.is_empty() && !member.roles.iter().any(|r| beta.level_roles.contains(&r.0))
+    {
+        unmet.push(format!(
+            "You must have one of the roles {}.",
+            beta.level_roles
+                .iter()
+                .map(|r| format!("<@&{}>", r))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    unmet
+}
+
+/// Queue a request for beta access for staff approval, unless one is already pending.
+/// Returns whether a new request was queued.
+pub async fn queue_request(
+    ctx: &serenity::Context,
+    data: &Data,
+    member: &Member,
+) -> Result<bool, Error> {
+    let requested: Document = BetaRequest {
+        requested: Some(Utc::now().timestamp()),
+        ..Default::default()
+    }
+    .into();
+
+    let result = data
+        .database
+        .update::<BetaRequest>(
+            "beta_requests",
+            doc! { "guild_id": member.guild_id.to_string(), "user_id": member.user.id.to_string(), "status": "pending" },
+            UpdateModifications::Document(doc! { "$setOnInsert": requested }),
+            Some(UpdateOptions::builder().upsert(true).build()),
+        )
+        .await?;
+    if result.upserted_id.is_none() {
+        return Ok(false);
+    }
+
+    let beta = &data.configuration.beta;
+    let approval_channel = if beta.approval_channel != 0 {
+        beta.approval_channel
+    } else {
+        data.configuration.log_channel(LogCategory::Moderation)
+    };
+
+    if let Err(err) = ChannelId(approval_channel)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title(format!("Beta access request from {}", member.user.tag()))
+                    .field("User", format!("<@{}>", member.user.id), true)
+                    .field(
+                        "Account created",
+                        format!("<t:{}:R>", member.user.created_at().unix_timestamp()),
+                        true,
+                    )
+                    .field(
+                        "Joined",
+                        member.joined_at.map_or("Unknown".to_string(), |j| {
+                            format!("<t:{}:R>", j.unix_timestamp())
+                        }),
+                        true,
+                    )
+                    .thumbnail(member.user.face())
+                    .color(data.configuration.general.embed_color)
+            })
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id(format!("beta:approve:{}", member.user.id))
+                            .label("Approve")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|b| {
+                        b.custom_id(format!("beta:deny:{}", member.user.id))
+                            .label("Deny")
+                            .style(ButtonStyle::Danger)
+                    })
+                })
+            })
+        })
+        .await
+    {
+        // Allow requesting again
+        data.database
+            .delete(
+                "beta_requests",
+                doc! { "guild_id": member.guild_id.to_string(), "user_id": member.user.id.to_string(), "status": "pending" },
+                None,
+            )
+            .await?;
+        return Err(err.into());
+    }
+
+    debug!("Queued a beta access request of {}", member.user.tag());
+    Ok(true)
+}
+
+/// Grant the tester role and record the access, so inactive testers can be revoked later.
+pub async fn grant_access(
+    ctx: &serenity::Context,
+    data: &Data,
+    member: &mut Member,
+    moderator_id: Option<UserId>,
+) -> Result<(), Error> {
+    dry_run::execute(
+        format!("add tester role to {}", member.user.tag()),
+        member.add_role(&ctx.http, RoleId(data.configuration.beta.role)),
+    )
+    .await?;
+
+    let granted: Document = BetaRequest {
+        status: Some(BetaStatus::Approved),
+        moderator_id: moderator_id.map(|id| id.to_string()),
+        decided: Some(Utc::now().timestamp()),
+        ..Default::default()
+    }
+    .into();
+
+    data.database
+        .update::<BetaRequest>(
+            "beta_requests",
+            doc! { "guild_id": member.guild_id.to_string(), "user_id": member.user.id.to_string(), "status": { "$in": ["pending", "approved"] } },
+            UpdateModifications::Document(doc! { "$set": granted }),
+            Some(UpdateOptions::builder().upsert(true).build()),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Handle the Approve and Deny buttons of beta access requests.
+pub async fn handle_beta_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    let mut parts = component.data.custom_id.splitn(3, ':').skip(1);
+    let (decision, user_id) = match (
+        parts.next(),
+        parts.next().and_then(|id| id.parse::<u64>().ok()),
+    ) {
+        (Some(decision), Some(user_id)) => (decision, UserId(user_id)),
+        _ => return,
+    };
+    let guild_id = match component.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    let data = get_data(ctx).await;
+    let is_allowed = component
+        .member
+        .as_ref()
+        .is_some_and(|m| is_administrator(&data.configuration.administrators, m));
+    if !is_allowed {
+        respond_ephemeral(ctx, component, "You do not have permission to do this.").await;
+        return;
+    }
+
+    let decision = match decision {
+        "approve" => {
+            let result = match fetch_member(ctx, guild_id, user_id).await {
+                Ok(mut member) => {
+                    grant_access(ctx, &data, &mut member, Some(component.user.id)).await
+                },
+                Err(err) => Err(err.into()),
+            };
+
+            match result {
+                Ok(_) => format!("Approved by <@{}>", component.user.id),
+                Err(err) => {
+                    error!("Failed to grant beta access to {}: {:?}", user_id, err);
+                    respond_ephemeral(ctx, component, "Failed to grant beta access.").await;
+                    return;
+                },
+            }
+        },
+        "deny" => {
+            let denied: Document = BetaRequest {
+                status: Some(BetaStatus::Denied),
+                moderator_id: Some(component.user.id.to_string()),
+                decided: Some(Utc::now().timestamp()),
+                ..Default::default()
+            }
+            .into();
+
+            match data
+                .database
+                .update::<BetaRequest>(
+                    "beta_requests",
+                    doc! { "guild_id": guild_id.to_string(), "user_id": user_id.to_string(), "status": "pending" },
+                    UpdateModifications::Document(doc! { "$set": denied }),
+                    None,
+                )
+                .await
+            {
+                Ok(result) if result.modified_count == 0 => {
+                    respond_ephemeral(ctx, component, "This request was already decided.").await;
+                    return;
+                },
+                Ok(_) => format!("Denied by <@{}>", component.user.id),
+                Err(err) => {
+                    error!("Failed to deny beta access request: {:?}", err);
+                    respond_ephemeral(ctx, component, "Failed to deny this request.").await;
+                    return;
+                },
+            }
+        },
+        _ => return,
+    };
+
+    update_with_decision(ctx, component, &decision).await;
+}
+
+/// Revoke the tester role of testers who have not sent a message within the inactivity period.
+async fn revoke_inactive(ctx: &serenity::Context, guild_id: GuildId) {
+    let data = get_data(ctx).await;
+    let beta = &data.configuration.beta;
+    let inactivity = match parse_duration(&beta.inactivity).filter(|d| !d.is_zero()) {
+        Some(inactivity) => inactivity,
+        None => return,
+    };
+    let cutoff = (Utc::now() - inactivity).timestamp();
+
+    // Testers granted access recently had no chance to be active yet
+    let testers = match data
+        .database
+        .collect::<BetaRequest>(
+            "beta_requests",
+            doc! { "guild_id": guild_id.to_string(), "status": "approved", "decided": { "$lt": cutoff } },
+        )
+        .await
+    {
+        Ok(testers) => testers,
+        Err(err) => {
+            error!("Failed to query database for beta testers: {:?}", err);
+            return;
+        },
+    };
+
+    for tester in testers {
+        let Some(user_id) = tester.user_id.and_then(|id| id.parse::<u64>().ok()) else {
+            continue;
+        };
+
+        let is_active = match data
+            .database
+            .collect::<Activity>(
+                "activity",
+                doc! { "guild_id": guild_id.to_string(), "user_id": user_id.to_string(), "last_message": { "$gte": cutoff } },
+            )
+            .await
+        {
+            Ok(activity) => !activity.is_empty(),
+            Err(err) => {
+                error!("Failed to query activity of {}: {:?}", user_id, err);
+                continue;
+            },
+        };
+        if is_active {
+            continue;
+        }
+
+        // Members who left lose their roles anyway
+        if let Ok(mut member) = fetch_member(ctx, guild_id, UserId(user_id)).await {
+            if let Err(err) = dry_run::execute(
+                format!("remove tester role from {}", member.user.tag()),
+                member.remove_role(&ctx.http, RoleId(beta.role)),
+            )
+            .await
+            {
+                error!("Failed to revoke beta access of {}: {:?}", user_id, err);
+                continue;
+            }
+        }
+
+        let revoked: Document = BetaRequest {
+            status: Some(BetaStatus::Revoked),
+            decided: Some(Utc::now().timestamp()),
+            ..Default::default()
+        }
+        .into();
+        if let Err(err) = data
+            .database
+            .update::<BetaRequest>(
+                "beta_requests",
+                doc! { "guild_id": guild_id.to_string(), "user_id": user_id.to_string(), "status": "approved" },
+                UpdateModifications::Document(doc! { "$set": revoked }),
+                None,
+            )
+            .await
+        {
+            error!("Failed to record revocation of {}: {:?}", user_id, err);
+        }
+
+        debug!("Revoked beta access of inactive tester {}", user_id);
+    }
+}
+
+/// Periodically revoke beta access of inactive testers.
+pub fn schedule_revocation(ctx: &serenity::Context) {
+    // Ready is dispatched again when reconnecting
+    if REVOCATION_SCHEDULED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(REVOCATION_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+
+            let enabled = {
+                let data = get_data(&ctx).await;
+                data.configuration.beta.role != 0
+            };
+            if !enabled {
+                continue;
+            }
+
+            for guild_id in ctx.cache.guilds() {
+                revoke_inactive(&ctx, guild_id).await;
+            }
+        }
+    });
+}
@@ -1,15 +1,339 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use poise::serenity_prelude::{self as serenity, RwLock};
+use bson::{doc, Document};
+use chrono::Utc;
+use poise::serenity_prelude::{self as serenity, ChannelId, GuildId, UserId};
+use serde::de::DeserializeOwned;
+use tracing::{debug, error, trace};
 
+use super::dry_run;
+use super::member_cache::fetch_member;
+use super::moderation::{
+    queue_invite_resume,
+    queue_slowmode_reset,
+    queue_unban,
+    queue_unmute_member,
+};
+use crate::db::document_store::{self, DocumentStore};
+use crate::db::model::{InvitePause, Muted, SlowmodeReset, TempBan};
 use crate::model::application::Configuration;
-use crate::Data;
+use crate::{Data, Error};
+
+// How often to look for mutes and temporary bans which expired without being lifted
+const EXPIRY_SWEEP_INTERVAL_SECS: u64 = 5 * 60;
+
+static EXPIRY_SWEEPER_SCHEDULED: AtomicBool = AtomicBool::new(false);
+static EXPIRIES_REQUEUED: AtomicBool = AtomicBool::new(false);
 
 pub fn load_configuration() -> Configuration {
-    Configuration::load().expect("Failed to load configuration")
+    let configuration = Configuration::load().expect("Failed to load configuration");
+    dry_run::set(configuration.dry_run);
+    configuration
+}
+
+// Take a snapshot of the shared data. Replacing the configuration swaps in a new snapshot, so
+// readers never wait for each other
+pub async fn get_data(ctx: &serenity::Context) -> Arc<Data> {
+    ctx.data.read().await.get::<Data>().unwrap().load_full()
+}
+
+/// Records which are lifted once they expire.
+trait Expiring: DeserializeOwned {
+    fn expires(&self) -> i64;
+}
+
+impl Expiring for Muted {
+    fn expires(&self) -> i64 {
+        self.expires.unwrap_or_default() as i64
+    }
+}
+
+impl Expiring for TempBan {
+    fn expires(&self) -> i64 {
+        self.expires.unwrap_or_default()
+    }
+}
+
+impl Expiring for InvitePause {
+    fn expires(&self) -> i64 {
+        self.expires.unwrap_or_default()
+    }
+}
+
+impl Expiring for SlowmodeReset {
+    fn expires(&self) -> i64 {
+        self.expires.unwrap_or_default()
+    }
+}
+
+/// The filter of records which expired by now.
+fn expired_filter(now: i64) -> Document {
+    doc! { "expires": { "$lte": now } }
 }
 
-// Share the lock reference between the threads in serenity framework
-pub async fn get_data_lock(ctx: &serenity::Context) -> Arc<RwLock<Data>> {
-    ctx.data.read().await.get::<Data>().unwrap().clone()
+/// The stored records matching a filter, each with the seconds left until it expires.
+async fn expiring<T: Expiring>(
+    store: &impl DocumentStore,
+    collection: &str,
+    filter: Document,
+    now: i64,
+) -> Result<Vec<(T, u64)>, Error> {
+    Ok(document_store::collect::<T>(store, collection, filter)
+        .await?
+        .into_iter()
+        .map(|record| {
+            let remaining = (record.expires() - now).max(0) as u64;
+            (record, remaining)
+        })
+        .collect())
+}
+
+/// Queue the unmutes of the stored mutes matching a filter, unless one is already pending.
+async fn queue_unmutes(ctx: &serenity::Context, filter: Document) {
+    let data = get_data(ctx).await;
+
+    let now = Utc::now().timestamp();
+    let mutes = match expiring::<Muted>(&*data.database, "muted", filter, now).await {
+        Ok(mutes) => mutes,
+        Err(err) => {
+            error!("Failed to query database for muted members: {:?}", err);
+            return;
+        },
+    };
+
+    for (muted, remaining) in mutes {
+        let (guild_id, user_id) = match (
+            muted.guild_id.and_then(|id| id.parse::<u64>().ok()),
+            muted.user_id.and_then(|id| id.parse::<u64>().ok()),
+        ) {
+            (Some(guild_id), Some(user_id)) => (GuildId(guild_id), UserId(user_id)),
+            _ => continue,
+        };
+
+        let is_pending = data
+            .pending_unmutes
+            .lock()
+            .await
+            .get(&user_id.0)
+            .is_some_and(|pending| !pending.is_finished());
+        if is_pending {
+            continue;
+        }
+
+        match fetch_member(ctx, guild_id, user_id).await {
+            Ok(member) => {
+                data.pending_unmutes.lock().await.insert(
+                    user_id.0,
+                    queue_unmute_member(
+                        &ctx.http,
+                        &data.database,
+                        &member,
+                        data.configuration.general.mute.role,
+                        remaining,
+                    ),
+                );
+            },
+            // The member left, so there are no roles to restore once the mute is over
+            Err(_) if remaining == 0 => {
+                debug!(
+                    "Dropping the expired mute of {}, who left {}",
+                    user_id, guild_id
+                );
+                if let Err(err) = data
+                    .database
+                    .delete(
+                        "muted",
+                        doc! { "guild_id": guild_id.to_string(), "user_id": user_id.to_string() },
+                        None,
+                    )
+                    .await
+                {
+                    error!("Failed to drop the expired mute of {}: {:?}", user_id, err);
+                }
+            },
+            Err(_) => trace!("Failed to find member {} in guild {}", user_id, guild_id),
+        }
+    }
+}
+
+/// Queue lifting the stored temporary bans matching a filter.
+async fn queue_unbans(ctx: &serenity::Context, filter: Document) {
+    let data = get_data(ctx).await;
+
+    let now = Utc::now().timestamp();
+    let temp_bans = match expiring::<TempBan>(&*data.database, "temp_bans", filter, now).await {
+        Ok(temp_bans) => temp_bans,
+        Err(err) => {
+            error!("Failed to query database for temporary bans: {:?}", err);
+            return;
+        },
+    };
+
+    for (temp_ban, remaining) in temp_bans {
+        if let (Some(guild_id), Some(user_id)) = (
+            temp_ban.guild_id.and_then(|id| id.parse::<u64>().ok()),
+            temp_ban.user_id.and_then(|id| id.parse::<u64>().ok()),
+        ) {
+            // Lifting the ban twice is prevented by removing the stored temporary ban first
+            queue_unban(
+                &ctx.http,
+                &data.database,
+                GuildId(guild_id),
+                UserId(user_id),
+                remaining,
+            );
+        }
+    }
+}
+
+/// Queue resuming the invites of the stored invite pauses matching a filter.
+async fn queue_invite_resumes(ctx: &serenity::Context, filter: Document) {
+    let data = get_data(ctx).await;
+
+    let now = Utc::now().timestamp();
+    let invite_pauses =
+        match expiring::<InvitePause>(&*data.database, "invite_pauses", filter, now).await {
+            Ok(invite_pauses) => invite_pauses,
+            Err(err) => {
+                error!("Failed to query database for invite pauses: {:?}", err);
+                return;
+            },
+        };
+
+    for (invite_pause, remaining) in invite_pauses {
+        if let Some(guild_id) = invite_pause.guild_id.and_then(|id| id.parse::<u64>().ok()) {
+            // Resuming twice is prevented by removing the stored invite pause first
+            queue_invite_resume(&ctx.http, &data.database, GuildId(guild_id), remaining);
+        }
+    }
+}
+
+/// Queue restoring the previous slowmode of the stored slowmode resets matching a filter.
+async fn queue_slowmode_resets(ctx: &serenity::Context, filter: Document) {
+    let data = get_data(ctx).await;
+
+    let now = Utc::now().timestamp();
+    let slowmode_resets =
+        match expiring::<SlowmodeReset>(&*data.database, "slowmode_resets", filter, now).await {
+            Ok(slowmode_resets) => slowmode_resets,
+            Err(err) => {
+                error!("Failed to query database for slowmode resets: {:?}", err);
+                return;
+            },
+        };
+
+    for (slowmode_reset, remaining) in slowmode_resets {
+        if let Some(channel_id) = slowmode_reset
+            .channel_id
+            .and_then(|id| id.parse::<u64>().ok())
+        {
+            // Resetting twice is prevented by removing the stored slowmode reset first
+            queue_slowmode_reset(&ctx.http, &data.database, ChannelId(channel_id), remaining);
+        }
+    }
+}
+
+/// Queue the unmutes, unbans, invite resumes and slowmode resets of all stored mutes, temporary
+/// bans, invite pauses and temporary slowmodes, which are lost when the bot restarts.
+pub async fn requeue_expiries(ctx: &serenity::Context) {
+    // Ready is dispatched again when reconnecting, but only unmutes are tracked as pending
+    if EXPIRIES_REQUEUED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    queue_unmutes(ctx, doc! {}).await;
+    queue_unbans(ctx, doc! {}).await;
+    queue_invite_resumes(ctx, doc! {}).await;
+    queue_slowmode_resets(ctx, doc! {}).await;
+}
+
+/// Periodically lift mutes, temporary bans, invite pauses and temporary slowmodes which expired without being
+/// lifted, such as ones which expired while the bot was offline or mutes of members who rejoined.
+pub fn schedule_expiry_sweeper(ctx: &serenity::Context) {
+    // Ready is dispatched again when reconnecting
+    if EXPIRY_SWEEPER_SCHEDULED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(EXPIRY_SWEEP_INTERVAL_SECS));
+        // Startup already queued everything
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+
+            let expired = expired_filter(Utc::now().timestamp());
+            queue_unmutes(&ctx, expired.clone()).await;
+            queue_unbans(&ctx, expired.clone()).await;
+            queue_invite_resumes(&ctx, expired.clone()).await;
+            queue_slowmode_resets(&ctx, expired).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::document_store::MemoryStore;
+
+    fn temp_ban(user_id: &str, expires: i64) -> TempBan {
+        TempBan {
+            guild_id: Some("1".to_string()),
+            user_id: Some(user_id.to_string()),
+            expires: Some(expires),
+            ..Default::default()
+        }
+    }
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn requeues_all_records_with_their_remaining_time() {
+        block_on(async {
+            let store = MemoryStore::default();
+            store.insert("temp_bans", &temp_ban("2", 90));
+            store.insert("temp_bans", &temp_ban("3", 150));
+
+            let remaining = expiring::<TempBan>(&store, "temp_bans", doc! {}, 100)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|(temp_ban, remaining)| (temp_ban.user_id.unwrap(), remaining))
+                .collect::<Vec<_>>();
+
+            // Records which expired while the bot was offline are lifted right away
+            assert_eq!(remaining, [("2".to_string(), 0), ("3".to_string(), 50)]);
+        });
+    }
+
+    #[test]
+    fn sweeps_only_expired_records() {
+        block_on(async {
+            let store = MemoryStore::default();
+            for (user_id, expires) in [("2", 99), ("3", 100), ("4", 101)] {
+                store.insert("muted", &Muted {
+                    user_id: Some(user_id.to_string()),
+                    expires: Some(expires),
+                    ..Default::default()
+                });
+            }
+
+            let swept = expiring::<Muted>(&store, "muted", expired_filter(100), 100)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|(muted, _)| muted.user_id.unwrap())
+                .collect::<Vec<_>>();
+
+            assert_eq!(swept, ["2", "3"]);
+        });
+    }
 }
@@ -0,0 +1,176 @@
+use bson::doc;
+use chrono::Utc;
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument, UpdateModifications};
+use poise::serenity_prelude::{ChannelId, GuildId, UserId};
+use tracing::error;
+
+use crate::db::database::Database;
+use crate::db::model::{Case, Counter};
+use crate::model::application::Bans;
+use crate::Error;
+
+// Discord rejects audit log reasons longer than this
+const MAX_REASON_LENGTH: usize = 512;
+
+/// Increment a named counter, returning its new value. Counters start at 1.
+pub async fn increment_counter(database: &Database, name: &str) -> Result<i64, Error> {
+    let counter = database
+        .find_and_update::<Counter>(
+            "counters",
+            doc! { "name": name },
+            UpdateModifications::Document(doc! { "$inc": { "value": 1_i64 } }),
+            Some(
+                FindOneAndUpdateOptions::builder()
+                    .upsert(true)
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            ),
+        )
+        .await?;
+
+    counter
+        .and_then(|c| c.value)
+        .ok_or_else(|| Error::from(format!("Failed to increment the counter {}", name)))
+}
+
+/// Reserve the next case number of a guild.
+pub async fn next_case_id(database: &Database, guild_id: GuildId) -> Result<i64, Error> {
+    increment_counter(database, &format!("cases:{}", guild_id)).await
+}
+
+/// What a moderation action was taken against.
+#[derive(Clone, Copy)]
+pub enum CaseTarget {
+    User(UserId),
+    Channel(ChannelId),
+}
+
+/// Record a moderation action under a reserved case number.
+pub async fn record_case(
+    database: &Database,
+    guild_id: GuildId,
+    case_id: i64,
+    action: &str,
+    target: CaseTarget,
+    moderator_id: UserId,
+    reason: Option<&str>,
+) -> Result<(), Error> {
+    let (user_id, channel_id) = match target {
+        CaseTarget::User(user_id) => (Some(user_id.to_string()), None),
+        CaseTarget::Channel(channel_id) => (None, Some(channel_id.to_string())),
+    };
+
+    database
+        .insert::<Case>(
+            "cases",
+            Case {
+                guild_id: Some(guild_id.to_string()),
+                case_id: Some(case_id),
+                action: Some(action.to_string()),
+                user_id,
+                channel_id,
+                moderator_id: Some(moderator_id.to_string()),
+                reason: reason.map(str::to_string),
+                timestamp: Some(Utc::now().timestamp()),
+                ..Default::default()
+            },
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Reserve the next case number and record a moderation action under it.
+///
+/// The action was already taken, so failures are only logged instead of failing the action.
+pub async fn open_case(
+    database: &Database,
+    guild_id: GuildId,
+    action: &str,
+    target: CaseTarget,
+    moderator_id: UserId,
+    reason: Option<&str>,
+) -> Option<i64> {
+    let case_id = match next_case_id(database, guild_id).await {
+        Ok(case_id) => case_id,
+        Err(err) => {
+            error!("Failed to reserve a case number: {:?}", err);
+            return None;
+        },
+    };
+
+    match record_case(
+        database,
+        guild_id,
+        case_id,
+        action,
+        target,
+        moderator_id,
+        reason,
+    )
+    .await
+    {
+        Ok(_) => Some(case_id),
+        Err(err) => {
+            error!("Failed to record case {}: {:?}", case_id, err);
+            None
+        },
+    }
+}
+
+/// Record the second moderator who approved the action of a case.
+pub async fn record_approver(
+    database: &Database,
+    guild_id: GuildId,
+    case_id: i64,
+    approver_id: UserId,
+) {
+    if let Err(err) = database
+        .update::<Case>(
+            "cases",
+            doc! { "guild_id": guild_id.to_string(), "case_id": case_id },
+            UpdateModifications::Document(doc! {
+                "$set": { "approver_id": approver_id.to_string() }
+            }),
+            None,
+        )
+        .await
+    {
+        error!(
+            "Failed to record the approver of case {}: {:?}",
+            case_id, err
+        );
+    }
+}
+
+/// Find a case of a guild by its number.
+pub async fn find_case(
+    database: &Database,
+    guild_id: GuildId,
+    case_id: i64,
+) -> Result<Option<Case>, Error> {
+    Ok(database
+        .collect::<Case>(
+            "cases",
+            doc! { "guild_id": guild_id.to_string(), "case_id": case_id },
+        )
+        .await?
+        .pop())
+}
+
+/// Compose the reason shown to banned users from the configured template.
+/// Supports the placeholders `{case}`, `{reason}` and `{appeal}`.
+pub fn ban_reason(bans: &Bans, case_id: i64, reason: &str) -> String {
+    if bans.reason_template.is_empty() {
+        return reason.to_string();
+    }
+
+    bans.reason_template
+        .replace("{case}", &case_id.to_string())
+        .replace("{reason}", reason)
+        .replace("{appeal}", &bans.appeal_url)
+        .chars()
+        .take(MAX_REASON_LENGTH)
+        .collect()
+}
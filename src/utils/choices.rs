@@ -0,0 +1,118 @@
+use poise::serenity_prelude::{CreateApplicationCommands, Http};
+use serde_json::{json, Value};
+
+use super::command_sync::sync_global_commands;
+use crate::model::application::Configuration;
+use crate::Error;
+
+// Discord rejects options with more choices than this
+const MAX_CHOICES: usize = 25;
+// Discord rejects choice names and values longer than this
+const MAX_CHOICE_LENGTH: usize = 100;
+
+fn truncate(value: &str) -> String {
+    value.chars().take(MAX_CHOICE_LENGTH).collect()
+}
+
+fn choice(
+    name: &str,
+    value: Value,
+    localizations: impl IntoIterator<Item = (String, String)>,
+) -> Value {
+    json!({
+        "name": truncate(name),
+        "value": value,
+        "name_localizations": localizations
+            .into_iter()
+            .map(|(locale, name)| (locale, Value::from(truncate(&name))))
+            .collect::<serde_json::Map<_, _>>(),
+    })
+}
+
+/// Get the configured choices of a parameter of a command, if any.
+pub fn choices_for(configuration: &Configuration, command: &str, parameter: &str) -> Vec<Value> {
+    let choices = &configuration.choices;
+
+    match (command, parameter) {
+        ("mute", "duration") => choices
+            .durations
+            .iter()
+            .map(|c| choice(&c.name, Value::from(c.seconds), c.localizations.clone()))
+            .collect(),
+        ("mute" | "ban", "reason") => choices
+            .reasons
+            .iter()
+            .map(|c| {
+                choice(
+                    &c.name,
+                    Value::from(truncate(&c.value)),
+                    c.localizations.clone(),
+                )
+            })
+            .collect(),
+        ("faq", "topic") => configuration
+            .support
+            .faq
+            .iter()
+            .map(|f| {
+                choice(
+                    &f.question,
+                    Value::from(truncate(&f.question)),
+                    f.localizations.clone(),
+                )
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+    .into_iter()
+    .take(MAX_CHOICES)
+    .collect()
+}
+
+/// Create the slash commands of the bot with the choices from the configuration.
+/// Guild only commands are hidden in direct messages.
+pub fn create_commands<U, E>(
+    commands: &[poise::Command<U, E>],
+    configuration: &Configuration,
+) -> CreateApplicationCommands {
+    let mut builder = poise::builtins::create_application_commands(commands);
+
+    for command in builder.0.iter_mut() {
+        let name = match command["name"].as_str() {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+
+        let is_guild_only = commands.iter().any(|c| {
+            c.guild_only && (c.name == name || c.context_menu_name == Some(name.as_str()))
+        });
+        if is_guild_only {
+            command["dm_permission"] = Value::from(false);
+        }
+
+        if let Some(Value::Array(options)) = command.get_mut("options") {
+            for option in options {
+                let parameter = option["name"].as_str().unwrap_or_default();
+                let choices = choices_for(configuration, &name, parameter);
+                if !choices.is_empty() {
+                    option["choices"] = Value::from(choices);
+                }
+            }
+        }
+    }
+
+    builder
+}
+
+/// Register the slash commands of the bot globally.
+/// Only commands which differ from the registered ones are sent to Discord.
+pub async fn register_commands<U, E>(
+    http: impl AsRef<Http>,
+    commands: &[poise::Command<U, E>],
+    configuration: &Configuration,
+) -> Result<(), Error> {
+    let builder = create_commands(commands, configuration);
+    sync_global_commands(http, builder.0).await?;
+
+    Ok(())
+}
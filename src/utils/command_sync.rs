@@ -0,0 +1,160 @@
+use poise::serenity_prelude::{Command, CommandId, GuildId, Http};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::Error;
+
+// Fields which are set by Discord and are not part of the definition of a command
+const IGNORED_FIELDS: [&str; 7] = [
+    "id",
+    "application_id",
+    "guild_id",
+    "version",
+    "default_permission",
+    "name_localized",
+    "description_localized",
+];
+
+/// The changes required to turn the registered commands into the desired commands.
+#[derive(Default)]
+pub struct CommandDiff {
+    pub create: Vec<Value>,
+    pub update: Vec<(CommandId, Value)>,
+    pub delete: Vec<CommandId>,
+    pub unchanged: usize,
+}
+
+// Discord omits fields which are empty or false, and the builders do not set fields
+// which are left at their default
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(false) => true,
+        Value::String(string) => string.is_empty(),
+        Value::Array(array) => array.is_empty(),
+        Value::Object(object) => object.is_empty(),
+        _ => false,
+    }
+}
+
+fn equivalent(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => a
+            .keys()
+            .chain(b.keys())
+            .filter(|key| !IGNORED_FIELDS.contains(&key.as_str()))
+            .all(|key| match (a.get(key), b.get(key)) {
+                (Some(a), Some(b)) => equivalent(a, b),
+                (Some(value), None) | (None, Some(value)) => is_empty(value),
+                (None, None) => true,
+            }),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equivalent(a, b))
+        },
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        // Permissions are sent as strings but may be received as numbers
+        (Value::String(s), Value::Number(n)) | (Value::Number(n), Value::String(s)) => {
+            *s == n.to_string()
+        },
+        _ => a == b || (is_empty(a) && is_empty(b)),
+    }
+}
+
+/// Compare the registered commands with the desired commands by their name and type.
+pub fn diff_commands(registered: &[Command], desired: Vec<Value>) -> Result<CommandDiff, Error> {
+    let mut diff = CommandDiff::default();
+    let mut matched = Vec::new();
+
+    for mut command in desired {
+        // Commands without a type are chat input commands
+        let kind = command["type"].as_u64().unwrap_or(1);
+        command["type"] = Value::from(kind);
+        let name = command["name"].as_str().unwrap_or_default();
+
+        let existing = registered
+            .iter()
+            .find(|c| c.name == name && c.kind as u64 == kind);
+
+        match existing {
+            Some(existing) => {
+                matched.push(existing.id);
+                if equivalent(&serde_json::to_value(existing)?, &command) {
+                    diff.unchanged += 1;
+                } else {
+                    diff.update.push((existing.id, command));
+                }
+            },
+            None => diff.create.push(command),
+        }
+    }
+
+    diff.delete = registered
+        .iter()
+        .map(|c| c.id)
+        .filter(|id| !matched.contains(id))
+        .collect();
+
+    Ok(diff)
+}
+
+/// Register the desired global commands, only sending the changes to Discord.
+pub async fn sync_global_commands(
+    http: impl AsRef<Http>,
+    desired: Vec<Value>,
+) -> Result<CommandDiff, Error> {
+    let http = http.as_ref();
+    let registered = Command::get_global_application_commands(http).await?;
+    let diff = diff_commands(&registered, desired)?;
+
+    for command in &diff.create {
+        http.create_global_application_command(command).await?;
+    }
+    for (id, command) in &diff.update {
+        http.edit_global_application_command(id.0, command).await?;
+    }
+    for id in &diff.delete {
+        http.delete_global_application_command(id.0).await?;
+    }
+
+    debug!(
+        "Synced global slash commands: {} created, {} updated, {} deleted, {} unchanged",
+        diff.create.len(),
+        diff.update.len(),
+        diff.delete.len(),
+        diff.unchanged
+    );
+    Ok(diff)
+}
+
+/// Register the desired commands of a guild, only sending the changes to Discord.
+pub async fn sync_guild_commands(
+    http: impl AsRef<Http>,
+    guild_id: GuildId,
+    desired: Vec<Value>,
+) -> Result<CommandDiff, Error> {
+    let http = http.as_ref();
+    let registered = guild_id.get_application_commands(http).await?;
+    let diff = diff_commands(&registered, desired)?;
+
+    for command in &diff.create {
+        http.create_guild_application_command(guild_id.0, command)
+            .await?;
+    }
+    for (id, command) in &diff.update {
+        http.edit_guild_application_command(guild_id.0, id.0, command)
+            .await?;
+    }
+    for id in &diff.delete {
+        http.delete_guild_application_command(guild_id.0, id.0)
+            .await?;
+    }
+
+    debug!(
+        "Synced slash commands of guild {}: {} created, {} updated, {} deleted, {} unchanged",
+        guild_id,
+        diff.create.len(),
+        diff.update.len(),
+        diff.delete.len(),
+        diff.unchanged
+    );
+    Ok(diff)
+}
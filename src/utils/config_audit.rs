@@ -0,0 +1,146 @@
+use chrono::Utc;
+use poise::serenity_prelude::ChannelId;
+use serde_json::Value;
+use tracing::error;
+
+use crate::db::model::ConfigChange;
+use crate::model::application::{Configuration, LogCategory};
+use crate::Context;
+
+// Discord rejects embed fields which are longer than this, including the code block
+const MAX_VALUE_LENGTH: usize = 1000;
+
+/// The top level sections which differ between two configurations with their values before and
+/// after as JSON.
+fn changed_sections(
+    before: &Configuration,
+    after: &Configuration,
+) -> Vec<(String, Option<String>, Option<String>)> {
+    let (Ok(Value::Object(before)), Ok(Value::Object(after))) =
+        (serde_json::to_value(before), serde_json::to_value(after))
+    else {
+        return Vec::new();
+    };
+
+    let mut sections = before.keys().chain(after.keys()).collect::<Vec<_>>();
+    sections.sort();
+    sections.dedup();
+
+    sections
+        .into_iter()
+        .filter(|section| before.get(*section) != after.get(*section))
+        .map(|section| {
+            (
+                section.clone(),
+                before.get(section).map(Value::to_string),
+                after.get(section).map(Value::to_string),
+            )
+        })
+        .collect()
+}
+
+/// Shorten a value to fit into an embed field.
+fn field_value(value: &Option<String>) -> String {
+    match value {
+        Some(value) if value.chars().count() > MAX_VALUE_LENGTH => format!(
+            "```json\n{}…\n```",
+            value.chars().take(MAX_VALUE_LENGTH).collect::<String>()
+        ),
+        Some(value) => format!("```json\n{}\n```", value),
+        None => "Not set".to_string(),
+    }
+}
+
+/// Record a change of the configuration of the bot in the database and the config audit channel,
+/// which is the moderation log if not configured.
+pub async fn record_config_change(
+    ctx: &Context<'_>,
+    subject: &str,
+    before: Option<String>,
+    after: Option<String>,
+) {
+    let data = ctx.data().load_full();
+    let configuration = &data.configuration;
+
+    let embed_color = configuration.general.embed_color;
+    let channel_id = match configuration.config_audit.channel {
+        0 => ChannelId(configuration.log_channel(LogCategory::Moderation)),
+        channel_id => ChannelId(channel_id),
+    };
+
+    if let Err(err) = channel_id
+        .send_message(&ctx.discord().http, |m| {
+            m.embed(|e| {
+                e.title(format!("Changed {}", subject))
+                    .field("Before", field_value(&before), false)
+                    .field("After", field_value(&after), false)
+                    .field("Changed by", format!("<@{}>", ctx.author().id), true)
+                    .field(
+                        "Command",
+                        format!("/{}", ctx.command().qualified_name),
+                        true,
+                    )
+                    .color(embed_color)
+            })
+        })
+        .await
+    {
+        error!("Failed to log configuration change: {:?}", err);
+    }
+
+    let change = ConfigChange {
+        guild_id: ctx.guild_id().map(|g| g.to_string()),
+        user_id: Some(ctx.author().id.to_string()),
+        command: Some(ctx.command().qualified_name.clone()),
+        subject: Some(subject.to_string()),
+        before,
+        after,
+        timestamp: Some(Utc::now().timestamp()),
+    };
+    if let Err(err) = data
+        .database
+        .insert::<ConfigChange>("config_audit", change, None)
+        .await
+    {
+        error!("Failed to record configuration change: {:?}", err);
+    }
+}
+
+/// Record each section which differs between two configurations.
+pub async fn record_configuration_changes(
+    ctx: &Context<'_>,
+    before: &Configuration,
+    after: &Configuration,
+) {
+    for (section, before, after) in changed_sections(before, after) {
+        record_config_change(
+            ctx,
+            &format!("the {} configuration", section),
+            before,
+            after,
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::configuration;
+
+    #[test]
+    fn finds_changed_sections() {
+        let before = configuration(|_| {});
+        let after = configuration(|c| {
+            c.dry_run = !c.dry_run;
+            c.preset_reasons.push("Spam".to_string());
+        });
+
+        let sections = changed_sections(&before, &after)
+            .into_iter()
+            .map(|(section, ..)| section)
+            .collect::<Vec<_>>();
+        assert_eq!(sections, ["dry_run", "preset_reasons"]);
+        assert!(changed_sections(&before, &configuration(|_| {})).is_empty());
+    }
+}
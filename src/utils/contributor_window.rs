@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{ChannelId, Mutex, RoleId};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::{dry_run, *};
+use crate::model::application::ContributorWindow;
+
+// When the open windows close by their name
+static OPEN_WINDOWS: Lazy<Mutex<HashMap<String, i64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Whether a member with the given roles may post in the channels of an open window.
+fn may_post(window: &ContributorWindow, exempt_roles: &[u64], roles: &[RoleId]) -> bool {
+    roles
+        .iter()
+        .any(|r| window.roles.contains(&r.0) || exempt_roles.contains(&r.0))
+}
+
+/// Post a notice in all channels of a window.
+async fn announce(ctx: &serenity::Context, window: &ContributorWindow, notice: &str) {
+    if !window.announce {
+        return;
+    }
+
+    for channel_id in &window.channels {
+        if let Err(err) = ChannelId(*channel_id)
+            .send_message(&ctx.http, |m| {
+                m.content(notice).allowed_mentions(|a| a.empty_parse())
+            })
+            .await
+        {
+            error!("Failed to announce the window {}: {:?}", window.name, err);
+        }
+    }
+}
+
+/// Open the windows triggered by a message, such as a release announcement, and reopen their
+/// channels to everyone once they close. Triggering an open window again extends it.
+pub async fn open_windows(ctx: &serenity::Context, new_message: &serenity::Message) {
+    if new_message.guild_id.is_none() {
+        return;
+    }
+
+    let data = get_data(ctx).await;
+    let now = Utc::now().timestamp();
+
+    for window in data
+        .configuration
+        .automod
+        .windows
+        .iter()
+        .filter(|w| w.trigger_channel == new_message.channel_id.0)
+    {
+        let closes = now + window.minutes * 60;
+        let was_open = OPEN_WINDOWS
+            .lock()
+            .await
+            .insert(window.name.clone(), closes)
+            .is_some_and(|previous| previous > now);
+
+        debug!("Opened the window {} until {}", window.name, closes);
+
+        if !was_open {
+            let roles = window
+                .roles
+                .iter()
+                .map(|r| format!("<@&{}>", r))
+                .collect::<Vec<_>>()
+                .join(", ");
+            announce(
+                ctx,
+                window,
+                &format!(
+                    "Only {} can post here for the next {} minutes.",
+                    roles, window.minutes
+                ),
+            )
+            .await;
+        }
+
+        let ctx = ctx.clone();
+        let name = window.name.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(
+                (closes - Utc::now().timestamp()).max(0) as u64,
+            ))
+            .await;
+
+            // The window may have been extended in the meantime
+            {
+                let mut open_windows = OPEN_WINDOWS.lock().await;
+                if open_windows.get(&name) != Some(&closes) {
+                    return;
+                }
+                open_windows.remove(&name);
+            }
+
+            debug!("Closed the window {}", name);
+
+            let data = get_data(&ctx).await;
+            if let Some(window) = data
+                .configuration
+                .automod
+                .windows
+                .iter()
+                .find(|w| w.name == name)
+            {
+                announce(&ctx, window, "Everyone can post here again.").await;
+            }
+        });
+    }
+}
+
+/// Delete messages of members without the roles of an open window in its channels.
+/// Returns whether the message was deleted.
+pub async fn enforce_windows(ctx: &serenity::Context, new_message: &serenity::Message) -> bool {
+    let member = match &new_message.member {
+        Some(member) if !new_message.author.bot => member,
+        _ => return false,
+    };
+
+    let data = get_data(ctx).await;
+    let automod = &data.configuration.automod;
+    let now = Utc::now().timestamp();
+
+    let window = {
+        let open_windows = OPEN_WINDOWS.lock().await;
+        automod.windows.iter().find(|w| {
+            w.channels.contains(&new_message.channel_id.0)
+                && open_windows
+                    .get(&w.name)
+                    .is_some_and(|&closes| closes > now)
+        })
+    };
+    let Some(window) = window else {
+        return false;
+    };
+    if may_post(window, &automod.exempt_roles, &member.roles) {
+        return false;
+    }
+
+    debug!(
+        "Deleting the message of {} during the window {}",
+        new_message.author.tag(),
+        window.name
+    );
+
+    if let Err(err) = dry_run::execute(
+        format!("delete message {}", new_message.id),
+        new_message.delete(&ctx.http),
+    )
+    .await
+    {
+        error!("Failed to delete message during a window: {:?}", err);
+        return false;
+    }
+
+    if let Err(err) = new_message
+        .author
+        .direct_message(&ctx.http, |m| {
+            m.content(format!(
+                "<#{}> is restricted for a while, please try again later.",
+                new_message.channel_id
+            ))
+        })
+        .await
+    {
+        debug!(
+            "Failed to notify {} of the window {}: {:?}",
+            new_message.author.tag(),
+            window.name,
+            err
+        );
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_window_and_exempt_roles() {
+        let window = ContributorWindow {
+            name: "release".to_string(),
+            trigger_channel: 1,
+            channels: vec![2],
+            roles: vec![10],
+            minutes: 60,
+            announce: false,
+        };
+
+        assert!(may_post(&window, &[20], &[RoleId(10)]));
+        assert!(may_post(&window, &[20], &[RoleId(30), RoleId(20)]));
+        assert!(!may_post(&window, &[20], &[RoleId(30)]));
+        assert!(!may_post(&window, &[20], &[]));
+    }
+}
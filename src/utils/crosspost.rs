@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{ChannelId, GuildId, MessageId, Mutex, UserId};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::moderation::moderation_buttons;
+use super::{dry_run, *};
+use crate::db::model::{AutomodHit, AutomodHitStatus};
+use crate::model::application::LogCategory;
+
+// The name of crossposts in the automod statistics
+const RULE_NAME: &str = "crosspost";
+
+struct RecentPost {
+    key: String,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    timestamp: i64,
+}
+
+// The recent posts of a member by guild and user
+type MemberPosts = HashMap<(GuildId, UserId), Vec<RecentPost>>;
+
+static RECENT_POSTS: Lazy<Mutex<MemberPosts>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Identify the content of a message regardless of case, whitespace and attachment names.
+fn post_key(content: &str, attachment_sizes: &[u64]) -> String {
+    let mut key = content
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    for size in attachment_sizes {
+        key.push_str(&format!("\n{}", size));
+    }
+    key
+}
+
+/// The first post with the same content in another channel within the window.
+fn find_original<'a>(
+    posts: &'a [RecentPost],
+    key: &str,
+    channel_id: ChannelId,
+    since: i64,
+) -> Option<&'a RecentPost> {
+    posts
+        .iter()
+        .find(|p| p.key == key && p.channel_id != channel_id && p.timestamp >= since)
+}
+
+/// Delete messages which a member already posted in another channel within a few minutes,
+/// keeping the first, and log them as automod hits. Returns whether the message was deleted.
+pub async fn dedupe_crossposts(ctx: &serenity::Context, new_message: &serenity::Message) -> bool {
+    let (Some(guild_id), Some(member)) = (new_message.guild_id, &new_message.member) else {
+        return false;
+    };
+    if new_message.author.bot {
+        return false;
+    }
+
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+    let automod = &configuration.automod;
+    let crossposts = &automod.crossposts;
+
+    if !crossposts.enabled
+        || new_message.content.chars().count() < crossposts.min_length
+        || member
+            .roles
+            .iter()
+            .any(|r| automod.exempt_roles.contains(&r.0))
+    {
+        return false;
+    }
+
+    let now = Utc::now().timestamp();
+    let since = now - crossposts.minutes * 60;
+    let key = post_key(
+        &new_message.content,
+        &new_message
+            .attachments
+            .iter()
+            .map(|a| a.size)
+            .collect::<Vec<_>>(),
+    );
+
+    let original = {
+        let mut recent_posts = RECENT_POSTS.lock().await;
+        let posts = recent_posts
+            .entry((guild_id, new_message.author.id))
+            .or_default();
+        posts.retain(|p| p.timestamp >= since);
+
+        match find_original(posts, &key, new_message.channel_id, since) {
+            Some(original) => (original.channel_id, original.message_id),
+            None => {
+                posts.push(RecentPost {
+                    key,
+                    channel_id: new_message.channel_id,
+                    message_id: new_message.id,
+                    timestamp: now,
+                });
+                return false;
+            },
+        }
+    };
+    let original_link = format!(
+        "https://discord.com/channels/{}/{}/{}",
+        guild_id, original.0, original.1
+    );
+
+    debug!(
+        "{} crossposted into {}",
+        new_message.author.tag(),
+        new_message.channel_id
+    );
+
+    let result = dry_run::execute(
+        format!("delete crossposted message {}", new_message.id),
+        new_message.delete(&ctx.http),
+    )
+    .await;
+    if let Err(err) = &result {
+        error!("Failed to delete crossposted message: {:?}", err);
+    }
+
+    let hit = AutomodHit {
+        message_id: Some(new_message.id.to_string()),
+        channel_id: Some(new_message.channel_id.to_string()),
+        guild_id: Some(guild_id.to_string()),
+        user_id: Some(new_message.author.id.to_string()),
+        rule: Some(RULE_NAME.to_string()),
+        content: Some(new_message.content.clone()),
+        score: Some(1.0),
+        status: Some(AutomodHitStatus::Actioned),
+        timestamp: Some(now),
+        ..Default::default()
+    };
+    if let Err(err) = data
+        .database
+        .insert::<AutomodHit>("automod_hits", hit, None)
+        .await
+    {
+        error!("Failed to store automod hit: {:?}", err);
+    }
+
+    if let Err(err) = ChannelId(configuration.log_channel(LogCategory::Moderation))
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                let e = e
+                    .title(format!("Automod: {}", RULE_NAME))
+                    .description(&new_message.content)
+                    .field("User", format!("<@{}>", new_message.author.id), true)
+                    .field(
+                        "Original",
+                        format!("[Jump to message]({})", original_link),
+                        true,
+                    )
+                    .field(
+                        "Deleted from",
+                        format!("<#{}>", new_message.channel_id),
+                        true,
+                    )
+                    .thumbnail(new_message.author.face())
+                    .color(configuration.general.embed_color);
+
+                if let Err(err) = &result {
+                    e.field("Exception", err.to_string(), false);
+                }
+
+                e
+            })
+            .components(|c| moderation_buttons(c, new_message.author.id))
+        })
+        .await
+    {
+        error!("Failed to log crosspost: {:?}", err);
+    }
+
+    result.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(key: &str, channel_id: u64, timestamp: i64) -> RecentPost {
+        RecentPost {
+            key: key.to_string(),
+            channel_id: ChannelId(channel_id),
+            message_id: MessageId(timestamp as u64),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn ignores_case_and_whitespace() {
+        assert_eq!(post_key(" Free  NITRO\nhere ", &[]), "free nitro here");
+        assert_ne!(post_key("scam", &[1]), post_key("scam", &[2]));
+    }
+
+    #[test]
+    fn finds_recent_posts_in_other_channels() {
+        let posts = [post("a", 1, 100), post("b", 2, 10)];
+
+        assert_eq!(
+            find_original(&posts, "a", ChannelId(2), 50).map(|p| p.message_id),
+            Some(MessageId(100))
+        );
+        // Repeating a message in the same channel is not a crosspost
+        assert!(find_original(&posts, "a", ChannelId(1), 50).is_none());
+        assert!(find_original(&posts, "b", ChannelId(1), 50).is_none());
+    }
+}
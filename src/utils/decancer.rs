@@ -5,6 +5,13 @@ use super::*;
 
 const DECANCER: Decancer = Decancer::new();
 
+pub fn cure_name(name: &str) -> String {
+    DECANCER.cure(name).replace(
+        |c: char| !(c == ' ' || c == '-' || c == '_' || c.is_ascii_alphanumeric()),
+        "",
+    )
+}
+
 pub async fn cure(
     ctx: &serenity::Context,
     old_if_available: &Option<serenity::Member>,
@@ -27,10 +34,7 @@ pub async fn cure(
         }
     }
 
-    let mut cured_name = DECANCER.cure(&name).replace(
-        |c: char| !(c == ' ' || c == '-' || c == '_' || c.is_ascii_alphanumeric()),
-        "",
-    );
+    let mut cured_name = cure_name(&name);
 
     if cured_name.is_empty() {
         cured_name = "ReVanced user".to_string();
@@ -0,0 +1,33 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+// Mentions such as <@123> and plain snowflakes, which are at least 17 digits long
+static USER_ID: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<@!?(\d{17,20})>|\b(\d{17,20})\b").unwrap());
+
+static INVITE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)(?:discord\.gg|discord(?:app)?\.com/invite)/([a-z0-9-]+)").unwrap()
+});
+
+/// Extract the distinct user ids mentioned in a text.
+pub fn extract_user_ids(text: &str) -> Vec<u64> {
+    let mut ids = USER_ID
+        .captures_iter(text)
+        .filter_map(|c| c.get(1).or_else(|| c.get(2)))
+        .filter_map(|m| m.as_str().parse().ok())
+        .collect::<Vec<u64>>();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+/// Extract the distinct invite codes in a text.
+pub fn extract_invites(text: &str) -> Vec<String> {
+    let mut codes = INVITE
+        .captures_iter(text)
+        .map(|c| c[1].to_string())
+        .collect::<Vec<_>>();
+    codes.sort_unstable();
+    codes.dedup();
+    codes
+}
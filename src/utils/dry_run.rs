@@ -0,0 +1,35 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::info;
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+// Set from the command line, so reloading the configuration can not disable it
+static FORCED: AtomicBool = AtomicBool::new(false);
+
+pub fn set(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+pub fn force() {
+    FORCED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    FORCED.load(Ordering::Relaxed) || DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Execute a call which mutates state on Discord.
+/// In dry-run mode the call is only logged and simulated to succeed.
+pub async fn execute<T: Default, E>(
+    action: impl Display,
+    call: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    if is_enabled() {
+        info!("Dry run: {}", action);
+        return Ok(T::default());
+    }
+
+    call.await
+}
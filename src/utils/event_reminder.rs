@@ -0,0 +1,228 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::Utc;
+use poise::serenity_prelude::{
+    ChannelId,
+    GuildId,
+    ScheduledEvent,
+    ScheduledEventStatus,
+    UserId,
+    UserPagination,
+};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::time::{format_timestamp, parse_duration};
+use super::*;
+use crate::db::model::EventReminder;
+
+// How often to check for upcoming events
+const POLL_INTERVAL_SECS: u64 = 60;
+// The maximal amount of interested users which can be fetched at once
+const MAX_FETCH: u64 = 100;
+// Leave some room below the message limit of 2000 characters
+const MAX_MESSAGE_LENGTH: usize = 1900;
+
+static REMINDERS_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+/// Fetch all users interested in a scheduled event.
+async fn interested_users(
+    ctx: &serenity::Context,
+    event: &ScheduledEvent,
+) -> serenity::Result<Vec<UserId>> {
+    let mut users = Vec::new();
+    let mut after: Option<UserId> = None;
+    loop {
+        let page = event
+            .guild_id
+            .scheduled_event_users_optioned(
+                &ctx.http,
+                event.id,
+                Some(MAX_FETCH),
+                after.map(UserPagination::After),
+                None,
+            )
+            .await?;
+
+        let exhausted = (page.len() as u64) < MAX_FETCH;
+        after = page.last().map(|u| u.user.id);
+        users.extend(page.into_iter().filter(|u| !u.user.bot).map(|u| u.user.id));
+
+        if exhausted {
+            return Ok(users);
+        }
+    }
+}
+
+/// Remind interested users of an event which starts soon.
+async fn remind(
+    ctx: &serenity::Context,
+    channel: ChannelId,
+    role: u64,
+    event: &ScheduledEvent,
+) -> Result<(), crate::Error> {
+    let mut mentions = interested_users(ctx, event)
+        .await?
+        .into_iter()
+        .map(|u| format!("<@{}>", u))
+        .collect::<Vec<_>>();
+    if role != 0 {
+        mentions.insert(0, format!("<@&{}>", role));
+    }
+
+    let start = event.start_time.unix_timestamp();
+    channel
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title(format!("Starting soon: {}", event.name))
+                    .description(event.description.as_deref().unwrap_or_default())
+                    .field("Starts", format_timestamp(start), false)
+                    .url(format!(
+                        "https://discord.com/events/{}/{}",
+                        event.guild_id, event.id
+                    ))
+            })
+        })
+        .await?;
+
+    // Mention everyone in as few messages as possible
+    let mut chunk = String::new();
+    for mention in mentions {
+        if chunk.len() + mention.len() + 1 > MAX_MESSAGE_LENGTH {
+            channel.say(&ctx.http, &chunk).await?;
+            chunk.clear();
+        }
+        chunk.push_str(&mention);
+        chunk.push(' ');
+    }
+    if !chunk.is_empty() {
+        channel.say(&ctx.http, &chunk).await?;
+    }
+
+    Ok(())
+}
+
+/// Send reminders for all scheduled events in a guild which are due.
+async fn send_due_reminders(ctx: &serenity::Context, guild_id: GuildId) {
+    let (channel, role, offsets) = {
+        let data = get_data(ctx).await;
+        let reminders = &data.configuration.event_reminders;
+
+        let mut offsets = reminders
+            .offsets
+            .iter()
+            .filter_map(|o| parse_duration(o))
+            .map(|o| o.num_seconds())
+            .collect::<Vec<_>>();
+        offsets.sort_unstable();
+
+        (ChannelId(reminders.channel), reminders.role, offsets)
+    };
+
+    let events = match guild_id.scheduled_events(&ctx.http, false).await {
+        Ok(events) => events,
+        Err(err) => {
+            debug!(
+                "Failed to fetch scheduled events of {}: {:?}",
+                guild_id, err
+            );
+            return;
+        },
+    };
+
+    let now = Utc::now().timestamp();
+    for event in events {
+        if !matches!(event.status, ScheduledEventStatus::Scheduled) {
+            continue;
+        }
+
+        let remaining = event.start_time.unix_timestamp() - now;
+        if remaining < 0 {
+            continue;
+        }
+
+        // Only the closest offset is due, so that an event created shortly before it starts
+        // is not announced once for every offset
+        let offset = match offsets.iter().find(|&&o| remaining <= o) {
+            Some(offset) => *offset,
+            None => continue,
+        };
+
+        let reminder = EventReminder {
+            event_id: Some(event.id.to_string()),
+            offset: Some(offset),
+            ..Default::default()
+        };
+
+        {
+            let data = get_data(ctx).await;
+            match data
+                .database
+                .find::<EventReminder>("event_reminders", reminder.into(), None)
+                .await
+            {
+                Ok(mut cursor) => {
+                    if cursor.advance().await.unwrap_or_default() {
+                        continue;
+                    }
+                },
+                Err(err) => {
+                    error!("Failed to look up event reminder: {:?}", err);
+                    continue;
+                },
+            }
+        }
+
+        if let Err(err) = remind(ctx, channel, role, &event).await {
+            error!("Failed to remind of event {}: {:?}", event.name, err);
+            continue;
+        }
+
+        let data = get_data(ctx).await;
+        if let Err(err) = data
+            .database
+            .insert::<EventReminder>(
+                "event_reminders",
+                EventReminder {
+                    event_id: Some(event.id.to_string()),
+                    offset: Some(offset),
+                    timestamp: Some(now),
+                },
+                None,
+            )
+            .await
+        {
+            error!("Failed to store event reminder: {:?}", err);
+        }
+    }
+}
+
+/// Periodically remind users of upcoming scheduled events.
+/// Sent reminders are stored, so that restarting does not send them again.
+pub fn schedule_reminders(ctx: &serenity::Context) {
+    // Ready is dispatched again when reconnecting
+    if REMINDERS_SCHEDULED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(POLL_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+
+            let enabled = {
+                let data = get_data(&ctx).await;
+                data.configuration.event_reminders.channel != 0
+            };
+            if !enabled {
+                continue;
+            }
+
+            for guild_id in ctx.cache.guilds() {
+                send_due_reminders(&ctx, guild_id).await;
+            }
+        }
+    });
+}
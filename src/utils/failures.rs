@@ -0,0 +1,284 @@
+use bson::doc;
+use chrono::Utc;
+use mongodb::options::UpdateModifications;
+use poise::serenity_prelude::{
+    ButtonStyle,
+    CreateComponents,
+    CreateEmbed,
+    GuildId,
+    MessageComponentInteraction,
+    RoleId,
+    UserId,
+};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::cases::increment_counter;
+use super::interaction::{respond_ephemeral, update_with_decision};
+use super::member_cache::fetch_member;
+use super::moderation::{
+    add_roles_with_audit_reason,
+    is_administrator,
+    remove_roles_with_audit_reason,
+    unban_with_audit_reason,
+};
+use super::{dry_run, *};
+use crate::db::database::Database;
+use crate::db::model::{FailedOperation, Failure};
+use crate::Error;
+
+/// Remember a moderation operation which failed in the background, so staff can retry it.
+pub async fn record_failure(
+    database: &Database,
+    guild_id: GuildId,
+    user_id: UserId,
+    operation: FailedOperation,
+    role_ids: &[RoleId],
+    err: &impl std::fmt::Display,
+) {
+    let failure_id = match increment_counter(database, &format!("failures:{}", guild_id)).await {
+        Ok(failure_id) => failure_id,
+        Err(err) => {
+            error!("Failed to number a failed operation: {:?}", err);
+            return;
+        },
+    };
+
+    if let Err(err) = database
+        .insert::<Failure>(
+            "failures",
+            Failure {
+                guild_id: Some(guild_id.to_string()),
+                failure_id: Some(failure_id),
+                operation: Some(operation),
+                user_id: Some(user_id.to_string()),
+                role_ids: Some(role_ids.iter().map(|r| r.to_string()).collect()),
+                error: Some(err.to_string()),
+                timestamp: Some(Utc::now().timestamp()),
+            },
+            None,
+        )
+        .await
+    {
+        error!("Failed to record a failed operation: {:?}", err);
+    }
+}
+
+/// Describe the operation of a failure.
+fn describe(failure: &Failure) -> String {
+    let user = format!("<@{}>", failure.user_id.as_deref().unwrap_or_default());
+    let roles = failure
+        .role_ids
+        .iter()
+        .flatten()
+        .map(|r| format!("<@&{}>", r))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match failure.operation {
+        Some(FailedOperation::AddRoles) => format!("Give {} the roles {}", user, roles),
+        Some(FailedOperation::RemoveRoles) => format!("Take the roles {} from {}", roles, user),
+        Some(FailedOperation::Unban) => format!("Unban {}", user),
+        None => "Unknown operation".to_string(),
+    }
+}
+
+/// Build the embed of a failure.
+pub fn failure_embed<'a>(
+    embed: &'a mut CreateEmbed,
+    failure: &Failure,
+    embed_color: i32,
+) -> &'a mut CreateEmbed {
+    embed
+        .title(format!(
+            "Failure #{}",
+            failure.failure_id.unwrap_or_default()
+        ))
+        .description(describe(failure))
+        .field(
+            "Error",
+            failure.error.as_deref().unwrap_or("Unknown"),
+            false,
+        )
+        .field(
+            "Failed",
+            format!("<t:{}:R>", failure.timestamp.unwrap_or_default()),
+            true,
+        )
+        .color(embed_color)
+}
+
+/// Add the Retry and Discard buttons of a failure.
+pub fn failure_buttons<'a>(
+    components: &'a mut CreateComponents,
+    failure: &Failure,
+) -> &'a mut CreateComponents {
+    let guild_id = failure.guild_id.as_deref().unwrap_or_default();
+    let failure_id = failure.failure_id.unwrap_or_default();
+
+    components.create_action_row(|r| {
+        r.create_button(|b| {
+            b.custom_id(format!("failure:retry:{}:{}", guild_id, failure_id))
+                .label("Retry")
+                .style(ButtonStyle::Primary)
+        })
+        .create_button(|b| {
+            b.custom_id(format!("failure:discard:{}:{}", guild_id, failure_id))
+                .label("Discard")
+                .style(ButtonStyle::Secondary)
+        })
+    })
+}
+
+/// Run the operation of a failure again.
+async fn retry(ctx: &serenity::Context, guild_id: GuildId, failure: &Failure) -> Result<(), Error> {
+    let user_id = UserId(failure.user_id.as_ref().ok_or("No user")?.parse()?);
+    let role_ids = failure
+        .role_ids
+        .iter()
+        .flatten()
+        .filter_map(|r| r.parse().ok())
+        .map(RoleId)
+        .collect::<Vec<_>>();
+
+    match failure.operation.ok_or("No operation")? {
+        FailedOperation::AddRoles => {
+            let mut member = fetch_member(ctx, guild_id, user_id).await?;
+            dry_run::execute(
+                format!("give {} their roles again", member.user.tag()),
+                add_roles_with_audit_reason(
+                    &ctx.http,
+                    &mut member,
+                    &role_ids,
+                    "Retried a failed operation",
+                ),
+            )
+            .await?;
+        },
+        FailedOperation::RemoveRoles => {
+            let mut member = fetch_member(ctx, guild_id, user_id).await?;
+            dry_run::execute(
+                format!("take roles from {} again", member.user.tag()),
+                remove_roles_with_audit_reason(
+                    &ctx.http,
+                    &mut member,
+                    &role_ids,
+                    "Retried a failed operation",
+                ),
+            )
+            .await?;
+        },
+        FailedOperation::Unban => {
+            dry_run::execute(
+                format!("unban {} again", user_id),
+                unban_with_audit_reason(&ctx.http, guild_id, user_id, "Retried a failed operation"),
+            )
+            .await?;
+        },
+    }
+
+    Ok(())
+}
+
+/// Handle the Retry and Discard buttons of failures.
+pub async fn handle_failure_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    let mut parts = component.data.custom_id.splitn(4, ':').skip(1);
+    let (action, guild_id, failure_id) = match (
+        parts.next(),
+        parts.next().and_then(|id| id.parse::<u64>().ok()),
+        parts.next().and_then(|id| id.parse::<i64>().ok()),
+    ) {
+        (Some(action), Some(guild_id), Some(failure_id)) => (action, GuildId(guild_id), failure_id),
+        _ => return,
+    };
+
+    let data = get_data(ctx).await;
+    let is_allowed = component
+        .member
+        .as_ref()
+        .is_some_and(|m| is_administrator(&data.configuration.administrators, m));
+    if !is_allowed {
+        respond_ephemeral(ctx, component, "You do not have permission to do this.").await;
+        return;
+    }
+
+    let filter = doc! { "guild_id": guild_id.to_string(), "failure_id": failure_id };
+
+    if action == "discard" {
+        match data
+            .database
+            .find_and_delete::<Failure>("failures", filter, None)
+            .await
+        {
+            Ok(Some(_)) => {
+                update_with_decision(
+                    ctx,
+                    component,
+                    &format!("Discarded by <@{}>", component.user.id),
+                )
+                .await
+            },
+            Ok(None) => {
+                respond_ephemeral(ctx, component, "This failure was already resolved.").await
+            },
+            Err(err) => {
+                error!("Failed to discard failure: {:?}", err);
+                respond_ephemeral(ctx, component, "Failed to discard this failure.").await;
+            },
+        }
+        return;
+    }
+
+    let failure = match data
+        .database
+        .collect::<Failure>("failures", filter.clone())
+        .await
+    {
+        Ok(failures) => match failures.into_iter().next() {
+            Some(failure) => failure,
+            None => {
+                respond_ephemeral(ctx, component, "This failure was already resolved.").await;
+                return;
+            },
+        },
+        Err(err) => {
+            error!("Failed to find failure: {:?}", err);
+            respond_ephemeral(ctx, component, "Failed to retry this failure.").await;
+            return;
+        },
+    };
+
+    match retry(ctx, guild_id, &failure).await {
+        Ok(_) => {
+            debug!("{} retried failure #{}", component.user.tag(), failure_id);
+
+            if let Err(err) = data.database.delete("failures", filter, None).await {
+                error!("Failed to remove retried failure: {:?}", err);
+            }
+            update_with_decision(
+                ctx,
+                component,
+                &format!("Retried by <@{}>", component.user.id),
+            )
+            .await;
+        },
+        Err(err) => {
+            if let Err(err) = data
+                .database
+                .update::<Failure>(
+                    "failures",
+                    filter,
+                    UpdateModifications::Document(doc! { "$set": { "error": err.to_string() } }),
+                    None,
+                )
+                .await
+            {
+                error!("Failed to update failure: {:?}", err);
+            }
+            respond_ephemeral(ctx, component, &format!("Retrying failed again: {}", err)).await;
+        },
+    }
+}
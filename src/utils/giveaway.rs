@@ -0,0 +1,301 @@
+use bson::doc;
+use chrono::Utc;
+use mongodb::options::UpdateModifications;
+use poise::serenity_prelude::{ButtonStyle, ChannelId, MessageComponentInteraction, MessageId};
+use rand::seq::SliceRandom;
+use tracing::error;
+
+use super::bot::get_data;
+use super::interaction::respond_ephemeral;
+use super::*;
+use crate::db::model::{Giveaway, GiveawayEntry};
+use crate::model::application::LogCategory;
+use crate::Error;
+
+/// Create the entry button of a giveaway.
+pub fn entry_button(
+    components: &mut serenity::CreateComponents,
+    message_id: MessageId,
+) -> &mut serenity::CreateComponents {
+    components.create_action_row(|r| {
+        r.create_button(|b| {
+            b.custom_id(format!("giveaway:enter:{}", message_id))
+                .label("Enter")
+                .emoji('🎉')
+                .style(ButtonStyle::Success)
+        })
+    })
+}
+
+/// Pick distinct winners uniformly at random from the entrants which have not won already.
+pub fn pick_winners(entrants: &[String], exclude: &[String], count: usize) -> Vec<String> {
+    let candidates = entrants
+        .iter()
+        .filter(|e| !exclude.contains(e))
+        .collect::<Vec<_>>();
+
+    candidates
+        .choose_multiple(&mut rand::thread_rng(), count)
+        .map(|e| e.to_string())
+        .collect()
+}
+
+async fn find_giveaway(ctx: &serenity::Context, message_id: &str) -> Option<Giveaway> {
+    let data = get_data(ctx).await;
+
+    let mut cursor = data
+        .database
+        .find::<Giveaway>("giveaways", doc! { "message_id": message_id }, None)
+        .await
+        .ok()?;
+
+    match cursor.advance().await {
+        Ok(true) => cursor.deserialize_current().ok(),
+        _ => None,
+    }
+}
+
+/// Announce the winners of a giveaway and log them to the member log.
+pub async fn announce_winners(
+    ctx: &serenity::Context,
+    giveaway: &Giveaway,
+    winners: &[String],
+    reroll: bool,
+) -> Result<(), Error> {
+    let channel_id = ChannelId(giveaway.channel_id.as_ref().ok_or("No channel")?.parse()?);
+    let message_id = MessageId(giveaway.message_id.as_ref().ok_or("No message")?.parse()?);
+    let prize = giveaway.prize.as_deref().unwrap_or_default();
+    let entrants = giveaway.entrants.as_ref().map_or(0, |e| e.len());
+    let mentions = winners
+        .iter()
+        .map(|w| format!("<@{}>", w))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let data = get_data(ctx).await;
+    let embed_color = data.configuration.general.embed_color;
+
+    let winners_text = if winners.is_empty() {
+        "Nobody entered.".to_string()
+    } else {
+        mentions.clone()
+    };
+
+    if !reroll {
+        channel_id
+            .edit_message(&ctx.http, message_id, |m| {
+                m.embed(|e| {
+                    e.title(format!("Giveaway: {}", prize))
+                        .description("This giveaway has ended.")
+                        .field("Winners", &winners_text, false)
+                        .field("Entrants", entrants.to_string(), true)
+                        .color(embed_color)
+                })
+                .components(|c| c)
+            })
+            .await?;
+    }
+
+    if !winners.is_empty() {
+        channel_id
+            .send_message(&ctx.http, |m| {
+                m.content(format!(
+                    "Congratulations {}, you won **{}**!",
+                    mentions, prize
+                ))
+                .reference_message((channel_id, message_id))
+            })
+            .await?;
+    }
+
+    ChannelId(data.configuration.log_channel(LogCategory::Members))
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title(if reroll {
+                    "Giveaway rerolled"
+                } else {
+                    "Giveaway ended"
+                })
+                .field("Prize", prize, true)
+                .field("Entrants", entrants.to_string(), true)
+                .field("Winners", &winners_text, false)
+                .field(
+                    "Reference",
+                    format!(
+                        "[Jump to giveaway](https://discord.com/channels/{}/{}/{})",
+                        giveaway.guild_id.as_deref().unwrap_or_default(),
+                        channel_id,
+                        message_id
+                    ),
+                    false,
+                )
+                .color(embed_color)
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// End a giveaway, if it has not ended yet, and announce its winners.
+pub async fn end_giveaway(ctx: &serenity::Context, message_id: &str) -> Result<(), Error> {
+    let giveaway = find_giveaway(ctx, message_id)
+        .await
+        .ok_or("This giveaway does not exist")?;
+    if giveaway.ended.unwrap_or_default() {
+        return Ok(());
+    }
+
+    let winners = pick_winners(
+        giveaway.entrants.as_deref().unwrap_or_default(),
+        &[],
+        giveaway.winners.unwrap_or(1) as usize,
+    );
+
+    {
+        let data = get_data(ctx).await;
+        let result = data
+            .database
+            .update::<Giveaway>(
+                "giveaways",
+                // Only end the giveaway once, even if it is ended manually while it expires
+                doc! { "message_id": message_id, "ended": false },
+                UpdateModifications::Document(doc! {
+                    "$set": { "ended": true, "winner_ids": &winners }
+                }),
+                None,
+            )
+            .await?;
+
+        if result.modified_count == 0 {
+            return Ok(());
+        }
+    }
+
+    announce_winners(ctx, &giveaway, &winners, false).await
+}
+
+/// End a giveaway after the given amount of seconds.
+pub fn schedule_end(ctx: &serenity::Context, message_id: String, seconds: u64) {
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(seconds)).await;
+
+        if let Err(err) = end_giveaway(&ctx, &message_id).await {
+            error!("Failed to end giveaway {}: {:?}", message_id, err);
+        }
+    });
+}
+
+/// Schedule the end of all giveaways which are still running.
+pub async fn load_giveaways(ctx: &serenity::Context) {
+    let giveaways = {
+        let data = get_data(ctx).await;
+        match data
+            .database
+            .collect::<Giveaway>("giveaways", doc! { "ended": false })
+            .await
+        {
+            Ok(giveaways) => giveaways,
+            Err(err) => {
+                error!("Failed to load giveaways: {:?}", err);
+                return;
+            },
+        }
+    };
+
+    let now = Utc::now().timestamp();
+    for giveaway in giveaways {
+        if let (Some(message_id), Some(expires)) = (giveaway.message_id, giveaway.expires) {
+            schedule_end(ctx, message_id, (expires - now).max(0) as u64);
+        }
+    }
+}
+
+/// Handle the entry button of giveaways.
+pub async fn handle_giveaway_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    let message_id = match component.data.custom_id.strip_prefix("giveaway:enter:") {
+        Some(message_id) => message_id,
+        None => return,
+    };
+
+    let giveaway = match find_giveaway(ctx, message_id).await {
+        Some(giveaway) if !giveaway.ended.unwrap_or_default() => giveaway,
+        _ => {
+            respond_ephemeral(ctx, component, "This giveaway has ended.").await;
+            return;
+        },
+    };
+
+    let has_required_role = match &giveaway.required_role {
+        Some(role) => component
+            .member
+            .as_ref()
+            .is_some_and(|m| m.roles.iter().any(|r| &r.to_string() == role)),
+        None => true,
+    };
+    if !has_required_role {
+        respond_ephemeral(
+            ctx,
+            component,
+            &format!(
+                "You need the <@&{}> role to enter this giveaway.",
+                giveaway.required_role.unwrap_or_default()
+            ),
+        )
+        .await;
+        return;
+    }
+
+    let user_id = component.user.id.to_string();
+    if giveaway
+        .entrants
+        .as_ref()
+        .is_some_and(|e| e.contains(&user_id))
+    {
+        respond_ephemeral(ctx, component, "You have already entered this giveaway.").await;
+        return;
+    }
+
+    let result: Result<(), Error> = async {
+        let data = get_data(ctx).await;
+
+        data.database
+            .update::<Giveaway>(
+                "giveaways",
+                doc! { "message_id": message_id },
+                UpdateModifications::Document(doc! { "$addToSet": { "entrants": &user_id } }),
+                None,
+            )
+            .await?;
+
+        // Keep a record of every entry for auditing the draw
+        data.database
+            .insert::<GiveawayEntry>(
+                "giveaway_entries",
+                GiveawayEntry {
+                    message_id: Some(message_id.to_string()),
+                    user_id: Some(user_id.clone()),
+                    timestamp: Some(Utc::now().timestamp()),
+                },
+                None,
+            )
+            .await?;
+
+        Ok(())
+    }
+    .await;
+
+    match result {
+        Ok(_) => {
+            respond_ephemeral(ctx, component, "You have entered the giveaway. Good luck!").await
+        },
+        Err(err) => {
+            error!("Failed to enter giveaway: {:?}", err);
+            respond_ephemeral(ctx, component, "Failed to enter the giveaway.").await;
+        },
+    }
+}
@@ -0,0 +1,182 @@
+use image::imageops::FilterType;
+use poise::serenity_prelude::{ChannelId, User};
+use tracing::{debug, error, info};
+
+use super::bot::get_data;
+use super::decancer::cure_name;
+use super::*;
+use crate::model::application::LogCategory;
+
+// Size of the average hash, results in a 64 bit hash
+const HASH_SIZE: u32 = 8;
+
+/// Compute the average hash of the avatar of a user.
+async fn avatar_hash(user: &User) -> Option<u64> {
+    let avatar = user.avatar.as_ref()?;
+    // The smallest size Discord serves
+    let url = format!(
+        "https://cdn.discordapp.com/avatars/{}/{}.png?size=16",
+        user.id, avatar
+    );
+
+    let bytes = reqwest::get(url).await.ok()?.bytes().await.ok()?;
+    let image = image::load_from_memory(&bytes)
+        .ok()?
+        .resize_exact(HASH_SIZE, HASH_SIZE, FilterType::Triangle)
+        .to_luma8();
+
+    let pixels = image.pixels().map(|p| p.0[0] as u64).collect::<Vec<_>>();
+    let average = pixels.iter().sum::<u64>() / pixels.len() as u64;
+
+    Some(
+        pixels
+            .iter()
+            .enumerate()
+            .filter(|(_, &p)| p > average)
+            .fold(0, |hash, (i, _)| hash | 1 << i),
+    )
+}
+
+fn name_similarity(name: &str, other: &str) -> f64 {
+    strsim::normalized_levenshtein(
+        &cure_name(name).to_lowercase(),
+        &cure_name(other).to_lowercase(),
+    )
+}
+
+pub async fn check_impersonation(
+    ctx: &serenity::Context,
+    old_if_available: &Option<serenity::Member>,
+    member: &serenity::Member,
+) {
+    if member.user.bot {
+        return;
+    }
+
+    if let Some(old) = old_if_available {
+        if old.display_name() == member.display_name() && old.user.avatar == member.user.avatar {
+            return;
+        }
+    }
+
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+    let impersonation = &configuration.impersonation;
+
+    if !impersonation.enabled {
+        return;
+    }
+
+    let is_staff =
+        |m: &serenity::Member| m.roles.iter().any(|r| impersonation.roles.contains(&r.0));
+    if is_staff(member) {
+        return;
+    }
+
+    let staff = match ctx.cache.guild(member.guild_id) {
+        Some(guild) => guild
+            .members
+            .into_values()
+            .filter(is_staff)
+            .collect::<Vec<_>>(),
+        None => return,
+    };
+
+    let name = member.display_name().to_string();
+    let hash = avatar_hash(&member.user).await;
+
+    for staff_member in staff {
+        let similarity = name_similarity(&name, &staff_member.display_name())
+            .max(name_similarity(&name, &staff_member.user.name));
+        let distance = match hash {
+            Some(hash) => avatar_hash(&staff_member.user)
+                .await
+                .map(|staff_hash| (hash ^ staff_hash).count_ones()),
+            None => None,
+        };
+
+        let similar_name = similarity >= impersonation.name_similarity;
+        let similar_avatar = distance.is_some_and(|d| d <= impersonation.avatar_distance);
+
+        if !(similar_name || similar_avatar) {
+            continue;
+        }
+
+        info!(
+            "{} may impersonate {} (name similarity: {:.2}, avatar distance: {:?})",
+            member.user.tag(),
+            staff_member.user.tag(),
+            similarity,
+            distance
+        );
+
+        let renamed = if impersonation.rename && similar_name {
+            match member
+                .guild_id
+                .edit_member(&ctx.http, member.user.id, |m| m.nickname("ReVanced user"))
+                .await
+            {
+                Ok(_) => true,
+                Err(err) => {
+                    error!("Failed to rename {}: {:?}", member.user.tag(), err);
+                    false
+                },
+            }
+        } else {
+            false
+        };
+
+        let describe = |e: &mut serenity::CreateEmbed| {
+            e.title("Possible impersonation")
+                .description(format!(
+                    "<@{}> may be impersonating <@{}>.",
+                    member.user.id, staff_member.user.id
+                ))
+                .field("Name", &name, true)
+                .field(
+                    "Name similarity",
+                    format!("{:.0}%", similarity * 100.0),
+                    true,
+                )
+                .field(
+                    "Avatar distance",
+                    distance.map_or("Unknown".to_string(), |d| d.to_string()),
+                    true,
+                )
+                .field("Renamed", renamed.to_string(), true)
+                .thumbnail(member.user.face())
+                .color(configuration.general.embed_color);
+        };
+
+        if let Err(err) = ChannelId(configuration.log_channel(LogCategory::Moderation))
+            .send_message(&ctx.http, |m| {
+                m.embed(|e| {
+                    describe(e);
+                    e
+                })
+            })
+            .await
+        {
+            error!("Failed to log impersonation: {:?}", err);
+        }
+
+        if let Err(err) = staff_member
+            .user
+            .direct_message(&ctx.http, |m| {
+                m.embed(|e| {
+                    describe(e);
+                    e
+                })
+            })
+            .await
+        {
+            debug!(
+                "Failed to alert {} about impersonation: {:?}",
+                staff_member.user.tag(),
+                err
+            );
+        }
+
+        break;
+    }
+}
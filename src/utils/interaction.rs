@@ -0,0 +1,77 @@
+use poise::serenity_prelude::{
+    CreateEmbed,
+    InteractionResponseType,
+    Message,
+    MessageComponentInteraction,
+};
+use tracing::error;
+
+use super::*;
+
+/// Respond to a component interaction with an ephemeral message.
+pub async fn respond_ephemeral(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+    content: &str,
+) {
+    if let Err(err) = component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.ephemeral(true).content(content))
+        })
+        .await
+    {
+        error!("Failed to respond to interaction: {:?}", err);
+    }
+}
+
+/// The embeds of a message with a decision recorded on the first one.
+fn decided_embeds(message: &Message, decision: &str) -> Vec<CreateEmbed> {
+    message
+        .embeds
+        .iter()
+        .cloned()
+        .map(CreateEmbed::from)
+        .enumerate()
+        .map(|(i, mut embed)| {
+            if i == 0 {
+                embed.field("Decision", decision, false);
+            }
+            embed
+        })
+        .collect()
+}
+
+/// Record a decision on the message of a component interaction and remove its components.
+pub async fn update_with_decision(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+    decision: &str,
+) {
+    let embeds = decided_embeds(&component.message, decision);
+
+    if let Err(err) = component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| d.set_embeds(embeds).components(|c| c))
+        })
+        .await
+    {
+        error!("Failed to update interaction message: {:?}", err);
+    }
+}
+
+/// Record a decision on a message whose component interaction was already responded to.
+pub async fn edit_with_decision(ctx: &serenity::Context, message: &Message, decision: &str) {
+    let embeds = decided_embeds(message, decision);
+
+    if let Err(err) = message
+        .channel_id
+        .edit_message(&ctx.http, message.id, |m| {
+            m.set_embeds(embeds).components(|c| c)
+        })
+        .await
+    {
+        error!("Failed to update message: {:?}", err);
+    }
+}
@@ -0,0 +1,330 @@
+use bson::{doc, Document};
+use chrono::Utc;
+use mongodb::options::UpdateModifications;
+use poise::serenity_prelude::{
+    ButtonStyle,
+    ChannelId,
+    GuildId,
+    MessageComponentInteraction,
+    ReactionType,
+    UserId,
+};
+use tracing::{debug, error, trace};
+
+use super::bot::get_data;
+use super::interaction::{respond_ephemeral, update_with_decision};
+use super::member_cache::fetch_member;
+use super::moderation::{is_administrator, mute_member, unmute_user};
+use super::time::parse_duration;
+use super::*;
+use crate::db::model::{JuryMute, JuryMuteStatus};
+use crate::model::application::Jury;
+use crate::Error;
+
+/// Whether a member holds one of the roles trusted to vote.
+fn is_trusted(jury: &Jury, roles: &[RoleId]) -> bool {
+    roles.iter().any(|r| jury.trusted_roles.contains(&r.0))
+}
+
+/// Collect the trusted members which voted with the configured emoji, excluding the author.
+async fn collect_voters(
+    ctx: &serenity::Context,
+    jury: &Jury,
+    message: &serenity::Message,
+    guild_id: GuildId,
+    emoji: &ReactionType,
+) -> Result<Vec<UserId>, Error> {
+    let users = message
+        .reaction_users(&ctx.http, emoji.clone(), Some(100), None)
+        .await?;
+
+    let mut voters = Vec::new();
+    for user in users
+        .into_iter()
+        .filter(|u| !u.bot && u.id != message.author.id)
+    {
+        if let Ok(member) = fetch_member(ctx, guild_id, user.id).await {
+            if is_trusted(jury, &member.roles) {
+                voters.push(user.id);
+            }
+        }
+    }
+
+    Ok(voters)
+}
+
+/// Mute the author of a message once enough trusted members voted for it with the configured emoji.
+pub async fn handle_jury_reaction(ctx: &serenity::Context, reaction: &serenity::Reaction) {
+    let guild_id = match reaction.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    let data = get_data(ctx).await;
+    let jury = &data.configuration.jury;
+    if !jury.enabled || jury.votes == 0 {
+        return;
+    }
+
+    if jury.emoji.parse::<ReactionType>().ok().as_ref() != Some(&reaction.emoji) {
+        return;
+    }
+
+    // Votes of untrusted members never count, so there is no need to recount
+    if !reaction
+        .member
+        .as_ref()
+        .is_some_and(|m| is_trusted(jury, &m.roles))
+    {
+        return;
+    }
+
+    let message = match reaction.message(&ctx.http).await {
+        Ok(message) => message,
+        Err(err) => {
+            error!("Failed to fetch message for jury vote: {:?}", err);
+            return;
+        },
+    };
+    if message.author.bot {
+        return;
+    }
+
+    let voters = match collect_voters(ctx, jury, &message, guild_id, &reaction.emoji).await {
+        Ok(voters) if voters.len() >= jury.votes => voters,
+        Ok(_) => return,
+        Err(err) => {
+            error!("Failed to count jury votes: {:?}", err);
+            return;
+        },
+    };
+
+    let mut member = match fetch_member(ctx, guild_id, message.author.id).await {
+        Ok(member) => member,
+        Err(err) => {
+            debug!("Failed to find member for jury mute: {:?}", err);
+            return;
+        },
+    };
+    if is_administrator(&data.configuration.administrators, &member)
+        || is_trusted(jury, &member.roles)
+    {
+        return;
+    }
+
+    // The message id is unique, so only the first vote reaching the threshold mutes
+    if data
+        .database
+        .insert::<JuryMute>(
+            "jury_mutes",
+            JuryMute {
+                message_id: Some(message.id.to_string()),
+                channel_id: Some(message.channel_id.to_string()),
+                guild_id: Some(guild_id.to_string()),
+                user_id: Some(message.author.id.to_string()),
+                content: Some(message.content.clone()),
+                voters: Some(voters.iter().map(|v| v.to_string()).collect()),
+                status: Some(JuryMuteStatus::Pending),
+                timestamp: Some(Utc::now().timestamp()),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .is_err()
+    {
+        trace!("Message {} was already judged", message.id);
+        return;
+    }
+
+    debug!(
+        "{} trusted members voted to mute {}",
+        voters.len(),
+        message.author.tag()
+    );
+
+    let duration = parse_duration(&jury.duration).unwrap_or_else(|| chrono::Duration::minutes(10));
+    let error = mute_member(
+        &ctx.http,
+        &data,
+        &mut member,
+        duration,
+        &format!("Jury mute: {} votes", voters.len()),
+        None,
+    )
+    .await;
+
+    if let Some(err) = &error {
+        error!("Failed to jury mute {}: {:?}", message.author.tag(), err);
+    }
+
+    if let Err(err) = ChannelId(jury.review_channel)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                let e = e
+                    .title(format!("Jury muted {}", message.author.tag()))
+                    .description(&message.content)
+                    .field("User", format!("<@{}>", message.author.id), true)
+                    .field("Duration", &jury.duration, true)
+                    .field(
+                        "Reference",
+                        format!("[Jump to message]({})", message.link()),
+                        true,
+                    )
+                    .field(
+                        "Voters",
+                        voters
+                            .iter()
+                            .map(|v| format!("<@{}>", v))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        false,
+                    )
+                    .thumbnail(message.author.face())
+                    .color(data.configuration.general.embed_color);
+
+                if let Some(err) = &error {
+                    e.field("Error", err.user_message(), false);
+                }
+
+                e
+            })
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id(format!("jury:uphold:{}", message.id))
+                            .label("Uphold")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|b| {
+                        b.custom_id(format!("jury:revert:{}", message.id))
+                            .label("Revert")
+                            .style(ButtonStyle::Danger)
+                    })
+                })
+            })
+        })
+        .await
+    {
+        error!("Failed to log jury mute: {:?}", err);
+    }
+}
+
+/// Set the status of a pending jury mute, returning it if it was still pending.
+async fn judge(
+    ctx: &serenity::Context,
+    message_id: &str,
+    status: JuryMuteStatus,
+    moderator_id: UserId,
+) -> Result<Option<JuryMute>, Error> {
+    let data = get_data(ctx).await;
+
+    let updated: Document = JuryMute {
+        status: Some(status),
+        moderator_id: Some(moderator_id.to_string()),
+        ..Default::default()
+    }
+    .into();
+
+    let result = data
+        .database
+        .update::<JuryMute>(
+            "jury_mutes",
+            // Only review a jury mute once, even if both buttons are clicked at the same time
+            doc! { "message_id": message_id, "status": "pending" },
+            UpdateModifications::Document(doc! { "$set": updated }),
+            None,
+        )
+        .await?;
+
+    if result.modified_count == 0 {
+        return Ok(None);
+    }
+
+    let mut cursor = data
+        .database
+        .find::<JuryMute>(
+            "jury_mutes",
+            JuryMute {
+                message_id: Some(message_id.to_string()),
+                ..Default::default()
+            }
+            .into(),
+            None,
+        )
+        .await?;
+
+    Ok(match cursor.advance().await? {
+        true => Some(cursor.deserialize_current()?),
+        false => None,
+    })
+}
+
+/// Unmute the member of a reverted jury mute.
+async fn revert(ctx: &serenity::Context, jury_mute: &JuryMute) -> Result<(), Error> {
+    let guild_id = GuildId(jury_mute.guild_id.as_ref().ok_or("No guild")?.parse()?);
+    let user_id = UserId(jury_mute.user_id.as_ref().ok_or("No user")?.parse()?);
+
+    unmute_user(ctx, guild_id, user_id).await
+}
+
+/// Handle the Uphold and Revert buttons of jury mutes.
+pub async fn handle_jury_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    let mut parts = component.data.custom_id.splitn(3, ':').skip(1);
+    let (decision, message_id) = match (parts.next(), parts.next()) {
+        (Some(decision), Some(message_id)) => (decision, message_id),
+        _ => return,
+    };
+
+    {
+        let data = get_data(ctx).await;
+        let is_allowed = component
+            .member
+            .as_ref()
+            .is_some_and(|m| is_administrator(&data.configuration.administrators, m));
+
+        if !is_allowed {
+            drop(data);
+            respond_ephemeral(ctx, component, "You do not have permission to do this.").await;
+            return;
+        }
+    }
+
+    let status = match decision {
+        "uphold" => JuryMuteStatus::Upheld,
+        "revert" => JuryMuteStatus::Reverted,
+        _ => return,
+    };
+
+    let jury_mute = match judge(ctx, message_id, status, component.user.id).await {
+        Ok(Some(jury_mute)) => jury_mute,
+        Ok(None) => {
+            respond_ephemeral(ctx, component, "This jury mute was already reviewed.").await;
+            return;
+        },
+        Err(err) => {
+            error!("Failed to review jury mute: {:?}", err);
+            respond_ephemeral(ctx, component, "Failed to review this jury mute.").await;
+            return;
+        },
+    };
+
+    let decision = match status {
+        JuryMuteStatus::Reverted => match revert(ctx, &jury_mute).await {
+            Ok(_) => format!("Reverted by <@{}>", component.user.id),
+            Err(err) => {
+                error!("Failed to revert jury mute: {:?}", err);
+                format!(
+                    "Reverted by <@{}>, but failed to unmute: {}",
+                    component.user.id, err
+                )
+            },
+        },
+        _ => format!("Upheld by <@{}>", component.user.id),
+    };
+
+    update_with_decision(ctx, component, &decision).await;
+}
@@ -0,0 +1,245 @@
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::ChannelId;
+use regex::Regex;
+use reqwest::Url;
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::moderation::moderation_buttons;
+use super::qr_code::is_blocked;
+use super::{dry_run, *};
+use crate::model::application::LogCategory;
+
+// Shorteners which take longer than this to redirect are checked without being expanded
+const EXPAND_TIMEOUT: Duration = Duration::from_secs(10);
+// Keeps messages full of links from sending a request for each of them
+const MAX_LINKS: usize = 5;
+
+// Links wrapped in angle brackets have no embed, so they are left alone
+static URL: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|[^<])(https?://[^\s<>]+)").unwrap());
+
+/// Follow the redirects of a shortened url without downloading what it leads to.
+async fn expand(url: &Url) -> Url {
+    let client = match reqwest::Client::builder().timeout(EXPAND_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(_) => return url.clone(),
+    };
+
+    match client.head(url.clone()).send().await {
+        Ok(response) => response.url().clone(),
+        Err(err) => {
+            debug!("Failed to expand {}: {:?}", url, err);
+            url.clone()
+        },
+    }
+}
+
+/// Whether a query parameter is a tracking parameter. Names ending with `*` match prefixes.
+fn is_tracking(parameters: &[String], name: &str) -> bool {
+    parameters
+        .iter()
+        .any(|parameter| match parameter.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == parameter,
+        })
+}
+
+/// Remove the tracking parameters from the query of a url.
+fn strip_tracking(parameters: &[String], url: &Url) -> Url {
+    let pairs = url
+        .query_pairs()
+        .filter(|(name, _)| !is_tracking(parameters, name))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    // Keep the original encoding of urls without tracking parameters
+    if pairs.len() == url.query_pairs().count() {
+        return url.clone();
+    }
+
+    let mut stripped = url.clone();
+    if pairs.is_empty() {
+        stripped.set_query(None);
+    } else {
+        stripped.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    stripped
+}
+
+/// Delete a message with a link to a blocked domain and log it.
+/// Returns whether the message was deleted.
+async fn block(
+    ctx: &serenity::Context,
+    new_message: &serenity::Message,
+    url: &Url,
+    expanded: &Url,
+) -> bool {
+    debug!("{} posted a link to {}", new_message.author.tag(), expanded);
+
+    if let Err(err) = dry_run::execute(
+        format!("delete message {}", new_message.id),
+        new_message.delete(&ctx.http),
+    )
+    .await
+    {
+        error!("Failed to delete blocked link: {:?}", err);
+        return false;
+    }
+
+    let data = get_data(ctx).await;
+    if let Err(err) = ChannelId(data.configuration.log_channel(LogCategory::Moderation))
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                let e = e
+                    .title("Blocked link")
+                    .description(&new_message.content)
+                    .field("User", format!("<@{}>", new_message.author.id), true)
+                    .field("Channel", format!("<#{}>", new_message.channel_id), true)
+                    .field("URL", format!("`{}`", url), false)
+                    .thumbnail(new_message.author.face())
+                    .color(data.configuration.general.embed_color);
+
+                if expanded != url {
+                    e.field("Redirects to", format!("`{}`", expanded), false);
+                }
+
+                e
+            })
+            .components(|c| moderation_buttons(c, new_message.author.id))
+        })
+        .await
+    {
+        error!("Failed to log blocked link: {:?}", err);
+    }
+
+    true
+}
+
+/// Expand shortened links and strip tracking parameters from links. Messages with links leading
+/// to blocked domains are deleted, others can have their embeds suppressed and the cleaned links
+/// reposted. Returns whether the message was deleted.
+pub async fn clean_links(ctx: &serenity::Context, new_message: &serenity::Message) -> bool {
+    if new_message.guild_id.is_none() || new_message.author.bot {
+        return false;
+    }
+
+    let data = get_data(ctx).await;
+    let automod = &data.configuration.automod;
+    let links = &automod.links;
+    if !links.enabled {
+        return false;
+    }
+
+    if let Some(member) = &new_message.member {
+        if member
+            .roles
+            .iter()
+            .any(|r| automod.exempt_roles.contains(&r.0))
+        {
+            return false;
+        }
+    }
+
+    let mut cleaned = Vec::new();
+    for found in URL
+        .captures_iter(&new_message.content)
+        .filter_map(|c| c.get(1))
+        .take(MAX_LINKS)
+    {
+        // Punctuation after a link usually belongs to the sentence
+        let url = match Url::parse(found.as_str().trim_end_matches(['.', ',', ')', '!', '?'])) {
+            Ok(url) => url,
+            Err(_) => continue,
+        };
+
+        let expanded = if is_blocked(&links.shorteners, &url) {
+            expand(&url).await
+        } else {
+            url.clone()
+        };
+
+        // Phishing domains of QR codes are blocked in links as well
+        if is_blocked(&links.blocked_domains, &expanded)
+            || is_blocked(&data.configuration.qr_codes.domains, &expanded)
+        {
+            drop(data);
+            return block(ctx, new_message, &url, &expanded).await;
+        }
+
+        let stripped = strip_tracking(&links.tracking_parameters, &expanded);
+        if stripped != url {
+            cleaned.push(stripped);
+        }
+    }
+
+    if cleaned.is_empty() {
+        return false;
+    }
+
+    debug!(
+        "Cleaned {} links of {}",
+        cleaned.len(),
+        new_message.author.tag()
+    );
+
+    if links.suppress_embeds {
+        let mut message = new_message.clone();
+        if let Err(err) = dry_run::execute(
+            format!("suppress the embeds of message {}", message.id),
+            message.suppress_embeds(ctx),
+        )
+        .await
+        {
+            error!("Failed to suppress embeds: {:?}", err);
+        }
+    }
+
+    if links.repost {
+        if let Err(err) = new_message
+            .channel_id
+            .send_message(&ctx.http, |m| {
+                m.content(format!(
+                    "Cleaned links:\n{}",
+                    cleaned
+                        .iter()
+                        .map(Url::as_str)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ))
+                .reference_message(new_message)
+                .allowed_mentions(|a| a.empty_parse())
+            })
+            .await
+        {
+            error!("Failed to repost cleaned links: {:?}", err);
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_tracking_parameters() {
+        let parameters = vec!["utm_*".to_string(), "fbclid".to_string()];
+        let url = Url::parse("https://example.com/a?id=1&utm_source=x&fbclid=y").unwrap();
+        assert_eq!(
+            strip_tracking(&parameters, &url).as_str(),
+            "https://example.com/a?id=1"
+        );
+
+        let url = Url::parse("https://example.com/a?utm_medium=x").unwrap();
+        assert_eq!(
+            strip_tracking(&parameters, &url).as_str(),
+            "https://example.com/a"
+        );
+
+        // Untouched urls keep their encoding
+        let url = Url::parse("https://example.com/a?q=a%20b").unwrap();
+        assert_eq!(strip_tracking(&parameters, &url), url);
+    }
+}
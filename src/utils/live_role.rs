@@ -0,0 +1,114 @@
+use poise::serenity_prelude::{Activity, ActivityType, GuildId, UserId};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::{dry_run, *};
+use crate::model::application::LiveRole;
+
+// Platforms a streaming activity has to link to
+const STREAMING_PLATFORMS: [&str; 3] = ["twitch.tv", "youtube.com", "youtu.be"];
+
+fn is_live_activity(live_role: &LiveRole, activity: &Activity) -> bool {
+    if activity.kind != ActivityType::Streaming {
+        return false;
+    }
+
+    let is_supported_platform = activity.url.as_ref().is_some_and(|url| {
+        url.host_str()
+            .is_some_and(|host| STREAMING_PLATFORMS.iter().any(|p| host.ends_with(p)))
+    });
+    if !is_supported_platform {
+        return false;
+    }
+
+    if live_role.keywords.is_empty() {
+        return true;
+    }
+
+    let text = [
+        Some(&activity.name),
+        activity.details.as_ref(),
+        activity.state.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|t| t.to_lowercase())
+    .collect::<Vec<_>>()
+    .join(" ");
+
+    live_role
+        .keywords
+        .iter()
+        .any(|keyword| text.contains(&keyword.to_lowercase()))
+}
+
+/// Grant or remove the live role depending on whether the member streams in voice
+/// or has a matching streaming presence.
+pub async fn update_live_role(ctx: &serenity::Context, guild_id: GuildId, user_id: UserId) {
+    let data = get_data(ctx).await;
+    let live_role = &data.configuration.live_role;
+
+    if live_role.role == 0 {
+        return;
+    }
+
+    let (is_live, has_role) = match ctx.cache.guild(guild_id) {
+        Some(guild) => {
+            let is_streaming_in_voice = guild
+                .voice_states
+                .get(&user_id)
+                .is_some_and(|state| state.channel_id.is_some() && state.self_stream == Some(true));
+            let has_live_presence = guild.presences.get(&user_id).is_some_and(|presence| {
+                presence
+                    .activities
+                    .iter()
+                    .any(|activity| is_live_activity(live_role, activity))
+            });
+            let has_role = guild
+                .members
+                .get(&user_id)
+                .is_some_and(|member| member.roles.contains(&RoleId(live_role.role)));
+
+            (is_streaming_in_voice || has_live_presence, has_role)
+        },
+        None => return,
+    };
+
+    if is_live == has_role {
+        return;
+    }
+
+    debug!(
+        "{} the live role of {}",
+        if is_live { "Granting" } else { "Removing" },
+        user_id
+    );
+
+    let result = if is_live {
+        dry_run::execute(
+            format!("grant the live role to {}", user_id),
+            ctx.http.add_member_role(
+                guild_id.0,
+                user_id.0,
+                live_role.role,
+                Some("Started streaming"),
+            ),
+        )
+        .await
+    } else {
+        dry_run::execute(
+            format!("remove the live role from {}", user_id),
+            ctx.http.remove_member_role(
+                guild_id.0,
+                user_id.0,
+                live_role.role,
+                Some("Stopped streaming"),
+            ),
+        )
+        .await
+    };
+
+    if let Err(err) = result {
+        error!("Failed to update the live role of {}: {:?}", user_id, err);
+    }
+}
@@ -0,0 +1,158 @@
+use bson::doc;
+use chrono::Utc;
+use mongodb::options::UpdateModifications;
+use poise::serenity_prelude::{ChannelId, ChannelType, GuildId, UserId};
+
+use super::bot::get_data;
+use super::moderation::{lock_channel, unlock_channel};
+use super::*;
+use crate::db::model::Lockdown;
+use crate::error::BotError;
+use crate::model::application;
+
+/// The outcome of locking or unlocking each channel of a lockdown.
+#[derive(Default)]
+pub struct LockdownResult {
+    pub succeeded: Vec<ChannelId>,
+    pub failures: Vec<String>,
+}
+
+/// The text channels of the lockdown category and the additionally configured channels.
+fn lockdown_channels(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    lockdown: &application::Lockdown,
+) -> Vec<ChannelId> {
+    let mut channel_ids = if lockdown.category == 0 {
+        Vec::new()
+    } else {
+        ctx.cache
+            .guild_channels(guild_id)
+            .map(|channels| {
+                channels
+                    .iter()
+                    .filter(|c| {
+                        c.parent_id == Some(ChannelId(lockdown.category))
+                            && matches!(c.kind, ChannelType::Text | ChannelType::News)
+                    })
+                    .map(|c| c.id)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    channel_ids.extend(lockdown.channels.iter().map(|id| ChannelId(*id)));
+    channel_ids.sort();
+    channel_ids.dedup();
+    channel_ids
+}
+
+/// Lock all channels of the lockdown, remembering which ones to unlock when it ends.
+/// Channels which were locked before are left alone.
+pub async fn start_lockdown(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    moderator_id: UserId,
+) -> Result<LockdownResult, BotError> {
+    let data = get_data(ctx).await;
+    let database = &data.database;
+
+    let channel_ids = lockdown_channels(ctx, guild_id, &data.configuration.lockdown);
+    if channel_ids.is_empty() {
+        return Err(BotError::Config(
+            "No channels are configured for a lockdown.".to_string(),
+        ));
+    }
+
+    // The guild id is unique, so a guild can only be locked down once at a time
+    database
+        .insert::<Lockdown>(
+            "lockdowns",
+            Lockdown {
+                guild_id: Some(guild_id.to_string()),
+                moderator_id: Some(moderator_id.to_string()),
+                timestamp: Some(Utc::now().timestamp()),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .map_err(|_| BotError::Conflict("A lockdown is already in progress.".to_string()))?;
+
+    let mut result = LockdownResult::default();
+    for channel_id in channel_ids {
+        let channel = match ctx.cache.guild_channel(channel_id) {
+            Some(channel) => channel,
+            None => {
+                result
+                    .failures
+                    .push(format!("<#{}>: not found", channel_id));
+                continue;
+            },
+        };
+
+        match lock_channel(&ctx.http, database, &channel).await {
+            Some(err) => result
+                .failures
+                .push(format!("<#{}>: {}", channel_id, err.user_message())),
+            None => result.succeeded.push(channel_id),
+        }
+    }
+
+    let locked_channels = result
+        .succeeded
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>();
+    database
+        .update::<Lockdown>(
+            "lockdowns",
+            doc! { "guild_id": guild_id.to_string() },
+            UpdateModifications::Document(doc! { "$set": { "locked_channels": locked_channels } }),
+            None,
+        )
+        .await
+        .map_err(BotError::Database)?;
+
+    Ok(result)
+}
+
+/// Unlock the channels locked by the lockdown, restoring their original permission overwrites.
+pub async fn end_lockdown(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+) -> Result<LockdownResult, BotError> {
+    let data = get_data(ctx).await;
+    let database = &data.database;
+
+    let lockdown = database
+        .find_and_delete::<Lockdown>("lockdowns", doc! { "guild_id": guild_id.to_string() }, None)
+        .await
+        .map_err(BotError::Database)?
+        .ok_or_else(|| BotError::NotFound("No lockdown is in progress.".to_string()))?;
+
+    let mut result = LockdownResult::default();
+    for channel_id in lockdown.locked_channels.unwrap_or_default() {
+        let channel = match channel_id
+            .parse::<u64>()
+            .ok()
+            .and_then(|id| ctx.cache.guild_channel(id))
+        {
+            Some(channel) => channel,
+            None => {
+                result
+                    .failures
+                    .push(format!("<#{}>: not found", channel_id));
+                continue;
+            },
+        };
+
+        match unlock_channel(&ctx.http, database, &channel).await {
+            Some(err) => result
+                .failures
+                .push(format!("<#{}>: {}", channel_id, err.user_message())),
+            None => result.succeeded.push(channel.id),
+        }
+    }
+
+    Ok(result)
+}
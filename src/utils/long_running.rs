@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+
+use poise::serenity_prelude::CreateEmbed;
+use poise::ReplyHandle;
+
+use crate::{Context, Error};
+
+// Editing the response more often than this runs into rate limits
+const UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The response of a command which can take longer than Discord waits for a response.
+/// The interaction is deferred and the response is edited with the progress of the operation.
+pub struct LongRunning<'a> {
+    ctx: Context<'a>,
+    handle: ReplyHandle<'a>,
+    title: String,
+    embed_color: i32,
+    last_update: Instant,
+}
+
+impl<'a> LongRunning<'a> {
+    /// Defer the interaction and respond with the title of the operation.
+    pub async fn start(
+        ctx: Context<'a>,
+        title: impl Into<String>,
+        ephemeral: bool,
+    ) -> Result<LongRunning<'a>, Error> {
+        if ephemeral {
+            ctx.defer_ephemeral().await?;
+        } else {
+            ctx.defer().await?;
+        }
+
+        let title = title.into();
+        let embed_color = ctx.data().load_full().configuration.general.embed_color;
+        let handle = ctx
+            .send(|f| {
+                f.ephemeral(ephemeral).embed(|e| {
+                    e.title(&title)
+                        .description("Starting...")
+                        .color(embed_color)
+                })
+            })
+            .await?;
+
+        Ok(Self::from_handle(ctx, handle, title))
+    }
+
+    /// Report progress in a response which was already sent, such as a confirmation prompt.
+    pub fn from_handle(
+        ctx: Context<'a>,
+        handle: ReplyHandle<'a>,
+        title: impl Into<String>,
+    ) -> LongRunning<'a> {
+        LongRunning {
+            ctx,
+            handle,
+            title: title.into(),
+            embed_color: ctx.data().load_full().configuration.general.embed_color,
+            last_update: Instant::now(),
+        }
+    }
+
+    /// The handle of the response, for example to get its message.
+    pub fn handle(&self) -> &ReplyHandle<'a> {
+        &self.handle
+    }
+
+    /// Show the progress of the operation. Updates within the update interval are skipped.
+    pub async fn progress(&mut self, status: impl Into<String>) -> Result<(), Error> {
+        if self.last_update.elapsed() < UPDATE_INTERVAL {
+            return Ok(());
+        }
+        self.last_update = Instant::now();
+
+        let status = status.into();
+        self.handle
+            .edit(self.ctx, |f| {
+                f.embed(|e| {
+                    e.title(&self.title)
+                        .description(status)
+                        .color(self.embed_color)
+                })
+                .components(|c| c)
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Show the progress of the operation as done out of total items.
+    pub async fn progress_of(&mut self, done: usize, total: usize) -> Result<(), Error> {
+        self.progress(format!("{}/{}", done, total)).await
+    }
+
+    /// Replace the progress with the result of the operation.
+    pub async fn finish(
+        self,
+        build: impl FnOnce(&mut CreateEmbed) -> &mut CreateEmbed,
+    ) -> Result<(), Error> {
+        self.handle
+            .edit(self.ctx, |f| {
+                f.embed(|e| build(e.color(self.embed_color)))
+                    .components(|c| c)
+            })
+            .await?;
+
+        Ok(())
+    }
+}
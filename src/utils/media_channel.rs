@@ -1,6 +1,6 @@
 use tracing::error;
 
-use super::bot::get_data_lock;
+use super::bot::get_data;
 use super::*;
 
 pub async fn handle_media_channel(
@@ -9,9 +9,9 @@ pub async fn handle_media_channel(
 ) -> bool {
     let current_channel = new_message.channel_id.0;
 
-    let data_lock = get_data_lock(ctx).await;
+    let data = get_data(ctx).await;
 
-    let configuration = &data_lock.read().await.configuration;
+    let configuration = &data.configuration;
 
     let is_media_channel = configuration
         .general
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{ChunkGuildFilter, GuildId, Member, Mutex, UserId};
+use tracing::debug;
+
+use super::*;
+
+// How long members fetched over REST are kept. Members in the gateway cache are kept up to date
+// by events, but these are not, so they must expire
+const FALLBACK_TTL: Duration = Duration::from_secs(10 * 60);
+// The maximal amount of members fetched over REST to keep
+const FALLBACK_CAPACITY: usize = 1000;
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+// Members by guild and user with the time they were fetched
+type FetchedMembers = HashMap<(GuildId, UserId), (Member, Instant)>;
+
+// Members which were not in the gateway cache, such as before it has been warmed
+static FALLBACK: Lazy<Mutex<FetchedMembers>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Request all members of the guilds over the gateway, so they do not have to be fetched one by
+/// one later.
+pub fn warm(ctx: &serenity::Context, guilds: impl IntoIterator<Item = GuildId>) {
+    for guild_id in guilds {
+        debug!("Requesting members of guild {}", guild_id);
+        ctx.shard
+            .chunk_guild(guild_id, None, ChunkGuildFilter::None, None);
+    }
+}
+
+/// Get a member from the cache, falling back to fetching it over REST.
+pub async fn fetch_member(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> serenity::Result<Member> {
+    if let Some(member) = ctx.cache.member(guild_id, user_id) {
+        HITS.fetch_add(1, Ordering::Relaxed);
+        return Ok(member);
+    }
+
+    let mut fallback = FALLBACK.lock().await;
+    if let Some((member, fetched)) = fallback.get(&(guild_id, user_id)) {
+        if fetched.elapsed() < FALLBACK_TTL {
+            HITS.fetch_add(1, Ordering::Relaxed);
+            return Ok(member.clone());
+        }
+    }
+
+    MISSES.fetch_add(1, Ordering::Relaxed);
+    let member = ctx.http.get_member(guild_id.0, user_id.0).await?;
+
+    // Evict expired members first and the oldest member if there is still no room
+    fallback.retain(|_, (_, fetched)| fetched.elapsed() < FALLBACK_TTL);
+    if fallback.len() >= FALLBACK_CAPACITY {
+        if let Some(oldest) = fallback
+            .iter()
+            .min_by_key(|(_, (_, fetched))| *fetched)
+            .map(|(key, _)| *key)
+        {
+            fallback.remove(&oldest);
+        }
+    }
+    fallback.insert((guild_id, user_id), (member.clone(), Instant::now()));
+
+    Ok(member)
+}
+
+/// Drop a member fetched over REST, because it changed.
+pub async fn evict(guild_id: GuildId, user_id: UserId) {
+    FALLBACK.lock().await.remove(&(guild_id, user_id));
+}
+
+/// The amount of lookups which were served from the cache and which required a request.
+pub fn stats() -> (u64, u64) {
+    (HITS.load(Ordering::Relaxed), MISSES.load(Ordering::Relaxed))
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{ChannelId, GuildId, Member, Mutex, User};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::*;
+use crate::model::application::{Configuration, LogCategory};
+
+// The uses of the invites of each guild, to find out which invite a member joined with
+static INVITE_USES: Lazy<Mutex<HashMap<GuildId, HashMap<String, u64>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The channel joins and leaves are logged in, the member log by default.
+fn log_channel(configuration: &Configuration) -> ChannelId {
+    match configuration.member_log.channel {
+        0 => ChannelId(configuration.log_channel(LogCategory::Members)),
+        channel_id => ChannelId(channel_id),
+    }
+}
+
+/// Whether an account is younger than the given amount of days. Never if the amount is 0.
+fn is_new_account(created: i64, now: i64, days: i64) -> bool {
+    days > 0 && now - created < days * 24 * 60 * 60
+}
+
+/// The invite whose uses increased, if exactly one did.
+/// Invites created since the last snapshot count as increased if they were used.
+fn used_invite(before: &HashMap<String, u64>, after: &HashMap<String, u64>) -> Option<String> {
+    let mut increased = after
+        .iter()
+        .filter(|(code, &uses)| uses > before.get(*code).copied().unwrap_or_default());
+
+    match (increased.next(), increased.next()) {
+        (Some((code, _)), None) => Some(code.clone()),
+        _ => None,
+    }
+}
+
+/// Fetch the current uses of the invites of a guild.
+async fn fetch_invite_uses(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+) -> Option<HashMap<String, u64>> {
+    match guild_id.invites(&ctx.http).await {
+        Ok(invites) => Some(
+            invites
+                .into_iter()
+                .map(|invite| (invite.code, invite.uses))
+                .collect(),
+        ),
+        Err(err) => {
+            debug!("Failed to fetch the invites of {}: {:?}", guild_id, err);
+            None
+        },
+    }
+}
+
+/// Remember the uses of the invites of the guilds, so the invite of the next join can be resolved.
+pub async fn cache_invites(ctx: &serenity::Context, guild_ids: impl Iterator<Item = GuildId>) {
+    for guild_id in guild_ids {
+        if let Some(uses) = fetch_invite_uses(ctx, guild_id).await {
+            INVITE_USES.lock().await.insert(guild_id, uses);
+        }
+    }
+}
+
+/// Resolve the invite a member joined with and update the remembered uses.
+async fn resolve_invite(ctx: &serenity::Context, guild_id: GuildId) -> Option<String> {
+    let after = fetch_invite_uses(ctx, guild_id).await?;
+    let before = INVITE_USES.lock().await.insert(guild_id, after.clone())?;
+
+    used_invite(&before, &after)
+}
+
+/// Log a member joining with their account age and the invite they used, if it can be resolved.
+pub async fn log_join(ctx: &serenity::Context, new_member: &Member) {
+    if new_member.user.bot {
+        return;
+    }
+
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+
+    let created = new_member.user.created_at().unix_timestamp();
+    let is_new = is_new_account(
+        created,
+        Utc::now().timestamp(),
+        configuration.member_log.new_account_days,
+    );
+    let invite = resolve_invite(ctx, new_member.guild_id).await;
+
+    if let Err(err) = log_channel(configuration)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title(if is_new {
+                    "⚠️ Member joined with a new account"
+                } else {
+                    "Member joined"
+                })
+                .field("User", format!("<@{}>", new_member.user.id), true)
+                .field("Created", format!("<t:{}:R>", created), true)
+                .field(
+                    "Invite",
+                    invite.map_or("Unknown".to_string(), |code| format!("`{}`", code)),
+                    true,
+                )
+                .footer(|f| f.text(format!("ID: {}", new_member.user.id)))
+                .thumbnail(new_member.user.face())
+                .color(configuration.general.embed_color)
+            })
+        })
+        .await
+    {
+        error!("Failed to log member join: {:?}", err);
+    }
+}
+
+/// Log a member leaving with how long they were a member, if known.
+pub async fn log_leave(ctx: &serenity::Context, user: &User, member: Option<&Member>) {
+    if user.bot {
+        return;
+    }
+
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+
+    if let Err(err) = log_channel(configuration)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title("Member left")
+                    .field("User", format!("<@{}>", user.id), true)
+                    .field(
+                        "Created",
+                        format!("<t:{}:R>", user.created_at().unix_timestamp()),
+                        true,
+                    )
+                    .footer(|f| f.text(format!("ID: {}", user.id)))
+                    .thumbnail(user.face())
+                    .color(configuration.general.embed_color);
+
+                if let Some(joined_at) = member.and_then(|m| m.joined_at) {
+                    e.field(
+                        "Joined",
+                        format!("<t:{}:R>", joined_at.unix_timestamp()),
+                        true,
+                    );
+                }
+
+                e
+            })
+        })
+        .await
+    {
+        error!("Failed to log member leave: {:?}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_new_accounts() {
+        let day = 24 * 60 * 60;
+
+        assert!(is_new_account(0, day, 7));
+        assert!(!is_new_account(0, 8 * day, 7));
+        assert!(!is_new_account(0, day, 0));
+    }
+
+    #[test]
+    fn resolves_the_only_used_invite() {
+        let uses = |invites: &[(&str, u64)]| {
+            invites
+                .iter()
+                .map(|(code, uses)| (code.to_string(), *uses))
+                .collect::<HashMap<_, _>>()
+        };
+        let before = uses(&[("a", 1), ("b", 5)]);
+
+        assert_eq!(
+            used_invite(&before, &uses(&[("a", 2), ("b", 5)])),
+            Some("a".to_string())
+        );
+        assert_eq!(
+            used_invite(&before, &uses(&[("a", 1), ("b", 5), ("c", 1)])),
+            Some("c".to_string())
+        );
+        assert_eq!(used_invite(&before, &uses(&[("a", 2), ("b", 6)])), None);
+        assert_eq!(used_invite(&before, &before), None);
+    }
+}
@@ -0,0 +1,285 @@
+use poise::serenity_prelude::automod::{Action, Trigger};
+use poise::serenity_prelude::{ButtonStyle, ChannelId, GuildId, MessageComponentInteraction};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::interaction::{respond_ephemeral, update_with_decision};
+use super::moderation::is_administrator;
+use super::{dry_run, *};
+use crate::model::application::{LogCategory, MentionGuard};
+use crate::Error;
+
+// The name of the Discord AutoMod rule managed by the bot
+const AUTOMOD_RULE_NAME: &str = "Mass mention guard";
+
+fn keywords(guild_id: GuildId, mention_guard: &MentionGuard) -> Vec<String> {
+    let mut keywords = mention_guard
+        .roles
+        .iter()
+        .filter(|&&role| role != guild_id.0)
+        .map(|role| format!("<@&{}>", role))
+        .collect::<Vec<_>>();
+
+    if mention_guard.everyone {
+        keywords.push("@everyone".to_string());
+        keywords.push("@here".to_string());
+    }
+
+    keywords
+}
+
+/// Create or update the Discord AutoMod rule blocking mass mentions.
+pub async fn sync_automod_rule(ctx: &serenity::Context, guild_id: GuildId) {
+    let data = get_data(ctx).await;
+    let mention_guard = &data.configuration.mention_guard;
+
+    let keywords = keywords(guild_id, mention_guard);
+    if !mention_guard.enabled || keywords.is_empty() {
+        return;
+    }
+
+    let mut exempt_roles = mention_guard.exempt_roles.clone();
+    exempt_roles.extend(&data.configuration.administrators.roles);
+
+    let existing = match guild_id.automod_rules(&ctx.http).await {
+        Ok(rules) => rules.into_iter().find(|r| r.name == AUTOMOD_RULE_NAME),
+        Err(err) => {
+            error!("Failed to fetch AutoMod rules of {}: {:?}", guild_id, err);
+            return;
+        },
+    };
+
+    let result = match existing {
+        Some(rule) => {
+            guild_id
+                .edit_automod_rule(&ctx.http, rule.id, |r| {
+                    r.trigger(Trigger::Keyword(keywords))
+                        .exempt_roles(exempt_roles.into_iter().map(RoleId))
+                })
+                .await
+        },
+        None => {
+            guild_id
+                .create_automod_rule(&ctx.http, |r| {
+                    r.name(AUTOMOD_RULE_NAME)
+                        .trigger(Trigger::Keyword(keywords))
+                        .actions(vec![Action::BlockMessage])
+                        .exempt_roles(exempt_roles.into_iter().map(RoleId))
+                        .enabled(true)
+                })
+                .await
+        },
+    };
+
+    if let Err(err) = result {
+        error!("Failed to sync the AutoMod rule of {}: {:?}", guild_id, err);
+    }
+}
+
+/// Delete messages of non-exempt members mentioning guarded roles, in case AutoMod did not block them.
+/// Returns whether the message was deleted.
+pub async fn guard_mentions(ctx: &serenity::Context, new_message: &serenity::Message) -> bool {
+    let guild_id = match new_message.guild_id {
+        Some(guild_id) if !new_message.author.bot => guild_id,
+        _ => return false,
+    };
+
+    let data = get_data(ctx).await;
+    let mention_guard = &data.configuration.mention_guard;
+    if !mention_guard.enabled {
+        return false;
+    }
+
+    let mentions_guarded = (mention_guard.everyone && new_message.mention_everyone)
+        || new_message
+            .mention_roles
+            .iter()
+            .any(|r| mention_guard.roles.contains(&r.0));
+    if !mentions_guarded {
+        return false;
+    }
+
+    let administrators = &data.configuration.administrators;
+    let is_exempt = administrators.users.contains(&new_message.author.id.0)
+        || new_message.member.as_ref().is_some_and(|m| {
+            m.roles.iter().any(|r| {
+                mention_guard.exempt_roles.contains(&r.0) || administrators.roles.contains(&r.0)
+            })
+        });
+    if is_exempt {
+        return false;
+    }
+
+    debug!(
+        "{} mentioned a guarded role in {}",
+        new_message.author.tag(),
+        guild_id
+    );
+
+    if let Err(err) = dry_run::execute(
+        format!("delete message {}", new_message.id),
+        new_message.delete(&ctx.http),
+    )
+    .await
+    {
+        error!("Failed to delete mass mention: {:?}", err);
+        return false;
+    }
+
+    if let Err(err) = new_message
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.content(format!(
+                "<@{}>, please do not mention this role. Use `/pingrequest` to ask staff to \
+                 mention it for you.",
+                new_message.author.id
+            ))
+            .allowed_mentions(|a| a.users(vec![new_message.author.id]))
+        })
+        .await
+    {
+        error!("Failed to warn about mass mention: {:?}", err);
+    }
+
+    true
+}
+
+/// Queue a request to mention a guarded role for review by staff.
+pub async fn request_ping(
+    ctx: &serenity::Context,
+    channel_id: ChannelId,
+    requester: &serenity::User,
+    role: &serenity::Role,
+    message: &str,
+) -> Result<(), Error> {
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+    let mention_guard = &configuration.mention_guard;
+
+    let is_guarded = mention_guard.roles.contains(&role.id.0)
+        || (mention_guard.everyone && role.id.0 == role.guild_id.0);
+    if !is_guarded {
+        return Err(Error::from("This role can be mentioned without a request"));
+    }
+
+    let request_channel = if mention_guard.request_channel != 0 {
+        mention_guard.request_channel
+    } else {
+        configuration.log_channel(LogCategory::Moderation)
+    };
+
+    ChannelId(request_channel)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title("Ping request")
+                    .description(message)
+                    .field("Requested by", format!("<@{}>", requester.id), true)
+                    .field("Role", format!("<@&{}>", role.id), true)
+                    .field("Channel", format!("<#{}>", channel_id), true)
+                    .thumbnail(requester.face())
+                    .color(configuration.general.embed_color)
+            })
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id(format!(
+                            "pingrequest:approve:{}:{}:{}",
+                            channel_id, role.id, requester.id
+                        ))
+                        .label("Approve")
+                        .style(ButtonStyle::Success)
+                    })
+                    .create_button(|b| {
+                        b.custom_id(format!(
+                            "pingrequest:deny:{}:{}:{}",
+                            channel_id, role.id, requester.id
+                        ))
+                        .label("Deny")
+                        .style(ButtonStyle::Danger)
+                    })
+                })
+            })
+        })
+        .await?;
+
+    Ok(())
+}
+
+/// Handle the Approve and Deny buttons of ping requests.
+pub async fn handle_ping_request_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    let ids = component
+        .data
+        .custom_id
+        .split(':')
+        .skip(2)
+        .filter_map(|id| id.parse::<u64>().ok())
+        .collect::<Vec<_>>();
+    let (decision, channel_id, role_id, requester_id) =
+        match (component.data.custom_id.split(':').nth(1), ids.as_slice()) {
+            (Some(decision), &[channel_id, role_id, requester_id]) => {
+                (decision, channel_id, role_id, requester_id)
+            },
+            _ => return,
+        };
+
+    {
+        let data = get_data(ctx).await;
+        let is_allowed = component
+            .member
+            .as_ref()
+            .is_some_and(|m| is_administrator(&data.configuration.administrators, m));
+
+        if !is_allowed {
+            drop(data);
+            respond_ephemeral(ctx, component, "You do not have permission to do this.").await;
+            return;
+        }
+    }
+
+    let decision = match decision {
+        "approve" => {
+            let message = component
+                .message
+                .embeds
+                .first()
+                .and_then(|e| e.description.clone())
+                .unwrap_or_default();
+            let is_everyone = component.guild_id.is_some_and(|g| g.0 == role_id);
+            let mention = if is_everyone {
+                "@everyone".to_string()
+            } else {
+                format!("<@&{}>", role_id)
+            };
+
+            match ChannelId(channel_id)
+                .send_message(&ctx.http, |m| {
+                    m.content(format!(
+                        "{} {}\n- Requested by <@{}>",
+                        mention, message, requester_id
+                    ))
+                    .allowed_mentions(|a| {
+                        if is_everyone {
+                            a.parse(serenity::ParseValue::Everyone)
+                        } else {
+                            a.roles(vec![role_id])
+                        }
+                    })
+                })
+                .await
+            {
+                Ok(_) => format!("Approved by <@{}>", component.user.id),
+                Err(err) => format!(
+                    "Approved by <@{}>, but failed to send the ping: {}",
+                    component.user.id, err
+                ),
+            }
+        },
+        "deny" => format!("Denied by <@{}>", component.user.id),
+        _ => return,
+    };
+
+    update_with_decision(ctx, component, &decision).await;
+}
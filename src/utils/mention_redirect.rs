@@ -0,0 +1,116 @@
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::{dry_run, *};
+
+/// Redirect members mentioning opted-in developers in support channels to the support forum.
+/// Returns whether the message was deleted.
+pub async fn redirect_mentions(ctx: &serenity::Context, new_message: &serenity::Message) -> bool {
+    if new_message.guild_id.is_none() || new_message.author.bot {
+        return false;
+    }
+
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+    let mention_redirect = &configuration.mention_redirect;
+    if !mention_redirect.enabled {
+        return false;
+    }
+
+    let mentioned = new_message
+        .mentions
+        .iter()
+        .filter(|u| mention_redirect.users.contains(&u.id.0) && u.id != new_message.author.id)
+        .map(|u| u.id)
+        .collect::<Vec<_>>();
+    if mentioned.is_empty() {
+        return false;
+    }
+
+    // Threads belong to the support channel they were created in
+    let channels = if mention_redirect.channels.is_empty() {
+        &configuration.support.channels
+    } else {
+        &mention_redirect.channels
+    };
+    let parent_id = new_message
+        .channel_id
+        .to_channel_cached(&ctx.cache)
+        .and_then(|c| c.guild())
+        .and_then(|c| c.parent_id);
+    if !channels.contains(&new_message.channel_id.0)
+        && !parent_id.is_some_and(|p| channels.contains(&p.0))
+    {
+        return false;
+    }
+
+    let administrators = &configuration.administrators;
+    let is_exempt = administrators.users.contains(&new_message.author.id.0)
+        || new_message
+            .member
+            .as_ref()
+            .is_some_and(|m| m.roles.iter().any(|r| administrators.roles.contains(&r.0)));
+    if is_exempt {
+        return false;
+    }
+
+    debug!(
+        "{} mentioned {} developers in {}",
+        new_message.author.tag(),
+        mentioned.len(),
+        new_message.channel_id
+    );
+
+    let deleted = mention_redirect.delete
+        && match dry_run::execute(
+            format!("delete message {}", new_message.id),
+            new_message.delete(&ctx.http),
+        )
+        .await
+        {
+            Ok(_) => true,
+            Err(err) => {
+                error!("Failed to delete developer mention: {:?}", err);
+                false
+            },
+        };
+
+    let forum = if mention_redirect.forum != 0 {
+        format!("<#{}>", mention_redirect.forum)
+    } else {
+        "the support forum".to_string()
+    };
+
+    if let Err(err) = new_message
+        .channel_id
+        .send_message(&ctx.http, |m| {
+            m.content(format!(
+                "<@{}>, please do not mention developers for support. Ask in {} instead, where \
+                 everyone can help you.",
+                new_message.author.id, forum
+            ))
+            .allowed_mentions(|a| a.users(vec![new_message.author.id]));
+
+            // Repost the message without mentions, so the question is not lost
+            if deleted {
+                m.embed(|e| {
+                    e.description(new_message.content_safe(&ctx.cache))
+                        .author(|a| {
+                            a.name(new_message.author.tag())
+                                .icon_url(new_message.author.face())
+                        })
+                        .color(configuration.general.embed_color)
+                });
+            } else {
+                m.reference_message(new_message);
+            }
+
+            m
+        })
+        .await
+    {
+        error!("Failed to redirect developer mention: {:?}", err);
+    }
+
+    deleted
+}
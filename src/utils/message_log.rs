@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{
+    Action,
+    ChannelId,
+    GuildId,
+    Message,
+    MessageAction,
+    MessageId,
+    MessageUpdateEvent,
+    Mutex,
+    UserId,
+};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::*;
+use crate::db::model::{LoggedMessage, MessageEvent};
+use crate::model::application::{Configuration, LogCategory};
+use crate::Data;
+
+// The amount of recent audit log entries to consider for attribution
+const AUDIT_LOG_ENTRIES: u8 = 10;
+// The maximal age of a new audit log entry to be attributed to a deletion
+const MAX_ENTRY_AGE_SECS: i64 = 10;
+// Discord rejects embed fields which are longer than this
+const MAX_FIELD_LENGTH: usize = 1024;
+
+// Deletions of messages of the same author in the same channel by the same moderator are grouped
+// into one audit log entry with a count, so the last seen count of each entry is remembered
+static DELETION_COUNTS: Lazy<Mutex<HashMap<u64, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+enum Attribution {
+    Moderator(UserId),
+    Ambiguous,
+    Unknown,
+}
+
+/// Attribute the deletion of a message to a moderator by consulting the audit log.
+async fn attribute(ctx: &serenity::Context, guild_id: GuildId, message: &Message) -> Attribution {
+    let entries = match guild_id
+        .audit_logs(
+            &ctx.http,
+            Some(Action::Message(MessageAction::Delete).num()),
+            None,
+            None,
+            Some(AUDIT_LOG_ENTRIES),
+        )
+        .await
+    {
+        Ok(audit_logs) => audit_logs.entries,
+        Err(err) => {
+            debug!("Failed to fetch the audit log of {}: {:?}", guild_id, err);
+            return Attribution::Unknown;
+        },
+    };
+
+    let now = Utc::now().timestamp();
+    let mut counts = DELETION_COUNTS.lock().await;
+    let candidates = entries
+        .iter()
+        .filter(|entry| {
+            let options = entry.options.as_ref();
+            let count = options.and_then(|o| o.count).unwrap_or(1);
+            let is_target = entry.target_id == Some(message.author.id.0)
+                && options.and_then(|o| o.channel_id) == Some(message.channel_id);
+
+            let is_new = match counts.insert(entry.id.0, count) {
+                Some(previous) => count > previous,
+                None => now - entry.id.created_at().unix_timestamp() <= MAX_ENTRY_AGE_SECS,
+            };
+
+            is_target && is_new
+        })
+        .map(|entry| entry.user_id)
+        .collect::<Vec<_>>();
+
+    match candidates.as_slice() {
+        [moderator] => Attribution::Moderator(*moderator),
+        [] => Attribution::Unknown,
+        _ => Attribution::Ambiguous,
+    }
+}
+
+/// The channel edited and deleted messages are logged in, the message log by default.
+fn log_channel(configuration: &Configuration) -> ChannelId {
+    match configuration.message_log.channel {
+        0 => ChannelId(configuration.log_channel(LogCategory::Messages)),
+        channel_id => ChannelId(channel_id),
+    }
+}
+
+/// Store an edited or deleted message in the database, if enabled.
+async fn store_message(data: &Data, message: &Message, event: MessageEvent, content: &str) {
+    if !data.configuration.message_log.store {
+        return;
+    }
+
+    let logged_message = LoggedMessage {
+        guild_id: message.guild_id.map(|g| g.to_string()),
+        channel_id: Some(message.channel_id.to_string()),
+        message_id: Some(message.id.to_string()),
+        author_id: Some(message.author.id.to_string()),
+        event: Some(event),
+        content: Some(content.to_string()),
+        previous_content: match event {
+            MessageEvent::Edit => Some(message.content.clone()),
+            MessageEvent::Delete => None,
+        },
+        attachments: Some(message.attachments.iter().map(|a| a.url.clone()).collect()),
+        logged_at: Some(bson::DateTime::now()),
+    };
+
+    if let Err(err) = data
+        .database
+        .insert::<LoggedMessage>("message_log", logged_message, None)
+        .await
+    {
+        error!("Failed to store logged message: {:?}", err);
+    }
+}
+
+/// Log an edited message with its content before and after, if it was cached.
+pub async fn log_edited_message(
+    ctx: &serenity::Context,
+    old_if_available: Option<&Message>,
+    event: &MessageUpdateEvent,
+) {
+    let (Some(old), Some(content)) = (old_if_available, &event.content) else {
+        return;
+    };
+    // Embeds being resolved also edit a message
+    if old.author.bot || event.guild_id.is_none() || &old.content == content {
+        return;
+    }
+
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+
+    if let Err(err) = log_channel(configuration)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title("Message edited")
+                    .description(format!(
+                        "[Jump to the message](https://discord.com/channels/{}/{}/{})",
+                        event.guild_id.unwrap_or_default(),
+                        event.channel_id,
+                        event.id
+                    ))
+                    .field("Before", truncate(&old.content), false)
+                    .field("After", truncate(content), false)
+                    .field("Author", format!("<@{}>", old.author.id), true)
+                    .field("Channel", format!("<#{}>", event.channel_id), true)
+                    .thumbnail(old.author.face())
+                    .color(configuration.general.embed_color)
+            })
+        })
+        .await
+    {
+        error!("Failed to log edited message: {:?}", err);
+    }
+
+    store_message(&data, old, MessageEvent::Edit, content).await;
+}
+
+/// Shorten content to fit into an embed field, which can not be empty.
+fn truncate(content: &str) -> String {
+    if content.is_empty() {
+        return "*Empty*".to_string();
+    }
+    if content.chars().count() <= MAX_FIELD_LENGTH {
+        return content.to_string();
+    }
+    let mut truncated = content
+        .chars()
+        .take(MAX_FIELD_LENGTH - 1)
+        .collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+/// Log a deleted message to the message log, if it was cached.
+pub async fn log_deleted_message(
+    ctx: &serenity::Context,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    guild_id: Option<GuildId>,
+) {
+    let guild_id = match guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+    let message = match ctx.cache.message(channel_id, message_id) {
+        Some(message) if !message.author.bot => message,
+        _ => return,
+    };
+
+    let deleted_by = match attribute(ctx, guild_id, &message).await {
+        Attribution::Moderator(moderator) => format!("<@{}>", moderator),
+        Attribution::Ambiguous => "Ambiguous, multiple moderators deleted messages".to_string(),
+        // Discord does not log deletions by the author
+        Attribution::Unknown => "The author or unknown".to_string(),
+    };
+
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+
+    if let Err(err) = log_channel(configuration)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title("Message deleted")
+                    .description(&message.content)
+                    .field("Author", format!("<@{}>", message.author.id), true)
+                    .field("Channel", format!("<#{}>", channel_id), true)
+                    .field("Deleted by", deleted_by, true)
+                    .thumbnail(message.author.face())
+                    .color(configuration.general.embed_color);
+
+                if !message.attachments.is_empty() {
+                    e.field(
+                        "Attachments",
+                        message
+                            .attachments
+                            .iter()
+                            .map(|a| a.filename.clone())
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                        false,
+                    );
+                }
+
+                e
+            })
+        })
+        .await
+    {
+        error!("Failed to log deleted message: {:?}", err);
+    }
+
+    store_message(&data, &message, MessageEvent::Delete, &message.content).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates_content_to_a_field() {
+        assert_eq!(truncate(""), "*Empty*");
+        assert_eq!(truncate("hello"), "hello");
+
+        let truncated = truncate(&"é".repeat(2000));
+        assert_eq!(truncated.chars().count(), MAX_FIELD_LENGTH);
+        assert!(truncated.ends_with('…'));
+    }
+}
@@ -0,0 +1,183 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::Utc;
+use poise::serenity_prelude::{ChannelId, GuildId, RoleId, UserId};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::{dry_run, *};
+use crate::model::application::{MilestoneTier, Milestones};
+use crate::Error;
+
+// How often to check for members reaching a milestone
+const CHECK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+// The maximal amount of members which can be fetched at once
+const MAX_FETCH: u64 = 1000;
+const DEFAULT_MESSAGE: &str = "{user} has been a member for {name}!";
+
+static CHECK_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+/// The highest tier a member reached after being a member for the given amount of days.
+fn reached_tier(tiers: &[MilestoneTier], days: i64) -> Option<&MilestoneTier> {
+    tiers
+        .iter()
+        .filter(|t| t.days <= days)
+        .max_by_key(|t| t.days)
+}
+
+/// Announce that a member reached a milestone, if an announcement channel is configured.
+async fn announce(
+    ctx: &serenity::Context,
+    milestones: &Milestones,
+    user_id: UserId,
+    tier: &MilestoneTier,
+) {
+    if milestones.announcement_channel == 0 {
+        return;
+    }
+
+    let message = if milestones.message.is_empty() {
+        DEFAULT_MESSAGE
+    } else {
+        &milestones.message
+    };
+
+    if let Err(err) = ChannelId(milestones.announcement_channel)
+        .say(
+            &ctx.http,
+            message
+                .replace("{user}", &format!("<@{}>", user_id))
+                .replace("{name}", &tier.name)
+                .replace("{days}", &tier.days.to_string()),
+        )
+        .await
+    {
+        error!("Failed to announce milestone of {}: {:?}", user_id, err);
+    }
+}
+
+/// Give members the role of the highest milestone they reached and take the roles of lower ones.
+async fn grant_milestones(ctx: &serenity::Context, guild_id: GuildId) -> Result<(), Error> {
+    let data = get_data(ctx).await;
+    let milestones = &data.configuration.milestones;
+    let now = Utc::now().timestamp();
+
+    let mut after: Option<UserId> = None;
+    loop {
+        let page = guild_id.members(&ctx.http, Some(MAX_FETCH), after).await?;
+        let exhausted = (page.len() as u64) < MAX_FETCH;
+        after = page.last().map(|m| m.user.id);
+
+        for mut member in page.into_iter().filter(|m| !m.user.bot) {
+            let Some(joined_at) = member.joined_at else {
+                continue;
+            };
+            let days = (now - joined_at.unix_timestamp()) / (24 * 60 * 60);
+            let Some(tier) = reached_tier(&milestones.tiers, days) else {
+                continue;
+            };
+            if member.roles.contains(&RoleId(tier.role)) {
+                continue;
+            }
+
+            debug!("{} reached the milestone {}", member.user.tag(), tier.name);
+
+            if let Err(err) = dry_run::execute(
+                format!("give {} the milestone {}", member.user.tag(), tier.name),
+                member.add_role(&ctx.http, tier.role),
+            )
+            .await
+            {
+                error!(
+                    "Failed to give {} the milestone {}: {:?}",
+                    member.user.tag(),
+                    tier.name,
+                    err
+                );
+                continue;
+            }
+
+            let lower_roles = milestones
+                .tiers
+                .iter()
+                .filter(|t| t.role != tier.role && member.roles.contains(&RoleId(t.role)))
+                .map(|t| RoleId(t.role))
+                .collect::<Vec<_>>();
+            if !lower_roles.is_empty() {
+                if let Err(err) = dry_run::execute(
+                    format!("take lower milestones from {}", member.user.tag()),
+                    member.remove_roles(&ctx.http, &lower_roles),
+                )
+                .await
+                {
+                    error!(
+                        "Failed to take lower milestones from {}: {:?}",
+                        member.user.tag(),
+                        err
+                    );
+                }
+            }
+
+            announce(ctx, milestones, member.user.id, tier).await;
+        }
+
+        if exhausted {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check for members reaching a milestone once a day.
+pub fn schedule_milestones(ctx: &serenity::Context) {
+    // Ready is dispatched again when reconnecting
+    if CHECK_SCHEDULED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(CHECK_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+
+            let enabled = {
+                let data = get_data(&ctx).await;
+                data.configuration.milestones.enabled
+            };
+            if !enabled {
+                continue;
+            }
+
+            for guild_id in ctx.cache.guilds() {
+                if let Err(err) = grant_milestones(&ctx, guild_id).await {
+                    error!("Failed to grant milestones in {}: {:?}", guild_id, err);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tier(days: i64, role: u64) -> MilestoneTier {
+        MilestoneTier {
+            name: format!("{} days", days),
+            days,
+            role,
+        }
+    }
+
+    #[test]
+    fn finds_highest_reached_tier() {
+        let tiers = vec![tier(730, 2), tier(365, 1), tier(1095, 3)];
+
+        assert!(reached_tier(&tiers, 100).is_none());
+        assert_eq!(reached_tier(&tiers, 365).map(|t| t.role), Some(1));
+        assert_eq!(reached_tier(&tiers, 800).map(|t| t.role), Some(2));
+    }
+}
@@ -1,8 +1,64 @@
 use poise::serenity_prelude::{self as serenity, Member, RoleId};
 
+pub mod activity;
+pub mod alias;
+pub mod announcement;
+pub mod announcement_draft;
+pub mod appeal;
+pub mod audit;
+pub mod automod;
+pub mod autorespond;
+pub mod beta;
 pub mod bot;
+pub mod cases;
+pub mod choices;
+pub mod command_sync;
+pub mod config_audit;
+pub mod contributor_window;
+pub mod crosspost;
 pub mod decancer;
+pub mod dm_report;
+pub mod dry_run;
 pub mod embed;
+pub mod event_reminder;
+pub mod failures;
+pub mod giveaway;
+pub mod impersonation;
+pub mod interaction;
+pub mod jury;
+pub mod link_cleaner;
+pub mod live_role;
+pub mod lockdown;
+pub mod long_running;
+pub mod media_channel;
+pub mod member_cache;
+pub mod member_log;
+pub mod mention_guard;
+pub mod mention_redirect;
+pub mod message_log;
+pub mod milestones;
 pub mod moderation;
-pub mod autorespond;
-pub mod media_channel;
\ No newline at end of file
+pub mod monitor;
+pub mod notification;
+pub mod ocr;
+pub mod onboarding;
+pub mod paginator;
+pub mod panic;
+pub mod pin_vote;
+pub mod process;
+pub mod qr_code;
+pub mod reply;
+pub mod reply_draft;
+pub mod rules;
+pub mod slow_join;
+pub mod staff_report;
+pub mod storage;
+pub mod strikes;
+pub mod support;
+pub mod time;
+pub mod transcript;
+pub mod voice_log;
+pub mod voice_time;
+pub mod vouch;
+pub mod watchword;
+pub mod welcome;
@@ -1,34 +1,461 @@
 use std::cmp;
 use std::sync::Arc;
 
-use mongodb::options::FindOptions;
-use poise::serenity_prelude::{ChannelId, Http, User};
+use bson::{doc, Document};
+use chrono::{Duration, Utc};
+use mongodb::options::{FindOptions, UpdateModifications, UpdateOptions};
+use poise::serenity_prelude::http::request::RequestBuilder;
+use poise::serenity_prelude::http::routing::RouteInfo;
+use poise::serenity_prelude::json::JsonMap;
+use poise::serenity_prelude::{
+    ButtonStyle,
+    ChannelId,
+    CreateComponents,
+    CreateEmbed,
+    GuildChannel,
+    GuildId,
+    Http,
+    InteractionResponseType,
+    Message,
+    MessageComponentInteraction,
+    PermissionOverwrite,
+    PermissionOverwriteType,
+    Permissions,
+    SerenityError,
+    User,
+    UserId,
+};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde_json::{json, Value};
 use tokio::task::JoinHandle;
-use tracing::{debug, error};
+use tracing::{debug, error, trace, warn};
 
-use super::bot::get_data_lock;
-use super::*;
+use super::bot::get_data;
+use super::cases::{ban_reason, next_case_id, open_case, record_approver, record_case, CaseTarget};
+use super::failures::record_failure;
+use super::interaction::{edit_with_decision, respond_ephemeral, update_with_decision};
+use super::member_cache::fetch_member;
+use super::reply::reply_embed;
+use super::{dry_run, *};
 use crate::db::database::Database;
-use crate::db::model::Muted;
-use crate::model::application::Configuration;
-use crate::serenity::SerenityError;
-use crate::{Context, Error};
+use crate::db::model::{
+    FailedOperation,
+    InvitePause,
+    LockedChannel,
+    Muted,
+    SlowmodeReset,
+    TempBan,
+};
+use crate::error::BotError;
+use crate::model::application::{Administrators, BanApproval, Configuration, General, LogCategory};
+use crate::{Context, Data, Error};
 
 pub enum ModerationKind {
-    Mute(User, String, String, Option<Error>), // User, Reason, Expires, Error
-    Unmute(User, Option<Error>),               // User, Error
-    Ban(User, Option<String>, Option<SerenityError>), // User, Reason, Error
-    Unban(User, Option<SerenityError>),        // User, Error
-    Lock(String, Option<Error>),               // Channel name, Error
-    Unlock(String, Option<Error>),             // Channel name, Error
+    Mute(User, String, String, Option<BotError>), // User, Reason, Expires, Error
+    Unmute(User, Option<BotError>),               // User, Error
+    Ban(User, Option<String>, Option<i64>, Option<BotError>), // User, Reason, Expires, Error
+    Unban(User, Option<BotError>),                // User, Error
+    Lock(String, Option<BotError>),               // Channel name, Error
+    Unlock(String, Option<BotError>),             // Channel name, Error
+    LockVoice(String, usize, Option<BotError>),   // Channel name, Disconnected members, Error
+    UnlockVoice(String, Option<BotError>),        // Channel name, Error
+    Forget(User, Option<BotError>),               // User, Error
+    Warn(User, String, Option<BotError>),         // User, Reason, Error
+    Kick(User, Option<String>, Option<BotError>), // User, Reason, Error
+    Softban(User, Option<String>, Option<BotError>), // User, Reason, Error
+    Timeout(User, String, String, Option<BotError>), // User, Reason, Expires, Error
+    Purge(String, usize, Vec<String>, Option<BotError>), /* Channel name, Deleted messages, Filters, Error */
+    Panic(Vec<String>, Option<BotError>),                // Steps, Error
+    PanicEnd(Vec<String>, Option<BotError>),             // Steps, Error
+    PauseInvites(String, Option<BotError>),              // Expires, Error
+    ResumeInvites(Option<BotError>),                     // Error
+    ClearReactions(String, usize, Option<BotError>),     // Target, Removed reactions, Error
+    MassBan(Vec<UserId>, Vec<String>, String, Option<BotError>), /* Banned users, Failures, Reason, Error */
+    Punish(User, String, String, Option<BotError>),              // User, Reason, Step, Error
+    Lockdown(Vec<ChannelId>, Vec<String>, Option<BotError>), // Locked channels, Failures, Error
+    LockdownEnd(Vec<ChannelId>, Vec<String>, Option<BotError>), /* Unlocked channels, Failures, Error */
+    Slowmode(String, u64, Option<String>, Option<BotError>), /* Channel name, Seconds, Resets, Error */
+    VoiceKick(User, Option<String>, Option<BotError>),       // User, Reason, Error
+    VoiceMute(User, bool, Option<String>, Option<BotError>), // User, Muted, Reason, Error
 }
+impl ModerationKind {
+    pub fn error(&self) -> Option<&BotError> {
+        match self {
+            ModerationKind::Mute(.., error)
+            | ModerationKind::Timeout(.., error)
+            | ModerationKind::Unmute(_, error)
+            | ModerationKind::Ban(.., error)
+            | ModerationKind::Unban(_, error)
+            | ModerationKind::Lock(_, error)
+            | ModerationKind::Unlock(_, error)
+            | ModerationKind::LockVoice(.., error)
+            | ModerationKind::UnlockVoice(_, error)
+            | ModerationKind::Forget(_, error)
+            | ModerationKind::Warn(.., error)
+            | ModerationKind::Kick(.., error)
+            | ModerationKind::Softban(.., error)
+            | ModerationKind::Purge(.., error)
+            | ModerationKind::Panic(_, error)
+            | ModerationKind::PanicEnd(_, error)
+            | ModerationKind::PauseInvites(_, error)
+            | ModerationKind::ResumeInvites(error)
+            | ModerationKind::ClearReactions(.., error)
+            | ModerationKind::MassBan(.., error)
+            | ModerationKind::Punish(.., error)
+            | ModerationKind::Lockdown(.., error)
+            | ModerationKind::LockdownEnd(.., error)
+            | ModerationKind::Slowmode(.., error)
+            | ModerationKind::VoiceKick(.., error)
+            | ModerationKind::VoiceMute(.., error) => error.as_ref(),
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        self.error().is_some()
+    }
+
+    /// The color of embeds about this action.
+    pub fn color(&self, general: &General) -> i32 {
+        let colors = &general.colors;
+        if self.is_failure() {
+            return colors.failure.unwrap_or(general.embed_color);
+        }
+
+        match self {
+            ModerationKind::Mute(..)
+            | ModerationKind::Timeout(..)
+            | ModerationKind::VoiceMute(_, true, ..) => colors.mute,
+            ModerationKind::Unmute(..) | ModerationKind::VoiceMute(_, false, ..) => colors.unmute,
+            ModerationKind::Ban(..) | ModerationKind::Softban(..) | ModerationKind::MassBan(..) => {
+                colors.ban
+            },
+            ModerationKind::Unban(..) => colors.unban,
+            ModerationKind::Slowmode(_, 0, ..) => colors.unlock,
+            ModerationKind::Lock(..)
+            | ModerationKind::LockVoice(..)
+            | ModerationKind::Slowmode(..)
+            | ModerationKind::Panic(..)
+            | ModerationKind::Lockdown(..)
+            | ModerationKind::PauseInvites(..) => colors.lock,
+            ModerationKind::Unlock(..)
+            | ModerationKind::UnlockVoice(..)
+            | ModerationKind::PanicEnd(..)
+            | ModerationKind::LockdownEnd(..)
+            | ModerationKind::ResumeInvites(..) => colors.unlock,
+            ModerationKind::Forget(..) => colors.forget,
+            ModerationKind::Warn(..) => colors.warn,
+            ModerationKind::Kick(..) | ModerationKind::VoiceKick(..) => colors.kick,
+            ModerationKind::Purge(..)
+            | ModerationKind::ClearReactions(..)
+            | ModerationKind::Punish(..) => None,
+        }
+        .or(colors.success)
+        .unwrap_or(general.embed_color)
+    }
+}
+
+// Discord rejects timeouts which last longer than this
+const MAX_TIMEOUT_DAYS: i64 = 28;
+// Discord rejects embed fields which are longer than this
+const MAX_FIELD_LENGTH: usize = 1024;
+// The guild feature which pauses all invites
+pub const INVITES_DISABLED: &str = "INVITES_DISABLED";
+// Discord truncates audit log reasons which are longer than this
+const MAX_AUDIT_LOG_REASON_LENGTH: usize = 512;
+
 pub enum BanKind {
-    Ban(User, Option<u8>, Option<String>), // User, Amount of days to delete messages, Reason
-    Unban(User),                           // User
+    // User, Amount of days to delete messages, Reason, Expires
+    Ban(User, Option<u8>, Option<String>, Option<i64>),
+    Unban(User), // User
+    // User, Amount of days to delete messages, Reason
+    Softban(User, Option<u8>, Option<String>),
+}
+/// Check that both the moderator and the bot rank above a member, like Discord does.
+/// The owner of the guild ranks above everyone.
+async fn check_hierarchy(
+    ctx: &serenity::Context,
+    moderator: &Member,
+    member: &Member,
+) -> Option<BotError> {
+    // Fetch what is missing from the cache, so the check never passes for lack of information
+    let owner_id = match ctx.cache.guild_field(member.guild_id, |g| g.owner_id) {
+        Some(owner_id) => owner_id,
+        None => match member.guild_id.to_partial_guild(&ctx.http).await {
+            Ok(guild) => guild.owner_id,
+            Err(err) => return Some(err.into()),
+        },
+    };
+    if member.user.id == owner_id {
+        return Some(BotError::Permission(
+            "The owner of the server can not be moderated.".to_string(),
+        ));
+    }
+
+    let position = |m: &Member| m.highest_role_info(&ctx.cache).map_or(0, |(_, p)| p);
+    let target = position(member);
+
+    if moderator.user.id != owner_id && position(moderator) <= target {
+        return Some(BotError::Permission(
+            "Your highest role is not above the highest role of this member.".to_string(),
+        ));
+    }
+
+    let bot = match fetch_member(ctx, member.guild_id, ctx.cache.current_user_id()).await {
+        Ok(bot) => bot,
+        Err(err) => return Some(err.into()),
+    };
+    if position(&bot) <= target {
+        return Some(BotError::Permission(
+            "The role of the bot is not high enough to do this.".to_string(),
+        ));
+    }
+
+    None
+}
+
+/// Check that a moderator may moderate a user of a guild.
+/// The bot can not moderate itself, and users who are not members have no roles to compare.
+async fn check_moderator(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    moderator: Option<&Member>,
+    target: UserId,
+) -> Option<BotError> {
+    if target == ctx.cache.current_user_id() {
+        return Some(BotError::Permission(
+            "The bot can not moderate itself.".to_string(),
+        ));
+    }
+
+    let member = fetch_member(ctx, guild_id, target).await.ok()?;
+    let moderator = match moderator {
+        Some(moderator) => moderator,
+        None => {
+            return Some(BotError::NotFound(
+                "Failed to fetch your member.".to_string(),
+            ))
+        },
+    };
+
+    check_hierarchy(ctx, moderator, &member).await
+}
+
+/// Check that the invoking moderator may moderate a user, before muting, banning or warning them.
+pub async fn can_moderate(ctx: &Context<'_>, target: UserId) -> Option<BotError> {
+    let guild_id = ctx.guild_id()?;
+    let moderator = ctx.author_member().await;
+
+    check_moderator(ctx.discord(), guild_id, moderator.as_deref(), target).await
+}
+
+pub fn is_administrator(administrators: &Administrators, member: &Member) -> bool {
+    // Check if the user is an administrator
+    administrators.users.contains(&member.user.id.0)
+        // Has one of the administative roles
+        || member
+            .roles
+            .iter()
+            .any(|role| administrators.roles.contains(&role.0))
+}
+
+/// Truncate a reason to the length Discord keeps in the audit log.
+pub fn audit_log_reason(reason: &str) -> String {
+    reason.chars().take(MAX_AUDIT_LOG_REASON_LENGTH).collect()
+}
+
+/// Percent-encode a reason, because headers can only contain visible ASCII characters.
+fn encode_audit_log_reason(reason: &str) -> String {
+    audit_log_reason(reason)
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() {
+                (b as char).to_string()
+            } else {
+                format!("%{:02X}", b)
+            }
+        })
+        .collect()
+}
+
+fn audit_log_headers(reason: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&encode_audit_log_reason(reason)) {
+        headers.insert("X-Audit-Log-Reason", value);
+    }
+    headers
+}
+
+/// Ban a user with the reason shown in the audit log.
+pub async fn ban_with_audit_reason(
+    http: &Http,
+    guild_id: GuildId,
+    user_id: UserId,
+    delete_message_days: u8,
+    reason: &str,
+) -> Result<(), SerenityError> {
+    http.ban_user(
+        guild_id.0,
+        user_id.0,
+        cmp::min(delete_message_days, 7),
+        &audit_log_reason(reason),
+    )
+    .await
 }
+
+/// Unban a user with the reason shown in the audit log.
+pub async fn unban_with_audit_reason(
+    http: &Http,
+    guild_id: GuildId,
+    user_id: UserId,
+    reason: &str,
+) -> Result<(), SerenityError> {
+    http.remove_ban(guild_id.0, user_id.0, Some(&audit_log_reason(reason)))
+        .await
+}
+
+/// Replace the roles of a member in a single request, with the reason shown in the audit log.
+async fn set_roles_with_audit_reason(
+    http: &Http,
+    member: &mut Member,
+    roles: Vec<RoleId>,
+    reason: &str,
+) -> Result<(), SerenityError> {
+    let mut map = JsonMap::new();
+    map.insert(
+        "roles".to_string(),
+        roles.iter().map(|r| r.0.to_string()).collect(),
+    );
+
+    let edited = http
+        .edit_member(
+            member.guild_id.0,
+            member.user.id.0,
+            &map,
+            Some(&audit_log_reason(reason)),
+        )
+        .await?;
+    member.roles = edited.roles;
+    Ok(())
+}
+
+/// Add roles to a member with the reason shown in the audit log.
+pub async fn add_roles_with_audit_reason(
+    http: &Http,
+    member: &mut Member,
+    role_ids: &[RoleId],
+    reason: &str,
+) -> Result<(), SerenityError> {
+    let mut roles = member.roles.clone();
+    roles.extend(role_ids.iter().filter(|r| !member.roles.contains(r)));
+    set_roles_with_audit_reason(http, member, roles, reason).await
+}
+
+/// Remove roles from a member with the reason shown in the audit log.
+pub async fn remove_roles_with_audit_reason(
+    http: &Http,
+    member: &mut Member,
+    role_ids: &[RoleId],
+    reason: &str,
+) -> Result<(), SerenityError> {
+    let roles = member
+        .roles
+        .iter()
+        .filter(|r| !role_ids.contains(r))
+        .copied()
+        .collect();
+    set_roles_with_audit_reason(http, member, roles, reason).await
+}
+
+/// Edit the voice state of a member with the reason shown in the audit log.
+async fn edit_voice_with_audit_reason(
+    http: &Http,
+    guild_id: GuildId,
+    user_id: UserId,
+    key: &str,
+    value: Value,
+    reason: &str,
+) -> Result<(), SerenityError> {
+    let mut map = JsonMap::new();
+    map.insert(key.to_string(), value);
+
+    http.edit_member(guild_id.0, user_id.0, &map, Some(&audit_log_reason(reason)))
+        .await
+        .map(|_| ())
+}
+
+/// Disconnect a member from their voice channel with the reason shown in the audit log.
+pub async fn disconnect_with_audit_reason(
+    http: &Http,
+    guild_id: GuildId,
+    user_id: UserId,
+    reason: &str,
+) -> Result<(), SerenityError> {
+    edit_voice_with_audit_reason(http, guild_id, user_id, "channel_id", Value::Null, reason).await
+}
+
+/// Mute or unmute a member in voice channels with the reason shown in the audit log.
+pub async fn voice_mute_with_audit_reason(
+    http: &Http,
+    guild_id: GuildId,
+    user_id: UserId,
+    mute: bool,
+    reason: &str,
+) -> Result<(), SerenityError> {
+    edit_voice_with_audit_reason(http, guild_id, user_id, "mute", Value::Bool(mute), reason).await
+}
+
+/// Create or replace a permission overwrite of a channel with the reason shown in the audit log.
+pub async fn create_permission_with_audit_reason(
+    http: &Http,
+    channel_id: ChannelId,
+    overwrite: &PermissionOverwrite,
+    reason: &str,
+) -> Result<(), SerenityError> {
+    let (target_id, kind) = match overwrite.kind {
+        PermissionOverwriteType::Member(id) => (id.0, "member"),
+        PermissionOverwriteType::Role(id) => (id.0, "role"),
+        _ => return Err(SerenityError::Other("Unknown permission overwrite type")),
+    };
+    let body = serde_json::to_vec(&json!({
+        "allow": overwrite.allow.bits(),
+        "deny": overwrite.deny.bits(),
+        "id": target_id,
+        "type": kind,
+    }))?;
+
+    let mut request = RequestBuilder::new(RouteInfo::CreatePermission {
+        channel_id: channel_id.0,
+        target_id,
+    });
+    request
+        .body(Some(&body))
+        .headers(Some(audit_log_headers(reason)));
+    http.request(request.build()).await.map(|_| ())
+}
+
+/// Delete a permission overwrite of a channel with the reason shown in the audit log.
+pub async fn delete_permission_with_audit_reason(
+    http: &Http,
+    channel_id: ChannelId,
+    kind: PermissionOverwriteType,
+    reason: &str,
+) -> Result<(), SerenityError> {
+    let target_id = match kind {
+        PermissionOverwriteType::Member(id) => id.0,
+        PermissionOverwriteType::Role(id) => id.0,
+        _ => return Err(SerenityError::Other("Unknown permission overwrite type")),
+    };
+
+    let mut request = RequestBuilder::new(RouteInfo::DeletePermission {
+        channel_id: channel_id.0,
+        target_id,
+    });
+    request.headers(Some(audit_log_headers(reason)));
+    http.request(request.build()).await.map(|_| ())
+}
+
 pub async fn mute_on_join(ctx: &serenity::Context, new_member: &mut serenity::Member) {
-    let data = get_data_lock(ctx).await;
-    let data = data.read().await;
+    let data = get_data(ctx).await;
 
     if let Ok(mut cursor) = data
         .database
@@ -43,14 +470,30 @@ pub async fn mute_on_join(ctx: &serenity::Context, new_member: &mut serenity::Me
         )
         .await
     {
-        
         if let Ok(found) = cursor.advance().await {
             if found {
                 debug!("Muted member {} rejoined the server", new_member.user.tag());
-                if new_member
-                    .add_role(&ctx.http, RoleId(data.configuration.general.mute.role))
-                    .await
-                    .is_ok()
+                let mute_roles = cursor
+                    .deserialize_current()
+                    .ok()
+                    .and_then(|muted: Muted| muted.mute_roles)
+                    .map(|roles| roles.iter().filter_map(|r| r.parse().ok()).collect())
+                    .unwrap_or_else(|| vec![data.configuration.general.mute.role])
+                    .into_iter()
+                    .map(RoleId)
+                    .collect::<Vec<_>>();
+
+                if dry_run::execute(
+                    format!("add mute roles to {}", new_member.user.tag()),
+                    add_roles_with_audit_reason(
+                        &ctx.http,
+                        new_member,
+                        &mute_roles,
+                        "Muted member rejoined the server",
+                    ),
+                )
+                .await
+                .is_ok()
                 {
                     debug!(
                         "Muted member {} was successfully muted",
@@ -66,58 +509,1220 @@ pub async fn mute_on_join(ctx: &serenity::Context, new_member: &mut serenity::Me
         } else {
             error!("Failed to advance the cursor");
         }
-    } else {
-        error!("Failed to query database for muted users");
+    } else {
+        error!("Failed to query database for muted users");
+    }
+}
+
+pub fn queue_unmute_member(
+    http: &Arc<Http>,
+    database: &Arc<Database>,
+    member: &Member,
+    mute_role_id: u64,
+    mute_duration: u64,
+) -> JoinHandle<Option<BotError>> {
+    let http = http.clone();
+    let database = database.clone();
+    let mut member = member.clone();
+
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(mute_duration)).await;
+
+        let delete_result = database
+            .find_and_delete::<Muted>(
+                "muted",
+                Muted {
+                    user_id: Some(member.user.id.0.to_string()),
+                    ..Default::default()
+                }
+                .into(),
+                None,
+            )
+            .await;
+
+        let muted = match delete_result {
+            Ok(Some(muted)) => muted,
+            Ok(None) => return None,
+            Err(err) => return Some(BotError::Database(err)),
+        };
+
+        let taken_roles = taken_role_ids(&muted, member.user.id);
+        // Fall back to the configured mute role for mutes from before scoped mute roles
+        let mute_roles = muted
+            .mute_roles
+            .map(|roles| roles.iter().filter_map(|r| r.parse().ok()).collect())
+            .unwrap_or_else(|| vec![mute_role_id])
+            .into_iter()
+            .map(RoleId)
+            .collect::<Vec<_>>();
+
+        // Lift the mute first, so the member is never left muted because a role can not be restored
+        let remove_result = dry_run::execute(
+            format!("remove mute roles from {}", member.user.tag()),
+            remove_roles_with_audit_reason(&http, &mut member, &mute_roles, "Mute lifted"),
+        )
+        .await;
+        let add_result = dry_run::execute(
+            format!("restore taken roles of {}", member.user.tag()),
+            add_roles_with_audit_reason(&http, &mut member, &taken_roles, "Mute lifted"),
+        )
+        .await;
+
+        // Failures can be retried by staff, so the member is not left without their roles
+        if let Err(err) = &remove_result {
+            record_failure(
+                &database,
+                member.guild_id,
+                member.user.id,
+                FailedOperation::RemoveRoles,
+                &mute_roles,
+                err,
+            )
+            .await;
+        }
+        if let Err(err) = &add_result {
+            record_failure(
+                &database,
+                member.guild_id,
+                member.user.id,
+                FailedOperation::AddRoles,
+                &taken_roles,
+                err,
+            )
+            .await;
+        }
+
+        match (remove_result, add_result) {
+            (Err(err), _) => {
+                error!(
+                    "Failed to remove mute roles from {}: {:?}",
+                    member.user.tag(),
+                    err
+                );
+                Some(BotError::from(err))
+            },
+            (_, Err(err)) => {
+                error!(
+                    "Failed to restore taken roles of {}: {:?}",
+                    member.user.tag(),
+                    err
+                );
+                Some(BotError::from(err))
+            },
+            _ => None,
+        }
+    })
+}
+
+/// Unmute a member right away, cancelling their pending unmute.
+pub async fn unmute_user(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<(), Error> {
+    let mut member = fetch_member(ctx, guild_id, user_id).await?;
+    let data = get_data(ctx).await;
+
+    if data.configuration.general.mute.native {
+        return match remove_timeout(&ctx.http, &mut member).await {
+            Some(err) => Err(err.into()),
+            None => Ok(()),
+        };
+    }
+
+    if let Some(pending_unmute) = data.pending_unmutes.lock().await.remove(&user_id.0) {
+        trace!("Cancelling pending unmute for {}", user_id.0);
+        pending_unmute.abort();
+    }
+
+    match queue_unmute_member(
+        &ctx.http,
+        &data.database,
+        &member,
+        data.configuration.general.mute.role,
+        0,
+    )
+    .await?
+    {
+        Some(err) => Err(err.into()),
+        None => Ok(()),
+    }
+}
+
+/// The roles taken from a muted member. Missing or unparsable roles are skipped with a warning,
+/// so the remaining roles are still restored.
+fn taken_role_ids(muted: &Muted, user_id: UserId) -> Vec<RoleId> {
+    let Some(taken_roles) = &muted.taken_roles else {
+        warn!(
+            user_id = %user_id,
+            "No taken roles were stored for the mute, restoring none"
+        );
+        return Vec::new();
+    };
+
+    taken_roles
+        .iter()
+        .filter_map(|role| match role.parse() {
+            Ok(role_id) => Some(RoleId(role_id)),
+            Err(err) => {
+                warn!(
+                    user_id = %user_id,
+                    role = %role,
+                    error = %err,
+                    "Skipping unparsable taken role"
+                );
+                None
+            },
+        })
+        .collect()
+}
+
+/// Lift a temporary ban once it expires, unless it was lifted already.
+pub fn queue_unban(
+    http: &Arc<Http>,
+    database: &Arc<Database>,
+    guild_id: GuildId,
+    user_id: UserId,
+    ban_duration: u64,
+) -> JoinHandle<Option<BotError>> {
+    let http = http.clone();
+    let database = database.clone();
+
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(ban_duration)).await;
+
+        // Whoever removes the temporary ban lifts it, so it is never lifted twice. The ban may
+        // have been replaced by a longer one in the meantime, which must not be lifted yet
+        match database
+            .find_and_delete::<TempBan>(
+                "temp_bans",
+                doc! {
+                    "guild_id": guild_id.to_string(),
+                    "user_id": user_id.to_string(),
+                    "expires": { "$lte": Utc::now().timestamp() },
+                },
+                None,
+            )
+            .await
+        {
+            Ok(Some(_)) => {
+                let result = dry_run::execute(
+                    format!("lift the temporary ban of {}", user_id),
+                    unban_with_audit_reason(&http, guild_id, user_id, "Temporary ban expired"),
+                )
+                .await;
+
+                match result {
+                    Ok(_) => None,
+                    Err(err) => {
+                        record_failure(
+                            &database,
+                            guild_id,
+                            user_id,
+                            FailedOperation::Unban,
+                            &[],
+                            &err,
+                        )
+                        .await;
+                        Some(BotError::from(err))
+                    },
+                }
+            },
+            Ok(None) => None,
+            Err(err) => Some(BotError::Database(err)),
+        }
+    })
+}
+
+/// Pause or resume all invites of a guild with the invite pause feature of Discord.
+pub async fn set_invites_paused(
+    http: &Arc<Http>,
+    mut guild_id: GuildId,
+    paused: bool,
+) -> Option<BotError> {
+    let mut features = match http.get_guild(guild_id.0).await {
+        Ok(guild) => guild.features,
+        Err(err) => return Some(err.into()),
+    };
+    features.retain(|f| f != INVITES_DISABLED);
+    if paused {
+        features.push(INVITES_DISABLED.to_string());
+    }
+
+    dry_run::execute(
+        format!(
+            "{} invites of {}",
+            if paused { "pause" } else { "resume" },
+            guild_id
+        ),
+        async {
+            guild_id
+                .edit(http, |g| g.features(features))
+                .await
+                .map(|_| ())
+        },
+    )
+    .await
+    .err()
+    .map(BotError::from)
+}
+
+/// Resume the invites of a guild once its invite pause expires.
+pub fn queue_invite_resume(
+    http: &Arc<Http>,
+    database: &Arc<Database>,
+    guild_id: GuildId,
+    pause_duration: u64,
+) -> JoinHandle<Option<BotError>> {
+    let http = http.clone();
+    let database = database.clone();
+
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(pause_duration)).await;
+
+        // Like temporary bans, the pause may have been resumed or extended in the meantime
+        match database
+            .find_and_delete::<InvitePause>(
+                "invite_pauses",
+                doc! {
+                    "guild_id": guild_id.to_string(),
+                    "expires": { "$lte": Utc::now().timestamp() },
+                },
+                None,
+            )
+            .await
+        {
+            Ok(Some(_)) => set_invites_paused(&http, guild_id, false).await,
+            Ok(None) => None,
+            Err(err) => Some(BotError::Database(err)),
+        }
+    })
+}
+
+/// Set the slowmode of a channel, disabling it with 0 seconds.
+pub async fn set_slowmode(
+    http: &Arc<Http>,
+    channel_id: ChannelId,
+    seconds: u64,
+) -> Option<BotError> {
+    dry_run::execute(
+        format!("set the slowmode of {} to {}s", channel_id, seconds),
+        async {
+            channel_id
+                .edit(http, |c| c.rate_limit_per_user(seconds))
+                .await
+                .map(|_| ())
+        },
+    )
+    .await
+    .err()
+    .map(BotError::from)
+}
+
+/// Restore the previous slowmode of a channel once its temporary slowmode expires.
+pub fn queue_slowmode_reset(
+    http: &Arc<Http>,
+    database: &Arc<Database>,
+    channel_id: ChannelId,
+    slowmode_duration: u64,
+) -> JoinHandle<Option<BotError>> {
+    let http = http.clone();
+    let database = database.clone();
+
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(slowmode_duration)).await;
+
+        // Like invite pauses, the slowmode may have been changed or extended in the meantime
+        match database
+            .find_and_delete::<SlowmodeReset>(
+                "slowmode_resets",
+                doc! {
+                    "channel_id": channel_id.to_string(),
+                    "expires": { "$lte": Utc::now().timestamp() },
+                },
+                None,
+            )
+            .await
+        {
+            Ok(Some(reset)) => {
+                set_slowmode(&http, channel_id, reset.previous.unwrap_or_default()).await
+            },
+            Ok(None) => None,
+            Err(err) => Some(BotError::Database(err)),
+        }
+    })
+}
+
+/// Detect mute roles being removed by someone other than the bot and drop the pending unmute.
+pub async fn handle_external_unmute(
+    ctx: &serenity::Context,
+    old_if_available: &Option<serenity::Member>,
+    new: &serenity::Member,
+) {
+    let data = get_data(ctx).await;
+    let mute = &data.configuration.general.mute;
+
+    // Only members which were muted before the update are of interest
+    if let Some(old) = old_if_available {
+        if !old.roles.iter().any(|r| mute.is_mute_role(r.0)) {
+            return;
+        }
+    }
+
+    if new.roles.iter().any(|r| mute.is_mute_role(r.0)) {
+        return;
+    }
+
+    // The bot deletes the record before removing the mute roles, so a remaining record means
+    // the mute roles were removed manually
+    let muted = match data
+        .database
+        .find_and_delete::<Muted>(
+            "muted",
+            Muted {
+                user_id: Some(new.user.id.to_string()),
+                ..Default::default()
+            }
+            .into(),
+            None,
+        )
+        .await
+    {
+        Ok(Some(muted)) => muted,
+        Ok(None) => return,
+        Err(err) => {
+            error!("Failed to query database for muted users: {:?}", err);
+            return;
+        },
+    };
+
+    if let Some(pending_unmute) = data.pending_unmutes.lock().await.remove(&new.user.id.0) {
+        trace!("Cancelling pending unmute for {}", new.user.id.0);
+        pending_unmute.abort();
+    }
+
+    debug!("Member {} was unmuted externally", new.user.tag());
+
+    let taken_roles = taken_role_ids(&muted, new.user.id);
+
+    let mut member = new.clone();
+    let error = dry_run::execute(
+        format!("restore taken roles of {}", new.user.tag()),
+        add_roles_with_audit_reason(
+            &ctx.http,
+            &mut member,
+            &taken_roles,
+            "Mute role was removed manually",
+        ),
+    )
+    .await
+    .err();
+
+    if let Some(err) = &error {
+        error!(
+            "Failed to restore roles of externally unmuted member {}: {:?}",
+            new.user.tag(),
+            err
+        );
+    }
+
+    let configuration = &data.configuration;
+    if let Err(err) = ChannelId(configuration.log_channel(LogCategory::Moderation))
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                let e = e
+                    .title(format!("Externally unmuted {}", new.user.tag()))
+                    .description(
+                        "The mute role was removed manually. Cancelled the pending unmute.",
+                    )
+                    .thumbnail(new.user.face())
+                    .color(configuration.general.embed_color);
+
+                if let Some(reason) = &muted.reason {
+                    e.field("Reason", reason, false);
+                }
+
+                if let Some(err) = &error {
+                    e.field("Exception", err.to_string(), false);
+                }
+
+                e
+            })
+        })
+        .await
+    {
+        error!("Failed to log external unmute: {:?}", err);
+    }
+}
+
+/// Mute a member for the given duration and queue the unmute.
+pub async fn mute_member(
+    http: &Arc<Http>,
+    data: &Data,
+    member: &mut Member,
+    mute_duration: Duration,
+    reason: &str,
+    scope: Option<&str>,
+) -> Option<BotError> {
+    let unmute_time = Utc::now() + mute_duration;
+    let configuration = &data.configuration;
+    let mute = &configuration.general.mute;
+
+    // Discord lifts native timeouts by itself, so there is nothing to store or queue
+    if mute.native {
+        return timeout_member(http, member, mute_duration).await;
+    }
+    let mute_role_id = mute.role;
+    let take = &mute.take;
+    let is_currently_muted = member.roles.iter().any(|r| mute.is_mute_role(r.0));
+
+    // Keep mute roles of a previous mute, so all of them are removed when unmuting
+    let mut mute_roles = mute.roles_for(scope);
+    mute_roles.extend(
+        member
+            .roles
+            .iter()
+            .map(|r| r.0)
+            .filter(|&r| mute.is_mute_role(r) && !mute_roles.contains(&r))
+            .collect::<Vec<_>>(),
+    );
+
+    let result = if mute_roles.is_empty() {
+        Some(BotError::Config(
+            "No mute role configured for this scope".to_string(),
+        ))
+    } else if let Err(add_role_result) = dry_run::execute(
+        format!("add mute roles to {}", member.user.tag()),
+        add_roles_with_audit_reason(
+            http,
+            member,
+            &mute_roles.iter().map(|&r| RoleId(r)).collect::<Vec<_>>(),
+            reason,
+        ),
+    )
+    .await
+    {
+        Some(BotError::from(add_role_result))
+    } else {
+        // accumulate all roles to take from the member
+        let removed_roles = member
+            .roles
+            .iter()
+            .filter(|r| take.contains(&r.0))
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>();
+        // take them from the member, get remaining roles
+        let remaining_roles = dry_run::execute(
+            format!("take roles from {}", member.user.tag()),
+            remove_roles_with_audit_reason(
+                http,
+                member,
+                &take.iter().map(|&r| RoleId::from(r)).collect::<Vec<_>>(),
+                reason,
+            ),
+        )
+        .await;
+
+        if let Err(remove_role_result) = remaining_roles {
+            Some(BotError::from(remove_role_result))
+        } else {
+            // Roles which were removed from the user
+            let updated: Document = Muted {
+                guild_id: Some(member.guild_id.0.to_string()),
+                expires: Some(unmute_time.timestamp() as u64),
+                reason: Some(reason.to_string()),
+                mute_roles: Some(mute_roles.iter().map(|r| r.to_string()).collect()),
+                taken_roles: if is_currently_muted {
+                    // Prevent the bot from overriding the "take" field.
+                    // This would happen otherwise, because the bot would accumulate the users roles and then override the value in the database
+                    // resulting in the user being muted to have no roles to add back later.
+                    None
+                } else {
+                    Some(removed_roles)
+                },
+                ..Default::default()
+            }
+            .into();
+
+            data.database
+                .update::<Muted>(
+                    "muted",
+                    Muted {
+                        user_id: Some(member.user.id.0.to_string()),
+                        ..Default::default()
+                    }
+                    .into(),
+                    UpdateModifications::Document(doc! { "$set": updated}),
+                    Some(UpdateOptions::builder().upsert(true).build()),
+                )
+                .await
+                .err()
+                .map(BotError::Database)
+        }
+    };
+
+    let mut pending_unmutes = data.pending_unmutes.lock().await;
+    if let Some(pending_unmute) = pending_unmutes.get(&member.user.id.0) {
+        trace!("Cancelling pending unmute for {}", member.user.id.0);
+        pending_unmute.abort();
+    }
+
+    pending_unmutes.insert(
+        member.user.id.0,
+        queue_unmute_member(
+            http,
+            &data.database,
+            member,
+            mute_role_id,
+            mute_duration.num_seconds() as u64,
+        ),
+    );
+
+    result
+}
+
+/// Time out a member with the native communication timeout of Discord.
+pub async fn timeout_member(
+    http: &Arc<Http>,
+    member: &mut Member,
+    duration: Duration,
+) -> Option<BotError> {
+    if duration > Duration::days(MAX_TIMEOUT_DAYS) {
+        return Some(BotError::Conflict(format!(
+            "Timeouts can last at most {} days.",
+            MAX_TIMEOUT_DAYS
+        )));
+    }
+
+    let tag = member.user.tag();
+    dry_run::execute(
+        format!("time out {}", tag),
+        member.disable_communication_until_datetime(http, (Utc::now() + duration).into()),
+    )
+    .await
+    .err()
+    .map(BotError::from)
+}
+
+/// Lift the native communication timeout of a member.
+pub async fn remove_timeout(http: &Arc<Http>, member: &mut Member) -> Option<BotError> {
+    let tag = member.user.tag();
+    dry_run::execute(
+        format!("remove timeout of {}", tag),
+        member.enable_communication(http),
+    )
+    .await
+    .err()
+    .map(BotError::from)
+}
+
+// The permissions members lose in locked channels
+const LOCKED_PERMISSIONS: Permissions =
+    Permissions::SEND_MESSAGES.union(Permissions::ADD_REACTIONS);
+
+/// The overwrite denying the locked permissions in place of the given one.
+fn lock_overwrite(overwrite: &PermissionOverwrite) -> PermissionOverwrite {
+    PermissionOverwrite {
+        allow: overwrite.allow & !LOCKED_PERMISSIONS,
+        deny: overwrite.deny | LOCKED_PERMISSIONS,
+        kind: overwrite.kind,
+    }
+}
+
+/// The overwrites which allow sending messages and therefore need to be locked.
+/// Channels without an @everyone overwrite get an empty one, which is restored when unlocking.
+fn overwrites_to_lock(
+    overwrites: &[PermissionOverwrite],
+    everyone: RoleId,
+) -> Vec<PermissionOverwrite> {
+    let mut to_lock: Vec<_> = overwrites
+        .iter()
+        .filter(|r| r.allow.send_messages() || !r.deny.send_messages())
+        .cloned()
+        .collect();
+
+    if !overwrites
+        .iter()
+        .any(|o| o.kind == PermissionOverwriteType::Role(everyone))
+    {
+        to_lock.push(PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Role(everyone),
+        });
+    }
+
+    to_lock
+}
+
+/// Lock a channel by denying sending messages to everyone who can, saving the original overwrites.
+pub async fn lock_channel(
+    http: &Arc<Http>,
+    database: &Database,
+    channel: &GuildChannel,
+) -> Option<BotError> {
+    let query: Document = LockedChannel {
+        channel_id: Some(channel.id.to_string()),
+        ..Default::default()
+    }
+    .into();
+
+    // Check if channel is already muted, if so succeed.
+    if let Ok(mut cursor) = database
+        .find::<LockedChannel>("locked", query.clone(), None)
+        .await
+    {
+        if cursor.advance().await.unwrap() {
+            return Some(BotError::Conflict("Channel already locked".to_string()));
+        }
+    }
+
+    // accumulate all roles with write permissions, the @everyone role shares its id with the guild
+    let permission_overwrites =
+        overwrites_to_lock(&channel.permission_overwrites, RoleId(channel.guild_id.0));
+
+    // save the original overwrites
+    let updated: Document = LockedChannel {
+        overwrites: Some(permission_overwrites.clone()),
+        ..Default::default()
+    }
+    .into();
+
+    if let Err(err) = database
+        .update::<LockedChannel>(
+            "locked",
+            query,
+            UpdateModifications::Document(doc! { "$set": updated}),
+            Some(UpdateOptions::builder().upsert(true).build()),
+        )
+        .await
+    {
+        return Some(BotError::Database(err));
+    }
+
+    // lock the channel by and creating the new permission overwrite
+    for permission_overwrite in &permission_overwrites {
+        if let Err(err) = dry_run::execute(
+            format!("lock {}", channel.name),
+            create_permission_with_audit_reason(
+                http,
+                channel.id,
+                &lock_overwrite(permission_overwrite),
+                "Channel locked",
+            ),
+        )
+        .await
+        {
+            error!("Failed to create the new permission: {:?}", err);
+        }
+    }
+
+    None
+}
+
+/// Unlock a channel locked by [`lock_channel`] by restoring its original overwrites.
+pub async fn unlock_channel(
+    http: &Arc<Http>,
+    database: &Database,
+    channel: &GuildChannel,
+) -> Option<BotError> {
+    let locked_channel = match database
+        .find_and_delete::<LockedChannel>(
+            "locked",
+            LockedChannel {
+                channel_id: Some(channel.id.to_string()),
+                ..Default::default()
+            }
+            .into(),
+            None,
+        )
+        .await
+    {
+        Ok(Some(locked_channel)) => locked_channel,
+        Ok(None) => return Some(BotError::NotFound("Channel already unlocked".to_string())),
+        Err(err) => return Some(BotError::Database(err)),
+    };
+
+    for overwrite in &locked_channel.overwrites.unwrap_or_default() {
+        if let Err(err) = dry_run::execute(
+            format!("unlock {}", channel.name),
+            create_permission_with_audit_reason(http, channel.id, overwrite, "Channel unlocked"),
+        )
+        .await
+        {
+            return Some(err.into());
+        }
+    }
+
+    None
+}
+
+/// Add buttons to moderate a user directly from a log message.
+pub fn moderation_buttons(
+    components: &mut CreateComponents,
+    user_id: UserId,
+) -> &mut CreateComponents {
+    components.create_action_row(|r| {
+        r.create_button(|b| {
+            b.custom_id(format!("moderate:mute:{}", user_id))
+                .label("Mute 1h")
+                .style(ButtonStyle::Primary)
+        })
+        .create_button(|b| {
+            b.custom_id(format!("moderate:ban:{}", user_id))
+                .label("Ban")
+                .style(ButtonStyle::Danger)
+        })
+        .create_button(|b| {
+            b.custom_id(format!("moderate:ignore:{}", user_id))
+                .label("Ignore")
+                .style(ButtonStyle::Secondary)
+        })
+    })
+}
+
+/// Handle the buttons added by [`moderation_buttons`].
+pub async fn handle_moderation_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    const MUTE_DURATION_HOURS: i64 = 1;
+
+    let mut parts = component.data.custom_id.splitn(3, ':').skip(1);
+    let (action, user_id) = match (
+        parts.next(),
+        parts.next().and_then(|id| id.parse::<u64>().ok()),
+    ) {
+        (Some(action), Some(user_id)) => (action, UserId(user_id)),
+        _ => return,
+    };
+    let guild_id = match component.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    {
+        let data = get_data(ctx).await;
+        let is_allowed = component
+            .member
+            .as_ref()
+            .is_some_and(|m| is_administrator(&data.configuration.administrators, m));
+
+        if !is_allowed {
+            drop(data);
+            respond_ephemeral(ctx, component, "You do not have permission to do this.").await;
+            return;
+        }
+    }
+
+    // Ignoring does not moderate the user, so the hierarchy does not matter
+    if action != "ignore" {
+        if let Some(err) = check_moderator(ctx, guild_id, component.member.as_ref(), user_id).await
+        {
+            respond_ephemeral(ctx, component, &err.user_message()).await;
+            return;
+        }
+    }
+
+    let reason = format!("Moderated by {} from the logs", component.user.tag());
+    // Asking for the approval of a ban responds to the interaction
+    let mut responded = false;
+    let (verb, error, case_id) = match action {
+        "mute" => {
+            let error = match fetch_member(ctx, guild_id, user_id).await {
+                Ok(mut member) => {
+                    let data = get_data(ctx).await;
+
+                    mute_member(
+                        &ctx.http,
+                        &data,
+                        &mut member,
+                        Duration::hours(MUTE_DURATION_HOURS),
+                        &reason,
+                        None,
+                    )
+                    .await
+                },
+                Err(err) => Some(BotError::from(err)),
+            };
+
+            let case_id = if error.is_none() {
+                open_case(
+                    &get_data(ctx).await.database,
+                    guild_id,
+                    "mute",
+                    CaseTarget::User(user_id),
+                    component.user.id,
+                    Some(&reason),
+                )
+                .await
+            } else {
+                None
+            };
+            ("Muted", error, case_id)
+        },
+        "ban" => {
+            let data = get_data(ctx).await;
+
+            let approver_id =
+                match request_ban_approval_from_logs(ctx, &data, component, user_id).await {
+                    Ok(BanApprovalDecision::NotNeeded) => None,
+                    Ok(BanApprovalDecision::Approved(approver_id)) => Some(approver_id),
+                    Ok(BanApprovalDecision::Denied) => {
+                        edit_with_decision(
+                            ctx,
+                            &component.message,
+                            &format!(
+                                "Ban by <@{}> was not approved by a second moderator",
+                                component.user.id
+                            ),
+                        )
+                        .await;
+                        return;
+                    },
+                    Err(err) => {
+                        error!("Failed to request the approval of a ban: {:?}", err);
+                        respond_ephemeral(ctx, component, "Failed to request an approval.").await;
+                        return;
+                    },
+                };
+            responded = approver_id.is_some();
+
+            let case_id = match next_case_id(&data.database, guild_id).await {
+                Ok(case_id) => Some(case_id),
+                Err(err) => {
+                    error!("Failed to reserve a case number: {:?}", err);
+                    None
+                },
+            };
+            let audit_reason = case_id.map_or_else(
+                || reason.clone(),
+                |case_id| ban_reason(&data.configuration.bans, case_id, &reason),
+            );
+
+            let error = dry_run::execute(
+                format!("ban {}", user_id),
+                ban_with_audit_reason(&ctx.http, guild_id, user_id, 0, &audit_reason),
+            )
+            .await
+            .err()
+            .map(BotError::from);
+
+            if let (Some(case_id), None) = (case_id, &error) {
+                if let Err(err) = record_case(
+                    &data.database,
+                    guild_id,
+                    case_id,
+                    "ban",
+                    CaseTarget::User(user_id),
+                    component.user.id,
+                    Some(&audit_reason),
+                )
+                .await
+                {
+                    error!("Failed to record case {}: {:?}", case_id, err);
+                }
+                if let Some(approver_id) = approver_id {
+                    record_approver(&data.database, guild_id, case_id, approver_id).await;
+                }
+            }
+
+            ("Banned", error, case_id)
+        },
+        "ignore" => ("Ignored", None, None),
+        _ => return,
+    };
+
+    let decision = match error {
+        Some(err) => {
+            error!("Failed to moderate {} from the logs: {:?}", user_id, err);
+            format!("{} by <@{}>, but failed: {}", verb, component.user.id, err)
+        },
+        None => match case_id {
+            Some(case_id) => format!("{} by <@{}> (case #{})", verb, component.user.id, case_id),
+            None => format!("{} by <@{}>", verb, component.user.id),
+        },
+    };
+
+    if responded {
+        edit_with_decision(ctx, &component.message, &decision).await;
+    } else {
+        update_with_decision(ctx, component, &decision).await;
+    }
+}
+
+/// Ask a second moderator to approve a ban from the logs in response to the button.
+async fn request_ban_approval_from_logs(
+    ctx: &serenity::Context,
+    data: &Data,
+    component: &MessageComponentInteraction,
+    user_id: UserId,
+) -> Result<BanApprovalDecision, Error> {
+    let guild_id = component.guild_id.ok_or("Not in a server")?;
+    let Some(member_days) =
+        ban_approval_days(ctx, &data.configuration.bans.approval, guild_id, user_id).await
+    else {
+        return Ok(BanApprovalDecision::NotNeeded);
+    };
+    let user = user_id.to_user(ctx).await?;
+
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| {
+                    d.embed(|e| {
+                        ban_approval_embed(e, &user, member_days, component.user.id)
+                            .color(data.configuration.general.embed_color)
+                    })
+                    .components(ban_approval_buttons)
+                })
+        })
+        .await?;
+    let message = component.get_interaction_response(&ctx.http).await?;
+
+    await_ban_approval(
+        ctx,
+        &data.configuration.administrators,
+        &message,
+        component.user.id,
+        &user,
+    )
+    .await
+}
+
+/// Join the items of a list into lines, leaving out the ones which do not fit into an embed field.
+fn limited_list(items: &[String]) -> String {
+    let mut list = String::new();
+    for (index, item) in items.iter().enumerate() {
+        let more = format!("and {} more", items.len() - index);
+        if list.len() + item.len() + more.len() + 2 > MAX_FIELD_LENGTH {
+            list.push_str(&more);
+            break;
+        }
+        list.push_str(item);
+        list.push('\n');
+    }
+    list
+}
+
+/// Add the channels an action succeeded and failed in to an embed.
+fn channel_results<'a>(
+    embed: &'a mut CreateEmbed,
+    name: &str,
+    succeeded: &[ChannelId],
+    failures: &[String],
+) -> &'a mut CreateEmbed {
+    if !succeeded.is_empty() {
+        embed.field(
+            name,
+            limited_list(
+                &succeeded
+                    .iter()
+                    .map(|id| format!("<#{}>", id))
+                    .collect::<Vec<_>>(),
+            ),
+            false,
+        );
+    }
+    if !failures.is_empty() {
+        embed.field(
+            format!("Failed ({})", failures.len()),
+            limited_list(failures),
+            false,
+        );
+    }
+    embed
+}
+
+/// Parse user ids separated by commas, whitespace or new lines.
+/// Returns the unique valid ids and the parts which are not ids.
+pub fn parse_user_ids(input: &str) -> (Vec<UserId>, Vec<String>) {
+    let mut ids = Vec::new();
+    let mut invalid = Vec::new();
+
+    for part in input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|p| !p.is_empty())
+    {
+        match part.parse::<u64>() {
+            Ok(id) if id > 0 => {
+                if !ids.contains(&UserId(id)) {
+                    ids.push(UserId(id));
+                }
+            },
+            _ => invalid.push(part.to_string()),
+        }
     }
+
+    (ids, invalid)
 }
 
-pub fn queue_unmute_member(
-    http: &Arc<Http>,
-    database: &Arc<Database>,
-    member: &Member,
-    mute_role_id: u64,
-    mute_duration: u64,
-) -> JoinHandle<Option<Error>> {
-    let http = http.clone();
-    let database = database.clone();
-    let mut member = member.clone();
+/// Whether banning a member needs the approval of a second moderator, because they have been a
+/// member for long or hold a protected role.
+pub fn needs_ban_approval(approval: &BanApproval, member_days: i64, roles: &[RoleId]) -> bool {
+    (approval.member_days > 0 && member_days >= approval.member_days)
+        || roles.iter().any(|r| approval.roles.contains(&r.0))
+}
 
-    tokio::spawn(async move {
-        tokio::time::sleep(std::time::Duration::from_secs(mute_duration)).await;
+pub enum BanApprovalDecision {
+    NotNeeded,
+    Approved(UserId),
+    Denied,
+}
 
-        let delete_result = database
-            .find_and_delete::<Muted>(
-                "muted",
-                Muted {
-                    user_id: Some(member.user.id.0.to_string()),
-                    ..Default::default()
-                }
-                .into(),
-                None,
-            )
+/// For how many days a member has been a member, if banning them needs an approval.
+/// Users who are not members can not have been members for long.
+pub async fn ban_approval_days(
+    ctx: &serenity::Context,
+    approval: &BanApproval,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Option<i64> {
+    let member = fetch_member(ctx, guild_id, user_id).await.ok()?;
+    let member_days = member.joined_at.map_or(0, |joined_at| {
+        (Utc::now().timestamp() - joined_at.unix_timestamp()) / (24 * 60 * 60)
+    });
+
+    needs_ban_approval(approval, member_days, &member.roles).then_some(member_days)
+}
+
+/// Describe a ban which waits for the approval of a second moderator.
+pub fn ban_approval_embed<'a>(
+    embed: &'a mut CreateEmbed,
+    user: &User,
+    member_days: i64,
+    requester: UserId,
+) -> &'a mut CreateEmbed {
+    embed
+        .title(format!("Approve the ban of {}", user.tag()))
+        .description(format!(
+            "{} has been a member for {} days or holds a protected role. Another moderator has \
+             to approve the ban within 10 minutes.",
+            user.tag(),
+            member_days
+        ))
+        .field("Requested by", format!("<@{}>", requester), true)
+        .thumbnail(user.face())
+}
+
+/// Add the buttons to approve or cancel a ban.
+pub fn ban_approval_buttons(components: &mut CreateComponents) -> &mut CreateComponents {
+    components.create_action_row(|r| {
+        r.create_button(|b| {
+            b.custom_id("ban:approve")
+                .label("Approve")
+                .style(ButtonStyle::Danger)
+        })
+        .create_button(|b| {
+            b.custom_id("ban:cancel")
+                .label("Cancel")
+                .style(ButtonStyle::Secondary)
+        })
+    })
+}
+
+/// Wait for a second moderator to approve a ban requested with [`ban_approval_buttons`].
+/// Only the requesting moderator can cancel it.
+pub async fn await_ban_approval(
+    ctx: &serenity::Context,
+    administrators: &Administrators,
+    message: &Message,
+    requester: UserId,
+    user: &User,
+) -> Result<BanApprovalDecision, Error> {
+    // How long a second moderator has to approve the ban
+    const BAN_APPROVAL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+    let deadline = tokio::time::Instant::now() + BAN_APPROVAL_TIMEOUT;
+    loop {
+        let component = message
+            .await_component_interaction(ctx)
+            .timeout(deadline.saturating_duration_since(tokio::time::Instant::now()))
             .await;
 
-        if let Err(database_remove_result) = delete_result {
-            Some(database_remove_result)
-        } else if let Some(find_result) = delete_result.unwrap() {
-            let taken_roles = find_result
-                .taken_roles
-                .unwrap()
-                .into_iter()
-                .map(|r| RoleId::from(r.parse::<u64>().unwrap()))
-                .collect::<Vec<_>>();
-
-            if let Err(add_role_result) = member.add_roles(&http, &taken_roles).await {
-                Some(Error::from(add_role_result))
-            } else if let Err(remove_result) = member.remove_role(http, mute_role_id).await {
-                Some(Error::from(remove_result))
-            } else {
-                None
+        let component = match component {
+            Some(component) => component,
+            None => {
+                message
+                    .channel_id
+                    .edit_message(&ctx.http, message.id, |m| m.components(|c| c))
+                    .await?;
+                return Ok(BanApprovalDecision::Denied);
+            },
+        };
+
+        let is_requester = component.user.id == requester;
+        if component.data.custom_id == "ban:cancel" {
+            if !is_requester {
+                respond_ephemeral(
+                    ctx,
+                    &component,
+                    "Only the moderator who requested the ban can cancel it.",
+                )
+                .await;
+                continue;
             }
-        } else {
-            None
+
+            update_with_decision(ctx, &component, "Cancelled").await;
+            return Ok(BanApprovalDecision::Denied);
         }
-    })
+
+        let is_moderator = component
+            .member
+            .as_ref()
+            .is_some_and(|m| is_administrator(administrators, m));
+        if is_requester || !is_moderator {
+            respond_ephemeral(
+                ctx,
+                &component,
+                "A second moderator has to approve this ban.",
+            )
+            .await;
+            continue;
+        }
+
+        debug!(
+            "{} approved the ban of {} requested by {}",
+            component.user.tag(),
+            user.tag(),
+            requester
+        );
+        update_with_decision(
+            ctx,
+            &component,
+            &format!("Approved by <@{}>", component.user.id),
+        )
+        .await;
+        return Ok(BanApprovalDecision::Approved(component.user.id));
+    }
+}
+
+/// Parse a user mention or a raw user id.
+pub fn parse_user_id(input: &str) -> Option<UserId> {
+    let id = input.trim();
+    let id = id
+        .strip_prefix("<@")
+        .and_then(|id| id.strip_suffix('>'))
+        .map_or(id, |id| id.trim_start_matches('!'));
+
+    id.parse::<u64>().ok().filter(|id| *id > 0).map(UserId)
+}
+
+/// Fetch a user, or stand in for users who can not be fetched anymore, such as deleted accounts.
+pub async fn resolve_user(ctx: &Context<'_>, user_id: UserId) -> User {
+    match user_id.to_user(ctx.discord()).await {
+        Ok(user) => user,
+        Err(err) => {
+            debug!("Failed to fetch user {}: {:?}", user_id, err);
+            let mut user = User::default();
+            user.id = user_id;
+            user.name = format!("Unknown user {}", user_id);
+            user.discriminator = 0;
+            user.avatar = None;
+            user.bot = false;
+            user
+        },
+    }
 }
 
 // TODO: refactor
@@ -125,9 +1730,15 @@ pub async fn respond_moderation<'a>(
     ctx: &Context<'_>,
     moderation: &ModerationKind,
     configuration: &Configuration,
+    case_id: Option<i64>,
 ) -> Result<(), Error> {
     let current_user = ctx.discord().http.get_current_user().await?;
 
+    // The embed only explains the error, so keep the details in the log
+    if let Some(err) = moderation.error() {
+        error!("Moderation failed: {:?}", err);
+    }
+
     let create_embed = |f: &mut serenity::CreateEmbed| {
         let mut moderated_user: Option<&User> = None;
 
@@ -137,8 +1748,8 @@ pub async fn respond_moderation<'a>(
 
                 match error {
                     Some(err) => f.title(format!("Failed to mute {}", user.tag())).field(
-                        "Exception",
-                        err.to_string(),
+                        "Error",
+                        err.user_message(),
                         false,
                     ),
                     None => f.title(format!("Muted {}", user.tag())),
@@ -146,27 +1757,44 @@ pub async fn respond_moderation<'a>(
                 .field("Reason", reason, false)
                 .field("Expires", expires, false)
             },
+            ModerationKind::Timeout(user, reason, expires, error) => {
+                moderated_user = Some(user);
+
+                match error {
+                    Some(err) => f.title(format!("Failed to time out {}", user.tag())).field(
+                        "Error",
+                        err.user_message(),
+                        false,
+                    ),
+                    None => f.title(format!("Timed out {}", user.tag())),
+                }
+                .field("Reason", reason, false)
+                .field("Expires", expires, false)
+            },
             ModerationKind::Unmute(user, error) => {
                 moderated_user = Some(user);
                 match error {
                     Some(err) => f.title(format!("Failed to unmute {}", user.tag())).field(
-                        "Exception",
-                        err.to_string(),
+                        "Error",
+                        err.user_message(),
                         false,
                     ),
                     None => f.title(format!("Unmuted {}", user.tag())),
                 }
             },
-            ModerationKind::Ban(user, reason, error) => {
+            ModerationKind::Ban(user, reason, expires, error) => {
                 moderated_user = Some(user);
                 let f = match error {
                     Some(err) => f.title(format!("Failed to ban {}", user.tag())).field(
-                        "Exception",
-                        err.to_string(),
+                        "Error",
+                        err.user_message(),
                         false,
                     ),
                     None => f.title(format!("Banned {}", user.tag())),
                 };
+                if let Some(expires) = expires {
+                    f.field("Expires", format!("<t:{}:R>", expires), false);
+                }
                 if let Some(reason) = reason {
                     f.field("Reason", reason, false)
                 } else {
@@ -177,8 +1805,8 @@ pub async fn respond_moderation<'a>(
                 moderated_user = Some(user);
                 match error {
                     Some(err) => f.title(format!("Failed to unban {}", user.tag())).field(
-                        "Exception",
-                        err.to_string(),
+                        "Error",
+                        err.user_message(),
                         false,
                     ),
                     None => f.title(format!("Unbanned {}", user.tag())),
@@ -186,8 +1814,8 @@ pub async fn respond_moderation<'a>(
             },
             ModerationKind::Lock(channel, error) => match error {
                 Some(err) => f.title(format!("Failed to lock {} ", channel)).field(
-                    "Exception",
-                    err.to_string(),
+                    "Error",
+                    err.user_message(),
                     false,
                 ),
                 None => f.title(format!("Locked {}", channel)).description(
@@ -196,16 +1824,277 @@ pub async fn respond_moderation<'a>(
             },
             ModerationKind::Unlock(channel, error) => match error {
                 Some(err) => f.title(format!("Failed to unlock {}", channel)).field(
-                    "Exception",
-                    err.to_string(),
+                    "Error",
+                    err.user_message(),
+                    false,
+                ),
+                None => f
+                    .title(format!("Unlocked {}", channel))
+                    .description("Restored original permission overwrites."),
+            },
+            ModerationKind::LockVoice(channel, disconnected, error) => match error {
+                Some(err) => f.title(format!("Failed to lock {}", channel)).field(
+                    "Error",
+                    err.user_message(),
+                    false,
+                ),
+                None => f
+                    .title(format!("Locked {}", channel))
+                    .description(
+                        "Unlocking the voice channel will restore the original permission \
+                         overwrites.",
+                    )
+                    .field("Disconnected members", disconnected.to_string(), false),
+            },
+            ModerationKind::Forget(user, error) => {
+                moderated_user = Some(user);
+                match error {
+                    Some(err) => f.title(format!("Failed to forget {}", user.tag())).field(
+                        "Error",
+                        err.user_message(),
+                        false,
+                    ),
+                    None => f
+                        .title(format!("Forgot {}", user.tag()))
+                        .description("Anonymized all stored personal data."),
+                }
+            },
+            ModerationKind::Warn(user, reason, error) => {
+                moderated_user = Some(user);
+                match error {
+                    Some(err) => f.title(format!("Failed to warn {}", user.tag())).field(
+                        "Error",
+                        err.user_message(),
+                        false,
+                    ),
+                    None => f.title(format!("Warned {}", user.tag())),
+                }
+                .field("Reason", reason, false)
+            },
+            ModerationKind::Purge(channel, deleted, filters, error) => {
+                let f = match error {
+                    Some(err) => f.title(format!("Failed to purge {}", channel)).field(
+                        "Error",
+                        err.user_message(),
+                        false,
+                    ),
+                    None => f.title(format!("Purged {}", channel)),
+                }
+                .field("Deleted messages", deleted, false);
+                if filters.is_empty() {
+                    f
+                } else {
+                    f.field("Filters", filters.join("\n"), false)
+                }
+            },
+            ModerationKind::ClearReactions(target, removed, error) => match error {
+                Some(err) => {
+                    f.title("Failed to remove reactions")
+                        .field("Error", err.user_message(), false)
+                },
+                None => f.title("Removed reactions"),
+            }
+            .field("Target", target, false)
+            .field("Removed reactions", removed, false),
+            ModerationKind::Kick(user, reason, error) => {
+                moderated_user = Some(user);
+                let f = match error {
+                    Some(err) => f.title(format!("Failed to kick {}", user.tag())).field(
+                        "Error",
+                        err.user_message(),
+                        false,
+                    ),
+                    None => f.title(format!("Kicked {}", user.tag())),
+                };
+                if let Some(reason) = reason {
+                    f.field("Reason", reason, false)
+                } else {
+                    f
+                }
+            },
+            ModerationKind::Softban(user, reason, error) => {
+                moderated_user = Some(user);
+                let f = match error {
+                    Some(err) => f.title(format!("Failed to softban {}", user.tag())).field(
+                        "Error",
+                        err.user_message(),
+                        false,
+                    ),
+                    None => f
+                        .title(format!("Softbanned {}", user.tag()))
+                        .description("Banned and unbanned to delete their messages."),
+                };
+                if let Some(reason) = reason {
+                    f.field("Reason", reason, false)
+                } else {
+                    f
+                }
+            },
+            ModerationKind::Punish(user, reason, step, error) => {
+                moderated_user = Some(user);
+                match error {
+                    Some(err) => f.title(format!("Failed to punish {}", user.tag())).field(
+                        "Error",
+                        err.user_message(),
+                        false,
+                    ),
+                    None => f.title(format!("Punished {}", user.tag())),
+                }
+                .field("Step", step, false)
+                .field("Reason", reason, false)
+            },
+            ModerationKind::MassBan(banned, failures, reason, error) => {
+                let f = match error {
+                    Some(err) => {
+                        f.title("Failed to ban users")
+                            .field("Error", err.user_message(), false)
+                    },
+                    None => f.title(format!("Banned {} users", banned.len())),
+                };
+                if !banned.is_empty() {
+                    f.field(
+                        "Banned",
+                        limited_list(
+                            &banned
+                                .iter()
+                                .map(|id| format!("<@{}>", id))
+                                .collect::<Vec<_>>(),
+                        ),
+                        false,
+                    );
+                }
+                if !failures.is_empty() {
+                    f.field(
+                        format!("Failed ({})", failures.len()),
+                        limited_list(failures),
+                        false,
+                    );
+                }
+                f.field("Reason", reason, false)
+            },
+            ModerationKind::UnlockVoice(channel, error) => match error {
+                Some(err) => f.title(format!("Failed to unlock {}", channel)).field(
+                    "Error",
+                    err.user_message(),
                     false,
                 ),
                 None => f
                     .title(format!("Unlocked {}", channel))
                     .description("Restored original permission overwrites."),
             },
+            ModerationKind::Panic(steps, error) => match error {
+                Some(err) => f
+                    .title("Failed to panic")
+                    .field("Error", err.user_message(), false),
+                None => f.title("Panic").description(steps.join("\n")).field(
+                    "Revert",
+                    "End the panic with `/panic end`.",
+                    false,
+                ),
+            },
+            ModerationKind::PanicEnd(steps, error) => match error {
+                Some(err) => {
+                    f.title("Failed to end the panic")
+                        .field("Error", err.user_message(), false)
+                },
+                None => f.title("Ended the panic").description(steps.join("\n")),
+            },
+            ModerationKind::Lockdown(locked, failures, error) => {
+                let f = match error {
+                    Some(err) => f.title("Failed to start the lockdown").field(
+                        "Error",
+                        err.user_message(),
+                        false,
+                    ),
+                    None => f
+                        .title(format!("Locked down {} channels", locked.len()))
+                        .field("Revert", "End the lockdown with `/lockdown end`.", false),
+                };
+                channel_results(f, "Locked", locked, failures)
+            },
+            ModerationKind::LockdownEnd(unlocked, failures, error) => {
+                let f = match error {
+                    Some(err) => f.title("Failed to end the lockdown").field(
+                        "Error",
+                        err.user_message(),
+                        false,
+                    ),
+                    None => f.title(format!(
+                        "Ended the lockdown, unlocked {} channels",
+                        unlocked.len()
+                    )),
+                };
+                channel_results(f, "Unlocked", unlocked, failures)
+            },
+            ModerationKind::PauseInvites(expires, error) => match error {
+                Some(err) => {
+                    f.title("Failed to pause invites")
+                        .field("Error", err.user_message(), false)
+                },
+                None => f
+                    .title("Paused invites")
+                    .description("Nobody can join the server until invites are resumed.")
+                    .field("Resumes", expires, false),
+            },
+            ModerationKind::Slowmode(channel, seconds, resets, error) => match error {
+                Some(err) => f
+                    .title(format!("Failed to set the slowmode of {}", channel))
+                    .field("Error", err.user_message(), false),
+                None => {
+                    let f = if *seconds == 0 {
+                        f.title(format!("Disabled the slowmode of {}", channel))
+                    } else {
+                        f.title(format!("Set the slowmode of {}", channel)).field(
+                            "Slowmode",
+                            format!("{} seconds", seconds),
+                            true,
+                        )
+                    };
+                    if let Some(resets) = resets {
+                        f.field("Resets", resets, true);
+                    }
+                    f
+                },
+            },
+            ModerationKind::ResumeInvites(error) => match error {
+                Some(err) => {
+                    f.title("Failed to resume invites")
+                        .field("Error", err.user_message(), false)
+                },
+                None => f.title("Resumed invites"),
+            },
+            ModerationKind::VoiceKick(user, reason, error) => {
+                moderated_user = Some(user);
+                let f = match error {
+                    Some(err) => f
+                        .title(format!("Failed to disconnect {}", user.tag()))
+                        .field("Error", err.user_message(), false),
+                    None => f.title(format!("Disconnected {} from voice", user.tag())),
+                };
+                if let Some(reason) = reason {
+                    f.field("Reason", reason, false)
+                } else {
+                    f
+                }
+            },
+            ModerationKind::VoiceMute(user, muted, reason, error) => {
+                moderated_user = Some(user);
+                let action = if *muted { "voice mute" } else { "voice unmute" };
+                let f = match error {
+                    Some(err) => f
+                        .title(format!("Failed to {} {}", action, user.tag()))
+                        .field("Error", err.user_message(), false),
+                    None if *muted => f.title(format!("Voice muted {}", user.tag())),
+                    None => f.title(format!("Voice unmuted {}", user.tag())),
+                };
+                if let Some(reason) = reason {
+                    f.field("Reason", reason, false)
+                } else {
+                    f
+                }
+            },
         }
-        .color(configuration.general.embed_color);
+        .color(moderation.color(&configuration.general));
 
         let user = if let Some(user) = moderated_user {
             user.face()
@@ -213,33 +2102,50 @@ pub async fn respond_moderation<'a>(
             current_user.face()
         };
 
+        let mut footer = Vec::new();
+        if let Some(case_id) = case_id {
+            footer.push(format!("Case #{}", case_id));
+        }
+        if dry_run::is_enabled() {
+            footer.push("Dry run: no action was taken on Discord.".to_string());
+        }
+        if !footer.is_empty() {
+            result.footer(|f| f.text(footer.join(" • ")));
+        }
+
         result.thumbnail(&user);
     };
 
-    let reply = ctx
-        .send(|reply| {
-            reply.embed(|embed| {
-                create_embed(embed);
-                embed
-            })
-        })
-        .await?;
+    let response = reply_embed(ctx, &create_embed).await?;
+
+    // Responses redirected to the moderation log are not mirrored again
+    let logging_channel = ChannelId(configuration.log_channel(LogCategory::Moderation));
+    if response
+        .as_ref()
+        .is_some_and(|r| r.channel_id == logging_channel)
+    {
+        return Ok(());
+    }
 
-    let response = reply.message().await?;
-    ChannelId(configuration.general.logging_channel)
+    logging_channel
         .send_message(&ctx.discord().http, |reply| {
             reply.embed(|embed| {
                 create_embed(embed);
-                embed.field(
-                    "Reference",
-                    format!(
-                        "[Jump to message](https://discord.com/channels/{}/{}/{})",
-                        ctx.guild_id().unwrap().0,
-                        response.channel_id,
-                        response.id
-                    ),
-                    false,
-                )
+                // Ephemeral responses can not be jumped to
+                if let Some(response) = &response {
+                    embed.field(
+                        "Reference",
+                        format!(
+                            "[Jump to message](https://discord.com/channels/{}/{}/{})",
+                            ctx.guild_id()
+                                .map_or_else(|| "@me".to_string(), |g| g.to_string()),
+                            response.channel_id,
+                            response.id
+                        ),
+                        false,
+                    );
+                }
+                embed
             })
         })
         .await?;
@@ -247,42 +2153,181 @@ pub async fn respond_moderation<'a>(
     Ok(())
 }
 
-pub async fn ban_moderation(ctx: &Context<'_>, kind: &BanKind) -> Option<SerenityError> {
-    let guild_id = ctx.guild_id().unwrap().0;
+pub async fn ban_moderation(ctx: &Context<'_>, kind: &BanKind) -> Option<BotError> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => {
+            return Some(BotError::NotFound(
+                "Bans can only be managed in a server.".to_string(),
+            ))
+        },
+    };
     let http = &ctx.discord().http;
 
     match kind {
-        BanKind::Ban(user, dmd, reason) => {
+        BanKind::Ban(user, dmd, reason, _) => {
             let reason = reason
                 .clone()
                 .or_else(|| Some("None specified".to_string()))
                 .unwrap();
 
-            let ban_result = http
-                .ban_user(
-                    guild_id,
-                    user.id.0,
-                    cmp::min(dmd.unwrap_or(0), 7),
-                    reason.as_ref(),
-                )
-                .await;
+            let ban_result = dry_run::execute(
+                format!("ban {}", user.tag()),
+                ban_with_audit_reason(http, guild_id, user.id, dmd.unwrap_or(0), &reason),
+            )
+            .await;
 
             if let Err(err) = ban_result {
                 error!("Failed to ban user {}: {}", user.id.0, err);
-                Some(err)
+                Some(BotError::from(err))
             } else {
                 None
             }
         },
         BanKind::Unban(user) => {
-            let unban_result = http.remove_ban(guild_id, user.id.0, None).await;
+            let unban_result = dry_run::execute(
+                format!("unban {}", user.tag()),
+                unban_with_audit_reason(
+                    http,
+                    guild_id,
+                    user.id,
+                    &format!("Unbanned by {}", ctx.author().tag()),
+                ),
+            )
+            .await;
 
             if let Err(err) = unban_result {
                 error!("Failed to unban user {}: {}", user.id.0, err);
-                Some(err)
+                Some(BotError::from(err))
+            } else {
+                None
+            }
+        },
+        BanKind::Softban(user, dmd, reason) => {
+            let reason = reason.as_deref().unwrap_or("None specified");
+
+            let ban_result = dry_run::execute(
+                format!("softban {}", user.tag()),
+                ban_with_audit_reason(http, guild_id, user.id, dmd.unwrap_or(1), reason),
+            )
+            .await;
+            if let Err(err) = ban_result {
+                error!("Failed to softban user {}: {}", user.id.0, err);
+                return Some(BotError::from(err));
+            }
+
+            // Lift the ban right away, the deleted messages were the point of it
+            let unban_result = dry_run::execute(
+                format!("unban {} after softban", user.tag()),
+                unban_with_audit_reason(http, guild_id, user.id, "Softban"),
+            )
+            .await;
+
+            if let Err(err) = unban_result {
+                error!("Failed to unban softbanned user {}: {}", user.id.0, err);
+                Some(BotError::from(err))
             } else {
                 None
             }
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn denies_sending_in_locked_channels() {
+        let locked = lock_overwrite(&PermissionOverwrite {
+            allow: Permissions::SEND_MESSAGES | Permissions::VIEW_CHANNEL,
+            deny: Permissions::ATTACH_FILES,
+            kind: PermissionOverwriteType::Role(RoleId(1)),
+        });
+
+        assert_eq!(locked.allow, Permissions::VIEW_CHANNEL);
+        assert_eq!(
+            locked.deny,
+            Permissions::SEND_MESSAGES | Permissions::ADD_REACTIONS | Permissions::ATTACH_FILES
+        );
+    }
+
+    #[test]
+    fn locks_everyone_without_an_overwrite() {
+        let everyone = RoleId(1);
+        let denied = PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: Permissions::SEND_MESSAGES,
+            kind: PermissionOverwriteType::Role(RoleId(2)),
+        };
+
+        let locked = overwrites_to_lock(&[denied], everyone)
+            .iter()
+            .map(lock_overwrite)
+            .collect::<Vec<_>>();
+
+        assert_eq!(locked.len(), 1);
+        assert!(locked[0].kind == PermissionOverwriteType::Role(everyone));
+        assert_eq!(locked[0].deny, LOCKED_PERMISSIONS);
+    }
+
+    #[test]
+    fn parses_user_ids() {
+        let (ids, invalid) = parse_user_ids("1, 2\n3,,2 abc\n 0");
+
+        assert_eq!(ids, [UserId(1), UserId(2), UserId(3)]);
+        assert_eq!(invalid, ["abc", "0"]);
+    }
+
+    #[test]
+    fn requires_ban_approval_for_long_standing_members() {
+        let approval = BanApproval {
+            member_days: 365,
+            roles: vec![1],
+        };
+
+        assert!(!needs_ban_approval(&approval, 10, &[RoleId(2)]));
+        assert!(needs_ban_approval(&approval, 400, &[]));
+        assert!(needs_ban_approval(&approval, 10, &[RoleId(1)]));
+        assert!(!needs_ban_approval(&BanApproval::default(), 400, &[]));
+    }
+
+    #[test]
+    fn parses_user_mentions_and_ids() {
+        assert_eq!(parse_user_id("123"), Some(UserId(123)));
+        assert_eq!(parse_user_id(" <@123> "), Some(UserId(123)));
+        assert_eq!(parse_user_id("<@!123>"), Some(UserId(123)));
+        assert_eq!(parse_user_id("<#123>"), None);
+        assert_eq!(parse_user_id("0"), None);
+    }
+
+    #[test]
+    fn skips_unparsable_taken_roles() {
+        let muted = Muted {
+            taken_roles: Some(vec!["1".to_string(), "role".to_string(), "2".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(taken_role_ids(&muted, UserId(1)), [RoleId(1), RoleId(2)]);
+
+        assert!(taken_role_ids(&Muted::default(), UserId(1)).is_empty());
+    }
+
+    #[test]
+    fn encodes_truncated_audit_log_reasons() {
+        assert_eq!(encode_audit_log_reason("Spam 2x"), "Spam%202x");
+        assert_eq!(encode_audit_log_reason("é"), "%C3%A9");
+        assert_eq!(
+            audit_log_reason(&"é".repeat(600)).chars().count(),
+            MAX_AUDIT_LOG_REASON_LENGTH
+        );
+    }
+
+    #[test]
+    fn limits_lists_to_a_field() {
+        let items = vec!["x".repeat(100); 20];
+        let list = limited_list(&items);
+
+        assert!(list.len() <= MAX_FIELD_LENGTH);
+        assert!(list.ends_with("and 10 more"));
+    }
+}
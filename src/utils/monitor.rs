@@ -0,0 +1,168 @@
+use bson::doc;
+use poise::serenity_prelude::ChannelId;
+use regex::{Regex, RegexBuilder};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::*;
+use crate::db::model::Monitor;
+
+// The maximal amount of characters of each message shown as context
+const CONTEXT_LENGTH: usize = 200;
+// Discord rejects embed descriptions which are longer than this
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+
+/// Split a comma separated list of keywords, ignoring empty ones.
+pub fn parse_keywords(keywords: &str) -> Vec<String> {
+    keywords
+        .split(',')
+        .map(|k| k.trim().to_lowercase())
+        .filter(|k| !k.is_empty())
+        .collect()
+}
+
+/// Match any of the keywords literally and case insensitively.
+fn keyword_regex(keywords: &[String]) -> Option<Regex> {
+    if keywords.is_empty() {
+        return None;
+    }
+
+    RegexBuilder::new(
+        &keywords
+            .iter()
+            .map(|k| regex::escape(k))
+            .collect::<Vec<_>>()
+            .join("|"),
+    )
+    .case_insensitive(true)
+    .build()
+    .ok()
+}
+
+/// Shorten content to the given amount of characters.
+fn excerpt(content: &str, length: usize) -> String {
+    let mut excerpt = content.chars().take(length).collect::<String>();
+    if content.chars().count() > length {
+        excerpt.push('…');
+    }
+    excerpt
+}
+
+/// Mirror messages of monitored channels which match a keyword into the monitoring thread,
+/// along with the preceding messages as context.
+pub async fn mirror_monitored(ctx: &serenity::Context, new_message: &serenity::Message) {
+    if new_message.guild_id.is_none() || new_message.author.bot {
+        return;
+    }
+
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+
+    let monitor = match data
+        .database
+        .collect::<Monitor>(
+            "monitors",
+            doc! { "channel_id": new_message.channel_id.to_string() },
+        )
+        .await
+    {
+        Ok(monitors) => match monitors.into_iter().next() {
+            Some(monitor) => monitor,
+            None => return,
+        },
+        Err(err) => {
+            error!("Failed to query database for monitors: {:?}", err);
+            return;
+        },
+    };
+
+    let Some(regex) = keyword_regex(&monitor.keywords.unwrap_or_default()) else {
+        return;
+    };
+    if !regex.is_match(&new_message.content) {
+        return;
+    }
+    let Some(thread_id) = monitor.thread_id.and_then(|id| id.parse::<u64>().ok()) else {
+        return;
+    };
+
+    let context_messages = configuration.monitoring.context_messages;
+    let mut context = if context_messages > 0 {
+        new_message
+            .channel_id
+            .messages(&ctx.http, |r| {
+                r.before(new_message.id).limit(context_messages as u64)
+            })
+            .await
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    // The preceding messages are returned newest first
+    context.reverse();
+
+    let mut description = context
+        .iter()
+        .map(|m| {
+            format!(
+                "<@{}>: {}",
+                m.author.id,
+                excerpt(&m.content, CONTEXT_LENGTH)
+            )
+        })
+        .collect::<Vec<_>>();
+    // Highlight the keywords of the matching message
+    description.push(format!(
+        "**→** <@{}>: {}",
+        new_message.author.id,
+        regex.replace_all(&new_message.content, "__**$0**__")
+    ));
+
+    if let Err(err) = ChannelId(thread_id)
+        .send_message(&ctx.http, |m| {
+            m.allowed_mentions(|a| a.empty_parse()).embed(|e| {
+                e.description(excerpt(&description.join("\n"), MAX_DESCRIPTION_LENGTH - 1))
+                    .field("Channel", format!("<#{}>", new_message.channel_id), true)
+                    .field(
+                        "Message",
+                        format!("[Jump to message]({})", new_message.link()),
+                        true,
+                    )
+                    .thumbnail(new_message.author.face())
+                    .color(configuration.general.embed_color)
+            })
+        })
+        .await
+    {
+        error!("Failed to mirror monitored message: {:?}", err);
+        return;
+    }
+
+    debug!(
+        "Mirrored message {} of monitored channel {}",
+        new_message.id, new_message.channel_id
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keywords() {
+        assert_eq!(parse_keywords(" Leak, ,crash ,"), ["leak", "crash"]);
+        assert!(parse_keywords(" , ").is_empty());
+    }
+
+    #[test]
+    fn highlights_keywords_literally() {
+        let regex = keyword_regex(&parse_keywords("leak, c++")).unwrap();
+
+        assert_eq!(
+            regex.replace_all("A LEAK in C++", "__**$0**__"),
+            "A __**LEAK**__ in __**C++**__"
+        );
+        assert!(!regex.is_match("c+"));
+        assert!(keyword_regex(&[]).is_none());
+    }
+}
@@ -0,0 +1,163 @@
+use poise::serenity_prelude::{GuildId, User};
+use tracing::debug;
+
+use super::appeal::appeal_button;
+use super::*;
+use crate::model::application::Configuration;
+
+const DEFAULT_MUTE: &str = "Reason: {reason}\nExpires: {duration}";
+const DEFAULT_BAN: &str = "Reason: {reason}\nExpires: {duration}\nAppeal: {appeal}";
+const DEFAULT_WARN: &str = "Reason: {reason}";
+
+/// A moderation action which the moderated user is told about.
+pub enum Notification<'a> {
+    Mute {
+        reason: &'a str,
+        duration: String,
+    },
+    Ban {
+        reason: &'a str,
+        duration: String,
+        case_id: Option<i64>,
+    },
+    Warn {
+        reason: &'a str,
+    },
+}
+
+impl Notification<'_> {
+    fn title(&self, server: &str) -> String {
+        match self {
+            Notification::Mute {
+                ..
+            } => format!("You have been muted in {}", server),
+            Notification::Ban {
+                ..
+            } => format!("You have been banned from {}", server),
+            Notification::Warn {
+                ..
+            } => format!("You have been warned in {}", server),
+        }
+    }
+
+    /// Fill in the configured template, or a default one if it is empty.
+    /// Supports the placeholders `{server}`, `{reason}`, `{duration}`, `{appeal}` and `{case}`.
+    fn description(&self, configuration: &Configuration, server: &str) -> String {
+        let templates = &configuration.notifications;
+        let (template, default, reason, duration, case_id) = match self {
+            Notification::Mute {
+                reason,
+                duration,
+            } => (
+                &templates.mute,
+                DEFAULT_MUTE,
+                reason,
+                duration.as_str(),
+                None,
+            ),
+            Notification::Ban {
+                reason,
+                duration,
+                case_id,
+            } => (
+                &templates.ban,
+                DEFAULT_BAN,
+                reason,
+                duration.as_str(),
+                *case_id,
+            ),
+            Notification::Warn {
+                reason,
+            } => (&templates.warn, DEFAULT_WARN, reason, "", None),
+        };
+        let template = if template.is_empty() {
+            default
+        } else {
+            template
+        };
+
+        template
+            .replace("{server}", server)
+            .replace("{reason}", reason)
+            .replace("{duration}", duration)
+            .replace("{appeal}", &configuration.bans.appeal_url)
+            .replace(
+                "{case}",
+                &case_id.map_or_else(|| "unknown".to_string(), |c| c.to_string()),
+            )
+    }
+}
+
+/// Tell a user about a moderation action before it is applied, as they may no longer share a
+/// server with the bot afterwards. Mutes and bans can be appealed from the message. Users with
+/// closed direct messages are skipped.
+pub async fn notify_user(
+    ctx: &serenity::Context,
+    configuration: &Configuration,
+    guild_id: GuildId,
+    user: &User,
+    notification: Notification<'_>,
+) {
+    let server = guild_id
+        .name(ctx)
+        .unwrap_or_else(|| "the server".to_string());
+
+    let appealable = match notification {
+        Notification::Mute {
+            ..
+        } => Some("mute"),
+        Notification::Ban {
+            ..
+        } => Some("ban"),
+        Notification::Warn {
+            ..
+        } => None,
+    };
+
+    if let Err(err) = user
+        .direct_message(ctx, |m| {
+            m.embed(|e| {
+                e.title(notification.title(&server))
+                    .description(notification.description(configuration, &server))
+                    .color(configuration.general.embed_color)
+            });
+            if let Some(action) = appealable {
+                m.components(|c| {
+                    appeal_button(c, configuration.appeals.review_channel, guild_id, action)
+                });
+            }
+            m
+        })
+        .await
+    {
+        debug!("Failed to notify {}: {:?}", user.tag(), err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_placeholders() {
+        let mut configuration = Configuration::default();
+        configuration.bans.appeal_url = "https://example.com/appeal".to_string();
+        configuration.notifications.ban = "{reason} on {server}, case {case}: {appeal}".to_string();
+
+        let ban = Notification::Ban {
+            reason: "Spam",
+            duration: "permanent".to_string(),
+            case_id: Some(7),
+        };
+        assert_eq!(
+            ban.description(&configuration, "ReVanced"),
+            "Spam on ReVanced, case 7: https://example.com/appeal"
+        );
+
+        // An empty template falls back to the default one
+        let warn = Notification::Warn {
+            reason: "Spam",
+        };
+        assert_eq!(warn.description(&configuration, "ReVanced"), "Reason: Spam");
+    }
+}
@@ -0,0 +1,83 @@
+use poise::serenity_prelude::Attachment;
+#[cfg(not(feature = "tesseract"))]
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use tracing::{debug, error};
+
+use super::*;
+use crate::model::application::Ocr;
+use crate::Error;
+
+/// Extract the text of an image with the bundled tesseract.
+#[cfg(feature = "tesseract")]
+async fn extract_text(ocr: &Ocr, attachment: &Attachment) -> Result<String, Error> {
+    let image = attachment.download().await?;
+    let language = if ocr.language.is_empty() {
+        "eng".to_string()
+    } else {
+        ocr.language.clone()
+    };
+
+    // Tesseract blocks while recognizing, keep it off the async workers
+    tokio::task::spawn_blocking(move || -> Result<String, Error> {
+        Ok(tesseract::Tesseract::new(None, Some(&language))?
+            .set_image_from_mem(&image)?
+            .recognize()?
+            .get_text()?)
+    })
+    .await?
+}
+
+/// Extract the text of an image by posting it to the configured OCR endpoint.
+#[cfg(not(feature = "tesseract"))]
+async fn extract_text(ocr: &Ocr, attachment: &Attachment) -> Result<String, Error> {
+    if ocr.endpoint.is_empty() {
+        return Err(Error::from("No OCR endpoint configured"));
+    }
+
+    let mut request = reqwest::Client::new()
+        .post(&ocr.endpoint)
+        .header(
+            CONTENT_TYPE,
+            attachment.content_type.as_deref().unwrap_or("image/png"),
+        )
+        .body(attachment.download().await?);
+
+    if let Some(authorization) = &ocr.authorization {
+        request = request.header(AUTHORIZATION, authorization);
+    }
+
+    Ok(request.send().await?.error_for_status()?.text().await?)
+}
+
+/// Extract the text of all images attached to a message, if OCR is enabled.
+pub async fn image_text(ocr: &Ocr, message: &serenity::Message) -> Option<String> {
+    if !ocr.enabled {
+        return None;
+    }
+
+    let mut text = Vec::new();
+    for attachment in message.attachments.iter().filter(|a| {
+        a.content_type
+            .as_ref()
+            .is_some_and(|c| c.starts_with("image/"))
+            && (ocr.max_size == 0 || a.size <= ocr.max_size)
+    }) {
+        match extract_text(ocr, attachment).await {
+            Ok(extracted) => {
+                debug!(
+                    "Extracted {} characters from {}",
+                    extracted.len(),
+                    attachment.filename
+                );
+                text.push(extracted.trim().to_string());
+            },
+            Err(err) => error!(
+                "Failed to extract text from {}: {:?}",
+                attachment.filename, err
+            ),
+        }
+    }
+
+    text.retain(|t| !t.is_empty());
+    (!text.is_empty()).then(|| text.join("\n"))
+}
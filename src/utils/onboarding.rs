@@ -0,0 +1,169 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bson::doc;
+use chrono::Utc;
+use poise::serenity_prelude::GuildId;
+use reqwest::header::AUTHORIZATION;
+use serde::Deserialize;
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::*;
+use crate::db::model::InterestRole;
+use crate::Error;
+
+// How often to sync the interest roles with the onboarding of Discord
+const SYNC_INTERVAL_SECS: u64 = 6 * 60 * 60;
+
+static SYNC_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Deserialize)]
+struct Onboarding {
+    prompts: Vec<Prompt>,
+}
+
+#[derive(Deserialize)]
+struct Prompt {
+    title: String,
+    options: Vec<PromptOption>,
+}
+
+#[derive(Deserialize)]
+struct PromptOption {
+    title: String,
+    #[serde(default)]
+    role_ids: Vec<String>,
+}
+
+/// Fetch the onboarding questions of a guild, which serenity does not support yet.
+async fn fetch_onboarding(ctx: &serenity::Context, guild_id: GuildId) -> Result<Onboarding, Error> {
+    Ok(reqwest::Client::new()
+        .get(format!(
+            "https://discord.com/api/v10/guilds/{}/onboarding",
+            guild_id
+        ))
+        .header(AUTHORIZATION, &ctx.http.token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?)
+}
+
+/// The roles assigned by the options of the chosen prompts, or of all prompts if none are chosen.
+fn interest_roles(
+    onboarding: Onboarding,
+    guild_id: GuildId,
+    prompts: &[String],
+) -> Vec<InterestRole> {
+    let now = Utc::now().timestamp();
+
+    onboarding
+        .prompts
+        .into_iter()
+        .filter(|p| prompts.is_empty() || prompts.contains(&p.title))
+        .flat_map(|prompt| {
+            prompt.options.into_iter().flat_map(move |option| {
+                let prompt = prompt.title.clone();
+                option
+                    .role_ids
+                    .into_iter()
+                    .map(move |role_id| InterestRole {
+                        guild_id: Some(guild_id.to_string()),
+                        role_id: Some(role_id),
+                        name: Some(option.title.clone()),
+                        prompt: Some(prompt.clone()),
+                        synced: Some(now),
+                    })
+            })
+        })
+        .collect()
+}
+
+/// Replace the interest roles of a guild with the ones of its onboarding questions.
+pub async fn sync_interest_roles(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+) -> Result<usize, Error> {
+    let onboarding = fetch_onboarding(ctx, guild_id).await?;
+
+    let data = get_data(ctx).await;
+    let roles = interest_roles(onboarding, guild_id, &data.configuration.onboarding.prompts);
+    let count = roles.len();
+
+    data.database
+        .delete_many(
+            "interest_roles",
+            doc! { "guild_id": guild_id.to_string() },
+            None,
+        )
+        .await?;
+    for role in roles {
+        data.database
+            .insert::<InterestRole>("interest_roles", role, None)
+            .await?;
+    }
+
+    debug!("Synced {} interest roles of {}", count, guild_id);
+    Ok(count)
+}
+
+/// Periodically sync the interest roles of all guilds with their onboarding questions.
+pub fn schedule_sync(ctx: &serenity::Context) {
+    // Ready is dispatched again when reconnecting
+    if SYNC_SCHEDULED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(SYNC_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+
+            let enabled = {
+                let data = get_data(&ctx).await;
+                data.configuration.onboarding.enabled
+            };
+            if !enabled {
+                continue;
+            }
+
+            for guild_id in ctx.cache.guilds() {
+                if let Err(err) = sync_interest_roles(&ctx, guild_id).await {
+                    error!("Failed to sync interest roles of {}: {:?}", guild_id, err);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collects_roles_of_chosen_prompts() {
+        let onboarding: Onboarding = serde_json::from_str(
+            r#"{"prompts": [
+                {"title": "Interests", "options": [
+                    {"title": "Development", "role_ids": ["1", "2"]},
+                    {"title": "Support", "role_ids": []}
+                ]},
+                {"title": "Devices", "options": [{"title": "Android", "role_ids": ["3"]}]}
+            ]}"#,
+        )
+        .unwrap();
+
+        let roles = interest_roles(onboarding, GuildId(1), &["Interests".to_string()]);
+        assert_eq!(
+            roles
+                .iter()
+                .map(|r| r.role_id.as_deref().unwrap())
+                .collect::<Vec<_>>(),
+            ["1", "2"]
+        );
+        assert_eq!(roles[0].name.as_deref(), Some("Development"));
+    }
+}
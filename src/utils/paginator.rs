@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use poise::serenity_prelude::{
+    ButtonStyle,
+    CreateComponents,
+    CreateEmbed,
+    InteractionResponseType,
+};
+
+use crate::{Context, Error};
+
+// The amount of fields shown on one page
+const FIELDS_PER_PAGE: usize = 10;
+// How long the buttons keep working after they were last used
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+/// An embed whose fields are split across pages.
+pub struct Paginated {
+    pub title: String,
+    pub thumbnail: Option<String>,
+    pub empty: String,
+    pub fields: Vec<(String, String)>,
+    pub color: i32,
+}
+
+impl Paginated {
+    fn page_count(&self) -> usize {
+        self.fields.len().div_ceil(FIELDS_PER_PAGE).max(1)
+    }
+
+    fn build(&self, page: usize, embed: &mut CreateEmbed) {
+        embed.title(&self.title).color(self.color);
+
+        if let Some(thumbnail) = &self.thumbnail {
+            embed.thumbnail(thumbnail);
+        }
+
+        if self.fields.is_empty() {
+            embed.description(&self.empty);
+            return;
+        }
+
+        embed
+            .fields(
+                self.fields
+                    .iter()
+                    .skip(page * FIELDS_PER_PAGE)
+                    .take(FIELDS_PER_PAGE)
+                    .map(|(name, value)| (name, value, false)),
+            )
+            .footer(|f| {
+                f.text(format!(
+                    "Page {}/{} • {} entries",
+                    page + 1,
+                    self.page_count(),
+                    self.fields.len()
+                ))
+            });
+    }
+
+    fn buttons(&self, page: usize, components: &mut CreateComponents) {
+        if self.page_count() == 1 {
+            return;
+        }
+
+        components.create_action_row(|r| {
+            r.create_button(|b| {
+                b.custom_id("page:previous")
+                    .label("Previous")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page == 0)
+            })
+            .create_button(|b| {
+                b.custom_id("page:next")
+                    .label("Next")
+                    .style(ButtonStyle::Secondary)
+                    .disabled(page + 1 == self.page_count())
+            })
+        });
+    }
+
+    /// Respond with the first page, letting the author flip through the pages until the buttons
+    /// time out.
+    pub async fn send(self, ctx: Context<'_>) -> Result<(), Error> {
+        let mut page = 0;
+
+        let handle = ctx
+            .send(|f| {
+                f.embed(|e| {
+                    self.build(page, e);
+                    e
+                })
+                .components(|c| {
+                    self.buttons(page, c);
+                    c
+                })
+            })
+            .await?;
+
+        if self.page_count() == 1 {
+            return Ok(());
+        }
+
+        let message = handle.message().await?;
+        while let Some(component) = message
+            .await_component_interaction(ctx.discord())
+            .author_id(ctx.author().id)
+            .timeout(TIMEOUT)
+            .await
+        {
+            page = match component.data.custom_id.as_str() {
+                "page:previous" => page.saturating_sub(1),
+                "page:next" => (page + 1).min(self.page_count() - 1),
+                _ => page,
+            };
+
+            component
+                .create_interaction_response(&ctx.discord().http, |r| {
+                    r.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|d| {
+                            d.embed(|e| {
+                                self.build(page, e);
+                                e
+                            })
+                            .components(|c| {
+                                self.buttons(page, c);
+                                c
+                            })
+                        })
+                })
+                .await?;
+        }
+
+        // The buttons stop working once the collector times out
+        handle
+            .edit(ctx, |f| {
+                f.embed(|e| {
+                    self.build(page, e);
+                    e
+                })
+                .components(|c| c)
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paginated(entries: usize) -> Paginated {
+        Paginated {
+            title: String::new(),
+            thumbnail: None,
+            empty: String::new(),
+            fields: vec![(String::new(), String::new()); entries],
+            color: 0,
+        }
+    }
+
+    #[test]
+    fn counts_pages() {
+        assert_eq!(paginated(0).page_count(), 1);
+        assert_eq!(paginated(FIELDS_PER_PAGE).page_count(), 1);
+        assert_eq!(paginated(FIELDS_PER_PAGE + 1).page_count(), 2);
+    }
+}
@@ -0,0 +1,266 @@
+use bson::doc;
+use chrono::Utc;
+use mongodb::options::UpdateModifications;
+use poise::serenity_prelude::{ChannelId, GuildId, RoleId, UserId, VerificationLevel};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::moderation::{lock_channel, unlock_channel, INVITES_DISABLED};
+use super::{dry_run, *};
+use crate::db::model::Panic;
+use crate::error::BotError;
+use crate::model::application::LogCategory;
+
+// Members must have been members for ten minutes before they can talk
+const PANIC_VERIFICATION_LEVEL: VerificationLevel = VerificationLevel::High;
+
+/// Whether a panic is in progress, which puts the guild into raid mode.
+pub async fn is_panicking(ctx: &serenity::Context, guild_id: GuildId) -> bool {
+    let data = get_data(ctx).await;
+
+    data.database
+        .collect::<Panic>("panics", doc! { "guild_id": guild_id.to_string() })
+        .await
+        .is_ok_and(|panics| !panics.is_empty())
+}
+
+/// Kick members who join while the guild is in raid mode.
+pub async fn kick_during_panic(ctx: &serenity::Context, new_member: &serenity::Member) {
+    if !is_panicking(ctx, new_member.guild_id).await {
+        return;
+    }
+
+    debug!(
+        "Kicking {} who joined during a panic",
+        new_member.user.tag()
+    );
+
+    if let Err(err) = new_member
+        .user
+        .direct_message(&ctx.http, |m| {
+            m.content("The server is not accepting new members right now. Please try again later.")
+        })
+        .await
+    {
+        debug!(
+            "Failed to notify {} of raid mode: {:?}",
+            new_member.user.tag(),
+            err
+        );
+    }
+
+    if let Err(err) = dry_run::execute(
+        format!("kick {} during a panic", new_member.user.tag()),
+        new_member.kick_with_reason(&ctx.http, "Raid mode"),
+    )
+    .await
+    {
+        error!(
+            "Failed to kick {} during a panic: {:?}",
+            new_member.user.tag(),
+            err
+        );
+    }
+}
+
+/// Put the guild into raid mode, lock the configured channels, raise the verification level,
+/// pause invites and ping on-duty staff, remembering how to revert each step.
+pub async fn start_panic(
+    ctx: &serenity::Context,
+    mut guild_id: GuildId,
+    moderator_id: UserId,
+) -> Result<Vec<String>, BotError> {
+    let (verification_level, mut features) = ctx
+        .cache
+        .guild_field(guild_id, |g| (g.verification_level, g.features.clone()))
+        .ok_or_else(|| BotError::NotFound("The server is not cached.".to_string()))?;
+    let invites_disabled = features.iter().any(|f| f == INVITES_DISABLED);
+
+    let data = get_data(ctx).await;
+    let database = &data.database;
+    let configuration = &data.configuration;
+
+    // The guild id is unique, so a guild can only panic once at a time
+    database
+        .insert::<Panic>(
+            "panics",
+            Panic {
+                guild_id: Some(guild_id.to_string()),
+                moderator_id: Some(moderator_id.to_string()),
+                verification_level: Some(verification_level),
+                invites_disabled: Some(invites_disabled),
+                timestamp: Some(Utc::now().timestamp()),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .map_err(|_| BotError::Conflict("A panic is already in progress.".to_string()))?;
+
+    let slow_join = &configuration.slow_join;
+    let mut steps = vec![if slow_join.role == 0 {
+        "Enabled raid mode, new members are kicked.".to_string()
+    } else {
+        format!(
+            "Enabled raid mode, {} new members per minute get access.",
+            slow_join.per_minute.max(1)
+        )
+    }];
+
+    if !invites_disabled {
+        features.push(INVITES_DISABLED.to_string());
+    }
+    steps.push(
+        match dry_run::execute(
+            format!("raise verification and pause invites of {}", guild_id),
+            async {
+                guild_id
+                    .edit(&ctx.http, |g| {
+                        g.verification_level(PANIC_VERIFICATION_LEVEL)
+                            .features(features)
+                    })
+                    .await
+                    .map(|_| ())
+            },
+        )
+        .await
+        {
+            Ok(_) => "Raised the verification level and paused invites.".to_string(),
+            Err(err) => format!(
+                "Failed to raise the verification level and pause invites: {}",
+                BotError::from(err).user_message()
+            ),
+        },
+    );
+
+    let mut locked_channels = Vec::new();
+    for channel_id in &configuration.panic.channels {
+        let channel = match ctx.cache.guild_channel(*channel_id) {
+            Some(channel) => channel,
+            None => {
+                steps.push(format!("Failed to lock <#{}>: not found", channel_id));
+                continue;
+            },
+        };
+
+        match lock_channel(&ctx.http, database, &channel).await {
+            Some(err) => steps.push(format!(
+                "Failed to lock <#{}>: {}",
+                channel_id,
+                err.user_message()
+            )),
+            None => locked_channels.push(channel_id.to_string()),
+        }
+    }
+    if !locked_channels.is_empty() {
+        steps.push(format!("Locked {} channels.", locked_channels.len()));
+    }
+
+    database
+        .update::<Panic>(
+            "panics",
+            doc! { "guild_id": guild_id.to_string() },
+            UpdateModifications::Document(doc! { "$set": { "locked_channels": &locked_channels } }),
+            None,
+        )
+        .await
+        .map_err(BotError::Database)?;
+
+    let on_duty_role = configuration.panic.on_duty_role;
+    if on_duty_role != 0 {
+        if let Err(err) = ChannelId(configuration.log_channel(LogCategory::Moderation))
+            .send_message(&ctx.http, |m| {
+                m.content(format!(
+                    "<@&{}> <@{}> started a panic.",
+                    on_duty_role, moderator_id
+                ))
+                .allowed_mentions(|a| a.roles(vec![RoleId(on_duty_role)]))
+            })
+            .await
+        {
+            error!("Failed to ping on-duty staff: {:?}", err);
+        } else {
+            steps.push("Pinged on-duty staff.".to_string());
+        }
+    }
+
+    Ok(steps)
+}
+
+/// Revert everything a panic changed and leave raid mode.
+pub async fn end_panic(
+    ctx: &serenity::Context,
+    mut guild_id: GuildId,
+) -> Result<Vec<String>, BotError> {
+    let data = get_data(ctx).await;
+    let database = &data.database;
+
+    let panic = database
+        .find_and_delete::<Panic>("panics", doc! { "guild_id": guild_id.to_string() }, None)
+        .await
+        .map_err(BotError::Database)?
+        .ok_or_else(|| BotError::NotFound("No panic is in progress.".to_string()))?;
+
+    let mut steps = vec!["Disabled raid mode.".to_string()];
+
+    let mut features = ctx
+        .cache
+        .guild_field(guild_id, |g| g.features.clone())
+        .unwrap_or_default();
+    // Invites which were paused before the panic stay paused
+    if !panic.invites_disabled.unwrap_or_default() {
+        features.retain(|f| f != INVITES_DISABLED);
+    }
+    let verification_level = panic.verification_level.unwrap_or(PANIC_VERIFICATION_LEVEL);
+    steps.push(
+        match dry_run::execute(
+            format!("restore verification and invites of {}", guild_id),
+            async {
+                guild_id
+                    .edit(&ctx.http, |g| {
+                        g.verification_level(verification_level).features(features)
+                    })
+                    .await
+                    .map(|_| ())
+            },
+        )
+        .await
+        {
+            Ok(_) => "Restored the verification level and invites.".to_string(),
+            Err(err) => format!(
+                "Failed to restore the verification level and invites: {}",
+                BotError::from(err).user_message()
+            ),
+        },
+    );
+
+    let locked_channels = panic.locked_channels.unwrap_or_default();
+    let mut unlocked = 0;
+    for channel_id in &locked_channels {
+        let channel = match channel_id
+            .parse::<u64>()
+            .ok()
+            .and_then(|id| ctx.cache.guild_channel(id))
+        {
+            Some(channel) => channel,
+            None => {
+                steps.push(format!("Failed to unlock <#{}>: not found", channel_id));
+                continue;
+            },
+        };
+
+        match unlock_channel(&ctx.http, database, &channel).await {
+            Some(err) => steps.push(format!(
+                "Failed to unlock <#{}>: {}",
+                channel_id,
+                err.user_message()
+            )),
+            None => unlocked += 1,
+        }
+    }
+    if unlocked > 0 {
+        steps.push(format!("Unlocked {} channels.", unlocked));
+    }
+
+    Ok(steps)
+}
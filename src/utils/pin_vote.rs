@@ -0,0 +1,253 @@
+use bson::doc;
+use chrono::Utc;
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument, UpdateModifications};
+use poise::serenity_prelude::{
+    ButtonStyle,
+    ChannelId,
+    MessageComponentInteraction,
+    MessageId,
+    ReactionType,
+};
+use tracing::{debug, error, trace};
+
+use super::bot::get_data;
+use super::interaction::{respond_ephemeral, update_with_decision};
+use super::moderation::is_administrator;
+use super::{dry_run, *};
+use crate::db::model::{PinRequest, PinRequestStatus};
+use crate::model::application::PinVote;
+use crate::Error;
+
+/// Find the pin vote settings of a channel whose emoji matches the reaction.
+fn pin_vote_for<'a>(
+    pin_votes: &'a [PinVote],
+    channel_id: ChannelId,
+    emoji: &ReactionType,
+) -> Option<&'a PinVote> {
+    pin_votes.iter().find(|p| {
+        p.threshold > 0
+            && p.channels.contains(&channel_id.0)
+            && p.emoji.parse::<ReactionType>().ok().as_ref() == Some(emoji)
+    })
+}
+
+/// Ask staff to pin a message once enough members voted for it with the configured emoji.
+pub async fn handle_pin_reaction(ctx: &serenity::Context, reaction: &serenity::Reaction) {
+    let guild_id = match reaction.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    let data = get_data(ctx).await;
+    let pin_vote = match pin_vote_for(
+        &data.configuration.pin_votes,
+        reaction.channel_id,
+        &reaction.emoji,
+    ) {
+        Some(pin_vote) => pin_vote,
+        None => return,
+    };
+
+    let message = match reaction.message(&ctx.http).await {
+        Ok(message) => message,
+        Err(err) => {
+            error!("Failed to fetch message for pin vote: {:?}", err);
+            return;
+        },
+    };
+    if message.pinned {
+        return;
+    }
+
+    let voters = match message
+        .reaction_users(&ctx.http, reaction.emoji.clone(), Some(100), None)
+        .await
+    {
+        Ok(users) => users
+            .into_iter()
+            .filter(|u| !u.bot)
+            .map(|u| u.id)
+            .collect::<Vec<_>>(),
+        Err(err) => {
+            error!("Failed to count pin votes: {:?}", err);
+            return;
+        },
+    };
+    if voters.len() < pin_vote.threshold {
+        return;
+    }
+
+    // The message id is unique, so staff is only asked once per message
+    if data
+        .database
+        .insert::<PinRequest>(
+            "pin_requests",
+            PinRequest {
+                message_id: Some(message.id.to_string()),
+                channel_id: Some(message.channel_id.to_string()),
+                guild_id: Some(guild_id.to_string()),
+                voters: Some(voters.iter().map(|v| v.to_string()).collect()),
+                status: Some(PinRequestStatus::Pending),
+                timestamp: Some(Utc::now().timestamp()),
+                ..Default::default()
+            },
+            None,
+        )
+        .await
+        .is_err()
+    {
+        trace!("Pinning message {} was already requested", message.id);
+        return;
+    }
+
+    debug!("{} members voted to pin {}", voters.len(), message.id);
+
+    if let Err(err) = ChannelId(pin_vote.approval_channel)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title("Pin request")
+                    .description(&message.content)
+                    .field("Author", format!("<@{}>", message.author.id), true)
+                    .field("Votes", voters.len(), true)
+                    .field(
+                        "Reference",
+                        format!("[Jump to message]({})", message.link()),
+                        true,
+                    )
+                    .thumbnail(message.author.face())
+                    .color(data.configuration.general.embed_color)
+            })
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id(format!("pin:approve:{}", message.id))
+                            .label("Pin")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|b| {
+                        b.custom_id(format!("pin:deny:{}", message.id))
+                            .label("Deny")
+                            .style(ButtonStyle::Danger)
+                    })
+                })
+            })
+        })
+        .await
+    {
+        error!("Failed to request pinning {}: {:?}", message.id, err);
+    }
+}
+
+/// Set the status of a pending pin request, returning it if it was still pending.
+async fn decide(
+    ctx: &serenity::Context,
+    message_id: &str,
+    status: PinRequestStatus,
+    moderator_id: u64,
+) -> Result<Option<PinRequest>, Error> {
+    let data = get_data(ctx).await;
+
+    data.database
+        .find_and_update::<PinRequest>(
+            "pin_requests",
+            // Only decide once, even if both buttons are clicked at the same time
+            doc! { "message_id": message_id, "status": "pending" },
+            UpdateModifications::Document(doc! { "$set": {
+                "status": bson::to_bson(&status)?,
+                "moderator_id": moderator_id.to_string(),
+            } }),
+            Some(
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            ),
+        )
+        .await
+}
+
+/// Pin the message of an approved pin request.
+async fn pin(ctx: &serenity::Context, pin_request: &PinRequest) -> Result<(), Error> {
+    let channel_id = ChannelId(
+        pin_request
+            .channel_id
+            .as_ref()
+            .ok_or("No channel")?
+            .parse()?,
+    );
+    let message_id = MessageId(
+        pin_request
+            .message_id
+            .as_ref()
+            .ok_or("No message")?
+            .parse()?,
+    );
+
+    dry_run::execute(
+        format!("pin message {} in {}", message_id, channel_id),
+        channel_id.pin(&ctx.http, message_id),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Handle the Pin and Deny buttons of pin requests.
+pub async fn handle_pin_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    let mut parts = component.data.custom_id.splitn(3, ':').skip(1);
+    let (decision, message_id) = match (parts.next(), parts.next()) {
+        (Some(decision), Some(message_id)) => (decision, message_id),
+        _ => return,
+    };
+
+    {
+        let data = get_data(ctx).await;
+        let is_allowed = component
+            .member
+            .as_ref()
+            .is_some_and(|m| is_administrator(&data.configuration.administrators, m));
+
+        if !is_allowed {
+            drop(data);
+            respond_ephemeral(ctx, component, "You do not have permission to do this.").await;
+            return;
+        }
+    }
+
+    let status = match decision {
+        "approve" => PinRequestStatus::Approved,
+        "deny" => PinRequestStatus::Denied,
+        _ => return,
+    };
+
+    let pin_request = match decide(ctx, message_id, status, component.user.id.0).await {
+        Ok(Some(pin_request)) => pin_request,
+        Ok(None) => {
+            respond_ephemeral(ctx, component, "This pin request was already decided.").await;
+            return;
+        },
+        Err(err) => {
+            error!("Failed to decide pin request: {:?}", err);
+            respond_ephemeral(ctx, component, "Failed to decide this pin request.").await;
+            return;
+        },
+    };
+
+    let decision = match status {
+        PinRequestStatus::Approved => match pin(ctx, &pin_request).await {
+            Ok(_) => format!("Pinned by <@{}>", component.user.id),
+            Err(err) => {
+                error!("Failed to pin message {}: {:?}", message_id, err);
+                format!(
+                    "Approved by <@{}>, but failed to pin: {}",
+                    component.user.id, err
+                )
+            },
+        },
+        _ => format!("Denied by <@{}>", component.user.id),
+    };
+
+    update_with_decision(ctx, component, &decision).await;
+}
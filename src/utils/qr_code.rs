@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+use poise::serenity_prelude::{Attachment, ChannelId};
+use reqwest::Url;
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::moderation::moderation_buttons;
+use super::{dry_run, *};
+use crate::model::application::LogCategory;
+use crate::Error;
+
+// Shorteners which take longer than this to redirect are checked without being resolved
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Decode the contents of all QR codes in an image.
+async fn decode(attachment: &Attachment) -> Result<Vec<String>, Error> {
+    let bytes = attachment.download().await?;
+
+    // Detecting grids is expensive for large images, keep it off the async workers
+    tokio::task::spawn_blocking(move || -> Result<Vec<String>, Error> {
+        let image = image::load_from_memory(&bytes)?.to_luma8();
+        let mut prepared = rqrr::PreparedImage::prepare(image);
+
+        Ok(prepared
+            .detect_grids()
+            .iter()
+            .filter_map(|grid| grid.decode().ok())
+            .map(|(_, content)| content)
+            .collect())
+    })
+    .await?
+}
+
+/// Follow the redirects of a url, such as the ones of url shorteners.
+async fn resolve(url: &Url) -> Url {
+    let client = match reqwest::Client::builder().timeout(RESOLVE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(_) => return url.clone(),
+    };
+
+    match client.get(url.clone()).send().await {
+        Ok(response) => response.url().clone(),
+        Err(err) => {
+            debug!("Failed to resolve {}: {:?}", url, err);
+            url.clone()
+        },
+    }
+}
+
+/// Whether the host of a url is a blocked domain or a subdomain of one.
+pub fn is_blocked(domains: &[String], url: &Url) -> bool {
+    url.host_str().is_some_and(|host| {
+        let host = host.to_lowercase();
+        domains.iter().any(|domain| {
+            let domain = domain.to_lowercase();
+            host == domain || host.ends_with(&format!(".{}", domain))
+        })
+    })
+}
+
+/// Delete messages with images containing QR codes which lead to blocked domains.
+/// Returns whether the message was deleted.
+pub async fn scan_qr_codes(ctx: &serenity::Context, new_message: &serenity::Message) -> bool {
+    if new_message.guild_id.is_none() || new_message.author.bot {
+        return false;
+    }
+
+    let data = get_data(ctx).await;
+    let qr_codes = &data.configuration.qr_codes;
+    if !qr_codes.enabled {
+        return false;
+    }
+
+    let mut malicious = None;
+    'attachments: for attachment in new_message.attachments.iter().filter(|a| {
+        a.content_type
+            .as_ref()
+            .is_some_and(|c| c.starts_with("image/"))
+            && (qr_codes.max_size == 0 || a.size <= qr_codes.max_size)
+    }) {
+        let contents = match decode(attachment).await {
+            Ok(contents) => contents,
+            Err(err) => {
+                error!(
+                    "Failed to scan {} for QR codes: {:?}",
+                    attachment.filename, err
+                );
+                continue;
+            },
+        };
+
+        for url in contents.iter().filter_map(|c| Url::parse(c.trim()).ok()) {
+            if is_blocked(&qr_codes.domains, &url) {
+                malicious = Some((url, None));
+                break 'attachments;
+            }
+
+            let resolved = resolve(&url).await;
+            if is_blocked(&qr_codes.domains, &resolved) {
+                malicious = Some((url, Some(resolved)));
+                break 'attachments;
+            }
+        }
+    }
+
+    let (url, resolved) = match malicious {
+        Some(malicious) => malicious,
+        None => return false,
+    };
+
+    debug!(
+        "{} posted a QR code leading to {}",
+        new_message.author.tag(),
+        url
+    );
+
+    if let Err(err) = dry_run::execute(
+        format!("delete message {}", new_message.id),
+        new_message.delete(&ctx.http),
+    )
+    .await
+    {
+        error!("Failed to delete malicious QR code: {:?}", err);
+        return false;
+    }
+
+    if let Err(err) = ChannelId(data.configuration.log_channel(LogCategory::Moderation))
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                let e = e
+                    .title("Malicious QR code")
+                    .description(&new_message.content)
+                    .field("User", format!("<@{}>", new_message.author.id), true)
+                    .field("Channel", format!("<#{}>", new_message.channel_id), true)
+                    .field("URL", format!("`{}`", url), false)
+                    .thumbnail(new_message.author.face())
+                    .color(data.configuration.general.embed_color);
+
+                if let Some(resolved) = &resolved {
+                    e.field("Redirects to", format!("`{}`", resolved), false);
+                }
+
+                e
+            })
+            .components(|c| moderation_buttons(c, new_message.author.id))
+        })
+        .await
+    {
+        error!("Failed to log malicious QR code: {:?}", err);
+    }
+
+    true
+}
@@ -0,0 +1,76 @@
+use poise::serenity_prelude::{ChannelId, CreateEmbed, Message};
+
+use crate::model::application::{Configuration, LogCategory, Visibility};
+use crate::{Context, Error};
+
+/// The visibility of the responses of a command, public unless configured otherwise.
+fn visibility(configuration: &Configuration, command: &str) -> Visibility {
+    configuration
+        .responses
+        .commands
+        .get(command)
+        .copied()
+        .unwrap_or(Visibility::Public)
+}
+
+/// The channel responses redirected to staff are posted in, the moderation log by default.
+fn staff_channel(configuration: &Configuration) -> ChannelId {
+    match configuration.responses.staff_channel {
+        0 => ChannelId(configuration.log_channel(LogCategory::Moderation)),
+        channel_id => ChannelId(channel_id),
+    }
+}
+
+/// Respond to a command with an embed, as public, ephemeral or redirected to the staff channel
+/// depending on the configured visibility of the command.
+/// Returns the message with the embed, unless only the author can see it.
+pub async fn reply_embed(
+    ctx: &Context<'_>,
+    create_embed: impl Fn(&mut CreateEmbed),
+) -> Result<Option<Message>, Error> {
+    let configuration = &ctx.data().load_full().configuration;
+
+    match visibility(configuration, &ctx.command().qualified_name) {
+        Visibility::Public => {
+            let reply = ctx
+                .send(|reply| {
+                    reply.embed(|embed| {
+                        create_embed(embed);
+                        embed
+                    })
+                })
+                .await?;
+            Ok(Some(reply.message().await?.into_owned()))
+        },
+        Visibility::Ephemeral => {
+            ctx.send(|reply| {
+                reply.ephemeral(true).embed(|embed| {
+                    create_embed(embed);
+                    embed
+                })
+            })
+            .await?;
+            Ok(None)
+        },
+        Visibility::Staff => {
+            let channel_id = staff_channel(configuration);
+            let message = channel_id
+                .send_message(&ctx.discord().http, |m| {
+                    m.embed(|embed| {
+                        create_embed(embed);
+                        embed
+                    })
+                })
+                .await?;
+
+            // Interactions have to be responded to
+            ctx.send(|reply| {
+                reply
+                    .ephemeral(true)
+                    .content(format!("The response was posted in <#{}>.", channel_id))
+            })
+            .await?;
+            Ok(Some(message))
+        },
+    }
+}
@@ -0,0 +1,57 @@
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+use serde_json::{json, Value};
+
+use crate::model::application::{Faq, ReplyDrafting};
+use crate::Error;
+
+// Discord rejects messages longer than this
+pub const MAX_REPLY_LENGTH: usize = 2000;
+
+/// Draft a reply to a support question with the configured language model.
+///
+/// The draft is only returned, sending it is up to the staff member who requested it.
+pub async fn request_draft(
+    drafting: &ReplyDrafting,
+    question: &str,
+    faq: &[&Faq],
+) -> Result<String, Error> {
+    if drafting.endpoint.is_empty() {
+        return Err(Error::from("No reply drafting endpoint configured"));
+    }
+
+    let mut context = format!("Question:\n{}", question);
+    if !faq.is_empty() {
+        context.push_str("\n\nRelated FAQ entries:");
+        for entry in faq {
+            context.push_str(&format!("\n- {}: {}", entry.question, entry.url));
+        }
+    }
+
+    let mut request = reqwest::Client::new()
+        .post(&drafting.endpoint)
+        .header(CONTENT_TYPE, "application/json")
+        .body(
+            json!({
+                "model": drafting.model,
+                "messages": [
+                    { "role": "system", "content": drafting.prompt },
+                    { "role": "user", "content": context },
+                ],
+            })
+            .to_string(),
+        );
+
+    if let Some(authorization) = &drafting.authorization {
+        request = request.header(AUTHORIZATION, authorization);
+    }
+
+    let response = request.send().await?.error_for_status()?.text().await?;
+    let response = serde_json::from_str::<Value>(&response)?;
+
+    let draft = response["choices"][0]["message"]["content"]
+        .as_str()
+        .ok_or("The endpoint did not respond with a draft")?
+        .trim();
+
+    Ok(draft.chars().take(MAX_REPLY_LENGTH).collect())
+}
@@ -0,0 +1,106 @@
+use bson::doc;
+use mongodb::options::FindOptions;
+use poise::serenity_prelude::{ChannelId, GuildId};
+use tracing::debug;
+
+use super::*;
+use crate::db::database::Database;
+use crate::db::model::Rule;
+use crate::{Data, Error};
+
+const RULES_TITLE: &str = "Rules";
+// The amount of recent messages to search for the rules embed
+const RECENT_MESSAGES: u64 = 50;
+
+/// The rules of a guild, ordered by their number.
+pub async fn guild_rules(database: &Database, guild_id: GuildId) -> Result<Vec<Rule>, Error> {
+    let mut cursor = database
+        .find::<Rule>(
+            "rules",
+            doc! { "guild_id": guild_id.to_string() },
+            Some(FindOptions::builder().sort(doc! { "number": 1 }).build()),
+        )
+        .await?;
+
+    let mut rules = Vec::new();
+    while cursor.advance().await? {
+        rules.push(cursor.deserialize_current()?);
+    }
+
+    Ok(rules)
+}
+
+/// Format a rule as its number and title followed by its description.
+pub fn format_rule(rule: &Rule) -> String {
+    format!(
+        "**{}. {}**\n{}",
+        rule.number.unwrap_or_default(),
+        rule.title.as_deref().unwrap_or_default(),
+        rule.description.as_deref().unwrap_or_default()
+    )
+}
+
+/// Post the rules to the rules channel, or edit the embed posted before.
+pub async fn sync_rules(
+    ctx: &serenity::Context,
+    data: &Data,
+    guild_id: GuildId,
+) -> Result<(), Error> {
+    let channel_id = ChannelId(data.configuration.rules.channel);
+    if channel_id.0 == 0 {
+        return Ok(());
+    }
+
+    let description = guild_rules(&data.database, guild_id)
+        .await?
+        .iter()
+        .map(format_rule)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let description = if description.is_empty() {
+        "There are no rules yet.".to_string()
+    } else {
+        description
+    };
+    let embed_color = data.configuration.general.embed_color;
+
+    let bot_id = ctx.cache.current_user_id();
+    let existing = channel_id
+        .messages(&ctx.http, |m| m.limit(RECENT_MESSAGES))
+        .await?
+        .into_iter()
+        .find(|m| {
+            m.author.id == bot_id
+                && m.embeds
+                    .first()
+                    .is_some_and(|e| e.title.as_deref() == Some(RULES_TITLE))
+        });
+
+    match existing {
+        Some(mut message) => {
+            message
+                .edit(&ctx.http, |m| {
+                    m.embed(|e| {
+                        e.title(RULES_TITLE)
+                            .description(&description)
+                            .color(embed_color)
+                    })
+                })
+                .await?
+        },
+        None => {
+            channel_id
+                .send_message(&ctx.http, |m| {
+                    m.embed(|e| {
+                        e.title(RULES_TITLE)
+                            .description(&description)
+                            .color(embed_color)
+                    })
+                })
+                .await?;
+        },
+    }
+
+    debug!("Synced the rules of {}", guild_id);
+    Ok(())
+}
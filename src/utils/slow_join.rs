@@ -0,0 +1,197 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bson::doc;
+use chrono::Utc;
+use mongodb::options::{FindOptions, UpdateModifications, UpdateOptions};
+use poise::serenity_prelude::{GuildId, RoleId, UserId};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::member_cache::fetch_member;
+use super::panic::is_panicking;
+use super::{dry_run, *};
+use crate::db::model::QueuedJoin;
+use crate::Error;
+
+// How often queued members are admitted
+const RELEASE_INTERVAL_SECS: u64 = 60;
+
+static RELEASE_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+/// The amount of queued members admitted at once, all of them once raid mode ended.
+fn release_limit(per_minute: usize, panicking: bool) -> Option<i64> {
+    panicking.then(|| per_minute.max(1) as i64)
+}
+
+/// Give a new member the access role right away, or queue them during raid mode.
+/// Returns whether slow join took care of the member, so they are not kicked for raid mode.
+pub async fn admit_member(ctx: &serenity::Context, new_member: &mut serenity::Member) -> bool {
+    let data = get_data(ctx).await;
+    let role = data.configuration.slow_join.role;
+    if role == 0 || new_member.user.bot {
+        return false;
+    }
+
+    if !is_panicking(ctx, new_member.guild_id).await {
+        if let Err(err) = dry_run::execute(
+            format!("give {} access", new_member.user.tag()),
+            new_member.add_role(&ctx.http, RoleId(role)),
+        )
+        .await
+        {
+            error!("Failed to give {} access: {:?}", new_member.user.tag(), err);
+        }
+        return true;
+    }
+
+    debug!(
+        "Queueing {} who joined during a panic",
+        new_member.user.tag()
+    );
+
+    if let Err(err) = data
+        .database
+        .update::<QueuedJoin>(
+            "join_queue",
+            doc! {
+                "guild_id": new_member.guild_id.to_string(),
+                "user_id": new_member.user.id.to_string(),
+            },
+            UpdateModifications::Document(doc! {
+                "$setOnInsert": { "timestamp": Utc::now().timestamp() }
+            }),
+            Some(UpdateOptions::builder().upsert(true).build()),
+        )
+        .await
+    {
+        error!("Failed to queue {}: {:?}", new_member.user.tag(), err);
+    }
+
+    if let Err(err) = new_member
+        .user
+        .direct_message(&ctx.http, |m| {
+            m.content(
+                "The server is admitting new members slowly right now. You will gain access shortly.",
+            )
+        })
+        .await
+    {
+        debug!(
+            "Failed to notify {} of the join queue: {:?}",
+            new_member.user.tag(),
+            err
+        );
+    }
+
+    true
+}
+
+/// The queued members of a guild, the longest waiting first.
+pub async fn queued_joins(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    limit: Option<i64>,
+) -> Result<Vec<QueuedJoin>, Error> {
+    let data = get_data(ctx).await;
+
+    let mut cursor = data
+        .database
+        .find::<QueuedJoin>(
+            "join_queue",
+            doc! { "guild_id": guild_id.to_string() },
+            Some(
+                FindOptions::builder()
+                    .sort(doc! { "timestamp": 1 })
+                    .limit(limit)
+                    .build(),
+            ),
+        )
+        .await?;
+
+    let mut queued = Vec::new();
+    while cursor.advance().await? {
+        queued.push(cursor.deserialize_current()?);
+    }
+    Ok(queued)
+}
+
+/// Give the next queued members of a guild access.
+async fn release(ctx: &serenity::Context, guild_id: GuildId) -> Result<(), Error> {
+    let data = get_data(ctx).await;
+    let slow_join = &data.configuration.slow_join;
+
+    let limit = release_limit(slow_join.per_minute, is_panicking(ctx, guild_id).await);
+    for queued in queued_joins(ctx, guild_id, limit).await? {
+        let Some(user_id) = queued
+            .user_id
+            .as_ref()
+            .and_then(|id| id.parse().ok())
+            .map(UserId)
+        else {
+            continue;
+        };
+
+        // Members who left in the meantime are dropped from the queue
+        if let Ok(mut member) = fetch_member(ctx, guild_id, user_id).await {
+            dry_run::execute(
+                format!("give {} access", member.user.tag()),
+                member.add_role(&ctx.http, RoleId(slow_join.role)),
+            )
+            .await?;
+            debug!("Admitted queued member {}", member.user.tag());
+        }
+
+        data.database
+            .delete(
+                "join_queue",
+                doc! { "guild_id": guild_id.to_string(), "user_id": user_id.to_string() },
+                None,
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Admit queued members every minute. The queue is persisted, so it survives restarts.
+pub fn schedule_release(ctx: &serenity::Context) {
+    // Ready is dispatched again when reconnecting
+    if RELEASE_SCHEDULED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(RELEASE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+
+            let enabled = {
+                let data = get_data(&ctx).await;
+                data.configuration.slow_join.role != 0
+            };
+            if !enabled {
+                continue;
+            }
+
+            for guild_id in ctx.cache.guilds() {
+                if let Err(err) = release(&ctx, guild_id).await {
+                    error!("Failed to admit queued members in {}: {:?}", guild_id, err);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_releases_during_raid_mode() {
+        assert_eq!(release_limit(5, true), Some(5));
+        assert_eq!(release_limit(0, true), Some(1));
+        assert_eq!(release_limit(5, false), None);
+    }
+}
@@ -0,0 +1,277 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bson::doc;
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use poise::serenity_prelude::{GuildId, UserId};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::moderation::is_administrator;
+use super::*;
+use crate::db::model::{Case, InteractionLog, Resolution, SupportResponse};
+use crate::Error;
+
+// The maximal amount of members which can be fetched at once
+const MAX_FETCH: u64 = 1000;
+// The maximal amount of staff members to list, which keeps the report below the description limit
+const MAX_REPORT_ENTRIES: usize = 40;
+
+static REPORTS_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+/// The activity of a staff member within a month.
+#[derive(Default)]
+struct StaffActivity {
+    cases: usize,
+    tickets: usize,
+    response_times: Vec<i64>,
+    commands: usize,
+}
+
+impl StaffActivity {
+    fn average_response_time(&self) -> Option<i64> {
+        match self.response_times.len() {
+            0 => None,
+            n => Some(self.response_times.iter().sum::<i64>() / n as i64),
+        }
+    }
+}
+
+/// The start of the month following the given time.
+fn next_month_start(time: DateTime<Utc>) -> DateTime<Utc> {
+    let (year, month) = match time.month() {
+        12 => (time.year() + 1, 1),
+        month => (time.year(), month + 1),
+    };
+    let start = NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .expect("The first day of a month is a valid date");
+
+    DateTime::from_utc(start, Utc)
+}
+
+/// Format a duration in seconds as hours and minutes.
+fn format_duration(secs: i64) -> String {
+    match (secs / 3600, secs % 3600 / 60) {
+        (0, 0) => format!("{}s", secs),
+        (0, minutes) => format!("{}m", minutes),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
+}
+
+/// Compile the activity of the given staff members between two timestamps.
+async fn compile(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    staff: &[UserId],
+    since: i64,
+    until: i64,
+) -> Result<HashMap<String, StaffActivity>, Error> {
+    let data = get_data(ctx).await;
+    let database = &data.database;
+    let filter = doc! {
+        "guild_id": guild_id.to_string(),
+        "timestamp": { "$gte": since, "$lt": until },
+    };
+
+    let mut activity = staff
+        .iter()
+        .map(|s| (s.to_string(), StaffActivity::default()))
+        .collect::<HashMap<_, _>>();
+
+    for case in database.collect::<Case>("cases", filter.clone()).await? {
+        if let Some(a) = case.moderator_id.and_then(|m| activity.get_mut(&m)) {
+            a.cases += 1;
+        }
+    }
+    for resolution in database
+        .collect::<Resolution>("resolutions", filter.clone())
+        .await?
+    {
+        if let Some(a) = resolution.resolved_by.and_then(|r| activity.get_mut(&r)) {
+            a.tickets += 1;
+        }
+    }
+    for response in database
+        .collect::<SupportResponse>("support_responses", filter.clone())
+        .await?
+    {
+        if let (Some(a), Some(response_time)) = (
+            response.responder_id.and_then(|r| activity.get_mut(&r)),
+            response.response_time,
+        ) {
+            a.response_times.push(response_time);
+        }
+    }
+    for interaction in database
+        .collect::<InteractionLog>("interactions", filter)
+        .await?
+    {
+        if let Some(a) = interaction.user_id.and_then(|u| activity.get_mut(&u)) {
+            a.commands += 1;
+        }
+    }
+
+    Ok(activity)
+}
+
+/// Send the activity of staff between two timestamps to the lead moderators of a guild.
+async fn send_report(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<(), Error> {
+    let (lead_roles, embed_color) = {
+        let data = get_data(ctx).await;
+        (
+            data.configuration.staff_reports.lead_roles.clone(),
+            data.configuration.general.embed_color,
+        )
+    };
+    if lead_roles.is_empty() {
+        return Ok(());
+    }
+
+    let mut members = Vec::new();
+    let mut after: Option<UserId> = None;
+    loop {
+        let page = guild_id.members(&ctx.http, Some(MAX_FETCH), after).await?;
+        let exhausted = (page.len() as u64) < MAX_FETCH;
+        after = page.last().map(|m| m.user.id);
+        members.extend(page.into_iter().filter(|m| !m.user.bot));
+
+        if exhausted {
+            break;
+        }
+    }
+
+    let (staff, leads) = {
+        let data = get_data(ctx).await;
+        let administrators = &data.configuration.administrators;
+        let staff = members
+            .iter()
+            .filter(|m| is_administrator(administrators, m))
+            .map(|m| m.user.id)
+            .collect::<Vec<_>>();
+        let leads = members
+            .iter()
+            .filter(|m| m.roles.iter().any(|r| lead_roles.contains(&r.0)))
+            .map(|m| m.user.clone())
+            .collect::<Vec<_>>();
+        (staff, leads)
+    };
+
+    let activity = compile(ctx, guild_id, &staff, since.timestamp(), until.timestamp()).await?;
+
+    // Sort by the amount of handled cases and tickets, most active first
+    let mut ranked = activity.iter().collect::<Vec<_>>();
+    ranked.sort_by_key(|(id, a)| (Reverse(a.cases + a.tickets), *id));
+
+    let mut report = ranked
+        .iter()
+        .take(MAX_REPORT_ENTRIES)
+        .map(|(id, a)| {
+            format!(
+                "<@{}>: {} cases, {} tickets, {} commands, average response time: {}",
+                id,
+                a.cases,
+                a.tickets,
+                a.commands,
+                a.average_response_time()
+                    .map_or_else(|| "none".to_string(), format_duration)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if report.is_empty() {
+        report = "No staff members found.".to_string();
+    } else if ranked.len() > MAX_REPORT_ENTRIES {
+        report.push_str(&format!("\nand {} more", ranked.len() - MAX_REPORT_ENTRIES));
+    }
+
+    let guild_name = guild_id
+        .name(ctx)
+        .unwrap_or_else(|| "the server".to_string());
+    let title = format!(
+        "Staff activity in {} during {}",
+        guild_name,
+        since.format("%B %Y")
+    );
+
+    debug!(
+        "Sending staff report of {} to {} leads",
+        guild_id,
+        leads.len()
+    );
+
+    for lead in leads {
+        if let Err(err) = lead
+            .direct_message(ctx, |m| {
+                m.embed(|e| e.title(&title).description(&report).color(embed_color))
+            })
+            .await
+        {
+            error!("Failed to send staff report to {}: {:?}", lead.tag(), err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Send the activity of staff during the past month on the first day of every month at midnight UTC.
+pub fn schedule_reports(ctx: &serenity::Context) {
+    // Ready is dispatched again when reconnecting
+    if REPORTS_SCHEDULED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        loop {
+            let now = Utc::now();
+            let until = next_month_start(now);
+
+            tokio::time::sleep((until - now).to_std().unwrap_or_default()).await;
+
+            let since = match until.month() {
+                1 => until
+                    .with_year(until.year() - 1)
+                    .and_then(|t| t.with_month(12)),
+                month => until.with_month(month - 1),
+            }
+            .expect("The first day of a month is a valid date");
+
+            for guild_id in ctx.cache.guilds() {
+                if let Err(err) = send_report(&ctx, guild_id, since, until).await {
+                    error!("Failed to send staff report of {}: {:?}", guild_id, err);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn rolls_over_to_next_month() {
+        let mid_december = Utc.timestamp_opt(1_702_684_800, 0).unwrap(); // 2023-12-16
+        assert_eq!(next_month_start(mid_december).timestamp(), 1_704_067_200); // 2024-01-01
+
+        let first_of_march = Utc.timestamp_opt(1_709_251_200, 0).unwrap(); // 2024-03-01
+        assert_eq!(next_month_start(first_of_march).timestamp(), 1_711_929_600);
+        // 2024-04-01
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(42), "42s");
+        assert_eq!(format_duration(14 * 60 + 3), "14m");
+        assert_eq!(format_duration(2 * 3600 + 5 * 60), "2h 5m");
+    }
+}
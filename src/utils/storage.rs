@@ -0,0 +1,77 @@
+use poise::serenity_prelude::{Attachment, PremiumTier};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+
+use crate::model::application::Storage;
+use crate::Error;
+
+const MEBIBYTE: u64 = 1024 * 1024;
+
+/// The maximum size of files the bot can upload to a guild with the given boost tier.
+pub fn upload_limit(premium_tier: PremiumTier) -> u64 {
+    match premium_tier {
+        PremiumTier::Tier2 => 50 * MEBIBYTE,
+        PremiumTier::Tier3 => 100 * MEBIBYTE,
+        _ => 8 * MEBIBYTE,
+    }
+}
+
+/// Upload an attachment to the configured external storage and return its public url.
+pub async fn upload(storage: &Storage, attachment: &Attachment) -> Result<String, Error> {
+    let content_type = attachment
+        .content_type
+        .clone()
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+
+    // Compare without parameters such as "; charset=utf-8"
+    let mime_type = content_type.split(';').next().unwrap_or_default().trim();
+    if !storage.mime_types.iter().any(|m| m == mime_type) {
+        return Err(Error::from(format!(
+            "MIME type {} is not allowed",
+            mime_type
+        )));
+    }
+
+    if attachment.size > storage.max_size {
+        return Err(Error::from(format!(
+            "{} exceeds the maximum size of {} bytes",
+            attachment.filename, storage.max_size
+        )));
+    }
+
+    upload_bytes(
+        storage,
+        &format!("{}/{}", attachment.id, attachment.filename),
+        &content_type,
+        attachment.download().await?,
+    )
+    .await
+}
+
+/// Upload a file to the configured external storage and return its public url.
+pub async fn upload_bytes(
+    storage: &Storage,
+    path: &str,
+    content_type: &str,
+    bytes: Vec<u8>,
+) -> Result<String, Error> {
+    let mut request = reqwest::Client::new()
+        .put(format!(
+            "{}/{}",
+            storage.endpoint.trim_end_matches('/'),
+            path
+        ))
+        .header(CONTENT_TYPE, content_type)
+        .body(bytes);
+
+    if let Some(authorization) = &storage.authorization {
+        request = request.header(AUTHORIZATION, authorization);
+    }
+
+    request.send().await?.error_for_status()?;
+
+    Ok(format!(
+        "{}/{}",
+        storage.public_url.trim_end_matches('/'),
+        path
+    ))
+}
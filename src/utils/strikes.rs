@@ -0,0 +1,172 @@
+use bson::doc;
+use chrono::Utc;
+
+use crate::db::database::Database;
+use crate::db::model::{AutomodHit, Case};
+use crate::model::application::{Configuration, StrikeAction, StrikeStep, Strikes};
+use crate::utils::time::parse_duration;
+use crate::Error;
+
+// The points of infractions whose action has no configured points
+const DEFAULT_POINTS: u32 = 1;
+
+/// An infraction which still counts towards escalation.
+pub struct Strike {
+    pub action: String,
+    pub reason: String,
+    pub points: u32,
+    pub timestamp: i64,
+    pub expires: i64,
+}
+
+/// The time in seconds after which a strike no longer counts.
+pub fn decay_seconds(policy: &Strikes) -> i64 {
+    parse_duration(&policy.decay).map_or(0, |d| d.num_seconds())
+}
+
+/// The points of an infraction, from the reason preset it was given with or otherwise its action.
+pub fn points_for(configuration: &Configuration, action: &str, reason: &str) -> u32 {
+    configuration
+        .choices
+        .reasons
+        .iter()
+        .filter(|choice| !choice.value.is_empty() && reason.contains(&choice.value))
+        .find_map(|choice| choice.points)
+        .or_else(|| configuration.strikes.points.get(action).copied())
+        .unwrap_or(DEFAULT_POINTS)
+}
+
+/// Collect the strikes of a user which have not decayed yet, oldest first.
+/// Automod hits which were actioned or approved by staff and moderation cases count as strikes.
+pub async fn active_strikes(
+    database: &Database,
+    configuration: &Configuration,
+    guild_id: u64,
+    user_id: u64,
+) -> Result<Vec<Strike>, Error> {
+    let decay = decay_seconds(&configuration.strikes);
+    let since = Utc::now().timestamp() - decay;
+
+    let hits = database
+        .collect::<AutomodHit>("automod_hits", doc! {
+            "guild_id": guild_id.to_string(),
+            "user_id": user_id.to_string(),
+            "status": { "$in": ["actioned", "approved"] },
+            "timestamp": { "$gte": since },
+        })
+        .await?;
+
+    let cases = database
+        .collect::<Case>("cases", doc! {
+            "guild_id": guild_id.to_string(),
+            "user_id": user_id.to_string(),
+            "timestamp": { "$gte": since },
+        })
+        .await?;
+
+    let mut strikes = hits
+        .into_iter()
+        .filter_map(|hit| {
+            let timestamp = hit.timestamp?;
+            let reason = format!("Automod: {}", hit.rule.unwrap_or_default());
+            Some(Strike {
+                points: points_for(configuration, "automod", &reason),
+                action: "automod".to_string(),
+                reason,
+                timestamp,
+                expires: timestamp + decay,
+            })
+        })
+        .chain(cases.into_iter().filter_map(|case| {
+            let timestamp = case.timestamp?;
+            let action = case.action.unwrap_or_default();
+            let reason = case.reason.unwrap_or_default();
+            Some(Strike {
+                points: points_for(configuration, &action, &reason),
+                action,
+                reason,
+                timestamp,
+                expires: timestamp + decay,
+            })
+        }))
+        .collect::<Vec<_>>();
+    strikes.sort_by_key(|s| s.timestamp);
+
+    Ok(strikes)
+}
+
+/// The sum of the points of strikes.
+pub fn total_points(strikes: &[Strike]) -> u32 {
+    strikes.iter().map(|s| s.points).sum()
+}
+
+/// Find the step of the policy which applies at the given amount of points.
+/// This is the step with the highest threshold which has been reached.
+pub fn step_for(policy: &Strikes, points: u32) -> Option<&StrikeStep> {
+    policy
+        .steps
+        .iter()
+        .filter(|s| s.points <= points)
+        .max_by_key(|s| s.points)
+}
+
+/// Find the next step of the policy which has not been reached at the given amount of points.
+pub fn next_step(policy: &Strikes, points: u32) -> Option<&StrikeStep> {
+    policy
+        .steps
+        .iter()
+        .filter(|s| s.points > points)
+        .min_by_key(|s| s.points)
+}
+
+/// Describe the consequence of a step.
+pub fn describe(step: &StrikeStep) -> String {
+    match step.action {
+        StrikeAction::Warning => "A warning".to_string(),
+        StrikeAction::Mute => match &step.duration {
+            Some(duration) => format!("A mute for {}", duration),
+            None => "A mute".to_string(),
+        },
+        StrikeAction::Kick => "A kick".to_string(),
+        StrikeAction::Ban => "A ban".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::application::Choice;
+    use crate::testing::configuration;
+
+    #[test]
+    fn escalates_by_points() {
+        // The example policy applies steps at 1, 2, 4 and 6 points
+        let configuration = configuration(|_| {});
+        let policy = &configuration.strikes;
+
+        assert!(step_for(policy, 0).is_none());
+        assert_eq!(step_for(policy, 3).map(|s| s.points), Some(2));
+        assert_eq!(next_step(policy, 3).map(|s| s.points), Some(4));
+        assert!(next_step(policy, 6).is_none());
+    }
+
+    #[test]
+    fn reason_presets_override_action_points() {
+        let configuration = configuration(|c| {
+            c.strikes.points.insert("mute".to_string(), 2);
+            c.choices.reasons = vec![Choice {
+                name: "Scam".to_string(),
+                value: "Posting scams".to_string(),
+                localizations: Default::default(),
+                points: Some(5),
+            }];
+        });
+
+        assert_eq!(points_for(&configuration, "mute", "Spam"), 2);
+        assert_eq!(points_for(&configuration, "mute", "Posting scams"), 5);
+        assert_eq!(
+            points_for(&configuration, "unknown", "Spam"),
+            DEFAULT_POINTS
+        );
+    }
+}
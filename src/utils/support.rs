@@ -0,0 +1,405 @@
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bson::doc;
+use chrono::Utc;
+use poise::serenity_prelude::{ChannelId, GuildChannel, Message, MessageId};
+use tracing::{debug, error, trace};
+
+use super::bot::get_data;
+use super::*;
+use crate::db::database::Database;
+use crate::db::model::{Resolution, SupportResponse};
+use crate::model::application::{Faq, Support};
+
+const WEEK_SECS: i64 = 7 * 24 * 60 * 60;
+// The unix epoch was a Thursday, the first Monday was four days later
+const FIRST_MONDAY_SECS: i64 = 4 * 24 * 60 * 60;
+// The maximal amount of fields of an embed
+const MAX_DIGEST_ENTRIES: usize = 25;
+
+static DIGEST_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+// The amount of recently archived threads to compare against
+const RECENT_THREADS: u64 = 50;
+// The maximal amount of suggestions to reply with
+const MAX_SUGGESTIONS: usize = 3;
+// Words which carry no meaning for the comparison of questions
+const STOPWORDS: [&str; 24] = [
+    "the", "and", "for", "with", "this", "that", "not", "but", "can", "how", "does", "what", "why",
+    "when", "are", "was", "have", "has", "any", "you", "from", "there", "help", "please",
+];
+
+fn tokens(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(|token| token.to_lowercase())
+        .filter(|token| token.len() > 2 && !STOPWORDS.contains(&token.as_str()))
+        .collect()
+}
+
+/// The Jaccard similarity of the token sets of two texts.
+pub fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    a.intersection(b).count() as f64 / a.union(b).count() as f64
+}
+
+/// Find the FAQ entries similar to a question, most similar first.
+pub fn similar_faq<'a>(support: &'a Support, question: &str) -> Vec<&'a Faq> {
+    let question = tokens(question);
+
+    let mut similar = support
+        .faq
+        .iter()
+        .map(|faq| (similarity(&question, &tokens(&faq.question)), faq))
+        .filter(|(score, _)| *score >= support.similarity)
+        .collect::<Vec<_>>();
+    similar.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+    similar
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, faq)| faq)
+        .collect()
+}
+
+/// Find resolved threads and FAQ entries similar to a question, most similar first.
+async fn find_similar(
+    ctx: &serenity::Context,
+    database: &Database,
+    support: &Support,
+    channel_id: ChannelId,
+    question: &str,
+    exclude: Option<ChannelId>,
+) -> Vec<(String, String)> {
+    let question = tokens(question);
+
+    // Candidates as the text to compare, the title and the url
+    let mut candidates = support
+        .faq
+        .iter()
+        .map(|faq| (faq.question.clone(), faq.question.clone(), faq.url.clone()))
+        .collect::<Vec<_>>();
+
+    // Resolutions carry a summary, which makes them better candidates than their thread alone
+    match database
+        .collect::<Resolution>("resolutions", doc! { "channel_id": channel_id.to_string() })
+        .await
+    {
+        Ok(resolutions) => candidates.extend(
+            resolutions
+                .into_iter()
+                .filter(|r| r.thread_id != exclude.map(|e| e.to_string()))
+                .map(|r| {
+                    let title = r.title.unwrap_or_default();
+                    let text = format!("{} {}", title, r.summary.unwrap_or_default());
+                    let url = format!(
+                        "https://discord.com/channels/{}/{}",
+                        r.guild_id.unwrap_or_default(),
+                        r.thread_id.unwrap_or_default()
+                    );
+                    (text, title, url)
+                }),
+        ),
+        Err(err) => error!("Failed to query resolutions: {:?}", err),
+    }
+
+    match channel_id
+        .get_archived_public_threads(&ctx.http, None, Some(RECENT_THREADS))
+        .await
+    {
+        Ok(archived) => {
+            for thread in archived.threads {
+                let url = format!(
+                    "https://discord.com/channels/{}/{}",
+                    thread.guild_id, thread.id
+                );
+                // Resolved threads are already candidates
+                if Some(thread.id) == exclude || candidates.iter().any(|(.., u)| *u == url) {
+                    continue;
+                }
+
+                candidates.push((thread.name.clone(), thread.name, url));
+            }
+        },
+        Err(err) => error!(
+            "Failed to fetch archived threads of {}: {:?}",
+            channel_id, err
+        ),
+    }
+
+    let mut similar = candidates
+        .into_iter()
+        .map(|(text, title, url)| (similarity(&question, &tokens(&text)), title, url))
+        .filter(|(score, ..)| *score >= support.similarity)
+        .collect::<Vec<_>>();
+    similar.sort_by(|(a, ..), (b, ..)| b.total_cmp(a));
+
+    similar
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, title, url)| (title, url))
+        .collect()
+}
+
+async fn suggest(
+    ctx: &serenity::Context,
+    reply_to: ChannelId,
+    reference: Option<&Message>,
+    suggestions: &[(String, String)],
+    embed_color: i32,
+) {
+    if suggestions.is_empty() {
+        return;
+    }
+
+    let description = suggestions
+        .iter()
+        .map(|(title, url)| format!("- [{}]({})", title, url))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if let Err(err) = reply_to
+        .send_message(&ctx.http, |m| {
+            if let Some(reference) = reference {
+                m.reference_message(reference);
+            }
+
+            m.embed(|e| {
+                e.title("This may already be answered")
+                    .description(description)
+                    .color(embed_color)
+            })
+        })
+        .await
+    {
+        error!("Failed to suggest similar questions: {:?}", err);
+    }
+}
+
+/// Suggest similar questions for new threads in support channels.
+pub async fn suggest_for_thread(ctx: &serenity::Context, thread: &GuildChannel) {
+    let parent_id = match thread.parent_id {
+        Some(parent_id) => parent_id,
+        None => return,
+    };
+
+    let data = get_data(ctx).await;
+    let support = &data.configuration.support;
+    if !support.channels.contains(&parent_id.0) {
+        return;
+    }
+
+    // The starter message of forum posts shares its id with the thread
+    let mut question = thread.name.clone();
+    if let Ok(starter) = thread.id.message(&ctx.http, MessageId(thread.id.0)).await {
+        question.push(' ');
+        question.push_str(&starter.content);
+    }
+
+    let suggestions = find_similar(
+        ctx,
+        &data.database,
+        support,
+        parent_id,
+        &question,
+        Some(thread.id),
+    )
+    .await;
+    debug!(
+        "Found {} similar questions for {}",
+        suggestions.len(),
+        thread.name
+    );
+
+    suggest(
+        ctx,
+        thread.id,
+        None,
+        &suggestions,
+        data.configuration.general.embed_color,
+    )
+    .await;
+}
+
+/// Suggest similar questions for new messages in support channels.
+pub async fn suggest_for_message(ctx: &serenity::Context, new_message: &Message) {
+    if new_message.guild_id.is_none() || new_message.author.bot {
+        return;
+    }
+
+    let data = get_data(ctx).await;
+    let support = &data.configuration.support;
+    if !support.channels.contains(&new_message.channel_id.0) {
+        return;
+    }
+
+    let suggestions = find_similar(
+        ctx,
+        &data.database,
+        support,
+        new_message.channel_id,
+        &new_message.content,
+        None,
+    )
+    .await;
+
+    suggest(
+        ctx,
+        new_message.channel_id,
+        Some(new_message),
+        &suggestions,
+        data.configuration.general.embed_color,
+    )
+    .await;
+}
+
+/// Record how long it took staff to first respond in a support thread.
+pub async fn record_response(ctx: &serenity::Context, new_message: &Message) {
+    let guild_id = match new_message.guild_id {
+        Some(guild_id) if !new_message.author.bot => guild_id,
+        _ => return,
+    };
+
+    let data = get_data(ctx).await;
+    let administrators = &data.configuration.administrators;
+    let is_staff = administrators.users.contains(&new_message.author.id.0)
+        || new_message
+            .member
+            .as_ref()
+            .is_some_and(|m| m.roles.iter().any(|r| administrators.roles.contains(&r.0)));
+    if !is_staff {
+        return;
+    }
+
+    let thread = match new_message.channel_id.to_channel(&ctx).await {
+        Ok(channel) => match channel.guild() {
+            Some(thread) => thread,
+            None => return,
+        },
+        Err(err) => {
+            debug!("Failed to fetch channel of message: {:?}", err);
+            return;
+        },
+    };
+    let in_support_thread = thread
+        .parent_id
+        .is_some_and(|p| data.configuration.support.channels.contains(&p.0));
+    if !in_support_thread {
+        return;
+    }
+
+    let response_time =
+        new_message.timestamp.unix_timestamp() - thread.id.created_at().unix_timestamp();
+
+    // The thread id is unique, so only the first response is recorded
+    if data
+        .database
+        .insert::<SupportResponse>(
+            "support_responses",
+            SupportResponse {
+                thread_id: Some(thread.id.to_string()),
+                guild_id: Some(guild_id.to_string()),
+                responder_id: Some(new_message.author.id.to_string()),
+                response_time: Some(response_time.max(0)),
+                timestamp: Some(new_message.timestamp.unix_timestamp()),
+            },
+            None,
+        )
+        .await
+        .is_err()
+    {
+        trace!("Thread {} already has a response", thread.id);
+    }
+}
+
+/// Post the resolutions since the given timestamp as a digest per support channel.
+pub async fn post_digest(ctx: &serenity::Context, since: i64) {
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+    let support = &configuration.support;
+
+    if support.digest_channel == 0 {
+        return;
+    }
+
+    let resolutions = match data
+        .database
+        .collect::<Resolution>("resolutions", doc! { "timestamp": { "$gte": since } })
+        .await
+    {
+        Ok(resolutions) => resolutions,
+        Err(err) => {
+            error!("Failed to query resolutions: {:?}", err);
+            return;
+        },
+    };
+
+    let mut by_channel = BTreeMap::<String, Vec<Resolution>>::new();
+    for resolution in resolutions {
+        by_channel
+            .entry(resolution.channel_id.clone().unwrap_or_default())
+            .or_default()
+            .push(resolution);
+    }
+
+    for (channel_id, resolutions) in by_channel {
+        let fields = resolutions
+            .iter()
+            .take(MAX_DIGEST_ENTRIES)
+            .map(|r| {
+                (
+                    r.title.clone().unwrap_or_default(),
+                    format!(
+                        "{}\n[Jump to thread](https://discord.com/channels/{}/{})",
+                        r.summary.as_deref().unwrap_or_default(),
+                        r.guild_id.as_deref().unwrap_or_default(),
+                        r.thread_id.as_deref().unwrap_or_default()
+                    ),
+                    false,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if let Err(err) = ChannelId(support.digest_channel)
+            .send_message(&ctx.http, |m| {
+                m.embed(|e| {
+                    e.title("Common issues this week")
+                        .description(format!(
+                            "{} questions were resolved in <#{}>.",
+                            resolutions.len(),
+                            channel_id
+                        ))
+                        .fields(fields)
+                        .color(configuration.general.embed_color)
+                })
+            })
+            .await
+        {
+            error!("Failed to post the support digest: {:?}", err);
+        }
+    }
+}
+
+/// Post the digest of the past week every Monday at midnight UTC.
+pub fn schedule_digest(ctx: &serenity::Context) {
+    // Ready is dispatched again when reconnecting
+    if DIGEST_SCHEDULED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        loop {
+            let now = Utc::now().timestamp();
+            let next =
+                (now - FIRST_MONDAY_SECS) / WEEK_SECS * WEEK_SECS + FIRST_MONDAY_SECS + WEEK_SECS;
+
+            tokio::time::sleep(std::time::Duration::from_secs((next - now) as u64)).await;
+
+            post_digest(&ctx, next - WEEK_SECS).await;
+        }
+    });
+}
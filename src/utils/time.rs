@@ -0,0 +1,66 @@
+use bson::doc;
+use chrono::{Duration, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::db::database::Database;
+use crate::db::model::UserPreferences;
+
+/// Parse a duration such as `30m`, `12h` or `1w2d`.
+pub fn parse_duration(text: &str) -> Option<Duration> {
+    let mut duration = Duration::zero();
+    let mut amount = String::new();
+
+    for c in text.trim().chars() {
+        if c.is_ascii_digit() {
+            amount.push(c);
+            continue;
+        }
+
+        let value = amount.parse::<i64>().ok()?;
+        amount.clear();
+
+        duration = duration
+            + match c.to_ascii_lowercase() {
+                's' => Duration::seconds(value),
+                'm' => Duration::minutes(value),
+                'h' => Duration::hours(value),
+                'd' => Duration::days(value),
+                'w' => Duration::weeks(value),
+                _ => return None,
+            };
+    }
+
+    // A trailing number without a unit is not allowed
+    if !amount.is_empty() {
+        return None;
+    }
+
+    Some(duration)
+}
+
+/// Format a timestamp with Discord markup, which is shown in the local time of the reader.
+pub fn format_timestamp(timestamp: i64) -> String {
+    format!("<t:{0}:F> (<t:{0}:R>)", timestamp)
+}
+
+/// Format a timestamp in the given timezone, for content which does not support Discord markup.
+pub fn format_local_timestamp(timestamp: i64, timezone: Tz) -> String {
+    Utc.timestamp(timestamp, 0)
+        .with_timezone(&timezone)
+        .format("%Y-%m-%d %H:%M %Z")
+        .to_string()
+}
+
+/// Get the timezone a user has set.
+pub async fn user_timezone(database: &Database, user_id: u64) -> Option<Tz> {
+    let mut cursor = database
+        .find::<UserPreferences>("preferences", doc! { "user_id": user_id.to_string() }, None)
+        .await
+        .ok()?;
+
+    if !cursor.advance().await.ok()? {
+        return None;
+    }
+
+    cursor.deserialize_current().ok()?.timezone?.parse().ok()
+}
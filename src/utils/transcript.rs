@@ -0,0 +1,186 @@
+use std::borrow::Cow;
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{AttachmentType, ChannelId, GuildChannel, Http, Message, UserId};
+use regex::Regex;
+use tracing::debug;
+
+use crate::db::model::Transcript;
+use crate::{Data, Error};
+
+// The maximal amount of messages which can be fetched at once
+const MAX_FETCH: u64 = 100;
+
+// The beginning of a rendered message, the following lines belong to it until the next one
+static MESSAGE_START: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[\d{4}-\d{2}-\d{2} \d{2}:\d{2}\] ").unwrap());
+
+/// Fetch the full history of a channel, oldest first.
+pub async fn fetch_history(http: &Http, channel_id: ChannelId) -> Result<Vec<Message>, Error> {
+    // Messages are fetched from newest to oldest
+    let mut messages: Vec<Message> = Vec::new();
+    loop {
+        let before = messages.last().map(|m| m.id);
+        let batch = channel_id
+            .messages(http, |r| {
+                if let Some(before) = before {
+                    r.before(before);
+                }
+                r.limit(MAX_FETCH)
+            })
+            .await?;
+
+        let is_last = (batch.len() as u64) < MAX_FETCH;
+        messages.extend(batch);
+        if is_last {
+            break;
+        }
+    }
+    messages.reverse();
+
+    Ok(messages)
+}
+
+/// Render messages as a plain text transcript.
+pub fn render(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .map(|m| {
+            let mut line = format!(
+                "[{}] {}: {}",
+                m.timestamp.format("%Y-%m-%d %H:%M"),
+                m.author.tag(),
+                m.content
+            );
+            for attachment in &m.attachments {
+                line.push_str(&format!("\n    {}", attachment.url));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Split a rendered transcript into its messages, each with whether the given tag wrote it.
+fn split_messages<'a>(content: &'a str, tag: &str) -> Vec<(bool, Vec<&'a str>)> {
+    let author = format!("{}: ", tag);
+
+    let mut messages: Vec<(bool, Vec<&str>)> = Vec::new();
+    for line in content.lines() {
+        match (MESSAGE_START.find(line), messages.last_mut()) {
+            (Some(start), _) => {
+                messages.push((line[start.end()..].starts_with(&author), vec![line]))
+            },
+            (None, Some((_, lines))) => lines.push(line),
+            (None, None) => messages.push((false, vec![line])),
+        }
+    }
+    messages
+}
+
+/// The messages of a rendered transcript written by the given tag.
+pub fn messages_of(content: &str, tag: &str) -> Vec<String> {
+    split_messages(content, tag)
+        .into_iter()
+        .filter(|(is_author, _)| *is_author)
+        .map(|(_, lines)| lines.join("\n"))
+        .collect()
+}
+
+/// Replace the messages of a rendered transcript written by the given tag with a placeholder.
+/// The time of the messages is kept, so the course of the conversation stays readable.
+pub fn redact_messages_of(content: &str, tag: &str, placeholder: &str) -> String {
+    split_messages(content, tag)
+        .into_iter()
+        .map(|(is_author, lines)| {
+            if is_author {
+                let time = MESSAGE_START.find(lines[0]).map_or("", |m| m.as_str());
+                format!("{}{}", time, placeholder)
+            } else {
+                lines.join("\n")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Store the transcript of a resolved support thread and post it to the staff channel.
+pub async fn save_transcript(
+    http: &Http,
+    data: &Data,
+    thread: &GuildChannel,
+    resolved_by: UserId,
+) -> Result<(), Error> {
+    let messages = fetch_history(http, thread.id).await?;
+    let content = render(&messages);
+
+    data.database
+        .insert::<Transcript>(
+            "transcripts",
+            Transcript {
+                thread_id: Some(thread.id.to_string()),
+                channel_id: thread.parent_id.map(|id| id.to_string()),
+                guild_id: Some(thread.guild_id.to_string()),
+                title: Some(thread.name.clone()),
+                content: Some(content.clone()),
+                resolved_by: Some(resolved_by.to_string()),
+                timestamp: Some(Utc::now().timestamp()),
+            },
+            None,
+        )
+        .await?;
+
+    let transcript_channel = data.configuration.support.transcript_channel;
+    if transcript_channel != 0 {
+        ChannelId(transcript_channel)
+            .send_message(http, |m| {
+                m.add_file(AttachmentType::Bytes {
+                    data: Cow::from(content.into_bytes()),
+                    filename: format!("{}.txt", thread.id),
+                })
+                .embed(|e| {
+                    e.title(&thread.name)
+                        .field("Thread", format!("<#{}>", thread.id), true)
+                        .field("Messages", messages.len(), true)
+                        .field("Resolved by", format!("<@{}>", resolved_by), true)
+                        .color(data.configuration.general.embed_color)
+                })
+            })
+            .await?;
+    }
+
+    debug!(
+        "Saved a transcript of {} messages of {}",
+        messages.len(),
+        thread.name
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSCRIPT: &str = "[2024-01-01 12:00] helper#1: How can I help?\n[2024-01-01 12:01] \
+                              user#2: It crashes\nwhen patching\n    https://cdn/log.txt\n\
+                              [2024-01-01 12:02] helper#1: Update the app";
+
+    #[test]
+    fn finds_multiline_messages_of_an_author() {
+        assert_eq!(messages_of(TRANSCRIPT, "user#2"), [
+            "[2024-01-01 12:01] user#2: It crashes\nwhen patching\n    https://cdn/log.txt"
+        ]);
+        assert!(messages_of(TRANSCRIPT, "user#3").is_empty());
+    }
+
+    #[test]
+    fn redacts_messages_of_an_author() {
+        assert_eq!(
+            redact_messages_of(TRANSCRIPT, "user#2", "anonymized"),
+            "[2024-01-01 12:00] helper#1: How can I help?\n[2024-01-01 12:01] anonymized\n\
+             [2024-01-01 12:02] helper#1: Update the app"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use poise::serenity_prelude::{ChannelId, VoiceState};
+use tracing::error;
+
+use super::bot::get_data;
+use super::*;
+use crate::model::application::LogCategory;
+
+#[derive(Debug, PartialEq, Eq)]
+enum VoiceEvent {
+    Join(ChannelId),
+    Leave(ChannelId),
+    Move(ChannelId, ChannelId),
+}
+
+/// What changed about the voice channel of a member, if anything.
+/// Muting and deafening also update the voice state, but are not logged.
+fn voice_event(old: Option<ChannelId>, new: Option<ChannelId>) -> Option<VoiceEvent> {
+    match (old, new) {
+        (None, Some(new)) => Some(VoiceEvent::Join(new)),
+        (Some(old), None) => Some(VoiceEvent::Leave(old)),
+        (Some(old), Some(new)) if old != new => Some(VoiceEvent::Move(old, new)),
+        _ => None,
+    }
+}
+
+/// Log members joining, leaving and moving between voice channels to the voice log channel.
+pub async fn log_voice_state(ctx: &serenity::Context, old: Option<&VoiceState>, new: &VoiceState) {
+    if new.guild_id.is_none() || new.member.as_ref().is_some_and(|m| m.user.bot) {
+        return;
+    }
+    let Some(event) = voice_event(old.and_then(|o| o.channel_id), new.channel_id) else {
+        return;
+    };
+
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+    let channel_id = match configuration.voice_log.channel {
+        0 => configuration.log_channel(LogCategory::Voice),
+        channel_id => channel_id,
+    };
+    if channel_id == 0 {
+        return;
+    }
+
+    let (title, description) = match event {
+        VoiceEvent::Join(channel_id) => ("Joined voice", format!("<#{}>", channel_id)),
+        VoiceEvent::Leave(channel_id) => ("Left voice", format!("<#{}>", channel_id)),
+        VoiceEvent::Move(old, new) => ("Moved voice", format!("<#{}> → <#{}>", old, new)),
+    };
+
+    if let Err(err) = ChannelId(channel_id)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title(title)
+                    .description(description)
+                    .field("Member", format!("<@{}>", new.user_id), true)
+                    .footer(|f| f.text(format!("ID: {}", new.user_id)))
+                    .color(configuration.general.embed_color);
+
+                if let Some(member) = &new.member {
+                    e.thumbnail(member.user.face());
+                }
+
+                e
+            })
+        })
+        .await
+    {
+        error!("Failed to log voice state: {:?}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_joins_leaves_and_moves() {
+        let (a, b) = (ChannelId(1), ChannelId(2));
+
+        assert_eq!(voice_event(None, Some(a)), Some(VoiceEvent::Join(a)));
+        assert_eq!(voice_event(Some(a), None), Some(VoiceEvent::Leave(a)));
+        assert_eq!(voice_event(Some(a), Some(b)), Some(VoiceEvent::Move(a, b)));
+        assert_eq!(voice_event(Some(a), Some(a)), None);
+        assert_eq!(voice_event(None, None), None);
+    }
+}
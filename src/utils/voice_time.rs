@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use bson::doc;
+use chrono::{Duration, TimeZone, Utc};
+use mongodb::options::{UpdateModifications, UpdateOptions};
+use once_cell::sync::Lazy;
+use poise::serenity_prelude::{GuildId, Mutex, UserId};
+use tracing::error;
+
+use super::bot::get_data;
+use super::*;
+use crate::db::model::VoiceTime;
+
+// The format of the days voice time is aggregated by, which sorts chronologically
+pub const DAY_FORMAT: &str = "%Y-%m-%d";
+
+// When members joined a voice channel by guild and user.
+// Sessions which started before the bot did are not tracked until the member rejoins.
+static SESSIONS: Lazy<Mutex<HashMap<(u64, u64), i64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Split a session into the seconds spent on each day it spans.
+fn split_by_day(started: i64, ended: i64) -> Vec<(String, i64)> {
+    let mut days = Vec::new();
+    let mut start = started;
+    while start < ended {
+        let day = Utc.timestamp(start, 0).naive_utc().date();
+        let next_day = (day + Duration::days(1))
+            .and_hms_opt(0, 0, 0)
+            .map_or(ended, |midnight| midnight.timestamp());
+        let end = next_day.min(ended);
+
+        days.push((day.format(DAY_FORMAT).to_string(), end - start));
+        start = end;
+    }
+    days
+}
+
+/// The day which is the given amount of days ago.
+pub fn days_ago(days: i64) -> String {
+    (Utc::now().naive_utc().date() - Duration::days(days))
+        .format(DAY_FORMAT)
+        .to_string()
+}
+
+/// Format an amount of seconds as hours and minutes.
+pub fn format_voice_time(seconds: i64) -> String {
+    format!("{}h {}m", seconds / 3600, seconds % 3600 / 60)
+}
+
+/// The seconds a member has spent in the voice channel they are currently in.
+pub async fn current_session(guild_id: GuildId, user_id: UserId) -> i64 {
+    SESSIONS
+        .lock()
+        .await
+        .get(&(guild_id.0, user_id.0))
+        .map_or(0, |started| Utc::now().timestamp() - started)
+}
+
+/// Start a session when a member joins a voice channel and add its time to the daily
+/// aggregates when they leave. Moving between channels continues the session.
+pub async fn track_voice_time(ctx: &serenity::Context, new: &serenity::VoiceState) {
+    let guild_id = match new.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+    if new.member.as_ref().is_some_and(|m| m.user.bot) {
+        return;
+    }
+
+    let now = Utc::now().timestamp();
+    let started = {
+        let mut sessions = SESSIONS.lock().await;
+        let key = (guild_id.0, new.user_id.0);
+        if new.channel_id.is_some() {
+            sessions.entry(key).or_insert(now);
+            return;
+        }
+        sessions.remove(&key)
+    };
+    let Some(started) = started else {
+        return;
+    };
+
+    let data = get_data(ctx).await;
+    for (day, seconds) in split_by_day(started, now) {
+        if let Err(err) = data
+            .database
+            .update::<VoiceTime>(
+                "voice_time",
+                doc! {
+                    "guild_id": guild_id.to_string(),
+                    "user_id": new.user_id.to_string(),
+                    "day": day,
+                },
+                UpdateModifications::Document(doc! { "$inc": { "seconds": seconds } }),
+                Some(UpdateOptions::builder().upsert(true).build()),
+            )
+            .await
+        {
+            error!("Failed to record voice time: {:?}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_sessions_at_midnight() {
+        // 2022-01-01 23:00 until 2022-01-02 01:30
+        let started = 1641078000;
+        assert_eq!(split_by_day(started, started + 9000), vec![
+            ("2022-01-01".to_string(), 3600),
+            ("2022-01-02".to_string(), 5400),
+        ]);
+        assert!(split_by_day(started, started).is_empty());
+    }
+}
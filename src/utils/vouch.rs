@@ -0,0 +1,259 @@
+use bson::{doc, Document};
+use chrono::Utc;
+use mongodb::options::{UpdateModifications, UpdateOptions};
+use poise::serenity_prelude::{
+    ButtonStyle,
+    ChannelId,
+    GuildId,
+    MessageComponentInteraction,
+    User,
+    UserId,
+};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::interaction::{respond_ephemeral, update_with_decision};
+use super::member_cache::fetch_member;
+use super::moderation::is_administrator;
+use super::{dry_run, *};
+use crate::db::model::{Elevation, ElevationStatus, Vouch};
+use crate::{Data, Error};
+
+/// Open a staff poll to elevate a user to helper, unless one is already open.
+pub async fn open_poll(
+    ctx: &serenity::Context,
+    data: &Data,
+    guild_id: GuildId,
+    user: &User,
+    vouches: &[Vouch],
+) -> Result<(), Error> {
+    let vouching = &data.configuration.vouching;
+    let opened: Document = Elevation {
+        vouchers: Some(
+            vouches
+                .iter()
+                .filter_map(|v| v.voucher_id.clone())
+                .collect(),
+        ),
+        opened: Some(Utc::now().timestamp()),
+        ..Default::default()
+    }
+    .into();
+
+    // Only the vouch which opens the pending elevation creates the poll
+    let result = data
+        .database
+        .update::<Elevation>(
+            "elevations",
+            doc! { "guild_id": guild_id.to_string(), "user_id": user.id.to_string(), "status": "pending" },
+            UpdateModifications::Document(doc! { "$setOnInsert": opened }),
+            Some(UpdateOptions::builder().upsert(true).build()),
+        )
+        .await?;
+    if result.upserted_id.is_none() {
+        return Ok(());
+    }
+
+    let message = match ChannelId(vouching.poll_channel)
+        .send_message(&ctx.http, |m| {
+            m.embed(|e| {
+                e.title(format!("Elevate {} to helper?", user.tag()))
+                    .description(
+                        vouches
+                            .iter()
+                            .map(|v| {
+                                format!(
+                                    "<@{}>: {}",
+                                    v.voucher_id.as_deref().unwrap_or_default(),
+                                    v.reason.as_deref().unwrap_or("No reason given")
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    )
+                    .field("User", format!("<@{}>", user.id), true)
+                    .field("Vouches", vouches.len(), true)
+                    .thumbnail(user.face())
+                    .color(data.configuration.general.embed_color)
+            })
+            .components(|c| {
+                c.create_action_row(|r| {
+                    r.create_button(|b| {
+                        b.custom_id(format!("vouch:approve:{}", user.id))
+                            .label("Approve")
+                            .style(ButtonStyle::Success)
+                    })
+                    .create_button(|b| {
+                        b.custom_id(format!("vouch:deny:{}", user.id))
+                            .label("Deny")
+                            .style(ButtonStyle::Danger)
+                    })
+                })
+            })
+        })
+        .await
+    {
+        Ok(message) => message,
+        Err(err) => {
+            // Allow the next vouch to open the poll again
+            data.database
+                .delete(
+                    "elevations",
+                    doc! { "guild_id": guild_id.to_string(), "user_id": user.id.to_string(), "status": "pending" },
+                    None,
+                )
+                .await?;
+            return Err(err.into());
+        },
+    };
+
+    // Staff discuss the elevation in a thread of the poll
+    if let Err(err) = message
+        .channel_id
+        .create_public_thread(&ctx.http, message.id, |t| {
+            t.name(format!("Helper poll: {}", user.name))
+        })
+        .await
+    {
+        error!("Failed to create thread for helper poll: {:?}", err);
+    }
+
+    data.database
+        .update::<Elevation>(
+            "elevations",
+            doc! { "guild_id": guild_id.to_string(), "user_id": user.id.to_string(), "status": "pending" },
+            UpdateModifications::Document(
+                doc! { "$set": { "poll_message_id": message.id.to_string() } },
+            ),
+            None,
+        )
+        .await?;
+
+    debug!("Opened a helper poll for {}", user.tag());
+    Ok(())
+}
+
+/// Decide a pending elevation, returning whether it was still pending.
+async fn decide(
+    ctx: &serenity::Context,
+    guild_id: GuildId,
+    user_id: UserId,
+    status: ElevationStatus,
+    moderator_id: UserId,
+) -> Result<bool, Error> {
+    let data = get_data(ctx).await;
+
+    let decided: Document = Elevation {
+        status: Some(status),
+        moderator_id: Some(moderator_id.to_string()),
+        decided: Some(Utc::now().timestamp()),
+        ..Default::default()
+    }
+    .into();
+
+    let result = data
+        .database
+        .update::<Elevation>(
+            "elevations",
+            doc! { "guild_id": guild_id.to_string(), "user_id": user_id.to_string(), "status": "pending" },
+            UpdateModifications::Document(doc! { "$set": decided }),
+            None,
+        )
+        .await?;
+    if result.modified_count == 0 {
+        return Ok(false);
+    }
+
+    // The decision is kept in the elevation, vouching starts over afterwards
+    data.database
+        .delete_many(
+            "vouches",
+            doc! { "guild_id": guild_id.to_string(), "user_id": user_id.to_string() },
+            None,
+        )
+        .await?;
+
+    Ok(true)
+}
+
+/// Handle the Approve and Deny buttons of helper polls.
+pub async fn handle_vouch_interaction(
+    ctx: &serenity::Context,
+    component: &MessageComponentInteraction,
+) {
+    let mut parts = component.data.custom_id.splitn(3, ':').skip(1);
+    let (decision, user_id) = match (
+        parts.next(),
+        parts.next().and_then(|id| id.parse::<u64>().ok()),
+    ) {
+        (Some(decision), Some(user_id)) => (decision, UserId(user_id)),
+        _ => return,
+    };
+    let guild_id = match component.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    let helper_role = {
+        let data = get_data(ctx).await;
+        let is_allowed = component
+            .member
+            .as_ref()
+            .is_some_and(|m| is_administrator(&data.configuration.administrators, m));
+
+        if !is_allowed {
+            drop(data);
+            respond_ephemeral(ctx, component, "You do not have permission to do this.").await;
+            return;
+        }
+
+        data.configuration.vouching.helper_role
+    };
+
+    let status = match decision {
+        "approve" => ElevationStatus::Approved,
+        "deny" => ElevationStatus::Denied,
+        _ => return,
+    };
+
+    match decide(ctx, guild_id, user_id, status, component.user.id).await {
+        Ok(true) => {},
+        Ok(false) => {
+            respond_ephemeral(ctx, component, "This poll was already decided.").await;
+            return;
+        },
+        Err(err) => {
+            error!("Failed to decide helper poll: {:?}", err);
+            respond_ephemeral(ctx, component, "Failed to decide this poll.").await;
+            return;
+        },
+    }
+
+    let decision = match status {
+        ElevationStatus::Approved => {
+            let result = match fetch_member(ctx, guild_id, user_id).await {
+                Ok(mut member) => dry_run::execute(
+                    format!("add helper role to {}", member.user.tag()),
+                    member.add_role(&ctx.http, RoleId(helper_role)),
+                )
+                .await
+                .map_err(Error::from),
+                Err(err) => Err(err.into()),
+            };
+
+            match result {
+                Ok(_) => format!("Approved by <@{}>", component.user.id),
+                Err(err) => {
+                    error!("Failed to grant helper role to {}: {:?}", user_id, err);
+                    format!(
+                        "Approved by <@{}>, but failed to grant the role: {}",
+                        component.user.id, err
+                    )
+                },
+            }
+        },
+        _ => format!("Denied by <@{}>", component.user.id),
+    };
+
+    update_with_decision(ctx, component, &decision).await;
+}
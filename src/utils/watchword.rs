@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+
+use poise::serenity_prelude::{ChannelId, UserId};
+use regex::{Regex, RegexBuilder};
+use tracing::{debug, error};
+
+use super::bot::get_data;
+use super::*;
+use crate::db::model::Watchword;
+use crate::model::application::LogCategory;
+
+// The maximal amount of characters of the excerpt of a matching message
+const EXCERPT_LENGTH: usize = 500;
+
+/// Compile a watchword pattern, which matches case insensitively.
+pub fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+/// Alert members whose watchwords match a message.
+pub async fn alert_watchwords(ctx: &serenity::Context, new_message: &serenity::Message) {
+    let guild_id = match new_message.guild_id {
+        Some(guild_id) if !new_message.author.bot && !new_message.content.is_empty() => guild_id,
+        _ => return,
+    };
+
+    let data = get_data(ctx).await;
+    let configuration = &data.configuration;
+
+    let watchwords = match data
+        .database
+        .collect::<Watchword>(
+            "watchwords",
+            Watchword {
+                guild_id: Some(guild_id.to_string()),
+                ..Default::default()
+            }
+            .into(),
+        )
+        .await
+    {
+        Ok(watchwords) => watchwords,
+        Err(err) => {
+            error!("Failed to query database for watchwords: {:?}", err);
+            return;
+        },
+    };
+
+    // Matched patterns and whether to deliver as a direct message by subscriber
+    let mut matches: HashMap<UserId, (Vec<String>, bool)> = HashMap::new();
+    for watchword in watchwords {
+        let (Some(user_id), Some(pattern)) = (watchword.user_id, watchword.pattern) else {
+            continue;
+        };
+        let Ok(user_id) = user_id.parse::<u64>().map(UserId) else {
+            continue;
+        };
+
+        // Members are not alerted of their own messages
+        if user_id == new_message.author.id
+            || !compile(&pattern).is_ok_and(|regex| regex.is_match(&new_message.content))
+        {
+            continue;
+        }
+
+        let entry = matches.entry(user_id).or_default();
+        entry.0.push(pattern);
+        entry.1 |= watchword.direct_message.unwrap_or_default();
+    }
+
+    if matches.is_empty() {
+        return;
+    }
+
+    let mut excerpt = new_message
+        .content
+        .chars()
+        .take(EXCERPT_LENGTH)
+        .collect::<String>();
+    if new_message.content.chars().count() > EXCERPT_LENGTH {
+        excerpt.push('…');
+    }
+    let link = new_message.link();
+
+    let alert_channel = if configuration.watchwords.alert_channel != 0 {
+        configuration.watchwords.alert_channel
+    } else {
+        configuration.log_channel(LogCategory::Messages)
+    };
+
+    for (user_id, (patterns, direct_message)) in matches {
+        let patterns = patterns
+            .iter()
+            .map(|p| format!("`{}`", p))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let describe = |e: &mut serenity::CreateEmbed| {
+            e.title("Watchword alert")
+                .description(&excerpt)
+                .field("Watchwords", &patterns, false)
+                .field("Author", format!("<@{}>", new_message.author.id), true)
+                .field("Channel", format!("<#{}>", new_message.channel_id), true)
+                .field("Message", format!("[Jump to message]({})", link), true)
+                .thumbnail(new_message.author.face())
+                .color(configuration.general.embed_color);
+        };
+
+        // Fall back to the alert channel if the member does not accept direct messages
+        if direct_message {
+            let sent = match user_id.create_dm_channel(&ctx.http).await {
+                Ok(channel) => channel
+                    .send_message(&ctx.http, |m| {
+                        m.embed(|e| {
+                            describe(e);
+                            e
+                        })
+                    })
+                    .await
+                    .is_ok(),
+                Err(_) => false,
+            };
+
+            if sent {
+                debug!("Alerted {} of watchwords {}", user_id, patterns);
+                continue;
+            }
+        }
+
+        if let Err(err) = ChannelId(alert_channel)
+            .send_message(&ctx.http, |m| {
+                m.content(format!("<@{}>", user_id))
+                    .allowed_mentions(|a| a.users(vec![user_id]))
+                    .embed(|e| {
+                        describe(e);
+                        e
+                    })
+            })
+            .await
+        {
+            error!("Failed to send watchword alert for {}: {:?}", user_id, err);
+        }
+    }
+}
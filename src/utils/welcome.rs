@@ -0,0 +1,65 @@
+use poise::serenity_prelude::MessageType;
+use tracing::debug;
+
+use super::bot::get_data;
+use super::time::parse_duration;
+use super::{dry_run, *};
+
+/// Delete join and leave notices or chatter of non-staff members in the welcome channel after the
+/// configured delay.
+pub async fn clean_welcome_channel(ctx: &serenity::Context, new_message: &serenity::Message) {
+    let delay = {
+        let data = get_data(ctx).await;
+        let configuration = &data.configuration;
+        let welcome = &configuration.welcome_cleanup;
+
+        if welcome.channel == 0 || new_message.channel_id.0 != welcome.channel {
+            return;
+        }
+
+        // Join notices are sent by Discord, leave notices are usually sent by other bots
+        let is_notice = new_message.kind == MessageType::MemberJoin || new_message.author.bot;
+        let is_staff = configuration
+            .administrators
+            .users
+            .contains(&new_message.author.id.0)
+            || new_message.member.as_ref().is_some_and(|m| {
+                m.roles
+                    .iter()
+                    .any(|r| configuration.administrators.roles.contains(&r.0))
+            });
+
+        let should_delete = if is_notice {
+            welcome.notices
+        } else {
+            welcome.chatter && !is_staff
+        };
+        if !should_delete || new_message.author.id == ctx.cache.current_user_id() {
+            return;
+        }
+
+        match parse_duration(&welcome.delay) {
+            Some(delay) => delay.num_seconds().max(0) as u64,
+            None => return,
+        }
+    };
+
+    let http = ctx.http.clone();
+    let channel_id = new_message.channel_id;
+    let message_id = new_message.id;
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+
+        if let Err(err) = dry_run::execute(
+            format!("delete welcome channel message {}", message_id),
+            channel_id.delete_message(&http, message_id),
+        )
+        .await
+        {
+            debug!(
+                "Failed to delete welcome channel message {}: {:?}",
+                message_id, err
+            );
+        }
+    });
+}